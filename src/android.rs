@@ -0,0 +1,105 @@
+//! Saves of Android apps on a device connected over ADB.
+//! This requires `adb` to be in the PATH, and `run-as` only works for debuggable apps.
+//!
+//! Files on the device can't be read like local files, so they are never part of a normal scan.
+//! Instead, they are pulled into a local folder, which can then be backed up like any other
+//! (e.g., by adding a custom game for that folder).
+
+use crate::prelude::{Error, StrictPath};
+
+/// Copy the files in the app's private `files` folder on the device into `dest`,
+/// keeping their paths relative to that folder. Returns the local files, sorted.
+/// If `serial` is unset, then ADB uses its only connected device.
+pub fn pull_android_saves(package: &str, serial: Option<&str>, dest: &StrictPath) -> Result<Vec<StrictPath>, Error> {
+    let failed = || Error::UnableToPullAndroidSaves {
+        package: package.to_string(),
+    };
+    let output = adb_command(package, serial).output().map_err(|_| failed())?;
+    if !output.status.success() {
+        return Err(failed());
+    }
+    unpack_files(&output.stdout[..], &device_folder(package), dest).map_err(|_| failed())
+}
+
+fn device_folder(package: &str) -> String {
+    format!("/data/data/{}/files", package)
+}
+
+fn adb_command(package: &str, serial: Option<&str>) -> std::process::Command {
+    let mut command = std::process::Command::new("adb");
+    if let Some(serial) = serial {
+        command.args(["-s", serial]);
+    }
+    // Unlike `shell`, `exec-out` never converts line endings, so the tar stream stays intact.
+    command.args(["exec-out", "run-as", package, "tar", "cf", "-", &device_folder(package)]);
+    command
+}
+
+/// Tar drops the leading slash, so it is ignored when matching entries to the device folder.
+/// Entries outside of that folder, or that would climb out of it, are skipped.
+fn unpack_files<R: std::io::Read>(
+    reader: R,
+    device_folder: &str,
+    dest: &StrictPath,
+) -> std::io::Result<Vec<StrictPath>> {
+    let prefix = format!("{}/", device_folder.trim_start_matches('/'));
+    let mut files = vec![];
+    for entry in tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().to_string();
+        let relative = match path.trim_start_matches('/').strip_prefix(&prefix) {
+            Some(x) if !x.split('/').any(|part| part == "..") => x.to_string(),
+            _ => continue,
+        };
+        let target = dest.joined(&relative);
+        target.create_parent_dir()?;
+        entry.unpack(target.interpret())?;
+        files.push(target);
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_build_adb_command() {
+        let command = format!("{:?}", adb_command("com.example.game", Some("emulator-5554")));
+        assert_eq!(
+            r#""adb" "-s" "emulator-5554" "exec-out" "run-as" "com.example.game" "tar" "cf" "-" "/data/data/com.example.game/files""#,
+            command
+        );
+    }
+
+    #[test]
+    fn can_unpack_files_from_tar() {
+        let temp = TempDir::new("android");
+        let mut archive = tar::Builder::new(vec![]);
+        for (path, data) in &[
+            ("data/data/com.example.game/files/save.dat", &b"save"[..]),
+            ("data/data/com.example.game/files/slots/1.dat", &b"slot 1"[..]),
+            ("data/data/com.example.game/cache/skipped.dat", &b"cache"[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            archive.append_data(&mut header, path, *data).unwrap();
+        }
+        let stream = archive.into_inner().unwrap();
+
+        let files = unpack_files(&stream[..], &device_folder("com.example.game"), temp.path()).unwrap();
+        let read = |path: &str| std::fs::read_to_string(temp.joined(path).interpret()).ok();
+
+        assert_eq!(vec![temp.joined("save.dat"), temp.joined("slots/1.dat")], files);
+        assert_eq!(Some("save".to_string()), read("save.dat"));
+        assert_eq!(Some("slot 1".to_string()), read("slots/1.dat"));
+        assert_eq!(None, read("skipped.dat"));
+    }
+}
@@ -1,12 +1,21 @@
 use crate::{
-    config::{Config, RedirectConfig},
+    config::{Config, PortableProfile, RedirectConfig, RootsConfig},
+    diagnostics::SelfCheck,
     lang::Translator,
-    layout::BackupLayout,
-    manifest::{Game, Manifest, SteamMetadata},
+    layout::{BackupLayout, BackupReport, PrunePolicy},
+    manifest::{Game, Manifest, SteamMetadata, Store},
+    metrics::RunMetrics,
     prelude::{
-        app_dir, back_up_game, game_file_restoration_target, prepare_backup_target, restore_game, scan_game_for_backup,
-        scan_game_for_restoration, BackupInfo, Error, OperationStatus, OperationStepDecision, ScanInfo, StrictPath,
+        app_dir, app_version, change_file_owner, diff_game, display_path, escape_control_chars, find_restore_conflicts,
+        free_space, game_file_restoration_target, incremental_delta_bytes, open_game_backup_dir,
+        placeholder_applicability, prepare_backup_target, preview_restore_game, resolve_file_entry,
+        resolve_restore_conflicts, restore_games, restore_preview_tree, run_backup, scan_all_games,
+        scan_game_for_backup_with_roots_filter, scan_game_for_restoration, BackupEvent, BackupInfo, BackupRunOptions,
+        CancelToken, Error, GameDiff, GameSelection, InstallDirCache, IntegrityStatus, OperationStatus,
+        OperationStepDecision, RealFileSystem, RestoreConflict, RestoreOptions, RestorePreviewTree, RetryPolicy,
+        RunSummary, ScanInfo, ScanOptions, StrictPath, Timing,
     },
+    table::{self, sort_rows, Row as TableRow, RowStatus, SortKey},
 };
 use indicatif::ParallelProgressIterator;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
@@ -30,16 +39,26 @@ pub enum Subcommand {
         #[structopt(long)]
         preview: bool,
 
-        /// Directory in which to create the backup. The directory must not
-        /// already exist (unless you use --force), but it will be created if necessary.
+        /// Directory in which to create the backup. It will be created if necessary.
+        /// If it already exists, then see --merge and --overwrite.
         /// When unset, this defaults to the value from Ludusavi's config file.
         #[structopt(long, parse(from_str = parse_strict_path))]
         path: Option<StrictPath>,
 
-        /// Delete the target directory if it already exists.
+        /// Deprecated: use --overwrite and --yes instead.
+        /// This will be removed in a future release.
         #[structopt(long)]
         force: bool,
 
+        /// When not merging, replace the target directory even if it
+        /// already exists and does not look like a Ludusavi backup.
+        #[structopt(long)]
+        overwrite: bool,
+
+        /// Don't ask for confirmation before replacing an existing backup.
+        #[structopt(long)]
+        yes: bool,
+
         /// Merge into existing directory instead of deleting/recreating it.
         /// Within the target directory, the subdirectories for individual
         /// games will still be cleared out first, though.
@@ -62,6 +81,45 @@ pub enum Subcommand {
         #[structopt(long, conflicts_with("update"))]
         try_update: bool,
 
+        /// Don't look for saves inside of Proton prefixes for this run,
+        /// regardless of Ludusavi's config file.
+        #[structopt(long)]
+        no_proton_prefixes: bool,
+
+        /// On Windows, traverse NTFS junction points inside of save folders for this run,
+        /// regardless of Ludusavi's config file.
+        #[structopt(long)]
+        follow_junctions: bool,
+
+        /// Skip any folder containing a `.nobackup` file for this run,
+        /// regardless of Ludusavi's config file.
+        #[structopt(long)]
+        respect_nobackup_files: bool,
+
+        /// Limit the total size of the backup to this many bytes.
+        /// Games are counted in name order, and any game that would go over
+        /// the limit is skipped, while the rest are still backed up.
+        #[structopt(long)]
+        max_total_size: Option<u64>,
+
+        /// On Windows, read save files from a Volume Shadow Copy snapshot,
+        /// so that files which a running game has locked can still be backed up.
+        /// This requires running as administrator. If the snapshot can't be created,
+        /// then the files are read normally.
+        #[structopt(long)]
+        use_vss: bool,
+
+        /// At the end of the run, write metrics to this file in the format of
+        /// Prometheus's textfile collector, like the run's duration and byte count.
+        #[structopt(long, parse(from_str = parse_strict_path))]
+        metrics_out: Option<StrictPath>,
+
+        /// Save what the scan saw of your file system to this file as YAML,
+        /// so that the scan can be reproduced when reporting an issue.
+        /// This is mainly useful with --preview.
+        #[structopt(long, parse(from_str = parse_strict_path))]
+        record_scan: Option<StrictPath>,
+
         /// When naming specific games to process, this means that you'll
         /// provide the Steam IDs instead of the manifest names, and Ludusavi will
         /// look up those IDs in the manifest to find the corresponding names.
@@ -73,6 +131,25 @@ pub enum Subcommand {
         #[structopt(long)]
         api: bool,
 
+        /// Use `table` to print one row per game with its file count, size,
+        /// and status instead of listing every file.
+        #[structopt(long, possible_values = &["standard", "table"], conflicts_with("api"))]
+        output: Option<OutputFormat>,
+
+        /// Order of the games in `--output table`: by `name` or largest `size` first.
+        #[structopt(long, possible_values = &["name", "size"])]
+        sort: Option<SortKey>,
+
+        /// Show how long each game took to scan and back up,
+        /// along with the total time spent in each phase.
+        #[structopt(long)]
+        verbose: bool,
+
+        /// Only back up the games that failed or were cancelled in the last
+        /// backup (not counting previews). This always merges into the target.
+        #[structopt(long, conflicts_with_all(&["games", "by-steam-id", "no-merge"]))]
+        retry_failed: bool,
+
         /// Only back up these specific games.
         #[structopt()]
         games: Vec<String>,
@@ -88,10 +165,31 @@ pub enum Subcommand {
         #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
         path: Option<StrictPath>,
 
-        /// Don't ask for confirmation.
+        /// Deprecated: use --yes and --ignore-integrity instead.
+        /// This will be removed in a future release.
         #[structopt(long)]
         force: bool,
 
+        /// Don't ask for confirmation. This also skips choosing between games
+        /// that would restore the same file, in which case the last one restored wins.
+        #[structopt(long)]
+        yes: bool,
+
+        /// Also restore files that failed the backup integrity check,
+        /// which are skipped otherwise.
+        #[structopt(long)]
+        ignore_integrity: bool,
+
+        /// Also restore files into Ludusavi's own config folder,
+        /// which are skipped otherwise.
+        #[structopt(long)]
+        allow_config_overwrite: bool,
+
+        /// At the end of the run, write metrics to this file in the format of
+        /// Prometheus's textfile collector, like the run's duration and byte count.
+        #[structopt(long, parse(from_str = parse_strict_path))]
+        metrics_out: Option<StrictPath>,
+
         /// When naming specific games to process, this means that you'll
         /// provide the Steam IDs instead of the manifest names, and Ludusavi will
         /// look up those IDs in the manifest to find the corresponding names.
@@ -103,23 +201,427 @@ pub enum Subcommand {
         #[structopt(long)]
         api: bool,
 
+        /// Use `table` to print one row per game with its file count, size,
+        /// and status instead of listing every file.
+        #[structopt(long, possible_values = &["standard", "table"], conflicts_with("api"))]
+        output: Option<OutputFormat>,
+
+        /// Order of the games in `--output table`: by `name` or largest `size` first.
+        #[structopt(long, possible_values = &["name", "size"])]
+        sort: Option<SortKey>,
+
+        /// Show how long each game took to scan and restore,
+        /// along with the total time spent in each phase.
+        #[structopt(long)]
+        verbose: bool,
+
         /// Only restore these specific games.
         #[structopt()]
         games: Vec<String>,
     },
+    #[structopt(about = "Compare current data against the latest backup")]
+    Diff {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// When naming the game to process, this means that you'll
+        /// provide the Steam ID instead of the manifest name, and Ludusavi will
+        /// look up that ID in the manifest to find the corresponding name.
+        #[structopt(long)]
+        by_steam_id: bool,
+
+        /// Print information to stdout in machine-readable JSON.
+        /// This replaces the default, human-readable output.
+        #[structopt(long)]
+        api: bool,
+
+        /// Game to compare. When unset, every game that has a backup
+        /// or current data is compared.
+        #[structopt()]
+        game: Option<String>,
+    },
+    #[structopt(about = "Summarize the games in a backup")]
+    Report {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Print information to stdout in machine-readable JSON.
+        /// This replaces the default, human-readable output.
+        #[structopt(long)]
+        api: bool,
+
+        /// Replace your home folder with `<home>` in the listed paths,
+        /// which is handy when sharing the report.
+        #[structopt(long)]
+        redact_home: bool,
+
+        /// Only list games whose backup has not changed in more than this many days,
+        /// such as games that were uninstalled long ago.
+        #[structopt(long)]
+        older_than: Option<u64>,
+    },
+    #[structopt(about = "Check every game in a backup for corrupted or missing files")]
+    Verify {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+    },
+    #[structopt(about = "Remove surplus backups of games that have been backed up more than once")]
+    Prune {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Only remove backups that are older than this many days.
+        #[structopt(long)]
+        older_than: Option<u64>,
+
+        /// Remove the oldest backups only until the backup drive has this many
+        /// MiB free. Without a value, this uses `backup.minFreeSpaceMib`
+        /// from Ludusavi's config file.
+        #[structopt(long)]
+        min_free_space_mib: Option<Option<u64>>,
+
+        /// Don't ask for confirmation.
+        #[structopt(long)]
+        yes: bool,
+    },
+    #[structopt(about = "Remove backups of games that no longer exist, and empty drive folders")]
+    Compact {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// List what would be removed, but don't actually remove anything.
+        #[structopt(long)]
+        preview: bool,
+
+        /// Don't ask for confirmation.
+        #[structopt(long)]
+        yes: bool,
+    },
+    #[structopt(about = "Package a whole backup into one ZIP archive, such as for sharing it")]
+    ExportBackup {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Where to write the archive, or `-` to write it to standard output,
+        /// such as to pipe it into another program.
+        #[structopt(parse(from_str = parse_strict_path))]
+        archive: StrictPath,
+    },
+    #[structopt(about = "Extract an archive from `export-backup` into an empty folder")]
+    ImportBackup {
+        /// Where to extract the backup. It must not exist yet or be empty.
+        /// When unset, this defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(from_str = parse_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Archive created by `export-backup`.
+        #[structopt(parse(try_from_str = parse_existing_strict_path))]
+        archive: StrictPath,
+    },
+    #[structopt(about = "Save your backup/restore options, redirects, and custom games to share with another machine")]
+    ExportProfile {
+        /// Where to write the profile. Roots are left out, since they are specific to each machine.
+        #[structopt(parse(from_str = parse_strict_path))]
+        profile: StrictPath,
+    },
+    #[structopt(about = "Add the options from `export-profile` to your config file")]
+    ImportProfile {
+        /// Profile created by `export-profile`.
+        #[structopt(parse(try_from_str = parse_existing_strict_path))]
+        profile: StrictPath,
+    },
+    #[cfg(feature = "android")]
+    #[structopt(about = "Copy an Android app's saves from a device over ADB into a local folder")]
+    PullAndroid {
+        /// Serial of the device, as listed by `adb devices`.
+        /// When unset, ADB uses its only connected device.
+        #[structopt(long)]
+        serial: Option<String>,
+
+        /// Package name of the app, like `com.example.game`.
+        package: String,
+
+        /// Local folder for the saves, which can then be backed up like any other.
+        #[structopt(parse(from_str = parse_strict_path))]
+        dest: StrictPath,
+    },
+    #[structopt(about = "Open a game's backup folder in your file manager")]
+    Open {
+        /// Directory containing a Ludusavi backup. When unset, this
+        /// defaults to the backup path from Ludusavi's config file.
+        #[structopt(long, parse(try_from_str = parse_existing_strict_path))]
+        path: Option<StrictPath>,
+
+        /// Use the restore path from Ludusavi's config file
+        /// instead of the backup path.
+        #[structopt(long, conflicts_with("path"))]
+        restore: bool,
+
+        /// Game whose folder to open.
+        #[structopt()]
+        game: String,
+    },
+    #[structopt(about = "Inspect the manifest")]
+    Manifest {
+        #[structopt(subcommand)]
+        sub: ManifestSubcommand,
+    },
+    #[structopt(about = "Print the JSON Schema for the --api output")]
+    Schema,
+    #[structopt(about = "Print details about your setup to include in a bug report")]
+    SelfCheck {
+        /// Replace your home folder with `<home>` and your username
+        /// with `<user>` in the listed paths.
+        #[structopt(long)]
+        redact: bool,
+    },
+}
+
+#[derive(structopt::StructOpt, Clone, Debug, PartialEq)]
+pub enum ManifestSubcommand {
+    #[structopt(about = "Print a game's manifest entry")]
+    Show {
+        /// Also list the concrete paths that each file entry resolves to
+        /// for each configured root, and whether they exist.
+        #[structopt(long)]
+        resolved: bool,
+
+        /// Print information to stdout in machine-readable JSON.
+        /// This replaces the default YAML output.
+        #[structopt(long)]
+        api: bool,
+
+        /// Game to show.
+        #[structopt()]
+        game: String,
+    },
 }
 
 #[derive(structopt::StructOpt, Clone, Debug, PartialEq)]
-#[structopt(name = "ludusavi", about = "Back up and restore PC game saves", set_term_width = 79)]
+#[structopt(
+    name = "ludusavi",
+    about = "Back up and restore PC game saves",
+    set_term_width = 79,
+    setting(structopt::clap::AppSettings::ArgsNegateSubcommands)
+)]
 pub struct Cli {
+    /// Open the GUI. This is the default when no subcommand is given,
+    /// but it can be combined with --game and --screen.
+    #[structopt(long)]
+    pub gui: bool,
+
+    /// Open the GUI focused on this game: only this game will be scanned,
+    /// and its entry will be expanded. If there is no such game, then
+    /// the game list's search box will be filled in with this instead.
+    #[structopt(long, requires("gui"))]
+    pub game: Option<String>,
+
+    /// Open the GUI on this screen.
+    #[structopt(long, requires("gui"), possible_values = &["backup", "restore"])]
+    pub screen: Option<GuiScreen>,
+
     #[structopt(subcommand)]
     pub sub: Option<Subcommand>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GuiScreen {
+    Backup,
+    Restore,
+}
+
+impl std::str::FromStr for GuiScreen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "backup" => Ok(Self::Backup),
+            "restore" => Ok(Self::Restore),
+            _ => Err(format!("invalid screen: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Standard,
+    Table,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Self::Standard),
+            "table" => Ok(Self::Table),
+            _ => Err(format!("invalid output: {}", s)),
+        }
+    }
+}
+
 pub fn parse_cli() -> Cli {
     Cli::from_args()
 }
 
+#[derive(Debug, PartialEq)]
+enum BackupTargetAction {
+    /// The target does not exist yet, so it will be created.
+    Create,
+    /// The target will be kept, and only the individual game folders will be replaced.
+    Merge,
+    /// The target will be deleted and recreated.
+    Replace,
+}
+
+/// This is where each backup records the games for `--retry-failed`.
+fn games_to_retry_file() -> StrictPath {
+    StrictPath::from_std_path_buf(&app_dir().join("games-to-retry.yaml"))
+}
+
+/// An existing target may only be replaced without `--overwrite` if it is empty
+/// or if it looks like a previous Ludusavi backup.
+fn is_recognized_backup_target(target: &StrictPath) -> bool {
+    if !target.is_dir() {
+        return false;
+    }
+    let empty = match std::fs::read_dir(target.interpret()) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => false,
+    };
+    empty || !BackupLayout::new(target.clone()).mapping.games.is_empty()
+}
+
+fn decide_backup_target(
+    target: &StrictPath,
+    recognized: bool,
+    merge: bool,
+    overwrite: bool,
+) -> Result<BackupTargetAction, Error> {
+    if !target.exists() {
+        Ok(BackupTargetAction::Create)
+    } else if merge {
+        Ok(BackupTargetAction::Merge)
+    } else if recognized || overwrite {
+        Ok(BackupTargetAction::Replace)
+    } else {
+        Err(Error::CliBackupTargetExists { path: target.clone() })
+    }
+}
+
+fn request_confirmation(prompt: &str) -> Result<bool, Error> {
+    dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .interact()
+        .map_err(|_| Error::CliUnableToRequestConfirmation)
+}
+
+fn render_restore_conflict(translator: &Translator, conflict: &RestoreConflict) -> String {
+    let mut parts = vec![translator.cli_restore_conflict(&conflict.target)];
+    for source in &conflict.sources {
+        parts.push(format!("  - {}", translator.restore_conflict_source(source)));
+    }
+    parts.join("\n")
+}
+
+/// Ask which game should win a conflict, defaulting to the newest backup.
+fn request_restore_conflict_choice(translator: &Translator, conflict: &RestoreConflict) -> Result<usize, Error> {
+    let items: Vec<_> = conflict
+        .sources
+        .iter()
+        .map(|x| translator.restore_conflict_source(x))
+        .collect();
+    dialoguer::Select::new()
+        .with_prompt(translator.cli_restore_conflict(&conflict.target))
+        .items(&items)
+        .default(0)
+        .interact()
+        .map_err(|_| Error::CliUnableToRequestConfirmation)
+}
+
+/// Version of the `--api` output format. Adding a field does not change this,
+/// but renaming, removing, or changing the type of an existing field does.
+pub const API_VERSION: u32 = 1;
+
+/// Commands whose output may appear in the `command` field of the envelope.
+const API_COMMANDS: &[&str] = &["backup", "restore", "diff", "report", "manifest show"];
+
+/// Common fields for every `--api` document. The command-specific fields
+/// are flattened alongside these so that existing consumers keep working.
+#[derive(Debug, serde::Serialize)]
+struct ApiEnvelope<'a, T: serde::Serialize> {
+    #[serde(rename = "apiVersion")]
+    api_version: u32,
+    version: &'static str,
+    command: &'a str,
+    #[serde(flatten)]
+    output: &'a T,
+}
+
+fn to_api_json<T: serde::Serialize>(command: &str, output: &T) -> String {
+    serde_json::to_string_pretty(&ApiEnvelope {
+        api_version: API_VERSION,
+        version: app_version(),
+        command,
+        output,
+    })
+    .unwrap()
+}
+
+/// This is maintained by hand, so keep it in sync with the `Api*` and `Json*` types.
+fn api_schema() -> serde_json::Value {
+    let string_list = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let errors = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "someGamesFailed": { "type": "boolean" },
+            "unknownGames": string_list,
+        },
+    });
+    serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Ludusavi API output",
+        "type": "object",
+        "required": ["apiVersion", "version", "command"],
+        "properties": {
+            "apiVersion": { "type": "integer", "const": API_VERSION },
+            "version": { "type": "string" },
+            "command": { "type": "string", "enum": API_COMMANDS },
+            "errors": errors,
+            "overall": {
+                "type": "object",
+                "properties": {
+                    "totalGames": { "type": "integer" },
+                    "totalBytes": { "type": "integer" },
+                    "processedGames": { "type": "integer" },
+                    "processedBytes": { "type": "integer" },
+                    "roots": { "type": "array" },
+                    "timings": { "type": "object" },
+                },
+            },
+            "games": { "type": ["object", "array"] },
+            "generatedAt": { "type": "object" },
+            "name": { "type": "string" },
+            "entry": { "type": "object" },
+            "resolved": { "type": "array" },
+            "skippedPlaceholders": { "type": "object" },
+        },
+        "additionalProperties": true,
+    })
+}
+
 #[derive(Debug, Default, serde::Serialize)]
 struct ApiErrors {
     #[serde(rename = "someGamesFailed", skip_serializing_if = "Option::is_none")]
@@ -132,9 +634,26 @@ struct ApiErrors {
 struct ApiFile {
     #[serde(skip_serializing_if = "crate::serialization::is_false")]
     failed: bool,
+    #[serde(skip_serializing_if = "crate::serialization::is_false")]
+    corrupted: bool,
     bytes: u64,
+    /// When restoring, the file replaces (or would replace) an existing file.
+    #[serde(skip_serializing_if = "crate::serialization::is_false")]
+    overwrites: bool,
+    /// When restoring, the existing file at the target was modified too recently to replace.
+    #[serde(skip_serializing_if = "crate::serialization::is_false")]
+    skipped: bool,
     #[serde(rename = "originalPath", skip_serializing_if = "Option::is_none")]
     original_path: Option<String>,
+    /// When restoring with `restore.preserveOwnership`, the file's recorded owner could not be reapplied.
+    #[serde(rename = "ownerNotRestored", skip_serializing_if = "crate::serialization::is_false")]
+    owner_not_restored: bool,
+    /// When restoring, the file's original name could not be created, so it kept its name from the backup.
+    #[serde(
+        rename = "restoredWithStoredName",
+        skip_serializing_if = "crate::serialization::is_false"
+    )]
+    restored_with_stored_name: bool,
 }
 
 #[derive(Debug, Default, serde::Serialize)]
@@ -146,10 +665,297 @@ struct ApiRegistry {
 #[derive(Debug, Default, serde::Serialize)]
 struct ApiGame {
     decision: OperationStepDecision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    integrity: Option<IntegrityStatus>,
+    /// Everything was found in the Proton prefix of an uninstalled game.
+    #[serde(skip_serializing_if = "crate::serialization::is_false")]
+    uninstalled: bool,
+    /// Much less was found than in the latest backup, which had this many bytes.
+    #[serde(rename = "shrunkFromBytes", skip_serializing_if = "Option::is_none")]
+    shrunk_from_bytes: Option<u64>,
+    /// Groups of folders that look like alternative save locations.
+    #[serde(rename = "ambiguousLocations", skip_serializing_if = "Vec::is_empty")]
+    ambiguous_locations: Vec<Vec<ApiAmbiguousLocation>>,
     #[serde(serialize_with = "crate::serialization::ordered_map")]
     files: std::collections::HashMap<String, ApiFile>,
+    #[serde(rename = "missingFiles", skip_serializing_if = "Vec::is_empty")]
+    missing_files: Vec<String>,
     #[serde(serialize_with = "crate::serialization::ordered_map")]
     registry: std::collections::HashMap<String, ApiRegistry>,
+    /// When restoring, registry values that were left alone because they exist with a different type.
+    #[serde(rename = "registryTypeConflicts", skip_serializing_if = "Vec::is_empty")]
+    registry_type_conflicts: Vec<String>,
+    /// When restoring, registry keys of another system's user that could not be mapped onto a user here.
+    #[serde(rename = "unmappedRegistryKeys", skip_serializing_if = "Vec::is_empty")]
+    unmapped_registry_keys: Vec<String>,
+    /// When previewing a restore, where the files would go, grouped by folder.
+    #[serde(rename = "restoreTree", skip_serializing_if = "Option::is_none")]
+    restore_tree: Option<RestorePreviewTree>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<ApiTimings>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct ApiAmbiguousLocation {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<std::time::SystemTime>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct ApiTimings {
+    #[serde(rename = "scanMs")]
+    scan_ms: u64,
+    #[serde(rename = "copyMs")]
+    copy_ms: u64,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct ApiGameDiff {
+    #[serde(rename = "addedFiles", skip_serializing_if = "Vec::is_empty")]
+    added_files: Vec<String>,
+    #[serde(rename = "removedFiles", skip_serializing_if = "Vec::is_empty")]
+    removed_files: Vec<String>,
+    #[serde(rename = "resizedFiles", skip_serializing_if = "Vec::is_empty")]
+    resized_files: Vec<String>,
+    #[serde(rename = "modifiedFiles", skip_serializing_if = "Vec::is_empty")]
+    modified_files: Vec<String>,
+    #[serde(rename = "addedRegistry", skip_serializing_if = "Vec::is_empty")]
+    added_registry: Vec<String>,
+    #[serde(rename = "removedRegistry", skip_serializing_if = "Vec::is_empty")]
+    removed_registry: Vec<String>,
+    #[serde(rename = "changedBytes")]
+    changed_bytes: u64,
+}
+
+impl From<&GameDiff> for ApiGameDiff {
+    fn from(diff: &GameDiff) -> Self {
+        let render_paths =
+            |paths: &std::collections::HashSet<StrictPath>| itertools::sorted(paths).map(|x| x.render()).collect();
+        let render_keys = |keys: &std::collections::HashSet<String>| itertools::sorted(keys).cloned().collect();
+        Self {
+            added_files: render_paths(&diff.added_files),
+            removed_files: render_paths(&diff.removed_files),
+            resized_files: render_paths(&diff.resized_files),
+            modified_files: render_paths(&diff.modified_files),
+            added_registry: render_keys(&diff.added_registry_keys),
+            removed_registry: render_keys(&diff.removed_registry_keys),
+            changed_bytes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct JsonDiffOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<ApiErrors>,
+    #[serde(serialize_with = "crate::serialization::ordered_map")]
+    games: std::collections::HashMap<String, ApiGameDiff>,
+}
+
+/// Each entry is a game's name, its diff, and how much a new backup would copy
+/// (see `incremental_delta_bytes`).
+fn render_diff(translator: &Translator, diffs: &[(String, GameDiff, u64)], api: bool) -> String {
+    if api {
+        let mut output = JsonDiffOutput::default();
+        for (name, diff, changed_bytes) in diffs {
+            output.games.insert(
+                name.to_string(),
+                ApiGameDiff {
+                    changed_bytes: *changed_bytes,
+                    ..ApiGameDiff::from(diff)
+                },
+            );
+        }
+        return to_api_json("diff", &output);
+    }
+
+    diffs
+        .iter()
+        .map(|(name, diff, changed_bytes)| render_game_diff(translator, name, diff, *changed_bytes))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_game_diff(translator: &Translator, name: &str, diff: &GameDiff, changed_bytes: u64) -> String {
+    let mut parts = vec![translator.cli_diff_header(name)];
+    if diff.is_empty() {
+        parts.push(translator.cli_diff_no_changes());
+    }
+    for entry in itertools::sorted(&diff.added_files) {
+        parts.push(translator.cli_game_line_item_added(&entry.render()));
+    }
+    for entry in itertools::sorted(&diff.removed_files) {
+        parts.push(translator.cli_game_line_item_removed(&entry.render()));
+    }
+    for entry in itertools::sorted(&diff.resized_files) {
+        parts.push(translator.cli_game_line_item_resized(&entry.render()));
+    }
+    for entry in itertools::sorted(&diff.modified_files) {
+        parts.push(translator.cli_game_line_item_modified(&entry.render()));
+    }
+    for entry in itertools::sorted(&diff.added_registry_keys) {
+        parts.push(translator.cli_game_line_item_added(entry));
+    }
+    for entry in itertools::sorted(&diff.removed_registry_keys) {
+        parts.push(translator.cli_game_line_item_removed(entry));
+    }
+    if !diff.is_empty() {
+        parts.push(translator.cli_diff_changed_bytes(changed_bytes));
+    }
+    parts.join("\n")
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ManifestEntryOutput<'a> {
+    name: &'a str,
+    entry: &'a Game,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<Vec<ResolvedRoot>>,
+    #[serde(
+        rename = "skippedPlaceholders",
+        serialize_with = "crate::serialization::ordered_map",
+        skip_serializing_if = "std::collections::HashMap::is_empty"
+    )]
+    skipped_placeholders: std::collections::HashMap<String, Vec<&'static str>>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct ResolvedRoot {
+    path: String,
+    store: Store,
+    /// Keys are the file entries as written in the manifest.
+    #[serde(serialize_with = "crate::serialization::ordered_map")]
+    files: std::collections::HashMap<String, Vec<ResolvedPath>>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct ResolvedPath {
+    path: String,
+    exists: bool,
+}
+
+fn resolve_manifest_entry(
+    game: &Game,
+    name: &str,
+    roots: &[RootsConfig],
+    manifest_dir: &StrictPath,
+    scan_proton_prefixes: bool,
+) -> Vec<ResolvedRoot> {
+    let steam_id = game.steam.as_ref().and_then(|x| x.id);
+    let install_dirs = InstallDirCache::default();
+    roots
+        .iter()
+        .inspect(|root| install_dirs.load(root, &RealFileSystem))
+        .map(|root| ResolvedRoot {
+            path: root.path.render(),
+            store: root.store,
+            files: game
+                .files
+                .iter()
+                .flatten()
+                .map(|(raw_path, _)| {
+                    let mut paths: Vec<_> = resolve_file_entry(
+                        game,
+                        name,
+                        raw_path,
+                        root,
+                        &steam_id,
+                        manifest_dir,
+                        root.scan_proton_prefixes.unwrap_or(scan_proton_prefixes),
+                        &install_dirs,
+                    )
+                    .into_iter()
+                    .map(|candidate| ResolvedPath {
+                        exists: match glob::glob(&candidate.render()) {
+                            Ok(mut matches) => matches.any(|x| x.is_ok()),
+                            Err(_) => false,
+                        },
+                        path: candidate.render(),
+                    })
+                    .collect();
+                    paths.sort_by(|x, y| x.path.cmp(&y.path));
+                    (raw_path.to_string(), paths)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// For each file entry, the placeholders that are skipped on this system,
+/// which explains why those entries never resolve to anything.
+fn skipped_placeholders(game: &Game) -> std::collections::HashMap<String, Vec<&'static str>> {
+    game.files
+        .iter()
+        .flatten()
+        .filter_map(|(raw_path, _)| {
+            let skipped: Vec<_> = placeholder_applicability(raw_path)
+                .into_iter()
+                .filter(|(_, skipped)| *skipped)
+                .map(|(placeholder, _)| placeholder)
+                .collect();
+            if skipped.is_empty() {
+                None
+            } else {
+                Some((raw_path.to_string(), skipped))
+            }
+        })
+        .collect()
+}
+
+fn render_manifest_entry(name: &str, game: &Game, resolved: Option<Vec<ResolvedRoot>>, api: bool) -> String {
+    let output = ManifestEntryOutput {
+        name,
+        entry: game,
+        skipped_placeholders: match resolved {
+            Some(_) => skipped_placeholders(game),
+            None => Default::default(),
+        },
+        resolved,
+    };
+    if api {
+        to_api_json("manifest show", &output)
+    } else {
+        serde_yaml::to_string(&output).unwrap()
+    }
+}
+
+fn render_report(translator: &Translator, report: &BackupReport, location: &str, api: bool) -> String {
+    if api {
+        return to_api_json("report", &report);
+    }
+
+    let mut status = OperationStatus::default();
+    let mut parts = vec![];
+    for game in &report.games {
+        parts.push(translator.cli_report_line(&game.name, game.size_bytes, game.file_count));
+        status.total_games += 1;
+        status.total_bytes += game.size_bytes;
+    }
+    status.processed_games = status.total_games;
+    status.processed_bytes = status.total_bytes;
+    parts.join("\n") + "\n" + &translator.cli_summary(&status, location)
+}
+
+#[derive(Debug, Default)]
+struct IntegrityTally {
+    intact: usize,
+    broken: usize,
+    unknown: usize,
+}
+
+impl IntegrityTally {
+    fn add(&mut self, status: &IntegrityStatus) {
+        match status {
+            IntegrityStatus::Intact => self.intact += 1,
+            IntegrityStatus::Broken => self.broken += 1,
+            IntegrityStatus::Unknown => self.unknown += 1,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.intact + self.broken + self.unknown == 0
+    }
 }
 
 #[derive(Debug, Default, serde::Serialize)]
@@ -167,10 +973,21 @@ enum Reporter {
         translator: Translator,
         parts: Vec<String>,
         status: OperationStatus,
+        integrity: IntegrityTally,
+        /// These are used to shorten paths for display.
+        roots: Vec<RootsConfig>,
     },
     Json {
+        command: &'static str,
         output: JsonOutput,
     },
+    Table {
+        translator: Translator,
+        rows: Vec<TableRow>,
+        sort: SortKey,
+        /// Only restores know which files are new and which replace existing files.
+        count_changes: bool,
+    },
 }
 
 impl Reporter {
@@ -179,12 +996,24 @@ impl Reporter {
             translator,
             parts: vec![],
             status: Default::default(),
+            integrity: Default::default(),
+            roots: vec![],
         }
     }
 
-    fn json() -> Self {
-        Self::Json {
-            output: Default::default(),
+    fn table(translator: Translator, sort: Option<SortKey>, count_changes: bool) -> Self {
+        Self::Table {
+            translator,
+            rows: vec![],
+            sort: sort.unwrap_or(SortKey::Name),
+            count_changes,
+        }
+    }
+
+    fn json(command: &'static str) -> Self {
+        Self::Json {
+            command,
+            output: Default::default(),
         }
     }
 
@@ -214,6 +1043,50 @@ impl Reporter {
         }
     }
 
+    /// Attach where a previewed restore would put the game's files.
+    /// This must come after `add_game` for the same game.
+    fn add_restore_preview_tree(&mut self, name: &str, tree: RestorePreviewTree) {
+        if let Reporter::Json { output, .. } = self {
+            if let Some(game) = output.games.get_mut(name) {
+                game.restore_tree = Some(tree);
+            }
+        }
+    }
+
+    fn display_with_roots(&mut self, roots: &[RootsConfig]) {
+        if let Self::Standard {
+            roots: display_roots, ..
+        } = self
+        {
+            *display_roots = roots.to_vec();
+        }
+    }
+
+    fn track_roots(&mut self, roots: &[RootsConfig]) {
+        match self {
+            Self::Standard { status, .. } => status.track_roots(roots),
+            Self::Json { output, .. } => output.overall.track_roots(roots),
+            Self::Table { .. } => {}
+        }
+    }
+
+    /// Report how long each game and phase took.
+    fn track_timings(&mut self, manifest_update: Timing) {
+        match self {
+            Self::Standard { status, .. } => status.track_timings(manifest_update),
+            Self::Json { output, .. } => output.overall.track_timings(manifest_update),
+            Self::Table { .. } => {}
+        }
+    }
+
+    fn add_phase_timings(&mut self, scan: Timing, copy: Timing) {
+        match self {
+            Self::Standard { status, .. } => status.add_phase_timings(scan, copy),
+            Self::Json { output, .. } => output.overall.add_phase_timings(scan, copy),
+            Self::Table { .. } => {}
+        }
+    }
+
     fn add_game(
         &mut self,
         name: &str,
@@ -229,16 +1102,46 @@ impl Reporter {
                 parts,
                 status,
                 translator,
+                integrity,
+                roots,
             } => {
                 if !scan_info.found_anything() {
                     return true;
                 }
 
+                let title = if scan_info.backup_is_broken() {
+                    translator.game_list_entry_title_broken(&name)
+                } else if scan_info.shrunk_from_bytes.is_some() {
+                    translator.game_list_entry_title_shrunk(name)
+                } else if scan_info.uninstalled_prefix {
+                    translator.game_list_entry_title_uninstalled(name)
+                } else {
+                    name.to_string()
+                };
                 parts.push(translator.cli_game_header(
-                    &name,
+                    &title,
                     scan_info.sum_bytes(&Some(backup_info.to_owned())),
                     &decision,
                 ));
+                if let Some(previous_bytes) = scan_info.shrunk_from_bytes {
+                    parts.push(translator.cli_game_shrunk(previous_bytes));
+                }
+                for group in scan_info.ambiguous_locations() {
+                    let locations: Vec<_> = group
+                        .iter()
+                        .map(|x| (display_path(&x.path, roots), x.modified))
+                        .collect();
+                    parts.push(translator.cli_game_ambiguous_locations(&locations));
+                }
+                if !backup_info.overwritten_files.is_empty() {
+                    parts.push(
+                        translator
+                            .cli_game_overwrites(backup_info.overwritten_files.len(), scan_info.found_files.len()),
+                    );
+                }
+                if status.timings.is_some() {
+                    parts.push(translator.cli_game_timing(&scan_info.duration, &backup_info.duration));
+                }
                 for entry in itertools::sorted(&scan_info.found_files) {
                     let mut redirected_from = None;
                     let readable = if let Some(original_path) = &entry.original_path {
@@ -249,25 +1152,47 @@ impl Reporter {
                         entry.path.to_owned()
                     };
 
+                    let corrupted = match &scan_info.integrity {
+                        Some(x) => x.corrupted_files.contains(entry),
+                        None => false,
+                    };
                     if backup_info.failed_files.contains(entry) {
                         successful = false;
-                        parts.push(translator.cli_game_line_item_failed(&readable.render()));
+                    }
+                    let readable = display_path(&readable, roots);
+                    if corrupted {
+                        parts.push(translator.cli_game_line_item_corrupted(&readable));
+                    } else if backup_info.failed_files.contains(entry) {
+                        parts.push(translator.cli_game_line_item_failed(&readable));
+                    } else if backup_info.skipped_files.contains(entry) {
+                        parts.push(translator.cli_game_line_item_skipped(&readable));
+                    } else if backup_info.overwritten_files.contains(entry) {
+                        parts.push(translator.cli_game_line_item_overwritten(&readable));
                     } else {
-                        parts.push(translator.cli_game_line_item_successful(&readable.render()));
+                        parts.push(translator.cli_game_line_item_successful(&readable));
                     }
 
                     if let Some(redirected_from) = redirected_from {
-                        parts.push(translator.cli_game_line_item_redirected(&redirected_from.render()));
+                        parts.push(translator.cli_game_line_item_redirected(&display_path(&redirected_from, roots)));
+                    }
+                }
+                if let Some(scan_integrity) = &scan_info.integrity {
+                    for entry in itertools::sorted(&scan_integrity.missing_files) {
+                        parts.push(translator.cli_game_line_item_missing(&display_path(entry, roots)));
                     }
+                    integrity.add(&scan_integrity.status());
                 }
                 for entry in itertools::sorted(&scan_info.found_registry_keys) {
                     if backup_info.failed_registry.contains(entry) {
                         successful = false;
-                        parts.push(translator.cli_game_line_item_failed(entry));
+                        parts.push(translator.cli_game_line_item_failed(&escape_control_chars(entry)));
                     } else {
-                        parts.push(translator.cli_game_line_item_successful(entry));
+                        parts.push(translator.cli_game_line_item_successful(&escape_control_chars(entry)));
                     }
                 }
+                if !backup_info.successful() {
+                    parts.push(translator.cli_game_summary(&backup_info.summary()));
+                }
 
                 status.add_game(
                     &scan_info,
@@ -275,18 +1200,50 @@ impl Reporter {
                     decision == &OperationStepDecision::Processed,
                 );
             }
-            Self::Json { output } => {
+            Self::Json { output, .. } => {
                 if !scan_info.found_anything() {
                     return true;
                 }
 
                 let mut api_game = ApiGame::default();
                 api_game.decision = decision.clone();
+                api_game.uninstalled = scan_info.uninstalled_prefix;
+                api_game.shrunk_from_bytes = scan_info.shrunk_from_bytes;
+                api_game.ambiguous_locations = scan_info
+                    .ambiguous_locations()
+                    .into_iter()
+                    .map(|group| {
+                        group
+                            .into_iter()
+                            .map(|x| ApiAmbiguousLocation {
+                                path: x.path.render(),
+                                modified: x.modified,
+                            })
+                            .collect()
+                    })
+                    .collect();
+                if output.overall.timings.is_some() {
+                    api_game.timings = Some(ApiTimings {
+                        scan_ms: scan_info.duration.millis(),
+                        copy_ms: backup_info.duration.millis(),
+                    });
+                }
+                if let Some(integrity) = &scan_info.integrity {
+                    api_game.integrity = Some(integrity.status());
+                    api_game.missing_files = itertools::sorted(&integrity.missing_files)
+                        .map(|x| x.render())
+                        .collect();
+                }
 
                 for entry in itertools::sorted(&scan_info.found_files) {
                     let mut api_file = ApiFile::default();
                     api_file.bytes = entry.size;
                     api_file.failed = backup_info.failed_files.contains(entry);
+                    api_file.overwrites = backup_info.overwritten_files.contains(entry);
+                    api_file.skipped = backup_info.skipped_files.contains(entry);
+                    if let Some(integrity) = &scan_info.integrity {
+                        api_file.corrupted = integrity.corrupted_files.contains(entry);
+                    }
                     let readable = if let Some(original_path) = &entry.original_path {
                         let (target, original_target) = game_file_restoration_target(&original_path, &redirects);
                         api_file.original_path = original_target.map(|x| x.render());
@@ -297,6 +1254,8 @@ impl Reporter {
                     if api_file.failed {
                         successful = false;
                     }
+                    api_file.owner_not_restored = backup_info.owner_not_restored(entry);
+                    api_file.restored_with_stored_name = backup_info.restored_with_stored_names.contains(entry);
                     api_game.files.insert(readable.render(), api_file);
                 }
                 for entry in itertools::sorted(&scan_info.found_registry_keys) {
@@ -309,6 +1268,12 @@ impl Reporter {
                     }
                     api_game.registry.insert(entry.to_string(), api_registry);
                 }
+                api_game.registry_type_conflicts = itertools::sorted(&backup_info.registry_type_conflicts)
+                    .cloned()
+                    .collect();
+                api_game.unmapped_registry_keys = itertools::sorted(&backup_info.unmapped_registry_keys)
+                    .cloned()
+                    .collect();
 
                 output.games.insert(name.to_string(), api_game);
                 output.overall.add_game(
@@ -317,6 +1282,35 @@ impl Reporter {
                     decision == &OperationStepDecision::Processed,
                 );
             }
+            Self::Table {
+                rows, count_changes, ..
+            } => {
+                if !scan_info.found_anything() {
+                    return true;
+                }
+
+                successful = backup_info.failed_files.is_empty() && backup_info.failed_registry.is_empty();
+                let files = scan_info.found_files.len() - backup_info.skipped_files.len();
+                let changed = backup_info.overwritten_files.len();
+                rows.push(TableRow {
+                    name: name.to_string(),
+                    files,
+                    bytes: scan_info.sum_bytes(&Some(backup_info.to_owned())),
+                    new_files: if *count_changes {
+                        Some(files.saturating_sub(changed))
+                    } else {
+                        None
+                    },
+                    changed_files: if *count_changes { Some(changed) } else { None },
+                    status: if decision != &OperationStepDecision::Processed {
+                        RowStatus::Ignored
+                    } else if !successful {
+                        RowStatus::Failed
+                    } else {
+                        RowStatus::Ok
+                    },
+                });
+            }
         }
 
         if !successful {
@@ -331,8 +1325,36 @@ impl Reporter {
                 parts,
                 status,
                 translator,
-            } => parts.join("\n") + "\n" + &translator.cli_summary(&status, &path),
-            Self::Json { output } => serde_json::to_string_pretty(&output).unwrap(),
+                integrity,
+                ..
+            } => {
+                let mut rendered = parts.join("\n") + "\n" + &translator.cli_summary(&status, &path.render());
+                if !integrity.is_empty() {
+                    rendered +=
+                        &translator.cli_integrity_summary(integrity.intact, integrity.broken, integrity.unknown);
+                }
+                if !status.roots.is_empty() {
+                    rendered += &translator.cli_root_summary(&status.roots);
+                }
+                if let Some(timings) = &status.timings {
+                    rendered += &translator.cli_timing_summary(timings);
+                }
+                rendered
+            }
+            Self::Json { command, output } => to_api_json(command, output),
+            Self::Table {
+                translator, rows, sort, ..
+            } => {
+                let mut rows = rows.clone();
+                sort_rows(&mut rows, *sort);
+                table::render(
+                    translator,
+                    &rows,
+                    &path.render(),
+                    table::terminal_width(),
+                    table::use_color(),
+                )
+            }
         }
     }
 
@@ -349,8 +1371,67 @@ impl Reporter {
     }
 }
 
+/// Collects the summary of a run for `--metrics-out`, including the phase timings,
+/// which the reporter only tracks with `--verbose`.
+struct RunMetricsTracker {
+    status: OperationStatus,
+    failed_games: usize,
+}
+
+impl RunMetricsTracker {
+    fn new(manifest_update: Timing) -> Self {
+        let mut status = OperationStatus::default();
+        status.track_timings(manifest_update);
+        Self {
+            status,
+            failed_games: 0,
+        }
+    }
+
+    fn add_game(
+        &mut self,
+        scan_info: &ScanInfo,
+        backup_info: &BackupInfo,
+        decision: &OperationStepDecision,
+        successful: bool,
+    ) {
+        if !scan_info.found_anything() {
+            return;
+        }
+        self.status.add_game(
+            scan_info,
+            &Some(backup_info.clone()),
+            decision == &OperationStepDecision::Processed,
+        );
+        if !successful {
+            self.failed_games += 1;
+        }
+    }
+
+    fn add_phase_timings(&mut self, scan: Timing, copy: Timing) {
+        self.status.add_phase_timings(scan, copy);
+    }
+
+    fn write(&self, command: &str, started: std::time::Instant, path: &StrictPath) -> Result<(), Error> {
+        RunMetrics {
+            command,
+            finished: std::time::SystemTime::now(),
+            duration: started.elapsed(),
+            status: &self.status,
+            failed_games: self.failed_games,
+        }
+        .write(path)
+    }
+}
+
 pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
     let translator = Translator::default();
+    if let Subcommand::SelfCheck { redact } = sub {
+        // This should still work when the config is broken, since that's worth reporting.
+        let config = Config::load().map_err(|e| translator.handle_error(&e));
+        println!("{}", SelfCheck::gather(&config).render(redact));
+        return Ok(());
+    }
     let mut config = Config::load()?;
     let mut failed = false;
 
@@ -359,20 +1440,36 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             preview,
             path,
             force,
+            overwrite,
+            yes,
             merge,
             no_merge,
             update,
             try_update,
+            no_proton_prefixes,
+            follow_junctions,
+            respect_nobackup_files,
+            max_total_size,
+            use_vss,
+            metrics_out,
+            record_scan,
             by_steam_id,
             api,
+            output,
+            sort,
+            verbose,
+            retry_failed,
             games,
         } => {
             let mut reporter = if api {
-                Reporter::json()
+                Reporter::json("backup")
+            } else if output == Some(OutputFormat::Table) {
+                Reporter::table(translator, sort, false)
             } else {
                 Reporter::standard(translator)
             };
 
+            let manifest_started = std::time::Instant::now();
             let manifest = if try_update {
                 match Manifest::load(&mut config, true) {
                     Ok(x) => x,
@@ -383,35 +1480,59 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             };
 
             let backup_dir = match path {
-                None => config.backup.path.clone(),
+                None => config.backup.target(),
                 Some(p) => p,
             };
-            let roots = &config.roots;
+            let manifest_timing = Timing::since(manifest_started);
+            if verbose {
+                reporter.track_timings(manifest_timing);
+            }
+            let roots = config.scan_roots();
+            reporter.track_roots(&roots);
+            reporter.display_with_roots(&config.roots);
+
+            if force {
+                eprintln!("{}", translator.cli_deprecated_flag("--force", "--overwrite --yes"));
+            }
+            for (outer, nested) in config.overlapping_roots() {
+                eprintln!(
+                    "{}",
+                    translator.cli_nested_root(&outer, &nested, config.skips_nested_root(&outer, &nested))
+                );
+            }
+            let overwrite = overwrite || force;
+            let yes = yes || force;
+            // Retrying must not clear out the games that already succeeded.
+            let merge = if merge || retry_failed {
+                true
+            } else if no_merge {
+                false
+            } else {
+                config.backup.merge
+            };
 
+            // Read these before the target might be cleared out.
+            let recorded_sizes = BackupLayout::new(backup_dir.clone()).recorded_sizes();
             if !preview {
-                if !force && !merge && backup_dir.exists() {
-                    return Err(crate::prelude::Error::CliBackupTargetExists { path: backup_dir });
-                } else if let Err(e) = prepare_backup_target(
-                    &backup_dir,
-                    if merge {
-                        true
-                    } else if no_merge {
-                        false
-                    } else {
-                        config.backup.merge
-                    },
-                ) {
-                    return Err(e);
+                let recognized = is_recognized_backup_target(&backup_dir);
+                if decide_backup_target(&backup_dir, recognized, merge, overwrite)? == BackupTargetAction::Replace
+                    && !yes
+                    && !request_confirmation(&translator.cli_confirm_backup_replacement(&backup_dir))?
+                {
+                    return Ok(());
                 }
+                prepare_backup_target(&backup_dir, merge)?;
             }
 
             let steam_ids_to_names = &manifest.map_steam_ids_to_names();
-            let mut all_games = manifest.0;
-            for custom_game in &config.custom_games {
-                all_games.insert(custom_game.name.clone(), Game::from(custom_game.to_owned()));
-            }
+            let is_known_game =
+                |game: &str| manifest.contains_game(game) || config.custom_games.iter().any(|x| x.name == game);
+            let games = if retry_failed {
+                RunSummary::load_games_to_retry(&games_to_retry_file())
+            } else {
+                games
+            };
 
-            let games_specified = !games.is_empty();
             let mut invalid_games: Vec<_> = games
                 .iter()
                 .filter_map(|game| {
@@ -426,7 +1547,7 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                             }
                             Err(_) => Some(game.to_owned()),
                         }
-                    } else if !all_games.contains_key(game) {
+                    } else if !is_known_game(game) {
                         Some(game.to_owned())
                     } else {
                         None
@@ -440,94 +1561,125 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
                 return Err(crate::prelude::Error::CliUnrecognizedGames { games: invalid_games });
             }
 
-            let mut subjects: Vec<_> = if !&games.is_empty() {
-                if by_steam_id {
+            let selection = if retry_failed {
+                GameSelection::Only(games)
+            } else if games.is_empty() {
+                GameSelection::All
+            } else if by_steam_id {
+                GameSelection::Only(
                     games
                         .iter()
-                        .map(|game| &steam_ids_to_names[&game.parse::<u32>().unwrap()])
-                        .cloned()
-                        .collect()
-                } else {
-                    games
-                }
+                        .map(|game| steam_ids_to_names[&game.parse::<u32>().unwrap()].clone())
+                        .collect(),
+                )
             } else {
-                all_games.keys().cloned().collect()
+                GameSelection::Only(games)
             };
-            subjects.sort();
-
-            let layout = BackupLayout::new(backup_dir.clone());
-            let filter = config.backup.filter.clone();
-
-            let info: Vec<_> = subjects
-                .par_iter()
-                .progress_count(subjects.len() as u64)
-                .map(|name| {
-                    let game = &all_games[name];
-                    let steam_id = &game.steam.clone().unwrap_or(SteamMetadata { id: None }).id;
+            let options = BackupRunOptions {
+                preview,
+                path: Some(backup_dir.clone()),
+                skip_proton_prefixes: no_proton_prefixes,
+                follow_junctions,
+                respect_nobackup_files,
+                max_total_size,
+                record_scan: record_scan.is_some(),
+                recorded_sizes: Some(recorded_sizes),
+                use_vss,
+            };
+            let progress = indicatif::ProgressBar::new(0);
+            let events = |event: &BackupEvent| match event {
+                BackupEvent::Started { total_games } => progress.set_length(*total_games as u64),
+                BackupEvent::GameFinished => progress.inc(1),
+            };
+            let cancel = CancelToken::default();
+            cancel.cancel_on_interrupt();
+            let summary = run_backup(&config, &manifest, &selection, &options, &events, &cancel);
+            progress.finish_and_clear();
+            if !preview && summary.save_games_to_retry(&games_to_retry_file()).is_err() {
+                eprintln!(
+                    "{}",
+                    translator.cli_unable_to_save_games_to_retry(&games_to_retry_file())
+                );
+            }
 
-                    let scan_info = scan_game_for_backup(
-                        &game,
-                        &name,
-                        &roots,
-                        &StrictPath::from_std_path_buf(&app_dir()),
-                        &steam_id,
-                        &filter,
+            reporter.add_phase_timings(summary.scan_duration, summary.copy_duration);
+            let mut metrics = RunMetricsTracker::new(manifest_timing);
+            metrics.add_phase_timings(summary.scan_duration, summary.copy_duration);
+            for game in &summary.games {
+                if !game.scan_info.unsafe_paths.is_empty() {
+                    eprintln!(
+                        "{}",
+                        translator.cli_unsafe_paths_skipped(&game.name, &game.scan_info.unsafe_paths)
                     );
-                    let ignored = !&config.is_game_enabled_for_backup(&name) && !games_specified;
-                    let decision = if ignored {
-                        OperationStepDecision::Ignored
-                    } else {
-                        OperationStepDecision::Processed
-                    };
-                    let backup_info = if preview || ignored {
-                        crate::prelude::BackupInfo::default()
-                    } else {
-                        back_up_game(&scan_info, &name, &layout)
-                    };
-                    (name, scan_info, backup_info, decision)
-                })
-                .collect();
-
-            for (name, scan_info, backup_info, decision) in info {
-                if !reporter.add_game(&name, &scan_info, &backup_info, &decision, &[]) {
+                }
+                let successful = reporter.add_game(&game.name, &game.scan_info, &game.backup_info, &game.decision, &[]);
+                metrics.add_game(&game.scan_info, &game.backup_info, &game.decision, successful);
+                if !successful {
                     failed = true;
                 }
             }
             reporter.print(&backup_dir);
+            if let Some(metrics_out) = &metrics_out {
+                metrics.write("backup", manifest_started, metrics_out)?;
+            }
+            if let (Some(path), Some(scan)) = (&record_scan, &summary.recorded_scan) {
+                scan.save(path)?;
+            }
         }
         Subcommand::Restore {
             preview,
             path,
             force,
+            yes,
+            ignore_integrity,
+            allow_config_overwrite,
+            metrics_out,
             by_steam_id,
             api,
+            output,
+            sort,
+            verbose,
             games,
         } => {
             let mut reporter = if api {
-                Reporter::json()
+                Reporter::json("restore")
+            } else if output == Some(OutputFormat::Table) {
+                Reporter::table(translator, sort, true)
             } else {
                 Reporter::standard(translator)
             };
 
+            let manifest_started = std::time::Instant::now();
             let manifest = Manifest::load(&mut config, false)?;
+            let manifest_timing = Timing::since(manifest_started);
+            if verbose {
+                reporter.track_timings(manifest_timing);
+            }
+            reporter.display_with_roots(&config.roots);
 
             let restore_dir = match path {
-                None => config.restore.path.clone(),
+                None => config.restore.source(),
                 Some(p) => p,
             };
 
-            if !preview && !force {
-                match dialoguer::Confirm::new()
-                    .with_prompt(translator.cli_confirm_restoration(&restore_dir))
-                    .interact()
-                {
-                    Ok(true) => (),
-                    Ok(false) => return Ok(()),
-                    Err(_) => return Err(Error::CliUnableToRequestConfirmation),
-                }
+            if force {
+                eprintln!(
+                    "{}",
+                    translator.cli_deprecated_flag("--force", "--yes --ignore-integrity")
+                );
+            }
+            let yes = yes || force;
+            let ignore_integrity = ignore_integrity || force;
+
+            if !preview && !yes && !request_confirmation(&translator.cli_confirm_restoration(&restore_dir))? {
+                return Ok(());
             }
 
-            let layout = BackupLayout::new(restore_dir.clone());
+            let layout = BackupLayout {
+                roots: config.roots.clone(),
+                include_screenshots: config.restore.include_screenshots,
+                ..BackupLayout::new(restore_dir.clone())
+            };
 
             let steam_ids_to_names = &manifest.map_steam_ids_to_names();
             let restorable_names: Vec<_> = layout.mapping.games.keys().collect();
@@ -581,66 +1733,534 @@ pub fn run_cli(sub: Subcommand) -> Result<(), Error> {
             };
             subjects.sort();
 
-            let info: Vec<_> = subjects
+            let scan_started = std::time::Instant::now();
+            let mut scans: Vec<_> = subjects
                 .par_iter()
-                .progress_count(subjects.len() as u64)
                 .map(|name| {
-                    let scan_info = scan_game_for_restoration(&name, &layout);
+                    let mut scan_info = scan_game_for_restoration(&name, &layout);
+                    scan_info.exclude_locations(&config.restore.excluded_locations_for(&name));
                     let ignored = !&config.is_game_enabled_for_restore(&name) && !games_specified;
                     let decision = if ignored {
                         OperationStepDecision::Ignored
                     } else {
                         OperationStepDecision::Processed
                     };
-                    let restore_info = if preview || ignored {
-                        crate::prelude::BackupInfo::default()
-                    } else {
-                        restore_game(&scan_info, &config.get_redirects())
-                    };
-                    (name, scan_info, restore_info, decision)
+                    (name, scan_info, decision)
                 })
                 .collect();
+            let scan_duration = Timing::since(scan_started);
+
+            let mut selected: Vec<_> = scans
+                .iter()
+                .filter(|(_, _, decision)| decision == &OperationStepDecision::Processed)
+                .map(|(_, scan_info, _)| scan_info.clone())
+                .collect();
+            let conflicts = find_restore_conflicts(&selected, &config.get_redirects());
+            if !conflicts.is_empty() {
+                if preview || yes {
+                    for conflict in &conflicts {
+                        eprintln!("{}", render_restore_conflict(&translator, conflict));
+                    }
+                } else {
+                    let mut choices = vec![];
+                    for conflict in &conflicts {
+                        choices.push(request_restore_conflict_choice(&translator, conflict)?);
+                    }
+                    resolve_restore_conflicts(&mut selected, &conflicts, &choices);
+                    for (_, scan_info, _) in scans.iter_mut() {
+                        if let Some(resolved) = selected.iter().find(|x| x.game_name == scan_info.game_name) {
+                            scan_info.found_files = resolved.found_files.clone();
+                        }
+                    }
+                }
+            }
+
+            let restore_options = RestoreOptions {
+                ignore_integrity,
+                allow_unsafe_paths: config.allow_unsafe_paths,
+                allow_config_overwrite,
+            };
+            let copy_started = std::time::Instant::now();
+            let info: Vec<_> = if preview {
+                scans
+                    .par_iter()
+                    .progress_count(scans.len() as u64)
+                    .map(|(name, scan_info, decision)| {
+                        let restore_info = if decision == &OperationStepDecision::Ignored {
+                            crate::prelude::BackupInfo::default()
+                        } else {
+                            preview_restore_game(
+                                scan_info,
+                                &config.get_redirects(),
+                                &config.restore.mode,
+                                &restore_options,
+                            )
+                        };
+                        (name, scan_info, restore_info, decision)
+                    })
+                    .collect()
+            } else {
+                let processed: Vec<_> = scans
+                    .iter()
+                    .filter(|(_, _, decision)| decision == &OperationStepDecision::Processed)
+                    .map(|(_, scan_info, _)| scan_info.clone())
+                    .collect();
+                let progress = indicatif::ProgressBar::new(processed.len() as u64);
+                let mut restored = restore_games(
+                    &processed,
+                    &config.get_redirects(),
+                    &config.restore.mode,
+                    &restore_options,
+                    &RetryPolicy::default(),
+                    if config.restore.preserve_ownership {
+                        Some(&change_file_owner)
+                    } else {
+                        None
+                    },
+                    &|| progress.inc(1),
+                );
+                progress.finish_and_clear();
+                scans
+                    .iter()
+                    .map(|(name, scan_info, decision)| {
+                        let restore_info = restored.remove(&scan_info.game_name).unwrap_or_default();
+                        (name, scan_info, restore_info, decision)
+                    })
+                    .collect()
+            };
+            let copy_duration = Timing::since(copy_started);
 
+            reporter.add_phase_timings(scan_duration, copy_duration);
+            let mut metrics = RunMetricsTracker::new(manifest_timing);
+            metrics.add_phase_timings(scan_duration, copy_duration);
             for (name, scan_info, backup_info, decision) in info {
-                if !reporter.add_game(&name, &scan_info, &backup_info, &decision, &config.get_redirects()) {
+                if !backup_info.restored_with_stored_names.is_empty() {
+                    eprintln!(
+                        "{}",
+                        translator.cli_restored_with_stored_names(name, &backup_info.restored_with_stored_names)
+                    );
+                }
+                if !backup_info.registry_type_conflicts.is_empty() {
+                    eprintln!(
+                        "{}",
+                        translator.cli_registry_type_conflicts(name, &backup_info.registry_type_conflicts)
+                    );
+                }
+                if !backup_info.unmapped_registry_keys.is_empty() {
+                    eprintln!(
+                        "{}",
+                        translator.cli_unmapped_registry_keys(name, &backup_info.unmapped_registry_keys)
+                    );
+                }
+                if !backup_info.unrestored_owners.is_empty() {
+                    eprintln!(
+                        "{}",
+                        translator.cli_unrestored_owners(name, &backup_info.unrestored_owners)
+                    );
+                }
+                let successful = reporter.add_game(name, scan_info, &backup_info, decision, &config.get_redirects());
+                if api && preview && decision == &OperationStepDecision::Processed {
+                    reporter.add_restore_preview_tree(name, restore_preview_tree(scan_info, &config.get_redirects()));
+                }
+                metrics.add_game(scan_info, &backup_info, decision, successful);
+                if !successful {
                     failed = true;
                 }
             }
             reporter.print(&restore_dir);
+            if let Some(metrics_out) = &metrics_out {
+                metrics.write("restore", manifest_started, metrics_out)?;
+            }
         }
-    }
+        Subcommand::Diff {
+            path,
+            by_steam_id,
+            api,
+            game,
+        } => {
+            let manifest = Manifest::load(&mut config, false)?;
 
-    if failed {
-        Err(crate::prelude::Error::SomeEntriesFailed)
-    } else {
-        Ok(())
-    }
-}
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+            let layout = BackupLayout {
+                roots: config.roots.clone(),
+                ..BackupLayout::new(backup_dir)
+            };
+            let mut diffs = vec![];
+            match game {
+                None => {
+                    let mut scans: Vec<_> = scan_all_games(&manifest, &config).into_iter().collect();
+                    scans.sort_by(|x, y| x.0.cmp(&y.0));
+                    for (name, live) in scans {
+                        let backup = scan_game_for_restoration(&name, &layout);
+                        if !live.found_anything() && !backup.found_anything() {
+                            continue;
+                        }
+                        let diff = diff_game(&live, &backup, &layout);
+                        let changed_bytes = incremental_delta_bytes(&live, &diff);
+                        diffs.push((name, diff, changed_bytes));
+                    }
+                }
+                Some(game) => {
+                    let steam_ids_to_names = &manifest.map_steam_ids_to_names();
+                    let all_games = manifest.with_custom_games(&config);
 
-    fn s(text: &str) -> String {
-        text.to_string()
-    }
+                    let name = if by_steam_id {
+                        match game.parse::<u32>().ok().and_then(|id| steam_ids_to_names.get(&id)) {
+                            Some(x) => x.to_owned(),
+                            None => game.to_owned(),
+                        }
+                    } else {
+                        game.to_owned()
+                    };
+                    if !all_games.contains_key(&name) {
+                        if api {
+                            let output = JsonDiffOutput {
+                                errors: Some(ApiErrors {
+                                    unknown_games: Some(vec![game.to_owned()]),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            };
+                            println!("{}", to_api_json("diff", &output));
+                        }
+                        return Err(crate::prelude::Error::CliUnrecognizedGames { games: vec![game] });
+                    }
 
-    mod parser {
-        use super::*;
+                    let steam_id = &all_games[&name].steam.clone().unwrap_or(SteamMetadata { id: None }).id;
+                    let live = scan_game_for_backup_with_roots_filter(
+                        &all_games[&name],
+                        &name,
+                        &config.scan_roots(),
+                        |root| all_games[&name].is_compatible_with_store(root.store),
+                        &StrictPath::from_std_path_buf(&app_dir()),
+                        steam_id,
+                        &config.backup.filter,
+                        &ScanOptions {
+                            allow_unsafe_paths: config.allow_unsafe_paths,
+                            follow_junctions: config.backup.follow_junctions,
+                            respect_nobackup_files: config.backup.respect_nobackup_files,
+                            skip_proton_prefixes: !config.backup.scan_proton_prefixes,
+                            skip_uninstalled_prefixes: !config.backup.include_uninstalled_prefixes,
+                            ..Default::default()
+                        },
+                        &InstallDirCache::default(),
+                    );
+                    let backup = scan_game_for_restoration(&name, &layout);
+                    let diff = diff_game(&live, &backup, &layout);
+                    let changed_bytes = incremental_delta_bytes(&live, &diff);
+                    diffs.push((name, diff, changed_bytes));
+                }
+            }
 
-        fn check_args(args: &[&str], expected: Cli) {
-            assert_eq!(expected, Cli::from_clap(&Cli::clap().get_matches_from(args)));
+            println!("{}", render_diff(&translator, &diffs, api));
         }
-
-        fn check_args_err(args: &[&str], error: structopt::clap::ErrorKind) {
-            let result = Cli::clap().get_matches_from_safe(args);
-            assert!(result.is_err());
-            assert_eq!(error, result.unwrap_err().kind);
+        Subcommand::Report {
+            path,
+            api,
+            redact_home,
+            older_than,
+        } => {
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let layout = BackupLayout::new(backup_dir.clone());
+            let mut report = layout.generate_report();
+            if let Some(days) = older_than {
+                let stale = layout.games_older_than(days);
+                report.games.retain(|x| stale.contains(&x.name));
+            }
+            let location = if redact_home {
+                report.redact_home();
+                display_path(&backup_dir, &[])
+            } else {
+                backup_dir.render()
+            };
+            println!("{}", render_report(&translator, &report, &location, api));
+        }
+        Subcommand::Verify { path } => {
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let report = BackupLayout::new(backup_dir).integrity_check();
+            let mut corrupt_files = 0;
+            for (game, paths) in &report.corrupt_games {
+                println!("{}", translator.cli_verify_corrupt_game(game));
+                for path in paths {
+                    println!("{}", translator.cli_game_line_item_failed(&path.render()));
+                    corrupt_files += 1;
+                }
+            }
+            for folder in &report.missing_mapping {
+                println!("{}", translator.cli_verify_missing_mapping(folder));
+            }
+            println!(
+                "{}",
+                translator.cli_verify_summary(
+                    report.ok_games.len(),
+                    report.corrupt_games.len(),
+                    corrupt_files,
+                    report.missing_mapping.len()
+                )
+            );
+            if corrupt_files > 0 {
+                failed = true;
+            }
         }
+        Subcommand::Prune {
+            path,
+            older_than,
+            min_free_space_mib,
+            yes,
+        } => {
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let min_free_space_mib = match min_free_space_mib {
+                Some(None) => Some(config.backup.min_free_space_mib.ok_or(Error::CliPruneWithoutPolicy)?),
+                Some(Some(x)) => Some(x),
+                None => None,
+            };
+            let policy = PrunePolicy {
+                older_than_days: older_than,
+                min_free_bytes: min_free_space_mib.map(|x| x * 1024 * 1024),
+            };
+            if policy == PrunePolicy::default() {
+                return Err(Error::CliPruneWithoutPolicy);
+            }
+            if !yes && !request_confirmation(&translator.cli_confirm_prune(&backup_dir))? {
+                return Ok(());
+            }
 
-        #[test]
-        fn accepts_cli_without_arguments() {
-            check_args(&["ludusavi"], Cli { sub: None });
+            let report = BackupLayout::new(backup_dir.clone()).prune(&policy, || free_space(&backup_dir));
+            for (game, folder) in &report.removed_backups {
+                println!("{}", translator.cli_prune_removed_backup(game, folder));
+            }
+            println!(
+                "{}",
+                translator.cli_prune_summary(report.removed_backups.len(), report.reclaimed_bytes)
+            );
+        }
+        Subcommand::Compact { path, preview, yes } => {
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let manifest = Manifest::load(&mut config, false)?;
+            let is_known_game =
+                |game: &str| manifest.contains_game(game) || config.custom_games.iter().any(|x| x.name == game);
+            let layout = BackupLayout::new(backup_dir.clone());
+
+            let planned = layout.compact(is_known_game, true);
+            for (game, folder) in &planned.removed_games {
+                println!("{}", translator.cli_compact_removed_game(game, folder));
+            }
+            for folder in &planned.removed_folders {
+                println!("{}", translator.cli_compact_removed_folder(folder));
+            }
+            let planned_folders = planned.removed_games.len() + planned.removed_folders.len();
+            if preview || planned_folders == 0 {
+                println!(
+                    "{}",
+                    translator.cli_compact_summary(planned_folders, planned.reclaimed_bytes, preview)
+                );
+                return Ok(());
+            }
+            if !yes && !request_confirmation(&translator.cli_confirm_compact(&backup_dir))? {
+                return Ok(());
+            }
+
+            let report = layout.compact(is_known_game, false);
+            println!(
+                "{}",
+                translator.cli_compact_summary(
+                    report.removed_games.len() + report.removed_folders.len(),
+                    report.reclaimed_bytes,
+                    false
+                )
+            );
+        }
+        Subcommand::ExportBackup { path, archive } => {
+            let backup_dir = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let layout = BackupLayout::new(backup_dir);
+            if archive.raw() == "-" {
+                // A ZIP archive can't be written without seeking, so it's spooled to a file first.
+                let spool = StrictPath::from_std_path_buf(
+                    &std::env::temp_dir().join(format!("ludusavi-export-{}.zip", std::process::id())),
+                );
+                let copied = layout.zip_export(&spool).and_then(|_| {
+                    let stdout = std::io::stdout();
+                    let mut writer = stdout.lock();
+                    std::fs::File::open(spool.interpret())
+                        .and_then(|mut file| std::io::copy(&mut file, &mut writer))
+                        .and_then(|_| std::io::Write::flush(&mut writer))
+                        .map_err(|_| Error::UnableToExportArchive { path: archive })
+                });
+                let _ = spool.remove();
+                copied?;
+            } else {
+                let bytes = layout.zip_export(&archive)?;
+                println!("{}", translator.cli_export_summary(&archive, bytes));
+            }
+        }
+        Subcommand::ExportProfile { profile } => {
+            config.export_profile().save(&profile)?;
+            println!("{}", translator.cli_export_profile_summary(&profile));
+        }
+        Subcommand::ImportProfile { profile } => {
+            config.import_profile(&PortableProfile::load(&profile)?);
+            config.save();
+            println!("{}", translator.cli_import_profile_summary(&profile));
+        }
+        #[cfg(feature = "android")]
+        Subcommand::PullAndroid { serial, package, dest } => {
+            let files = crate::android::pull_android_saves(&package, serial.as_deref(), &dest)?;
+            println!("{}", translator.cli_android_pull_summary(files.len(), &dest));
+        }
+        Subcommand::ImportBackup { path, archive } => {
+            let target = match path {
+                None => config.backup.target(),
+                Some(p) => p,
+            };
+            let layout = BackupLayout::zip_import(&archive, &target)?;
+            println!("{}", translator.cli_import_summary(layout.mapping.games.len(), &target));
+        }
+        Subcommand::Open { path, restore, game } => {
+            let backup_dir = match (path, restore) {
+                (Some(p), _) => p,
+                (None, true) => config.restore.source(),
+                (None, false) => config.backup.target(),
+            };
+            open_game_backup_dir(&backup_dir, &game)?;
+        }
+        Subcommand::Manifest {
+            sub: ManifestSubcommand::Show { resolved, api, game },
+        } => {
+            let manifest = Manifest::load(&mut config, false)?;
+            let all_games = manifest.with_custom_games(&config);
+
+            let entry = match all_games.get(&game) {
+                Some(x) => x,
+                None => {
+                    if api {
+                        let output = JsonDiffOutput {
+                            errors: Some(ApiErrors {
+                                unknown_games: Some(vec![game.to_owned()]),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        };
+                        println!("{}", to_api_json("manifest show", &output));
+                    }
+                    return Err(crate::prelude::Error::CliUnrecognizedGames { games: vec![game] });
+                }
+            };
+            let resolved = if resolved {
+                Some(resolve_manifest_entry(
+                    entry,
+                    &game,
+                    &config.scan_roots(),
+                    &StrictPath::from_std_path_buf(&app_dir()),
+                    config.backup.scan_proton_prefixes,
+                ))
+            } else {
+                None
+            };
+
+            println!("{}", render_manifest_entry(&game, entry, resolved, api));
+        }
+        Subcommand::Schema => {
+            println!("{}", serde_json::to_string_pretty(&api_schema()).unwrap());
+        }
+        // This is handled before loading the config.
+        Subcommand::SelfCheck { .. } => {}
+    }
+
+    if failed {
+        Err(crate::prelude::Error::SomeEntriesFailed)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    mod parser {
+        use super::*;
+
+        fn check_args(args: &[&str], expected: Cli) {
+            assert_eq!(expected, Cli::from_clap(&Cli::clap().get_matches_from(args)));
+        }
+
+        fn check_args_err(args: &[&str], error: structopt::clap::ErrorKind) {
+            let result = Cli::clap().get_matches_from_safe(args);
+            assert!(result.is_err());
+            assert_eq!(error, result.unwrap_err().kind);
+        }
+
+        #[test]
+        fn accepts_cli_without_arguments() {
+            check_args(
+                &["ludusavi"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: None,
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_gui_with_focused_game() {
+            check_args(
+                &["ludusavi", "--gui", "--game", "foo", "--screen", "restore"],
+                Cli {
+                    gui: true,
+                    game: Some(s("foo")),
+                    screen: Some(GuiScreen::Restore),
+                    sub: None,
+                },
+            );
+        }
+
+        #[test]
+        fn rejects_cli_gui_focused_game_without_gui_flag() {
+            check_args_err(
+                &["ludusavi", "--game", "foo"],
+                structopt::clap::ErrorKind::MissingRequiredArgument,
+            );
+        }
+
+        #[test]
+        fn rejects_cli_gui_with_unknown_screen() {
+            check_args_err(
+                &["ludusavi", "--gui", "--screen", "other"],
+                structopt::clap::ErrorKind::InvalidValue,
+            );
+        }
+
+        #[test]
+        fn rejects_cli_gui_with_subcommand() {
+            check_args_err(
+                &["ludusavi", "--gui", "backup"],
+                structopt::clap::ErrorKind::UnknownArgument,
+            );
         }
 
         #[test]
@@ -648,16 +2268,32 @@ mod tests {
             check_args(
                 &["ludusavi", "backup"],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Backup {
                         preview: false,
                         path: None,
                         force: false,
+                        overwrite: false,
+                        yes: false,
                         merge: false,
                         no_merge: false,
                         update: false,
                         try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
                         by_steam_id: false,
                         api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: false,
                         games: vec![],
                     }),
                 },
@@ -673,25 +2309,53 @@ mod tests {
                     "--preview",
                     "--path",
                     "tests/backup",
-                    "--force",
+                    "--overwrite",
+                    "--yes",
                     "--merge",
                     "--update",
+                    "--no-proton-prefixes",
+                    "--follow-junctions",
+                    "--respect-nobackup-files",
+                    "--max-total-size",
+                    "1000",
+                    "--use-vss",
+                    "--metrics-out",
+                    "ludusavi.prom",
+                    "--record-scan",
+                    "scan.yaml",
                     "--by-steam-id",
                     "--api",
+                    "--verbose",
                     "game1",
                     "game2",
                 ],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Backup {
                         preview: true,
                         path: Some(StrictPath::new(s("tests/backup"))),
-                        force: true,
+                        force: false,
+                        overwrite: true,
+                        yes: true,
                         merge: true,
                         no_merge: false,
                         update: true,
                         try_update: false,
+                        no_proton_prefixes: true,
+                        follow_junctions: true,
+                        respect_nobackup_files: true,
+                        max_total_size: Some(1000),
+                        use_vss: true,
+                        metrics_out: Some(StrictPath::new(s("ludusavi.prom"))),
+                        record_scan: Some(StrictPath::new(s("scan.yaml"))),
                         by_steam_id: true,
                         api: true,
+                        output: None,
+                        sort: None,
+                        verbose: true,
+                        retry_failed: false,
                         games: vec![s("game1"), s("game2")],
                     }),
                 },
@@ -703,16 +2367,114 @@ mod tests {
             check_args(
                 &["ludusavi", "backup", "--path", "tests/fake"],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Backup {
                         preview: false,
                         path: Some(StrictPath::new(s("tests/fake"))),
                         force: false,
+                        overwrite: false,
+                        yes: false,
+                        merge: false,
+                        no_merge: false,
+                        update: false,
+                        try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
+                        by_steam_id: false,
+                        api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: false,
+                        games: vec![],
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_backup_with_retry_failed() {
+            check_args(
+                &["ludusavi", "backup", "--retry-failed"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Backup {
+                        preview: false,
+                        path: None,
+                        force: false,
+                        overwrite: false,
+                        yes: false,
+                        merge: false,
+                        no_merge: false,
+                        update: false,
+                        try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
+                        by_steam_id: false,
+                        api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: true,
+                        games: vec![],
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn rejects_cli_backup_with_retry_failed_and_games() {
+            check_args_err(
+                &["ludusavi", "backup", "--retry-failed", "game1"],
+                structopt::clap::ErrorKind::ArgumentConflict,
+            );
+        }
+
+        #[test]
+        fn accepts_cli_backup_with_table_output() {
+            check_args(
+                &["ludusavi", "backup", "--output", "table", "--sort", "size"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Backup {
+                        preview: false,
+                        path: None,
+                        force: false,
+                        overwrite: false,
+                        yes: false,
                         merge: false,
                         no_merge: false,
                         update: false,
                         try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
                         by_steam_id: false,
                         api: false,
+                        output: Some(OutputFormat::Table),
+                        sort: Some(SortKey::Size),
+                        verbose: false,
+                        retry_failed: false,
                         games: vec![],
                     }),
                 },
@@ -724,16 +2486,32 @@ mod tests {
             check_args(
                 &["ludusavi", "backup", "--no-merge"],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Backup {
                         preview: false,
                         path: None,
                         force: false,
+                        overwrite: false,
+                        yes: false,
                         merge: false,
                         no_merge: true,
                         update: false,
                         try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
                         by_steam_id: false,
                         api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: false,
                         games: vec![],
                     }),
                 },
@@ -745,22 +2523,139 @@ mod tests {
             check_args(
                 &["ludusavi", "backup", "--try-update"],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Backup {
                         preview: false,
                         path: None,
                         force: false,
+                        overwrite: false,
+                        yes: false,
                         merge: false,
                         no_merge: false,
                         update: false,
                         try_update: true,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
+                        by_steam_id: false,
+                        api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: false,
+                        games: vec![],
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_backup_with_deprecated_force() {
+            check_args(
+                &["ludusavi", "backup", "--force"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Backup {
+                        preview: false,
+                        path: None,
+                        force: true,
+                        overwrite: false,
+                        yes: false,
+                        merge: false,
+                        no_merge: false,
+                        update: false,
+                        try_update: false,
+                        no_proton_prefixes: false,
+                        follow_junctions: false,
+                        respect_nobackup_files: false,
+                        max_total_size: None,
+                        use_vss: false,
+                        metrics_out: None,
+                        record_scan: None,
                         by_steam_id: false,
                         api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        retry_failed: false,
                         games: vec![],
                     }),
                 },
             );
         }
 
+        #[test]
+        fn accepts_cli_backup_with_all_target_flag_combinations() {
+            for &overwrite in &[false, true] {
+                for &yes in &[false, true] {
+                    for &(merge, no_merge) in &[(false, false), (true, false), (false, true)] {
+                        let mut args = vec!["ludusavi", "backup"];
+                        if overwrite {
+                            args.push("--overwrite");
+                        }
+                        if yes {
+                            args.push("--yes");
+                        }
+                        if merge {
+                            args.push("--merge");
+                        }
+                        if no_merge {
+                            args.push("--no-merge");
+                        }
+                        check_args(
+                            &args,
+                            Cli {
+                                gui: false,
+                                game: None,
+                                screen: None,
+                                sub: Some(Subcommand::Backup {
+                                    preview: false,
+                                    path: None,
+                                    force: false,
+                                    overwrite,
+                                    yes,
+                                    merge,
+                                    no_merge,
+                                    update: false,
+                                    try_update: false,
+                                    no_proton_prefixes: false,
+                                    follow_junctions: false,
+                                    respect_nobackup_files: false,
+                                    max_total_size: None,
+                                    use_vss: false,
+                                    metrics_out: None,
+                                    record_scan: None,
+                                    by_steam_id: false,
+                                    api: false,
+                                    output: None,
+                                    sort: None,
+                                    verbose: false,
+                                    retry_failed: false,
+                                    games: vec![],
+                                }),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn rejects_cli_backup_with_merge_and_no_merge() {
+            check_args_err(
+                &["ludusavi", "backup", "--merge", "--no-merge"],
+                structopt::clap::ErrorKind::ArgumentConflict,
+            );
+        }
+
         #[test]
         fn rejects_cli_backup_with_update_and_try_update() {
             check_args_err(
@@ -774,12 +2669,22 @@ mod tests {
             check_args(
                 &["ludusavi", "restore"],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Restore {
                         preview: false,
                         path: None,
                         force: false,
+                        yes: false,
+                        ignore_integrity: false,
+                        allow_config_overwrite: false,
+                        metrics_out: None,
                         by_steam_id: false,
                         api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
                         games: vec![],
                     }),
                 },
@@ -795,25 +2700,105 @@ mod tests {
                     "--preview",
                     "--path",
                     "tests/backup",
-                    "--force",
+                    "--yes",
+                    "--ignore-integrity",
+                    "--allow-config-overwrite",
+                    "--metrics-out",
+                    "ludusavi.prom",
                     "--by-steam-id",
                     "--api",
+                    "--verbose",
                     "game1",
                     "game2",
                 ],
                 Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
                     sub: Some(Subcommand::Restore {
                         preview: true,
                         path: Some(StrictPath::new(s("tests/backup"))),
-                        force: true,
+                        force: false,
+                        yes: true,
+                        ignore_integrity: true,
+                        allow_config_overwrite: true,
+                        metrics_out: Some(StrictPath::new(s("ludusavi.prom"))),
                         by_steam_id: true,
                         api: true,
+                        output: None,
+                        sort: None,
+                        verbose: true,
                         games: vec![s("game1"), s("game2")],
                     }),
                 },
             );
         }
 
+        #[test]
+        fn accepts_cli_restore_with_deprecated_force() {
+            check_args(
+                &["ludusavi", "restore", "--force"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Restore {
+                        preview: false,
+                        path: None,
+                        force: true,
+                        yes: false,
+                        ignore_integrity: false,
+                        allow_config_overwrite: false,
+                        metrics_out: None,
+                        by_steam_id: false,
+                        api: false,
+                        output: None,
+                        sort: None,
+                        verbose: false,
+                        games: vec![],
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_restore_with_all_confirmation_flag_combinations() {
+            for &yes in &[false, true] {
+                for &ignore_integrity in &[false, true] {
+                    let mut args = vec!["ludusavi", "restore"];
+                    if yes {
+                        args.push("--yes");
+                    }
+                    if ignore_integrity {
+                        args.push("--ignore-integrity");
+                    }
+                    check_args(
+                        &args,
+                        Cli {
+                            gui: false,
+                            game: None,
+                            screen: None,
+                            sub: Some(Subcommand::Restore {
+                                preview: false,
+                                path: None,
+                                force: false,
+                                yes,
+                                ignore_integrity,
+                                allow_config_overwrite: false,
+                                metrics_out: None,
+                                by_steam_id: false,
+                                api: false,
+                                output: None,
+                                sort: None,
+                                verbose: false,
+                                games: vec![],
+                            }),
+                        },
+                    );
+                }
+            }
+        }
+
         #[test]
         fn rejects_cli_restore_with_nonexistent_path() {
             check_args_err(
@@ -821,51 +2806,1535 @@ mod tests {
                 structopt::clap::ErrorKind::ValueValidation,
             );
         }
-    }
-
-    mod reporter {
-        use super::*;
-        use crate::prelude::ScannedFile;
-        use maplit::hashset;
-        use pretty_assertions::assert_eq;
 
-        fn drive() -> String {
-            if cfg!(target_os = "windows") {
-                StrictPath::new(s("foo")).render()[..2].to_string()
-            } else {
-                s("")
-            }
+        #[test]
+        fn accepts_cli_diff_with_minimal_arguments() {
+            check_args(
+                &["ludusavi", "diff", "game1"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Diff {
+                        path: None,
+                        by_steam_id: false,
+                        api: false,
+                        game: Some(s("game1")),
+                    }),
+                },
+            );
         }
 
         #[test]
-        fn can_render_in_standard_mode_with_minimal_input() {
-            let mut reporter = Reporter::standard(Translator::default());
-            reporter.add_game(
-                "foo",
-                &ScanInfo::default(),
-                &BackupInfo::default(),
-                &OperationStepDecision::Processed,
-                &[],
+        fn accepts_cli_diff_without_game() {
+            check_args(
+                &["ludusavi", "diff"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Diff {
+                        path: None,
+                        by_steam_id: false,
+                        api: false,
+                        game: None,
+                    }),
+                },
             );
-            assert_eq!(
-                format!(
-                    r#"
+        }
 
-Overall:
-  Games: 0
-  Size: 0.00 MiB
-  Location: {}/dev/null
-                "#,
-                    &drive()
-                )
-                .trim_end(),
+        #[test]
+        fn accepts_cli_diff_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "diff",
+                    "--path",
+                    "tests/backup",
+                    "--by-steam-id",
+                    "--api",
+                    "game1",
+                ],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Diff {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        by_steam_id: true,
+                        api: true,
+                        game: Some(s("game1")),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_manifest_show_with_all_arguments() {
+            check_args(
+                &["ludusavi", "manifest", "show", "--resolved", "--api", "game1"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Manifest {
+                        sub: ManifestSubcommand::Show {
+                            resolved: true,
+                            api: true,
+                            game: s("game1"),
+                        },
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_report_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "report",
+                    "--path",
+                    "tests/backup",
+                    "--api",
+                    "--redact-home",
+                    "--older-than",
+                    "365",
+                ],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Report {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        api: true,
+                        redact_home: true,
+                        older_than: Some(365),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_prune_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "prune",
+                    "--path",
+                    "tests/backup",
+                    "--older-than",
+                    "365",
+                    "--min-free-space-mib",
+                    "1024",
+                    "--yes",
+                ],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Prune {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        older_than: Some(365),
+                        min_free_space_mib: Some(Some(1024)),
+                        yes: true,
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_prune_with_min_free_space_from_config() {
+            check_args(
+                &["ludusavi", "prune", "--min-free-space-mib"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Prune {
+                        path: None,
+                        older_than: None,
+                        min_free_space_mib: Some(None),
+                        yes: false,
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_compact_with_all_arguments() {
+            check_args(
+                &["ludusavi", "compact", "--path", "tests/backup", "--preview", "--yes"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Compact {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        preview: true,
+                        yes: true,
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_export_backup_with_all_arguments() {
+            check_args(
+                &["ludusavi", "export-backup", "--path", "tests/backup", "backup.zip"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::ExportBackup {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                        archive: StrictPath::new(s("backup.zip")),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_import_backup_with_all_arguments() {
+            check_args(
+                &[
+                    "ludusavi",
+                    "import-backup",
+                    "--path",
+                    "imported",
+                    "tests/root1/game1/subdir/file2.txt",
+                ],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::ImportBackup {
+                        path: Some(StrictPath::new(s("imported"))),
+                        archive: StrictPath::new(s("tests/root1/game1/subdir/file2.txt")),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_export_profile() {
+            check_args(
+                &["ludusavi", "export-profile", "profile.yaml"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::ExportProfile {
+                        profile: StrictPath::new(s("profile.yaml")),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_import_profile() {
+            check_args(
+                &["ludusavi", "import-profile", "tests/root1/game1/subdir/file2.txt"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::ImportProfile {
+                        profile: StrictPath::new(s("tests/root1/game1/subdir/file2.txt")),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_verify_with_path() {
+            check_args(
+                &["ludusavi", "verify", "--path", "tests/backup"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Verify {
+                        path: Some(StrictPath::new(s("tests/backup"))),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_open_with_restore_path() {
+            check_args(
+                &["ludusavi", "open", "--restore", "game1"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Open {
+                        path: None,
+                        restore: true,
+                        game: s("game1"),
+                    }),
+                },
+            );
+        }
+
+        #[test]
+        fn accepts_cli_schema() {
+            check_args(
+                &["ludusavi", "schema"],
+                Cli {
+                    gui: false,
+                    game: None,
+                    screen: None,
+                    sub: Some(Subcommand::Schema),
+                },
+            );
+        }
+    }
+
+    mod manifest_entry {
+        use super::*;
+        use maplit::hashmap;
+        use pretty_assertions::assert_eq;
+
+        fn repo() -> String {
+            env!("CARGO_MANIFEST_DIR").to_string()
+        }
+
+        fn game() -> Game {
+            Game {
+                files: Some(hashmap! {
+                    s("<base>/file1.txt") => Default::default(),
+                }),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn can_render_in_standard_mode() {
+            assert_eq!(
+                r#"
+---
+name: game1
+entry:
+  files:
+    "<base>/file1.txt":
+      tags: ~
+      when: ~
+  installDir: ~
+  registry: ~
+  steam: ~
+  rockstarTitle: ~
+  gog: ~
+  launch: ~
+  compatibleStores: ~
+                "#
+                .trim(),
+                render_manifest_entry("game1", &game(), None, false),
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_resolved_paths() {
+            let roots = vec![
+                RootsConfig {
+                    path: StrictPath::new(format!("{}/tests/root1", repo())),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(format!("{}/tests/root2", repo())),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+            ];
+            let resolved = resolve_manifest_entry(&game(), "game1", &roots, &StrictPath::new(repo()), true);
+
+            assert_eq!(
+                format!(
+                    r#"
+{{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "manifest show",
+  "name": "game1",
+  "entry": {{
+    "files": {{
+      "<base>/file1.txt": {{
+        "tags": null,
+        "when": null
+      }}
+    }},
+    "installDir": null,
+    "registry": null,
+    "steam": null,
+    "rockstarTitle": null,
+    "gog": null,
+    "launch": null,
+    "compatibleStores": null
+  }},
+  "resolved": [
+    {{
+      "path": "{0}/root1",
+      "store": "other",
+      "files": {{
+        "<base>/file1.txt": [
+          {{
+            "path": "{0}/root1/game1/file1.txt",
+            "exists": false
+          }}
+        ]
+      }}
+    }},
+    {{
+      "path": "{0}/root2",
+      "store": "other",
+      "files": {{
+        "<base>/file1.txt": [
+          {{
+            "path": "{0}/root2/game1/file1.txt",
+            "exists": true
+          }}
+        ]
+      }}
+    }}
+  ]
+}}
+                    "#,
+                    StrictPath::new(format!("{}/tests", repo())).render()
+                )
+                .trim()
+                .replace("<version>", app_version()),
+                render_manifest_entry("game1", &game(), Some(resolved), true),
+            );
+        }
+
+        #[test]
+        #[cfg(target_os = "linux")]
+        fn can_list_placeholders_skipped_on_linux() {
+            let game = Game {
+                files: Some(hashmap! {
+                    s("<base>/file1.txt") => Default::default(),
+                    s("<winAppData>/<game>/save.dat") => Default::default(),
+                    s("<regHkcu>/<winDocuments>/save.dat") => Default::default(),
+                }),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                hashmap! {
+                    s("<winAppData>/<game>/save.dat") => vec!["<winAppData>"],
+                    s("<regHkcu>/<winDocuments>/save.dat") => vec!["<regHkcu>", "<winDocuments>"],
+                },
+                skipped_placeholders(&game),
+            );
+        }
+    }
+
+    mod backup_target {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn repo() -> String {
+            env!("CARGO_MANIFEST_DIR").to_string()
+        }
+
+        #[test]
+        fn can_recognize_backup_target() {
+            assert!(is_recognized_backup_target(&StrictPath::new(format!(
+                "{}/tests/backup",
+                repo()
+            ))));
+            assert!(!is_recognized_backup_target(&StrictPath::new(format!(
+                "{}/tests/root1",
+                repo()
+            ))));
+            assert!(!is_recognized_backup_target(&StrictPath::new(format!(
+                "{}/tests/fake",
+                repo()
+            ))));
+        }
+
+        #[test]
+        fn can_decide_backup_target_action_for_all_combinations() {
+            let existing = StrictPath::new(format!("{}/tests/backup", repo()));
+            let nonexistent = StrictPath::new(format!("{}/tests/fake", repo()));
+
+            for &recognized in &[false, true] {
+                for &merge in &[false, true] {
+                    for &overwrite in &[false, true] {
+                        assert_eq!(
+                            Ok(BackupTargetAction::Create),
+                            decide_backup_target(&nonexistent, recognized, merge, overwrite),
+                        );
+
+                        let expected = if merge {
+                            Ok(BackupTargetAction::Merge)
+                        } else if recognized || overwrite {
+                            Ok(BackupTargetAction::Replace)
+                        } else {
+                            Err(Error::CliBackupTargetExists { path: existing.clone() })
+                        };
+                        assert_eq!(expected, decide_backup_target(&existing, recognized, merge, overwrite),);
+                    }
+                }
+            }
+        }
+    }
+
+    mod diff {
+        use super::*;
+        use maplit::hashset;
+        use pretty_assertions::assert_eq;
+
+        fn diff() -> GameDiff {
+            GameDiff {
+                added_files: hashset! { StrictPath::new(s("/added.txt")) },
+                removed_files: hashset! { StrictPath::new(s("/removed.txt")) },
+                resized_files: hashset! { StrictPath::new(s("/resized.txt")) },
+                modified_files: hashset! { StrictPath::new(s("/modified.txt")) },
+                added_registry_keys: hashset! { s("HKEY_CURRENT_USER/added") },
+                removed_registry_keys: hashset! { s("HKEY_CURRENT_USER/removed") },
+            }
+        }
+
+        fn drive() -> String {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(s("foo")).render()[..2].to_string()
+            } else {
+                s("")
+            }
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_without_changes() {
+            assert_eq!(
+                r#"
+foo:
+  No changes since the last backup.
+                "#
+                .trim(),
+                render_diff(&Translator::default(), &[(s("foo"), GameDiff::default(), 0)], false),
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_changes() {
+            assert_eq!(
+                format!(
+                    r#"
+foo:
+  - [ADDED] {0}/added.txt
+  - [REMOVED] {0}/removed.txt
+  - [RESIZED] {0}/resized.txt
+  - [MODIFIED] {0}/modified.txt
+  - [ADDED] HKEY_CURRENT_USER/added
+  - [REMOVED] HKEY_CURRENT_USER/removed
+  2.00 KiB to back up
+                "#,
+                    &drive()
+                )
+                .trim(),
+                render_diff(&Translator::default(), &[(s("foo"), diff(), 2048)], false),
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_several_games() {
+            assert_eq!(
+                r#"
+bar:
+  No changes since the last backup.
+
+foo:
+  No changes since the last backup.
+                "#
+                .trim(),
+                render_diff(
+                    &Translator::default(),
+                    &[(s("bar"), GameDiff::default(), 0), (s("foo"), GameDiff::default(), 0)],
+                    false
+                ),
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_changes() {
+            assert_eq!(
+                format!(
+                    r#"
+{{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "diff",
+  "games": {{
+    "foo": {{
+      "addedFiles": [
+        "{0}/added.txt"
+      ],
+      "removedFiles": [
+        "{0}/removed.txt"
+      ],
+      "resizedFiles": [
+        "{0}/resized.txt"
+      ],
+      "modifiedFiles": [
+        "{0}/modified.txt"
+      ],
+      "addedRegistry": [
+        "HKEY_CURRENT_USER/added"
+      ],
+      "removedRegistry": [
+        "HKEY_CURRENT_USER/removed"
+      ],
+      "changedBytes": 2048
+    }}
+  }}
+}}
+                "#,
+                    &drive()
+                )
+                .trim()
+                .replace("<version>", app_version()),
+                render_diff(&Translator::default(), &[(s("foo"), diff(), 2048)], true),
+            );
+        }
+    }
+
+    mod report {
+        use super::*;
+        use crate::layout::GameReport;
+        use pretty_assertions::assert_eq;
+
+        fn drive() -> String {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(s("foo")).render()[..2].to_string()
+            } else {
+                s("")
+            }
+        }
+
+        fn report() -> BackupReport {
+            BackupReport {
+                generated_at: std::time::UNIX_EPOCH,
+                games: vec![GameReport {
+                    name: s("foo"),
+                    backup_folder: StrictPath::new(s("/backup/foo")),
+                    size_bytes: 102_400,
+                    file_count: 2,
+                    last_modified: None,
+                }],
+            }
+        }
+
+        #[test]
+        fn can_render_in_standard_mode() {
+            assert_eq!(
+                format!(
+                    r#"
+foo [0.10 MiB]: 2 files
+
+Overall:
+  Games: 1
+  Size: 0.10 MiB
+  Location: {}/backup
+                "#,
+                    &drive()
+                )
+                .trim(),
+                render_report(
+                    &Translator::default(),
+                    &report(),
+                    &StrictPath::new(s("/backup")).render(),
+                    false
+                ),
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode() {
+            assert_eq!(
+                r#"
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "report",
+  "generatedAt": {
+    "secs_since_epoch": 0,
+    "nanos_since_epoch": 0
+  },
+  "games": [
+    {
+      "name": "foo",
+      "backupFolder": "/backup/foo",
+      "sizeBytes": 102400,
+      "fileCount": 2
+    }
+  ]
+}
+                "#
+                .trim()
+                .replace("<version>", app_version()),
+                render_report(&Translator::default(), &report(), "/backup", true),
+            );
+        }
+    }
+
+    mod api_schema {
+        use super::*;
+        use crate::layout::GameReport;
+        use serde_json::Value;
+
+        fn has_type(value: &Value, kind: &str) -> bool {
+            match kind {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                _ => false,
+            }
+        }
+
+        /// Just enough of JSON Schema to cover what `api_schema` uses.
+        fn validate(schema: &Value, value: &Value, at: &str) {
+            match &schema["type"] {
+                Value::String(kind) => assert!(has_type(value, kind), "{} is not {}", at, kind),
+                Value::Array(kinds) => assert!(
+                    kinds.iter().any(|kind| has_type(value, kind.as_str().unwrap())),
+                    "{} is not any of {:?}",
+                    at,
+                    kinds
+                ),
+                _ => {}
+            }
+            if let Some(expected) = schema.get("const") {
+                assert_eq!(expected, value, "{}", at);
+            }
+            if let Some(Value::Array(options)) = schema.get("enum") {
+                assert!(options.contains(value), "{} is not in {:?}", at, options);
+            }
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required {
+                    assert!(value.get(key.as_str().unwrap()).is_some(), "{} lacks {}", at, key);
+                }
+            }
+            if let (Some(Value::Object(properties)), Value::Object(object)) = (schema.get("properties"), value) {
+                for (key, property) in properties {
+                    if let Some(inner) = object.get(key) {
+                        validate(property, inner, &format!("{}.{}", at, key));
+                    }
+                }
+            }
+            if let (Some(items), Value::Array(array)) = (schema.get("items"), value) {
+                for (i, item) in array.iter().enumerate() {
+                    validate(items, item, &format!("{}[{}]", at, i));
+                }
+            }
+        }
+
+        fn check(document: String) {
+            let value: Value = serde_json::from_str(&document).unwrap();
+            validate(&api_schema(), &value, "$");
+        }
+
+        #[test]
+        fn example_documents_match_the_schema() {
+            let mut diff = JsonDiffOutput {
+                errors: Some(ApiErrors {
+                    unknown_games: Some(vec![s("foo")]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            diff.games.insert(s("bar"), ApiGameDiff::default());
+            check(to_api_json("diff", &diff));
+
+            check(to_api_json(
+                "report",
+                &BackupReport {
+                    generated_at: std::time::UNIX_EPOCH,
+                    games: vec![GameReport {
+                        name: s("foo"),
+                        backup_folder: StrictPath::new(s("/backup/foo")),
+                        size_bytes: 1,
+                        file_count: 1,
+                        last_modified: None,
+                    }],
+                },
+            ));
+
+            let mut reporter = Reporter::json("backup");
+            reporter.trip_some_games_failed();
+            check(reporter.render(&StrictPath::new(s("/dev/null"))));
+
+            check(Reporter::json("restore").render(&StrictPath::new(s("/dev/null"))));
+        }
+
+        #[test]
+        fn envelope_does_not_rename_existing_fields() {
+            let document = Reporter::json("backup").render(&StrictPath::new(s("/dev/null")));
+            let value: Value = serde_json::from_str(&document).unwrap();
+            assert_eq!(Value::from(API_VERSION), value["apiVersion"]);
+            assert_eq!(Value::from(app_version()), value["version"]);
+            assert_eq!(Value::from("backup"), value["command"]);
+            assert!(value["overall"].is_object());
+            assert!(value["games"].is_object());
+        }
+    }
+
+    mod restore_conflict {
+        use super::*;
+        use crate::prelude::{RestoreConflictSource, ScannedFile};
+        use pretty_assertions::assert_eq;
+
+        fn drive() -> String {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(s("foo")).render()[..2].to_string()
+            } else {
+                s("")
+            }
+        }
+
+        fn source(game_name: &str, modified: Option<std::time::SystemTime>) -> RestoreConflictSource {
+            RestoreConflictSource {
+                game_name: s(game_name),
+                file: ScannedFile {
+                    path: StrictPath::new(format!("/backup/{}/shared.cfg", game_name)),
+                    size: 1,
+                    original_path: Some(StrictPath::new(s("/original/shared.cfg"))),
+                    root: None,
+                },
+                modified,
+            }
+        }
+
+        #[test]
+        fn can_render_restore_conflict() {
+            let conflict = RestoreConflict {
+                target: StrictPath::new(s("/original/shared.cfg")),
+                sources: vec![
+                    source(
+                        "game1",
+                        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000)),
+                    ),
+                    source("game2", None),
+                ],
+            };
+            assert_eq!(
+                r#"
+Multiple games would restore <drive>/original/shared.cfg. Which one should be kept?
+  - game1 (backed up 2020-09-13 12:26:40 UTC)
+  - game2 (backup time unknown)
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                render_restore_conflict(&Translator::default(), &conflict),
+            );
+        }
+    }
+
+    mod reporter {
+        use super::*;
+        use crate::manifest::Store;
+        use crate::prelude::{IntegrityInfo, ScannedFile};
+        use maplit::{hashmap, hashset};
+        use pretty_assertions::assert_eq;
+
+        fn drive() -> String {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(s("foo")).render()[..2].to_string()
+            } else {
+                s("")
+            }
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_minimal_input() {
+            let mut reporter = Reporter::standard(Translator::default());
+            reporter.add_game(
+                "foo",
+                &ScanInfo::default(),
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                format!(
+                    r#"
+
+Overall:
+  Games: 0
+  Size: 0.00 MiB
+  Location: {}/dev/null
+                "#,
+                    &drive()
+                )
+                .trim_end(),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            )
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_one_game_in_backup_mode() {
+            let mut reporter = Reporter::standard(Translator::default());
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file1")),
+                            size: 102_400,
+                            original_path: None,
+                            root: None,
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(s("/file2")),
+                            size: 51_200,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    found_registry_keys: hashset! {
+                        s("HKEY_CURRENT_USER/Key1"),
+                        s("HKEY_CURRENT_USER/Key2")
+                    },
+                    registry_file: None,
+                    integrity: None,
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
+                },
+                &BackupInfo {
+                    failed_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file2")),
+                            size: 51_200,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    failed_registry: hashset! {
+                        s("HKEY_CURRENT_USER/Key1")
+                    },
+                    found_files: 2,
+                    found_registry_keys: 2,
+                    restored_with_stored_names: hashset! {},
+                    registry_type_conflicts: hashset! {},
+                    unmapped_registry_keys: hashset! {},
+                    overwritten_files: hashset! {},
+                    skipped_files: hashset! {},
+                    restored_files: Default::default(),
+                    unrestored_owners: hashset! {},
+                    duration: Default::default(),
+                },
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [0.10 MiB]:
+  - <drive>/file1
+  - [FAILED] <drive>/file2
+  - [FAILED] HKEY_CURRENT_USER/Key1
+  - HKEY_CURRENT_USER/Key2
+  Backed up 2 files (1 failed, 1 registry key failed)
+
+Overall:
+  Games: 1 of 1
+  Size: 0.10 of 0.15 MiB
+  Location: <drive>/dev/null
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_display_roots() {
+            let mut reporter = Reporter::standard(Translator::default());
+            reporter.display_with_roots(&[RootsConfig {
+                path: StrictPath::new(s("/games")),
+                store: Store::Other,
+                portable: false,
+                scan_proton_prefixes: None,
+            }]);
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/games/foo/save.dat")),
+                            size: 102_400,
+                            original_path: None,
+                            root: None,
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(s("/elsewhere/foo.dat")),
+                            size: 51_200,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    ..Default::default()
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [0.15 MiB]:
+  - <drive>/elsewhere/foo.dat
+  - <root1:Other>/foo/save.dat
+
+Overall:
+  Games: 1
+  Size: 0.15 MiB
+  Location: <drive>/dev/null
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_timings() {
+            let mut reporter = Reporter::standard(Translator::default());
+            reporter.track_timings(Timing(std::time::Duration::from_millis(5)));
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file1")),
+                            size: 102_400,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    duration: Timing(std::time::Duration::from_millis(20)),
+                    ..Default::default()
+                },
+                &BackupInfo {
+                    duration: Timing(std::time::Duration::from_millis(300)),
+                    ..Default::default()
+                },
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            reporter.add_phase_timings(
+                Timing(std::time::Duration::from_millis(20)),
+                Timing(std::time::Duration::from_millis(300)),
+            );
+            assert_eq!(
+                r#"
+foo [0.10 MiB]:
+  (scan: 20 ms, copy: 300 ms)
+  - <drive>/file1
+
+Overall:
+  Games: 1
+  Size: 0.10 MiB
+  Location: <drive>/dev/null
+  Time:
+    Manifest update: 5 ms
+    Scan: 20 ms
+    Copy: 300 ms
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_shrunk_game() {
+            let mut reporter = Reporter::standard(Translator::default());
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file1")),
+                            size: 102_400,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    shrunk_from_bytes: Some(41_943_040),
+                    ..Default::default()
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [SHRUNK] [0.10 MiB]:
+  (latest backup: 40.00 MiB; the save location may have moved)
+  - <drive>/file1
+
+Overall:
+  Games: 1
+  Size: 0.10 MiB
+  Location: <drive>/dev/null
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_ambiguous_locations() {
+            let mut reporter = Reporter::standard(Translator::default());
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            root: Some(RootsConfig {
+                                path: StrictPath::new(s("/old")),
+                                store: Store::Steam,
+                                ..Default::default()
+                            }),
+                            ..ScannedFile::new(StrictPath::new(s("/old/foo/save.dat"))).with_size(51_200)
+                        },
+                        ScannedFile {
+                            root: Some(RootsConfig {
+                                path: StrictPath::new(s("/new")),
+                                store: Store::Steam,
+                                ..Default::default()
+                            }),
+                            ..ScannedFile::new(StrictPath::new(s("/new/foo/save.dat"))).with_size(51_200)
+                        },
+                    },
+                    ..Default::default()
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [0.10 MiB]:
+  (the same saves were found in several roots: <drive>/new (modification time unknown), <drive>/old (modification time unknown))
+  - <drive>/new/foo/save.dat
+  - <drive>/old/foo/save.dat
+
+Overall:
+  Games: 1
+  Size: 0.10 MiB
+  Location: <drive>/dev/null
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_root_breakdown() {
+            let mut reporter = Reporter::standard(Translator::default());
+            let roots = vec![
+                RootsConfig {
+                    path: StrictPath::new(s("/root1")),
+                    store: Store::Steam,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("/root2")),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+            ];
+            reporter.track_roots(&roots);
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/root1/file1")),
+                            size: 102_400,
+                            original_path: None,
+                            root: Some(roots[0].clone()),
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(s("/file2")),
+                            size: 51_200,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    classification: Some(Default::default()),
+                    ..Default::default()
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [0.15 MiB]:
+  - <drive>/file2
+  - <drive>/root1/file1
+
+Overall:
+  Games: 1
+  Size: 0.15 MiB
+  Location: <drive>/dev/null
+  Roots:
+    <drive>/root1 (Steam): 1 games, 0.10 MiB
+    <drive>/root2 (Other): 0 games, 0.00 MiB
+    No root: 1 games, 0.05 MiB
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_root_breakdown() {
+            let mut reporter = Reporter::json("backup");
+            reporter.track_roots(&[RootsConfig {
+                path: StrictPath::new(s("/root1")),
+                store: Store::Steam,
+                portable: false,
+                scan_proton_prefixes: None,
+            }]);
+            reporter.add_game(
+                "foo",
+                &ScanInfo::default(),
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "backup",
+  "overall": {
+    "totalGames": 0,
+    "totalBytes": 0,
+    "processedGames": 0,
+    "processedBytes": 0,
+    "roots": [
+      {
+        "path": "<drive>/root1",
+        "store": "steam",
+        "games": 0,
+        "bytes": 0
+      }
+    ]
+  },
+  "games": {}
+}
+                "#
+                .trim()
+                .replace("<version>", app_version())
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_standard_mode_with_one_game_in_restore_mode() {
+            let mut reporter = Reporter::standard(Translator::default());
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 102_400,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(format!("{}/backup/file2", drive())),
+                            size: 51_200,
+                            original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
+                            root: None,
+                        },
+                    },
+                    found_registry_keys: hashset! {},
+                    registry_file: None,
+                    integrity: None,
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [0.15 MiB]:
+  - <drive>/original/file1
+  - <drive>/original/file2
+
+Overall:
+  Games: 1
+  Size: 0.15 MiB
+  Location: <drive>/dev/null
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_minimal_input() {
+            let mut reporter = Reporter::json("backup");
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo::default(),
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "backup",
+  "overall": {
+    "totalGames": 0,
+    "totalBytes": 0,
+    "processedGames": 0,
+    "processedBytes": 0
+  },
+  "games": {}
+}
+                "#
+                .trim()
+                .replace("<version>", app_version()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_one_game_in_backup_mode() {
+            let mut reporter = Reporter::json("backup");
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file1")),
+                            size: 100,
+                            original_path: None,
+                            root: None,
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(s("/file2")),
+                            size: 50,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    found_registry_keys: hashset! {
+                        s("HKEY_CURRENT_USER/Key1"),
+                        s("HKEY_CURRENT_USER/Key2")
+                    },
+                    registry_file: None,
+                    integrity: None,
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
+                },
+                &BackupInfo {
+                    failed_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(s("/file2")),
+                            size: 50,
+                            original_path: None,
+                            root: None,
+                        },
+                    },
+                    failed_registry: hashset! {
+                        s("HKEY_CURRENT_USER/Key1")
+                    },
+                    found_files: 2,
+                    found_registry_keys: 2,
+                    restored_with_stored_names: hashset! {},
+                    registry_type_conflicts: hashset! {},
+                    unmapped_registry_keys: hashset! {},
+                    overwritten_files: hashset! {},
+                    skipped_files: hashset! {},
+                    restored_files: Default::default(),
+                    unrestored_owners: hashset! {},
+                    duration: Default::default(),
+                },
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "backup",
+  "errors": {
+    "someGamesFailed": true
+  },
+  "overall": {
+    "totalGames": 1,
+    "totalBytes": 150,
+    "processedGames": 1,
+    "processedBytes": 100
+  },
+  "games": {
+    "foo": {
+      "decision": "Processed",
+      "files": {
+        "<drive>/file1": {
+          "bytes": 100
+        },
+        "<drive>/file2": {
+          "failed": true,
+          "bytes": 50
+        }
+      },
+      "registry": {
+        "HKEY_CURRENT_USER/Key1": {
+          "failed": true
+        },
+        "HKEY_CURRENT_USER/Key2": {}
+      }
+    }
+  }
+}
+                "#
+                .trim()
+                .replace("<version>", app_version())
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_one_game_in_restore_mode() {
+            let mut reporter = Reporter::json("restore");
+
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 100,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
+                        },
+                        ScannedFile {
+                            path: StrictPath::new(format!("{}/backup/file2", drive())),
+                            size: 50,
+                            original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
+                            root: None,
+                        },
+                    },
+                    found_registry_keys: hashset! {},
+                    registry_file: None,
+                    integrity: None,
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
+                },
+                &BackupInfo::default(),
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
+  "overall": {
+    "totalGames": 1,
+    "totalBytes": 150,
+    "processedGames": 1,
+    "processedBytes": 150
+  },
+  "games": {
+    "foo": {
+      "decision": "Processed",
+      "files": {
+        "<drive>/original/file1": {
+          "bytes": 100
+        },
+        "<drive>/original/file2": {
+          "bytes": 50
+        }
+      },
+      "registry": {}
+    }
+  }
+}
+                "#
+                .trim()
+                .replace("<version>", app_version())
+                .replace("<drive>", &drive()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
-            )
+            );
         }
 
         #[test]
-        fn can_render_in_standard_mode_with_one_game_in_backup_mode() {
-            let mut reporter = Reporter::standard(Translator::default());
+        fn can_render_in_json_mode_with_overwrites_in_restore_mode() {
+            let mut reporter = Reporter::json("restore");
 
             reporter.add_game(
                 "foo",
@@ -873,131 +4342,215 @@ Overall:
                     game_name: s("foo"),
                     found_files: hashset! {
                         ScannedFile {
-                            path: StrictPath::new(s("/file1")),
-                            size: 102_400,
-                            original_path: None,
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 100,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
                         },
                         ScannedFile {
-                            path: StrictPath::new(s("/file2")),
-                            size: 51_200,
-                            original_path: None,
+                            path: StrictPath::new(format!("{}/backup/file2", drive())),
+                            size: 50,
+                            original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
+                            root: None,
                         },
                     },
-                    found_registry_keys: hashset! {
-                        s("HKEY_CURRENT_USER/Key1"),
-                        s("HKEY_CURRENT_USER/Key2")
-                    },
+                    found_registry_keys: hashset! {},
                     registry_file: None,
+                    integrity: None,
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
                 },
                 &BackupInfo {
-                    failed_files: hashset! {
+                    overwritten_files: hashset! {
                         ScannedFile {
-                            path: StrictPath::new(s("/file2")),
-                            size: 51_200,
-                            original_path: None,
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 100,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
                         },
                     },
-                    failed_registry: hashset! {
-                        s("HKEY_CURRENT_USER/Key1")
-                    },
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
             );
             assert_eq!(
                 r#"
-foo [0.10 MiB]:
-  - <drive>/file1
-  - [FAILED] <drive>/file2
-  - [FAILED] HKEY_CURRENT_USER/Key1
-  - HKEY_CURRENT_USER/Key2
-
-Overall:
-  Games: 1 of 1
-  Size: 0.10 of 0.15 MiB
-  Location: <drive>/dev/null
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
+  "overall": {
+    "totalGames": 1,
+    "totalBytes": 150,
+    "processedGames": 1,
+    "processedBytes": 150
+  },
+  "games": {
+    "foo": {
+      "decision": "Processed",
+      "files": {
+        "<drive>/original/file1": {
+          "bytes": 100,
+          "overwrites": true
+        },
+        "<drive>/original/file2": {
+          "bytes": 50
+        }
+      },
+      "registry": {}
+    }
+  }
+}
                 "#
                 .trim()
+                .replace("<version>", app_version())
                 .replace("<drive>", &drive()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
             );
         }
 
         #[test]
-        fn can_render_in_standard_mode_with_one_game_in_restore_mode() {
-            let mut reporter = Reporter::standard(Translator::default());
+        fn can_render_in_json_mode_with_skipped_files_in_restore_mode() {
+            let mut reporter = Reporter::json("restore");
+            let file = |name: &str, size: u64| ScannedFile {
+                path: StrictPath::new(format!("{}/backup/{}", drive(), name)),
+                size,
+                original_path: Some(StrictPath::new(format!("{}/original/{}", drive(), name))),
+                root: None,
+            };
 
             reporter.add_game(
                 "foo",
                 &ScanInfo {
                     game_name: s("foo"),
-                    found_files: hashset! {
-                        ScannedFile {
-                            path: StrictPath::new(format!("{}/backup/file1", drive())),
-                            size: 102_400,
-                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
-                        },
-                        ScannedFile {
-                            path: StrictPath::new(format!("{}/backup/file2", drive())),
-                            size: 51_200,
-                            original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
-                        },
-                    },
-                    found_registry_keys: hashset! {},
-                    registry_file: None,
+                    found_files: hashset! { file("file1", 100), file("file2", 50) },
+                    ..Default::default()
+                },
+                &BackupInfo {
+                    skipped_files: hashset! { file("file2", 50) },
+                    ..Default::default()
                 },
-                &BackupInfo::default(),
                 &OperationStepDecision::Processed,
                 &[],
             );
             assert_eq!(
                 r#"
-foo [0.15 MiB]:
-  - <drive>/original/file1
-  - <drive>/original/file2
-
-Overall:
-  Games: 1
-  Size: 0.15 MiB
-  Location: <drive>/dev/null
+{
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
+  "overall": {
+    "totalGames": 1,
+    "totalBytes": 150,
+    "processedGames": 1,
+    "processedBytes": 100
+  },
+  "games": {
+    "foo": {
+      "decision": "Processed",
+      "files": {
+        "<drive>/original/file1": {
+          "bytes": 100
+        },
+        "<drive>/original/file2": {
+          "bytes": 50,
+          "skipped": true
+        }
+      },
+      "registry": {}
+    }
+  }
+}
                 "#
                 .trim()
+                .replace("<version>", app_version())
                 .replace("<drive>", &drive()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
             );
         }
 
         #[test]
-        fn can_render_in_json_mode_with_minimal_input() {
-            let mut reporter = Reporter::json();
+        #[cfg(not(target_os = "windows"))]
+        fn can_render_in_json_mode_with_restore_preview_tree() {
+            let mut reporter = Reporter::json("restore");
+            let info = ScanInfo {
+                game_name: s("foo"),
+                found_files: hashset! {
+                    ScannedFile {
+                        path: StrictPath::new(s("/backup/file1")),
+                        size: 100,
+                        original_path: Some(StrictPath::new(s("/original/file1"))),
+                        root: None,
+                    },
+                },
+                ..Default::default()
+            };
 
             reporter.add_game(
                 "foo",
-                &ScanInfo::default(),
+                &info,
                 &BackupInfo::default(),
                 &OperationStepDecision::Processed,
                 &[],
             );
+            reporter.add_restore_preview_tree("foo", restore_preview_tree(&info, &[]));
             assert_eq!(
                 r#"
 {
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
   "overall": {
-    "totalGames": 0,
-    "totalBytes": 0,
-    "processedGames": 0,
-    "processedBytes": 0
+    "totalGames": 1,
+    "totalBytes": 100,
+    "processedGames": 1,
+    "processedBytes": 100
   },
-  "games": {}
+  "games": {
+    "foo": {
+      "decision": "Processed",
+      "files": {
+        "/original/file1": {
+          "bytes": 100
+        }
+      },
+      "registry": {},
+      "restoreTree": {
+        "folders": {
+          "/": {
+            "folders": {
+              "original": {
+                "folders": {},
+                "files": [
+                  "file1"
+                ]
+              }
+            },
+            "files": []
+          }
+        },
+        "files": []
+      }
+    }
+  }
 }
                 "#
-                .trim(),
+                .trim()
+                .replace("<version>", app_version()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
             );
         }
 
         #[test]
-        fn can_render_in_json_mode_with_one_game_in_backup_mode() {
-            let mut reporter = Reporter::json();
+        fn can_render_in_json_mode_with_restore_warnings() {
+            let mut reporter = Reporter::json("restore");
 
             reporter.add_game(
                 "foo",
@@ -1005,33 +4558,38 @@ Overall:
                     game_name: s("foo"),
                     found_files: hashset! {
                         ScannedFile {
-                            path: StrictPath::new(s("/file1")),
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
                             size: 100,
-                            original_path: None,
-                        },
-                        ScannedFile {
-                            path: StrictPath::new(s("/file2")),
-                            size: 50,
-                            original_path: None,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
                         },
                     },
-                    found_registry_keys: hashset! {
-                        s("HKEY_CURRENT_USER/Key1"),
-                        s("HKEY_CURRENT_USER/Key2")
-                    },
-                    registry_file: None,
+                    found_registry_keys: hashset! { s("HKEY_CURRENT_USER/Software/Foo") },
+                    ..Default::default()
                 },
                 &BackupInfo {
-                    failed_files: hashset! {
+                    found_files: 1,
+                    found_registry_keys: 1,
+                    restored_files: hashmap! {
                         ScannedFile {
-                            path: StrictPath::new(s("/file2")),
-                            size: 50,
-                            original_path: None,
-                        },
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 100,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
+                        } => StrictPath::new(format!("{}/original/file1", drive())),
                     },
-                    failed_registry: hashset! {
-                        s("HKEY_CURRENT_USER/Key1")
+                    unrestored_owners: hashset! { StrictPath::new(format!("{}/original/file1", drive())) },
+                    restored_with_stored_names: hashset! {
+                        ScannedFile {
+                            path: StrictPath::new(format!("{}/backup/file1", drive())),
+                            size: 100,
+                            original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
+                        },
                     },
+                    registry_type_conflicts: hashset! { s("HKEY_CURRENT_USER/Software/Foo/value") },
+                    unmapped_registry_keys: hashset! { s("HKEY_USERS/S-1-5-21-1/Software/Foo") },
+                    ..Default::default()
                 },
                 &OperationStepDecision::Processed,
                 &[],
@@ -1039,12 +4597,12 @@ Overall:
             assert_eq!(
                 r#"
 {
-  "errors": {
-    "someGamesFailed": true
-  },
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
   "overall": {
     "totalGames": 1,
-    "totalBytes": 150,
+    "totalBytes": 100,
     "processedGames": 1,
     "processedBytes": 100
   },
@@ -1052,34 +4610,42 @@ Overall:
     "foo": {
       "decision": "Processed",
       "files": {
-        "<drive>/file1": {
-          "bytes": 100
-        },
-        "<drive>/file2": {
-          "failed": true,
-          "bytes": 50
+        "<drive>/original/file1": {
+          "bytes": 100,
+          "ownerNotRestored": true,
+          "restoredWithStoredName": true
         }
       },
       "registry": {
-        "HKEY_CURRENT_USER/Key1": {
-          "failed": true
-        },
-        "HKEY_CURRENT_USER/Key2": {}
-      }
+        "HKEY_CURRENT_USER/Software/Foo": {}
+      },
+      "registryTypeConflicts": [
+        "HKEY_CURRENT_USER/Software/Foo/value"
+      ],
+      "unmappedRegistryKeys": [
+        "HKEY_USERS/S-1-5-21-1/Software/Foo"
+      ]
     }
   }
 }
                 "#
                 .trim()
+                .replace("<version>", app_version())
                 .replace("<drive>", &drive()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
             );
         }
 
         #[test]
-        fn can_render_in_json_mode_with_one_game_in_restore_mode() {
-            let mut reporter = Reporter::json();
+        fn can_render_in_standard_mode_with_broken_backup_in_restore_mode() {
+            let mut reporter = Reporter::standard(Translator::default());
 
+            let corrupted = ScannedFile {
+                path: StrictPath::new(format!("{}/backup/file2", drive())),
+                size: 51_200,
+                original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
+                root: None,
+            };
             reporter.add_game(
                 "foo",
                 &ScanInfo {
@@ -1087,17 +4653,99 @@ Overall:
                     found_files: hashset! {
                         ScannedFile {
                             path: StrictPath::new(format!("{}/backup/file1", drive())),
-                            size: 100,
+                            size: 102_400,
                             original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                            root: None,
                         },
-                        ScannedFile {
-                            path: StrictPath::new(format!("{}/backup/file2", drive())),
-                            size: 50,
-                            original_path: Some(StrictPath::new(format!("{}/original/file2", drive()))),
-                        },
+                        corrupted.clone(),
                     },
                     found_registry_keys: hashset! {},
                     registry_file: None,
+                    integrity: Some(IntegrityInfo {
+                        known: true,
+                        missing_files: hashset! {
+                            StrictPath::new(format!("{}/original/file3", drive())),
+                        },
+                        corrupted_files: hashset! { corrupted.clone() },
+                    }),
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
+                },
+                &BackupInfo {
+                    failed_files: hashset! { corrupted },
+                    failed_registry: hashset! {},
+                    found_files: 2,
+                    found_registry_keys: 0,
+                    restored_with_stored_names: hashset! {},
+                    registry_type_conflicts: hashset! {},
+                    unmapped_registry_keys: hashset! {},
+                    overwritten_files: hashset! {},
+                    skipped_files: hashset! {},
+                    restored_files: Default::default(),
+                    unrestored_owners: hashset! {},
+                    duration: Default::default(),
+                },
+                &OperationStepDecision::Processed,
+                &[],
+            );
+            assert_eq!(
+                r#"
+foo [BROKEN BACKUP] [0.10 MiB]:
+  - <drive>/original/file1
+  - [CORRUPTED] <drive>/original/file2
+  - [MISSING] <drive>/original/file3
+  Backed up 2 files (1 failed)
+
+Overall:
+  Games: 1 of 1
+  Size: 0.10 of 0.15 MiB
+  Location: <drive>/dev/null
+  Integrity: 0 intact, 1 broken, 0 unknown
+                "#
+                .trim()
+                .replace("<drive>", &drive()),
+                reporter.render(&StrictPath::new(s("/dev/null")))
+            );
+        }
+
+        #[test]
+        fn can_render_in_json_mode_with_broken_backup_in_restore_mode() {
+            let mut reporter = Reporter::json("restore");
+
+            let corrupted = ScannedFile {
+                path: StrictPath::new(format!("{}/backup/file1", drive())),
+                size: 100,
+                original_path: Some(StrictPath::new(format!("{}/original/file1", drive()))),
+                root: None,
+            };
+            reporter.add_game(
+                "foo",
+                &ScanInfo {
+                    game_name: s("foo"),
+                    found_files: hashset! { corrupted.clone() },
+                    found_registry_keys: hashset! {},
+                    registry_file: None,
+                    integrity: Some(IntegrityInfo {
+                        known: true,
+                        missing_files: hashset! {
+                            StrictPath::new(format!("{}/original/file2", drive())),
+                        },
+                        corrupted_files: hashset! { corrupted },
+                    }),
+                    classification: None,
+                    reparse_points: Default::default(),
+                    unsafe_paths: Default::default(),
+                    compressed_files: Default::default(),
+                    file_owners: Default::default(),
+                    uninstalled_prefix: false,
+                    shrunk_from_bytes: None,
+                    duration: Default::default(),
                 },
                 &BackupInfo::default(),
                 &OperationStepDecision::Processed,
@@ -1106,29 +4754,35 @@ Overall:
             assert_eq!(
                 r#"
 {
+  "apiVersion": 1,
+  "version": "<version>",
+  "command": "restore",
   "overall": {
     "totalGames": 1,
-    "totalBytes": 150,
+    "totalBytes": 100,
     "processedGames": 1,
-    "processedBytes": 150
+    "processedBytes": 100
   },
   "games": {
     "foo": {
       "decision": "Processed",
+      "integrity": "Broken",
       "files": {
         "<drive>/original/file1": {
+          "corrupted": true,
           "bytes": 100
-        },
-        "<drive>/original/file2": {
-          "bytes": 50
         }
       },
+      "missingFiles": [
+        "<drive>/original/file2"
+      ],
       "registry": {}
     }
   }
 }
                 "#
                 .trim()
+                .replace("<version>", app_version())
                 .replace("<drive>", &drive()),
                 reporter.render(&StrictPath::new(s("/dev/null")))
             );
@@ -1,12 +1,13 @@
 use crate::{
-    manifest::Store,
-    prelude::{app_dir, Error, StrictPath},
+    layout::namespaced_path,
+    manifest::{Os, Store},
+    prelude::{app_dir, get_os, is_within, Error, StrictPath},
 };
 
 const MANIFEST_URL: &str = "https://raw.githubusercontent.com/mtkennerly/ludusavi-manifest/master/data/manifest.yaml";
 
 fn default_backup_dir() -> StrictPath {
-    let mut path = dirs::home_dir().unwrap();
+    let mut path = dirs::home_dir().unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
     path.push("ludusavi-backup");
     StrictPath::from_std_path_buf(&path)
 }
@@ -17,6 +18,10 @@ pub struct Config {
     pub roots: Vec<RootsConfig>,
     pub backup: BackupConfig,
     pub restore: RestoreConfig,
+    /// Scan and restore paths that climb out of the expected folders with `..`,
+    /// or that would restore into system folders. These are skipped by default.
+    #[serde(default, rename = "allowUnsafePaths")]
+    pub allow_unsafe_paths: bool,
     #[serde(default, rename = "customGames")]
     pub custom_games: Vec<CustomGame>,
 }
@@ -27,10 +32,146 @@ pub struct ManifestConfig {
     pub etag: Option<String>,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, serde::Serialize, serde::Deserialize)]
 pub struct RootsConfig {
     pub path: StrictPath,
     pub store: Store,
+    /// Record backed up files relative to this root rather than by absolute path,
+    /// so that they can be restored even if the root moves (e.g., to another drive letter).
+    #[serde(default, skip_serializing_if = "crate::serialization::is_false")]
+    pub portable: bool,
+    /// Set this to false to skip the Proton prefixes of this root.
+    /// It has no effect when `backup.scanProtonPrefixes` is disabled.
+    #[serde(default, rename = "scanProtonPrefixes", skip_serializing_if = "Option::is_none")]
+    pub scan_proton_prefixes: Option<bool>,
+}
+
+impl RootsConfig {
+    /// Check the usual install locations of each store and return a root for each one that exists.
+    /// For Steam, only the first one is used (see `detect_steam_root`).
+    pub fn detect_default_roots() -> Vec<Self> {
+        let [pf32, pf64] = program_files();
+
+        let candidates = vec![
+            // Epic:
+            (format!("{}/Epic Games", pf32), Store::Other),
+            (format!("{}/Epic Games", pf64), Store::Other),
+            // GOG:
+            ("C:/GOG Games".to_string(), Store::Other),
+            ("~/GOG Games".to_string(), Store::Other),
+            // GOG Galaxy:
+            (format!("{}/GOG Galaxy/Games", pf32), Store::Other),
+            (format!("{}/GOG Galaxy/Games", pf64), Store::Other),
+            // Uplay:
+            (format!("{}/Ubisoft/Ubisoft Game Launcher/games", pf32), Store::Other),
+            (format!("{}/Ubisoft/Ubisoft Game Launcher/games", pf64), Store::Other),
+            // Origin:
+            (format!("{}/Origin Games", pf32), Store::Other),
+            (format!("{}/Origin Games", pf64), Store::Other),
+            // Rockstar:
+            (format!("{}/Rockstar Games", pf32), Store::Rockstar),
+            (format!("{}/Rockstar Games", pf64), Store::Rockstar),
+            // Microsoft:
+            (format!("{}/WindowsApps", pf32), Store::Other),
+            (format!("{}/WindowsApps", pf64), Store::Other),
+        ];
+
+        Self::detect_steam_root()
+            .into_iter()
+            .chain(Self::existing_roots(candidates))
+            .collect()
+    }
+
+    /// Find the current user's Steam installation, so that a first run works
+    /// without configuring any roots. On Windows, this prefers the location
+    /// that Steam records in the registry.
+    pub fn detect_steam_root() -> Option<Self> {
+        Self::find_steam_root(&get_os(), Self::steam_path_from_registry(), &program_files(), |path| {
+            path.is_dir()
+        })
+    }
+
+    fn steam_root_candidates(os: &Os, registry_path: Option<String>, program_files: &[String]) -> Vec<String> {
+        let defaults: Vec<String> = match os {
+            Os::Windows => program_files.iter().map(|x| format!("{}/Steam", x)).collect(),
+            Os::Linux => vec![
+                "~/.steam/steam".to_string(),
+                "~/.local/share/Steam".to_string(),
+                "~/.var/app/com.valvesoftware.Steam/.local/share/Steam".to_string(),
+                "/usr/share/steam".to_string(),
+            ],
+            Os::Mac => vec!["~/Library/Application Support/Steam".to_string()],
+            Os::Other => vec![],
+        };
+        registry_path.into_iter().chain(defaults).collect()
+    }
+
+    fn find_steam_root(
+        os: &Os,
+        registry_path: Option<String>,
+        program_files: &[String],
+        exists: impl Fn(&StrictPath) -> bool,
+    ) -> Option<Self> {
+        Self::steam_root_candidates(os, registry_path, program_files)
+            .into_iter()
+            .map(StrictPath::new)
+            .find(|path| exists(path))
+            .map(|path| Self {
+                path,
+                store: Store::Steam,
+                portable: false,
+                scan_proton_prefixes: None,
+            })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn steam_path_from_registry() -> Option<String> {
+        winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER)
+            .open_subkey("Software\\Valve\\Steam")
+            .and_then(|key| key.get_value::<String, _>("SteamPath"))
+            .ok()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn steam_path_from_registry() -> Option<String> {
+        None
+    }
+
+    fn existing_roots(candidates: Vec<(String, Store)>) -> Vec<Self> {
+        let mut roots = vec![];
+        let mut checked = std::collections::HashSet::<StrictPath>::new();
+        for (path, store) in candidates {
+            let sp = StrictPath::new(path);
+            if checked.contains(&sp) {
+                continue;
+            }
+            if sp.is_dir() {
+                roots.push(Self {
+                    path: sp.clone(),
+                    store,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                });
+            }
+            checked.insert(sp);
+        }
+        roots
+    }
+}
+
+/// The 32-bit and 64-bit Program Files folders on Windows.
+fn program_files() -> [String; 2] {
+    let mut pf32 = "C:/Program Files (x86)".to_string();
+    let mut pf64 = "C:/Program Files".to_string();
+    if let Ok(x) = std::env::var("ProgramFiles(x86)") {
+        pf32 = x.trim_end_matches("[\\/]").to_string();
+    } else if let Ok(x) = std::env::var("PROGRAMFILES") {
+        pf32 = x.trim_end_matches("[\\/]").to_string();
+    }
+    if let Ok(x) = std::env::var("ProgramW6432") {
+        pf64 = x.trim_end_matches("[\\/]").to_string();
+    }
+    [pf32, pf64]
 }
 
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -53,6 +194,78 @@ pub struct BackupFilter {
         rename = "excludeStoreScreenshots"
     )]
     pub exclude_store_screenshots: bool,
+    /// Skip every root of these store types.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "excludeStores")]
+    pub exclude_stores: Vec<Store>,
+    /// Only back up files with these extensions (e.g., `sav`).
+    /// When unset, files are backed up regardless of their extension.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "allowedExtensions",
+        serialize_with = "crate::serialization::ordered_optional_set"
+    )]
+    pub allowed_extensions: Option<std::collections::HashSet<String>>,
+    /// Like `allowed_extensions`, but for specific games, taking precedence over it.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        rename = "gameAllowedExtensions",
+        serialize_with = "crate::serialization::ordered_map_of_sets"
+    )]
+    pub game_allowed_extensions: std::collections::HashMap<String, std::collections::HashSet<String>>,
+    /// Skip files within these folders for specific games, such as a stale save location
+    /// that a game no longer uses.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        rename = "gameExcludedLocations",
+        serialize_with = "crate::serialization::ordered_map_of_sets"
+    )]
+    pub game_excluded_locations: std::collections::HashMap<String, std::collections::HashSet<String>>,
+}
+
+impl BackupFilter {
+    pub fn allowed_extensions_for(&self, game: &str) -> Option<&std::collections::HashSet<String>> {
+        self.game_allowed_extensions
+            .get(game)
+            .or(self.allowed_extensions.as_ref())
+    }
+
+    pub fn excluded_locations_for(&self, game: &str) -> Vec<StrictPath> {
+        excluded_locations(&self.game_excluded_locations, game)
+    }
+
+    /// Add another filter's lists to this one, while its other options take precedence.
+    /// If either filter allows every extension, then so does the result.
+    fn merge(&mut self, other: &Self) {
+        self.exclude_other_os_data = other.exclude_other_os_data;
+        self.exclude_store_screenshots = other.exclude_store_screenshots;
+        for store in &other.exclude_stores {
+            if !self.exclude_stores.contains(store) {
+                self.exclude_stores.push(*store);
+            }
+        }
+        self.allowed_extensions = match (self.allowed_extensions.take(), &other.allowed_extensions) {
+            (Some(mut mine), Some(theirs)) => {
+                mine.extend(theirs.iter().cloned());
+                Some(mine)
+            }
+            _ => None,
+        };
+        merge_map_of_sets(&mut self.game_allowed_extensions, &other.game_allowed_extensions);
+        merge_map_of_sets(&mut self.game_excluded_locations, &other.game_excluded_locations);
+    }
+}
+
+fn excluded_locations(
+    locations: &std::collections::HashMap<String, std::collections::HashSet<String>>,
+    game: &str,
+) -> Vec<StrictPath> {
+    match locations.get(game) {
+        Some(x) => itertools::sorted(x).map(|x| StrictPath::new(x.to_string())).collect(),
+        None => vec![],
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -66,10 +279,61 @@ pub struct BackupConfig {
     pub ignored_games: std::collections::HashSet<String>,
     #[serde(default)]
     pub merge: bool,
+    /// Place each game's backup in a subfolder for the store where it was found,
+    /// like `<path>/steam/<game>`.
+    #[serde(default, rename = "groupByStore")]
+    pub group_by_store: bool,
+    /// Ignore any root that is nested within another root of the same store,
+    /// so that its folders are not scanned twice.
+    #[serde(default, rename = "skipNestedRoots")]
+    pub skip_nested_roots: bool,
+    /// Compress large files that compress well, storing them with a `.zst` extension.
+    #[serde(default, rename = "compressLargeFiles")]
+    pub compress_large_files: bool,
+    /// On Linux, also look for Windows saves inside of Steam's Proton prefixes.
+    #[serde(default = "crate::serialization::default_true", rename = "scanProtonPrefixes")]
+    pub scan_proton_prefixes: bool,
+    /// Look inside of Proton prefixes even when their game is no longer installed.
+    /// Saves found only there are flagged as coming from an uninstalled game.
+    #[serde(
+        default = "crate::serialization::default_true",
+        rename = "includeUninstalledPrefixes"
+    )]
+    pub include_uninstalled_prefixes: bool,
+    /// On Windows, traverse NTFS junction points inside of save folders,
+    /// only visiting each folder once even if junctions form a loop.
+    #[serde(default, rename = "followJunctions")]
+    pub follow_junctions: bool,
+    /// Skip any folder containing a `.nobackup` file, along with everything inside of it.
+    #[serde(default, rename = "respectNobackupFiles")]
+    pub respect_nobackup_files: bool,
+    /// When pruning with `--min-free-space-mib` but no value, remove the oldest
+    /// surplus backups until the backup drive has at least this much free space.
+    #[serde(default, rename = "minFreeSpaceMib", skip_serializing_if = "Option::is_none")]
+    pub min_free_space_mib: Option<u64>,
+    /// Warn about a game when its scan finds less than this percentage
+    /// of the data in its latest backup. Zero disables the warning.
+    #[serde(default = "default_shrink_warning_percent", rename = "shrinkWarningPercent")]
+    pub shrink_warning_percent: u8,
+    /// Put backups in this subfolder of the target, so that several machines
+    /// or users can share a target without replacing each other's backups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
     #[serde(default)]
     pub filter: BackupFilter,
 }
 
+impl BackupConfig {
+    /// This is where backups go: the configured path, plus the namespace, if any.
+    pub fn target(&self) -> StrictPath {
+        namespaced_path(&self.path, &self.namespace)
+    }
+}
+
+fn default_shrink_warning_percent() -> u8 {
+    25
+}
+
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct RestoreConfig {
     pub path: StrictPath,
@@ -81,6 +345,65 @@ pub struct RestoreConfig {
     pub ignored_games: std::collections::HashSet<String>,
     #[serde(default)]
     pub redirects: Vec<RedirectConfig>,
+    #[serde(default)]
+    pub mode: RestoreMode,
+    /// Restore store screenshots along with the saves.
+    /// They're skipped by default, since the store already has them.
+    #[serde(default, rename = "includeScreenshots")]
+    pub include_screenshots: bool,
+    /// Like `BackupFilter::game_excluded_locations`, but for where files would be restored.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        rename = "gameExcludedLocations",
+        serialize_with = "crate::serialization::ordered_map_of_sets"
+    )]
+    pub game_excluded_locations: std::collections::HashMap<String, std::collections::HashSet<String>>,
+    /// Like `BackupConfig::namespace`, for restoring one machine or user's backups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// On Unix, give restored files back to the user and group that owned them
+    /// during backup. This requires root, so files are left as-is otherwise.
+    #[serde(
+        default,
+        rename = "preserveOwnership",
+        skip_serializing_if = "crate::serialization::is_false"
+    )]
+    pub preserve_ownership: bool,
+}
+
+impl RestoreConfig {
+    pub fn excluded_locations_for(&self, game: &str) -> Vec<StrictPath> {
+        excluded_locations(&self.game_excluded_locations, game)
+    }
+
+    /// This is where backups are restored from: the configured path, plus the namespace, if any.
+    pub fn source(&self) -> StrictPath {
+        namespaced_path(&self.path, &self.namespace)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum RestoreMode {
+    /// Replace any existing files at the restoration target.
+    #[serde(rename = "overwrite")]
+    Overwrite,
+    /// Rename any existing files by appending a suffix, then restore.
+    /// If the renamed file would already exist, then a number is appended as well.
+    #[serde(rename = "backupExisting")]
+    BackupExisting {
+        suffix: String,
+        /// Existing files modified within this many days are left alone,
+        /// and the backup's copy of them is skipped.
+        #[serde(default, rename = "minAgeDays", skip_serializing_if = "Option::is_none")]
+        min_age_days: Option<u64>,
+    },
+}
+
+impl Default for RestoreMode {
+    fn default() -> Self {
+        Self::Overwrite
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -92,6 +415,49 @@ pub struct CustomGame {
     pub registry: Vec<String>,
 }
 
+/// Settings that can be shared with another machine, in the same format as the config file.
+/// Roots are specific to each machine, so they are left out, and importing a profile
+/// keeps the current backup/restore paths and namespaces.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PortableProfile {
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub restore: RestoreConfig,
+    #[serde(default, rename = "customGames")]
+    pub custom_games: Vec<CustomGame>,
+}
+
+impl PortableProfile {
+    pub fn load(path: &StrictPath) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path.interpret())
+            .map_err(|_| Error::UnableToImportProfile { path: path.clone() })?;
+        Self::load_from_string(&content)
+    }
+
+    pub fn save(&self, path: &StrictPath) -> Result<(), Error> {
+        path.write_bytes(self.serialize().as_bytes())
+            .map_err(|_| Error::UnableToExportProfile { path: path.clone() })
+    }
+
+    pub fn load_from_string(content: &str) -> Result<Self, Error> {
+        serde_yaml::from_str(content).map_err(|e| Error::ConfigInvalid { why: format!("{}", e) })
+    }
+
+    pub fn serialize(&self) -> String {
+        serde_yaml::to_string(&self).unwrap()
+    }
+}
+
+fn merge_map_of_sets(
+    target: &mut std::collections::HashMap<String, std::collections::HashSet<String>>,
+    source: &std::collections::HashMap<String, std::collections::HashSet<String>>,
+) {
+    for (key, values) in source {
+        target.entry(key.clone()).or_default().extend(values.iter().cloned());
+    }
+}
+
 impl Default for ManifestConfig {
     fn default() -> Self {
         Self {
@@ -107,6 +473,16 @@ impl Default for BackupConfig {
             path: default_backup_dir(),
             ignored_games: std::collections::HashSet::new(),
             merge: false,
+            group_by_store: false,
+            skip_nested_roots: false,
+            compress_large_files: false,
+            scan_proton_prefixes: true,
+            include_uninstalled_prefixes: true,
+            follow_junctions: false,
+            respect_nobackup_files: false,
+            min_free_space_mib: None,
+            shrink_warning_percent: default_shrink_warning_percent(),
+            namespace: None,
             filter: BackupFilter::default(),
         }
     }
@@ -118,12 +494,17 @@ impl Default for RestoreConfig {
             path: default_backup_dir(),
             ignored_games: std::collections::HashSet::new(),
             redirects: vec![],
+            mode: RestoreMode::default(),
+            include_screenshots: false,
+            game_excluded_locations: std::collections::HashMap::new(),
+            namespace: None,
+            preserve_ownership: false,
         }
     }
 }
 
 impl Config {
-    fn file() -> std::path::PathBuf {
+    pub fn file() -> std::path::PathBuf {
         let mut path = app_dir();
         path.push("config.yaml");
         path
@@ -159,57 +540,71 @@ impl Config {
     }
 
     pub fn add_common_roots(&mut self) {
-        let mut pf32 = "C:/Program Files (x86)".to_string();
-        let mut pf64 = "C:/Program Files".to_string();
-        if let Ok(x) = std::env::var("ProgramFiles(x86)") {
-            pf32 = x.trim_end_matches("[\\/]").to_string();
-        } else if let Ok(x) = std::env::var("PROGRAMFILES") {
-            pf32 = x.trim_end_matches("[\\/]").to_string();
-        }
-        if let Ok(x) = std::env::var("ProgramW6432") {
-            pf64 = x.trim_end_matches("[\\/]").to_string();
+        for root in RootsConfig::detect_default_roots() {
+            if !self.roots.contains(&root) {
+                self.roots.push(root);
+            }
         }
+    }
 
-        let candidates = vec![
-            // Steam:
-            (format!("{}/Steam", pf32), Store::Steam),
-            (format!("{}/Steam", pf64), Store::Steam),
-            ("~/.steam/steam".to_string(), Store::Steam),
-            ("~/Library/Application Support/Steam".to_string(), Store::Steam),
-            // Epic:
-            (format!("{}/Epic Games", pf32), Store::Other),
-            (format!("{}/Epic Games", pf64), Store::Other),
-            // GOG:
-            ("C:/GOG Games".to_string(), Store::Other),
-            ("~/GOG Games".to_string(), Store::Other),
-            // GOG Galaxy:
-            (format!("{}/GOG Galaxy/Games", pf32), Store::Other),
-            (format!("{}/GOG Galaxy/Games", pf64), Store::Other),
-            // Uplay:
-            (format!("{}/Ubisoft/Ubisoft Game Launcher/games", pf32), Store::Other),
-            (format!("{}/Ubisoft/Ubisoft Game Launcher/games", pf64), Store::Other),
-            // Origin:
-            (format!("{}/Origin Games", pf32), Store::Other),
-            (format!("{}/Origin Games", pf64), Store::Other),
-            // Microsoft:
-            (format!("{}/WindowsApps", pf32), Store::Other),
-            (format!("{}/WindowsApps", pf64), Store::Other),
-        ];
+    /// Pairs of roots where the second one is within the first (or they are the same folder).
+    fn overlapping_root_indices(&self) -> Vec<(usize, usize)> {
+        let paths: Vec<_> = self
+            .roots
+            .iter()
+            .map(|x| {
+                if x.path.raw().trim().is_empty() {
+                    None
+                } else {
+                    Some(x.path.render())
+                }
+            })
+            .collect();
 
-        let mut checked = std::collections::HashSet::<StrictPath>::new();
-        for (path, store) in candidates {
-            let sp = StrictPath::new(path);
-            if checked.contains(&sp) {
-                continue;
+        let mut overlapping = vec![];
+        for (i, outer) in paths.iter().enumerate() {
+            for (j, nested) in paths.iter().enumerate() {
+                if let (true, Some(outer), Some(nested)) = (i != j, outer, nested) {
+                    if is_within(nested, outer) && (i < j || !is_within(outer, nested)) {
+                        overlapping.push((i, j));
+                    }
+                }
             }
-            if sp.is_dir() {
-                self.roots.push(RootsConfig {
-                    path: sp.clone(),
-                    store,
-                });
-            }
-            checked.insert(sp);
         }
+        overlapping
+    }
+
+    pub fn overlapping_roots(&self) -> Vec<(RootsConfig, RootsConfig)> {
+        self.overlapping_root_indices()
+            .into_iter()
+            .map(|(i, j)| (self.roots[i].clone(), self.roots[j].clone()))
+            .collect()
+    }
+
+    /// The roots to scan for backups, without nested roots if `skipNestedRoots` is enabled.
+    pub fn scan_roots(&self) -> Vec<RootsConfig> {
+        if !self.backup.skip_nested_roots {
+            return self.roots.clone();
+        }
+        let nested: std::collections::HashSet<_> = self
+            .overlapping_root_indices()
+            .into_iter()
+            .filter(|(i, j)| self.skips_nested_root(&self.roots[*i], &self.roots[*j]))
+            .map(|(_, j)| j)
+            .collect();
+        self.roots
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !nested.contains(i))
+            .map(|(_, x)| x.clone())
+            .collect()
+    }
+
+    /// Nested roots are only skipped when they have the same store as the outer root,
+    /// since a root for another store may find saves that the outer root can't
+    /// (e.g., a Steam library inside of a general games folder).
+    pub fn skips_nested_root(&self, outer: &RootsConfig, nested: &RootsConfig) -> bool {
+        self.backup.skip_nested_roots && outer.store == nested.store
     }
 
     pub fn is_game_enabled_for_backup(&self, name: &str) -> bool {
@@ -248,6 +643,83 @@ impl Config {
         self.restore.redirects.to_vec()
     }
 
+    /// Find pairs of redirects where one's source is inside the other's
+    /// (by whole path components, so `/games2` is not inside `/games`).
+    /// Redirects are applied in order, so a file under both sources may be
+    /// redirected twice or not as intended.
+    pub fn find_conflicting_redirects(&self) -> Vec<(RedirectConfig, RedirectConfig)> {
+        let redirects: Vec<_> = self
+            .restore
+            .redirects
+            .iter()
+            .filter(|x| !x.source.raw().trim().is_empty())
+            .collect();
+
+        let mut conflicts = vec![];
+        for (i, first) in redirects.iter().enumerate() {
+            for second in redirects.iter().skip(i + 1) {
+                if first.source.is_under(&second.source) || second.source.is_under(&first.source) {
+                    conflicts.push(((*first).clone(), (*second).clone()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn export_profile(&self) -> PortableProfile {
+        PortableProfile {
+            backup: self.backup.clone(),
+            restore: self.restore.clone(),
+            custom_games: self.custom_games.clone(),
+        }
+    }
+
+    /// Apply a profile's options. Lists (like ignored games and redirects) are added
+    /// to the ones that are already configured, and custom games are added unless
+    /// one with the same name already exists.
+    pub fn import_profile(&mut self, profile: &PortableProfile) {
+        let mut backup = BackupConfig {
+            path: self.backup.path.clone(),
+            namespace: self.backup.namespace.clone(),
+            ignored_games: self.backup.ignored_games.clone(),
+            filter: self.backup.filter.clone(),
+            ..profile.backup.clone()
+        };
+        backup
+            .ignored_games
+            .extend(profile.backup.ignored_games.iter().cloned());
+        backup.filter.merge(&profile.backup.filter);
+        self.backup = backup;
+
+        let mut restore = RestoreConfig {
+            path: self.restore.path.clone(),
+            namespace: self.restore.namespace.clone(),
+            ignored_games: self.restore.ignored_games.clone(),
+            redirects: self.restore.redirects.clone(),
+            game_excluded_locations: self.restore.game_excluded_locations.clone(),
+            ..profile.restore.clone()
+        };
+        restore
+            .ignored_games
+            .extend(profile.restore.ignored_games.iter().cloned());
+        for redirect in &profile.restore.redirects {
+            if !restore.redirects.contains(redirect) {
+                restore.redirects.push(redirect.clone());
+            }
+        }
+        merge_map_of_sets(
+            &mut restore.game_excluded_locations,
+            &profile.restore.game_excluded_locations,
+        );
+        self.restore = restore;
+
+        for game in &profile.custom_games {
+            if !self.custom_games.iter().any(|x| x.name == game.name) {
+                self.custom_games.push(game.clone());
+            }
+        }
+    }
+
     pub fn add_custom_game(&mut self) {
         self.custom_games.push(CustomGame {
             name: "".to_string(),
@@ -260,7 +732,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use maplit::hashset;
+    use maplit::{hashmap, hashset};
     use pretty_assertions::assert_eq;
 
     fn s(text: &str) -> String {
@@ -294,16 +766,36 @@ mod tests {
                     path: StrictPath::new(s("~/backup")),
                     ignored_games: std::collections::HashSet::new(),
                     merge: false,
+                    group_by_store: false,
+                    skip_nested_roots: false,
+                    compress_large_files: false,
+                    scan_proton_prefixes: true,
+                    include_uninstalled_prefixes: true,
+                    follow_junctions: false,
+                    respect_nobackup_files: false,
+                    min_free_space_mib: None,
+                    shrink_warning_percent: 25,
+                    namespace: None,
                     filter: BackupFilter {
                         exclude_other_os_data: false,
                         exclude_store_screenshots: false,
+                        exclude_stores: vec![],
+                        allowed_extensions: None,
+                        game_allowed_extensions: Default::default(),
+                        game_excluded_locations: Default::default(),
                     },
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
                     ignored_games: std::collections::HashSet::new(),
                     redirects: vec![],
+                    mode: RestoreMode::Overwrite,
+                    include_screenshots: false,
+                    game_excluded_locations: Default::default(),
+                    namespace: None,
+                    preserve_ownership: false,
                 },
+                allow_unsafe_paths: false,
                 custom_games: vec![],
             },
             config,
@@ -329,9 +821,21 @@ mod tests {
                 - Backup Game 2
                 - Backup Game 2
               merge: true
+              followJunctions: true
+              respectNobackupFiles: true
               filter:
                 excludeOtherOsData: true
                 excludeStoreScreenshots: true
+                excludeStores:
+                  - steam
+                allowedExtensions:
+                  - sav
+                gameAllowedExtensions:
+                  Game 1:
+                    - dat
+                gameExcludedLocations:
+                  Game 1:
+                    - ~/old-saves
             restore:
               path: ~/restore
               ignoredGames:
@@ -341,6 +845,10 @@ mod tests {
               redirects:
                 - source: ~/old
                   target: ~/new
+              mode:
+                backupExisting:
+                  suffix: .bak
+                  minAgeDays: 7
             customGames:
               - name: Custom Game 1
               - name: Custom Game 2
@@ -366,10 +874,14 @@ mod tests {
                     RootsConfig {
                         path: StrictPath::new(s("~/steam")),
                         store: Store::Steam,
+                        portable: false,
+                        scan_proton_prefixes: None,
                     },
                     RootsConfig {
                         path: StrictPath::new(s("~/other")),
                         store: Store::Other,
+                        portable: false,
+                        scan_proton_prefixes: None,
                     },
                 ],
                 backup: BackupConfig {
@@ -379,9 +891,23 @@ mod tests {
                         s("Backup Game 2"),
                     },
                     merge: true,
+                    group_by_store: false,
+                    skip_nested_roots: false,
+                    compress_large_files: false,
+                    scan_proton_prefixes: true,
+                    include_uninstalled_prefixes: true,
+                    follow_junctions: true,
+                    respect_nobackup_files: true,
+                    min_free_space_mib: None,
+                    shrink_warning_percent: 25,
+                    namespace: None,
                     filter: BackupFilter {
                         exclude_other_os_data: true,
                         exclude_store_screenshots: true,
+                        exclude_stores: vec![Store::Steam],
+                        allowed_extensions: Some(hashset! { s("sav") }),
+                        game_allowed_extensions: hashmap! { s("Game 1") => hashset! { s("dat") } },
+                        game_excluded_locations: hashmap! { s("Game 1") => hashset! { s("~/old-saves") } },
                     },
                 },
                 restore: RestoreConfig {
@@ -394,7 +920,16 @@ mod tests {
                         source: StrictPath::new(s("~/old")),
                         target: StrictPath::new(s("~/new")),
                     },],
+                    mode: RestoreMode::BackupExisting {
+                        suffix: s(".bak"),
+                        min_age_days: Some(7),
+                    },
+                    include_screenshots: false,
+                    game_excluded_locations: Default::default(),
+                    namespace: None,
+                    preserve_ownership: false,
                 },
+                allow_unsafe_paths: false,
                 custom_games: vec![
                     CustomGame {
                         name: s("Custom Game 1"),
@@ -442,27 +977,184 @@ mod tests {
                 roots: vec![RootsConfig {
                     path: StrictPath::new(s("~/other")),
                     store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
                 }],
                 backup: BackupConfig {
                     path: StrictPath::new(s("~/backup")),
                     ignored_games: std::collections::HashSet::new(),
                     merge: false,
+                    group_by_store: false,
+                    skip_nested_roots: false,
+                    compress_large_files: false,
+                    scan_proton_prefixes: true,
+                    include_uninstalled_prefixes: true,
+                    follow_junctions: false,
+                    respect_nobackup_files: false,
+                    min_free_space_mib: None,
+                    shrink_warning_percent: 25,
+                    namespace: None,
                     filter: BackupFilter {
                         exclude_other_os_data: false,
                         exclude_store_screenshots: false,
+                        exclude_stores: vec![],
+                        allowed_extensions: None,
+                        game_allowed_extensions: Default::default(),
+                        game_excluded_locations: Default::default(),
                     },
                 },
                 restore: RestoreConfig {
                     path: StrictPath::new(s("~/restore")),
                     ignored_games: std::collections::HashSet::new(),
                     redirects: vec![],
+                    mode: RestoreMode::Overwrite,
+                    include_screenshots: false,
+                    game_excluded_locations: Default::default(),
+                    namespace: None,
+                    preserve_ownership: false,
                 },
+                allow_unsafe_paths: false,
                 custom_games: vec![],
             },
             config,
         );
     }
 
+    fn nested_roots_config(skip_nested_roots: bool) -> Config {
+        Config {
+            roots: vec![
+                RootsConfig {
+                    path: StrictPath::new(s("/games")),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("/games/gog")),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("/games-steam")),
+                    store: Store::Steam,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("/games/steam")),
+                    store: Store::Steam,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+            ],
+            backup: BackupConfig {
+                skip_nested_roots,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_detect_steam_root_for_each_os() {
+        let find = |os: Os, registry: Option<&str>, existing: &[&str]| {
+            let existing: Vec<_> = existing.iter().map(|x| StrictPath::new(x.to_string())).collect();
+            let program_files = [s("C:/Program Files (x86)"), s("C:/Program Files")];
+            RootsConfig::find_steam_root(&os, registry.map(|x| x.to_string()), &program_files, |path| {
+                existing.contains(path)
+            })
+            .map(|x| (x.path.raw(), x.store))
+        };
+
+        assert_eq!(
+            Some((s("C:/Program Files (x86)/Steam"), Store::Steam)),
+            find(Os::Windows, None, &["C:/Program Files (x86)/Steam"]),
+        );
+        assert_eq!(
+            Some((s("D:/Steam"), Store::Steam)),
+            find(
+                Os::Windows,
+                Some("D:/Steam"),
+                &["D:/Steam", "C:/Program Files (x86)/Steam"]
+            ),
+        );
+        assert_eq!(
+            Some((s("~/.local/share/Steam"), Store::Steam)),
+            find(Os::Linux, None, &["~/.local/share/Steam"]),
+        );
+        assert_eq!(
+            Some((s("~/.steam/steam"), Store::Steam)),
+            find(Os::Linux, None, &["~/.local/share/Steam", "~/.steam/steam"]),
+        );
+        assert_eq!(
+            Some((s("/usr/share/steam"), Store::Steam)),
+            find(Os::Linux, None, &["/usr/share/steam"]),
+        );
+        assert_eq!(
+            Some((s("~/Library/Application Support/Steam"), Store::Steam)),
+            find(Os::Mac, None, &["~/Library/Application Support/Steam"]),
+        );
+        assert_eq!(None, find(Os::Mac, None, &["~/.steam/steam"]));
+        assert_eq!(None, find(Os::Other, None, &["~/.steam/steam"]));
+    }
+
+    #[test]
+    fn can_detect_roots_that_exist() {
+        let repo = env!("CARGO_MANIFEST_DIR");
+        assert_eq!(
+            vec![
+                RootsConfig {
+                    path: StrictPath::new(format!("{}/tests/root1", repo)),
+                    store: Store::Steam,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+                RootsConfig {
+                    path: StrictPath::new(format!("{}/tests/root2", repo)),
+                    store: Store::Other,
+                    portable: false,
+                    scan_proton_prefixes: None,
+                },
+            ],
+            RootsConfig::existing_roots(vec![
+                (format!("{}/tests/root1", repo), Store::Steam),
+                (format!("{}/tests/nonexistent", repo), Store::Steam),
+                (format!("{}/tests/root1", repo), Store::Other),
+                (format!("{}/tests/root2", repo), Store::Other),
+            ]),
+        );
+    }
+
+    #[test]
+    fn can_detect_nested_roots() {
+        let config = nested_roots_config(false);
+        assert_eq!(
+            vec![
+                (config.roots[0].clone(), config.roots[1].clone()),
+                (config.roots[0].clone(), config.roots[3].clone()),
+            ],
+            config.overlapping_roots(),
+        );
+        assert_eq!(config.roots, config.scan_roots());
+    }
+
+    #[test]
+    fn can_skip_nested_roots() {
+        let config = nested_roots_config(true);
+        assert_eq!(
+            vec![
+                config.roots[0].clone(),
+                config.roots[2].clone(),
+                config.roots[3].clone()
+            ],
+            config.scan_roots(),
+        );
+        assert!(config.skips_nested_root(&config.roots[0], &config.roots[1]));
+        assert!(!config.skips_nested_root(&config.roots[0], &config.roots[3]));
+        assert!(!nested_roots_config(false).skips_nested_root(&config.roots[0], &config.roots[1]));
+    }
+
     #[test]
     fn can_be_serialized() {
         assert_eq!(
@@ -483,9 +1175,26 @@ backup:
     - Backup Game 2
     - Backup Game 3
   merge: true
+  groupByStore: false
+  skipNestedRoots: false
+  compressLargeFiles: false
+  scanProtonPrefixes: true
+  includeUninstalledPrefixes: true
+  followJunctions: false
+  respectNobackupFiles: false
+  shrinkWarningPercent: 25
   filter:
     excludeOtherOsData: true
     excludeStoreScreenshots: true
+    excludeStores:
+      - steam
+    allowedExtensions:
+      - dat
+      - sav
+    gameAllowedExtensions:
+      Game 1:
+        - bin
+        - dat
 restore:
   path: ~/restore
   ignoredGames:
@@ -495,6 +1204,11 @@ restore:
   redirects:
     - source: ~/old
       target: ~/new
+  mode:
+    backupExisting:
+      suffix: ".bak"
+  includeScreenshots: false
+allowUnsafePaths: false
 customGames:
   - name: Custom Game 1
     files: []
@@ -519,10 +1233,14 @@ customGames:
                     RootsConfig {
                         path: StrictPath::new(s("~/steam")),
                         store: Store::Steam,
+                        portable: false,
+                        scan_proton_prefixes: None,
                     },
                     RootsConfig {
                         path: StrictPath::new(s("~/other")),
                         store: Store::Other,
+                        portable: false,
+                        scan_proton_prefixes: None,
                     },
                 ],
                 backup: BackupConfig {
@@ -533,9 +1251,23 @@ customGames:
                         s("Backup Game 2"),
                     },
                     merge: true,
+                    group_by_store: false,
+                    skip_nested_roots: false,
+                    compress_large_files: false,
+                    scan_proton_prefixes: true,
+                    include_uninstalled_prefixes: true,
+                    follow_junctions: false,
+                    respect_nobackup_files: false,
+                    min_free_space_mib: None,
+                    shrink_warning_percent: 25,
+                    namespace: None,
                     filter: BackupFilter {
                         exclude_other_os_data: true,
                         exclude_store_screenshots: true,
+                        exclude_stores: vec![Store::Steam],
+                        allowed_extensions: Some(hashset! { s("sav"), s("dat") }),
+                        game_allowed_extensions: hashmap! { s("Game 1") => hashset! { s("dat"), s("bin") } },
+                        game_excluded_locations: Default::default(),
                     },
                 },
                 restore: RestoreConfig {
@@ -549,7 +1281,16 @@ customGames:
                         source: StrictPath::new(s("~/old")),
                         target: StrictPath::new(s("~/new")),
                     },],
+                    mode: RestoreMode::BackupExisting {
+                        suffix: s(".bak"),
+                        min_age_days: None,
+                    },
+                    include_screenshots: false,
+                    game_excluded_locations: Default::default(),
+                    namespace: None,
+                    preserve_ownership: false,
                 },
+                allow_unsafe_paths: false,
                 custom_games: vec![
                     CustomGame {
                         name: s("Custom Game 1"),
@@ -566,4 +1307,109 @@ customGames:
             .unwrap(),
         );
     }
+
+    #[test]
+    fn can_find_conflicting_redirects() {
+        let mut config = Config::default();
+        config.add_redirect(&StrictPath::new(s("/games")), &StrictPath::new(s("/old-games")));
+        config.add_redirect(&StrictPath::new(s("/other")), &StrictPath::new(s("/elsewhere")));
+        config.add_redirect(&StrictPath::new(s("/games/foo")), &StrictPath::new(s("/foo")));
+        config.add_redirect(&StrictPath::new(s("/games2")), &StrictPath::new(s("/bar")));
+        config.add_redirect(&StrictPath::new(s("")), &StrictPath::new(s("/blank")));
+
+        assert_eq!(
+            vec![(
+                RedirectConfig {
+                    source: StrictPath::new(s("/games")),
+                    target: StrictPath::new(s("/old-games")),
+                },
+                RedirectConfig {
+                    source: StrictPath::new(s("/games/foo")),
+                    target: StrictPath::new(s("/foo")),
+                },
+            )],
+            config.find_conflicting_redirects()
+        );
+    }
+
+    #[test]
+    fn can_round_trip_portable_profile() {
+        let mut config = Config::default();
+        config.backup.ignored_games = hashset! { s("Backup Game") };
+        config.backup.merge = true;
+        config.backup.filter.exclude_stores = vec![Store::Steam];
+        config.backup.filter.allowed_extensions = Some(hashset! { s("sav") });
+        config.backup.filter.game_excluded_locations = hashmap! { s("Game") => hashset! { s("/stale") } };
+        config.restore.ignored_games = hashset! { s("Restore Game") };
+        config.add_redirect(&StrictPath::new(s("/old")), &StrictPath::new(s("/new")));
+        config.custom_games.push(CustomGame {
+            name: s("Custom Game"),
+            files: vec![s("/custom")],
+            registry: vec![],
+        });
+        config.roots.push(RootsConfig {
+            path: StrictPath::new(s("/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        });
+
+        let temp = crate::testing::TempDir::new("profile");
+        let file = temp.joined("profile.yaml");
+        config.export_profile().save(&file).unwrap();
+        let serialized = std::fs::read_to_string(file.interpret()).unwrap();
+        let loaded = PortableProfile::load(&file).unwrap();
+        assert_eq!(config.export_profile(), loaded);
+        assert!(!serialized.contains("/steam"));
+
+        let mut other = Config::default();
+        other.backup.path = StrictPath::new(s("/other-backup"));
+        other.backup.namespace = Some(s("laptop"));
+        other.backup.filter.exclude_stores = vec![Store::Other];
+        other.backup.filter.allowed_extensions = Some(hashset! { s("dat") });
+        other.backup.filter.game_excluded_locations = hashmap! { s("Game") => hashset! { s("/older") } };
+        other.restore.ignored_games = hashset! { s("Other Game") };
+        other.add_redirect(&StrictPath::new(s("/old")), &StrictPath::new(s("/new")));
+        other.add_redirect(&StrictPath::new(s("/mine")), &StrictPath::new(s("/ours")));
+        other.custom_games.push(CustomGame {
+            name: s("Custom Game"),
+            files: vec![s("/mine")],
+            registry: vec![],
+        });
+        other.import_profile(&loaded);
+
+        assert!(other.backup.merge);
+        assert_eq!(StrictPath::new(s("/other-backup")), other.backup.path);
+        assert_eq!(Some(s("laptop")), other.backup.namespace);
+        assert_eq!(hashset! { s("Backup Game") }, other.backup.ignored_games);
+        assert_eq!(vec![Store::Other, Store::Steam], other.backup.filter.exclude_stores);
+        assert_eq!(
+            Some(hashset! { s("dat"), s("sav") }),
+            other.backup.filter.allowed_extensions
+        );
+        assert_eq!(
+            hashmap! { s("Game") => hashset! { s("/older"), s("/stale") } },
+            other.backup.filter.game_excluded_locations
+        );
+        assert_eq!(
+            hashset! { s("Restore Game"), s("Other Game") },
+            other.restore.ignored_games
+        );
+        assert_eq!(
+            vec![
+                RedirectConfig {
+                    source: StrictPath::new(s("/old")),
+                    target: StrictPath::new(s("/new")),
+                },
+                RedirectConfig {
+                    source: StrictPath::new(s("/mine")),
+                    target: StrictPath::new(s("/ours")),
+                },
+            ],
+            other.restore.redirects
+        );
+        assert_eq!(vec![s("/mine")], other.custom_games[0].files);
+        assert_eq!(1, other.custom_games.len());
+        assert!(other.roots.is_empty());
+    }
 }
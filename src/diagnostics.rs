@@ -0,0 +1,345 @@
+//! A summary of the environment for bug reports, from the `self-check` command.
+//! This is meant to be pasted into an issue as-is, so it is not translated.
+
+use crate::{
+    config::{Config, RootsConfig},
+    manifest::{Manifest, Os},
+    prelude::{app_dir, app_version, check_backup_target, display_path, get_os, BackupTargetHealth, StrictPath},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathCheck {
+    pub path: StrictPath,
+    pub exists: bool,
+    pub writable: bool,
+}
+
+impl PathCheck {
+    /// For a file, such as the config. It counts as writable if it can be opened for writing.
+    pub fn file(path: &StrictPath) -> Self {
+        let writable = std::fs::OpenOptions::new().append(true).open(path.interpret()).is_ok();
+        Self {
+            path: path.clone(),
+            exists: path.is_file(),
+            writable,
+        }
+    }
+
+    /// For a folder, with the same check that the CLI and GUI use for the backup target.
+    pub fn folder(path: &StrictPath, health: &BackupTargetHealth) -> Self {
+        Self {
+            path: path.clone(),
+            exists: health.exists,
+            writable: health.writable,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfCheck {
+    pub version: String,
+    pub os: Os,
+    pub app_dir: StrictPath,
+    pub config_file: PathCheck,
+    /// This is the reason why the config could not be loaded, if it couldn't.
+    /// The config-based checks are empty in that case.
+    pub config_error: Option<String>,
+    pub manifest_file: PathCheck,
+    pub manifest_age: Option<std::time::Duration>,
+    /// This is unset if the manifest could not be loaded.
+    pub manifest_entries: Option<usize>,
+    pub backup_target: Option<PathCheck>,
+    pub backup_free_bytes: Option<u64>,
+    pub restore_source: Option<PathCheck>,
+    pub roots: Vec<(RootsConfig, bool)>,
+    /// These use the same checks as the warnings in the CLI and GUI.
+    pub overlapping_roots: Vec<(RootsConfig, RootsConfig)>,
+    pub conflicting_redirects: Vec<(StrictPath, StrictPath)>,
+}
+
+impl SelfCheck {
+    pub fn gather(config: &Result<Config, String>) -> Self {
+        let app_dir = StrictPath::from_std_path_buf(&app_dir());
+        let manifest_file = StrictPath::from_std_path_buf(&Manifest::file());
+
+        let mut check = Self {
+            version: app_version().to_string(),
+            os: get_os(),
+            config_file: PathCheck::file(&StrictPath::from_std_path_buf(&Config::file())),
+            config_error: None,
+            manifest_file: PathCheck::file(&manifest_file),
+            manifest_age: std::fs::metadata(manifest_file.interpret())
+                .and_then(|x| x.modified())
+                .ok()
+                .and_then(|x| x.elapsed().ok()),
+            manifest_entries: Manifest::load_from_file(&manifest_file).ok().map(|x| x.len()),
+            backup_target: None,
+            backup_free_bytes: None,
+            restore_source: None,
+            roots: vec![],
+            overlapping_roots: vec![],
+            conflicting_redirects: vec![],
+            app_dir,
+        };
+
+        let config = match config {
+            Ok(x) => x,
+            Err(why) => {
+                check.config_error = Some(why.clone());
+                return check;
+            }
+        };
+
+        let backup_target = config.backup.target();
+        let backup_health = check_backup_target(&backup_target);
+        check.backup_free_bytes = backup_health.free_bytes;
+        check.backup_target = Some(PathCheck::folder(&backup_target, &backup_health));
+        let restore_source = config.restore.source();
+        check.restore_source = Some(PathCheck::folder(
+            &restore_source,
+            &check_backup_target(&restore_source),
+        ));
+        check.roots = config.roots.iter().map(|x| (x.clone(), x.path.is_dir())).collect();
+        check.overlapping_roots = config.overlapping_roots();
+        check.conflicting_redirects = config
+            .find_conflicting_redirects()
+            .into_iter()
+            .map(|(first, second)| (first.source, second.source))
+            .collect();
+
+        check
+    }
+
+    /// With `redact`, the home folder is replaced by `<home>` and the current
+    /// user's name by `<user>` wherever they appear in paths.
+    pub fn render(&self, redact: bool) -> String {
+        let username = if redact { whoami::username() } else { String::new() };
+        let show = |path: &StrictPath| {
+            if redact {
+                redact_username(&display_path(path, &[]), &username)
+            } else {
+                path.render()
+            }
+        };
+        let show_check = |check: &PathCheck| {
+            format!(
+                "{} ({}, {})",
+                show(&check.path),
+                if check.exists { "exists" } else { "missing" },
+                if check.writable { "writable" } else { "not writable" },
+            )
+        };
+
+        let mut lines = vec![
+            "### Ludusavi self-check".to_string(),
+            format!("* Version: {}", self.version),
+            format!("* OS: {}", format!("{:?}", self.os).to_lowercase()),
+            format!("* App folder: {}", show(&self.app_dir)),
+            format!("* Config: {}", show_check(&self.config_file)),
+        ];
+        if let Some(why) = &self.config_error {
+            lines.push(format!("  * Unable to load: {}", why));
+        }
+
+        let mut manifest = format!("* Manifest: {}", show_check(&self.manifest_file));
+        if let Some(age) = self.manifest_age {
+            manifest.push_str(&format!(", updated {:.1} day(s) ago", age.as_secs_f64() / 86400.0));
+        }
+        match self.manifest_entries {
+            Some(entries) => manifest.push_str(&format!(", {} entries", entries)),
+            None => manifest.push_str(", unable to load"),
+        }
+        lines.push(manifest);
+
+        if let Some(target) = &self.backup_target {
+            let mut line = format!("* Backup target: {}", show_check(target));
+            if let Some(bytes) = self.backup_free_bytes {
+                line.push_str(&format!(", {:.2} MiB free", bytes as f64 / 1024.0 / 1024.0));
+            }
+            lines.push(line);
+        }
+        if let Some(source) = &self.restore_source {
+            lines.push(format!("* Restore source: {}", show_check(source)));
+        }
+
+        if self.config_error.is_none() {
+            lines.push(format!("* Roots: {}", self.roots.len()));
+        }
+        for (root, exists) in &self.roots {
+            lines.push(format!(
+                "  * {} [{}] ({})",
+                show(&root.path),
+                format!("{:?}", root.store).to_lowercase(),
+                if *exists { "exists" } else { "missing" },
+            ));
+        }
+        for (outer, nested) in &self.overlapping_roots {
+            lines.push(format!(
+                "* Overlapping roots: {} contains {}",
+                show(&outer.path),
+                show(&nested.path)
+            ));
+        }
+        for (first, second) in &self.conflicting_redirects {
+            lines.push(format!("* Conflicting redirects: {} and {}", show(first), show(second)));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Replace whole path components that match the username.
+fn redact_username(path: &str, username: &str) -> String {
+    if username.is_empty() {
+        return path.to_string();
+    }
+    path.split_inclusive(&['/', '\\'][..])
+        .map(|part| {
+            let name = part.trim_end_matches(&['/', '\\'][..]);
+            if name == username {
+                part.replacen(name, "<user>", 1)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Store;
+    use crate::testing::TempDir;
+    use pretty_assertions::assert_eq;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    #[test]
+    fn can_redact_username_in_paths() {
+        assert_eq!("/home/<user>/saves", redact_username("/home/alice/saves", "alice"));
+        assert_eq!(
+            "C:\\Users\\<user>\\alice2",
+            redact_username("C:\\Users\\alice\\alice2", "alice")
+        );
+        assert_eq!("/home/alice", redact_username("/home/alice", ""));
+    }
+
+    #[test]
+    fn can_check_paths() {
+        let temp = TempDir::new("self-check");
+        let file = temp.write("config.yaml", "");
+        let folder = temp.path().clone();
+        let missing = temp.joined("missing");
+
+        assert_eq!(
+            PathCheck {
+                path: file.clone(),
+                exists: true,
+                writable: true,
+            },
+            PathCheck::file(&file),
+        );
+        assert_eq!(
+            PathCheck {
+                path: folder.clone(),
+                exists: true,
+                writable: true,
+            },
+            PathCheck::folder(&folder, &check_backup_target(&folder)),
+        );
+        assert_eq!(
+            PathCheck {
+                path: missing.clone(),
+                exists: false,
+                writable: false,
+            },
+            PathCheck::file(&missing),
+        );
+        assert_eq!(1, std::fs::read_dir(folder.interpret()).unwrap().count());
+    }
+
+    #[test]
+    fn can_render_self_check() {
+        let check = SelfCheck {
+            version: s("0.6.0"),
+            os: Os::Linux,
+            app_dir: StrictPath::new(s("/home/alice/.config/ludusavi")),
+            config_file: PathCheck {
+                path: StrictPath::new(s("/home/alice/.config/ludusavi/config.yaml")),
+                exists: true,
+                writable: true,
+            },
+            config_error: None,
+            manifest_file: PathCheck {
+                path: StrictPath::new(s("/home/alice/.config/ludusavi/manifest.yaml")),
+                exists: true,
+                writable: false,
+            },
+            manifest_age: Some(std::time::Duration::from_secs(86400 * 3 / 2)),
+            manifest_entries: Some(12),
+            backup_target: Some(PathCheck {
+                path: StrictPath::new(s("/mnt/backup")),
+                exists: true,
+                writable: true,
+            }),
+            backup_free_bytes: Some(1024 * 1024 * 3),
+            restore_source: Some(PathCheck {
+                path: StrictPath::new(s("/mnt/restore")),
+                exists: false,
+                writable: false,
+            }),
+            roots: vec![
+                (
+                    RootsConfig {
+                        path: StrictPath::new(s("/games")),
+                        store: Store::Other,
+                        ..Default::default()
+                    },
+                    true,
+                ),
+                (
+                    RootsConfig {
+                        path: StrictPath::new(s("/games/steam")),
+                        store: Store::Steam,
+                        ..Default::default()
+                    },
+                    false,
+                ),
+            ],
+            overlapping_roots: vec![(
+                RootsConfig {
+                    path: StrictPath::new(s("/games")),
+                    store: Store::Other,
+                    ..Default::default()
+                },
+                RootsConfig {
+                    path: StrictPath::new(s("/games/steam")),
+                    store: Store::Steam,
+                    ..Default::default()
+                },
+            )],
+            conflicting_redirects: vec![],
+        };
+
+        assert_eq!(
+            r#"
+### Ludusavi self-check
+* Version: 0.6.0
+* OS: linux
+* App folder: /home/alice/.config/ludusavi
+* Config: /home/alice/.config/ludusavi/config.yaml (exists, writable)
+* Manifest: /home/alice/.config/ludusavi/manifest.yaml (exists, not writable), updated 1.5 day(s) ago, 12 entries
+* Backup target: /mnt/backup (exists, writable), 3.00 MiB free
+* Restore source: /mnt/restore (missing, not writable)
+* Roots: 2
+  * /games [other] (exists)
+  * /games/steam [steam] (missing)
+* Overlapping roots: /games contains /games/steam
+            "#
+            .trim(),
+            check.render(false),
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{
+    cli::GuiScreen,
     config::{Config, RootsConfig},
     lang::Translator,
     layout::BackupLayout,
-    manifest::{Game, Manifest, SteamMetadata, Store},
+    manifest::{Manifest, Store},
     prelude::{
-        app_dir, back_up_game, game_file_restoration_target, prepare_backup_target, restore_game, scan_game_for_backup,
-        scan_game_for_restoration, BackupInfo, Error, OperationStatus, OperationStepDecision, ScanInfo, StrictPath,
+        change_file_owner, check_backup_target as check_backup_target_health, display_path,
+        game_file_restoration_target, list_existing_files, open_game_backup_dir, prepare_backup_target, restore_games,
+        scan_game_for_restoration, BackupInfo, BackupRun, BackupRunOptions, BackupTargetHealth, CancelToken, Error,
+        GameSelection, OperationStatus, OperationStepDecision, RestoreOptions, RetryPolicy, ScanInfo, StrictPath,
     },
     shortcuts::{Shortcut, TextHistory},
 };
@@ -14,7 +17,7 @@ use iced::{
     button, executor,
     keyboard::{KeyCode, ModifiersState},
     scrollable, text_input, Align, Application, Button, Checkbox, Column, Command, Container, Element, Font,
-    HorizontalAlignment, Length, ProgressBar, Radio, Row, Scrollable, Space, Subscription, Text, TextInput,
+    HorizontalAlignment, Length, ProgressBar, Radio, Rectangle, Row, Scrollable, Space, Subscription, Text, TextInput,
 };
 use native_dialog::Dialog;
 
@@ -108,8 +111,11 @@ struct App {
     restore_screen: RestoreScreenComponent,
     custom_games_screen: CustomGamesScreenComponent,
     other_screen: OtherScreenComponent,
-    operation_should_cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    operation_should_cancel: CancelToken,
     progress: DisappearingProgress,
+    /// This is measured from the first game that gets backed up,
+    /// then used to estimate the backup time of other games.
+    backup_throughput: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -119,10 +125,12 @@ enum Message {
     ConfirmBackupStart,
     BackupStart {
         preview: bool,
+        games: Option<Vec<String>>,
     },
     ConfirmRestoreStart,
     RestoreStart {
         preview: bool,
+        games: Option<Vec<String>>,
     },
     BackupStep {
         scan_info: Option<ScanInfo>,
@@ -138,6 +146,11 @@ enum Message {
     BackupComplete,
     RestoreComplete,
     EditedBackupTarget(String),
+    CheckBackupTarget,
+    CheckedBackupTarget {
+        path: StrictPath,
+        health: Option<BackupTargetHealth>,
+    },
     EditedBackupMerge(bool),
     EditedRestoreSource(String),
     EditedRoot(EditAction),
@@ -148,6 +161,9 @@ enum Message {
     EditedCustomGameRegistry(usize, EditAction),
     EditedExcludeOtherOsData(bool),
     EditedExcludeStoreScreenshots(bool),
+    EditedGroupByStore(bool),
+    EditedCompressLargeFiles(bool),
+    EditedIncludeScreenshots(bool),
     SwitchScreen(Screen),
     ToggleGameListEntryExpanded {
         name: String,
@@ -157,10 +173,30 @@ enum Message {
         enabled: bool,
         restoring: bool,
     },
+    OpenGameBackupDir {
+        name: String,
+        restoring: bool,
+    },
     BrowseDir(BrowseSubject),
     BrowseDirFailure,
     SelectAllGames,
     DeselectAllGames,
+    EditedSearchGameName {
+        restoring: bool,
+        text: String,
+    },
+    AppliedSearchGameName {
+        restoring: bool,
+        generation: u64,
+    },
+    ShowMoreGameListEntryLines {
+        name: String,
+        restoring: bool,
+    },
+    ToggleGameListTimings {
+        restoring: bool,
+        enabled: bool,
+    },
     SubscribedEvent(iced_native::Event),
 }
 
@@ -187,7 +223,7 @@ enum Screen {
 #[derive(Debug, Clone, PartialEq)]
 enum ModalTheme {
     Error { variant: Error },
-    ConfirmBackup,
+    ConfirmBackup { existing_files: usize },
     ConfirmRestore,
 }
 
@@ -286,8 +322,14 @@ impl ModalComponent {
         )
         .on_press(match theme {
             ModalTheme::Error { .. } => Message::Idle,
-            ModalTheme::ConfirmBackup => Message::BackupStart { preview: false },
-            ModalTheme::ConfirmRestore => Message::RestoreStart { preview: false },
+            ModalTheme::ConfirmBackup { .. } => Message::BackupStart {
+                preview: false,
+                games: None,
+            },
+            ModalTheme::ConfirmRestore => Message::RestoreStart {
+                preview: false,
+                games: None,
+            },
         })
         .width(Length::Units(125))
         .style(style::Button::Primary);
@@ -321,13 +363,14 @@ impl ModalComponent {
                                 .align_items(Align::Center)
                                 .push(Text::new(match theme {
                                     ModalTheme::Error { variant } => translator.handle_error(variant),
-                                    ModalTheme::ConfirmBackup => translator.modal_confirm_backup(
-                                        &config.backup.path,
-                                        config.backup.path.exists(),
+                                    ModalTheme::ConfirmBackup { existing_files } => translator.modal_confirm_backup(
+                                        &config.backup.target(),
+                                        config.backup.target().exists(),
                                         config.backup.merge,
+                                        *existing_files,
                                     ),
                                     ModalTheme::ConfirmRestore => {
-                                        translator.modal_confirm_restore(&config.restore.path)
+                                        translator.modal_confirm_restore(&config.restore.source())
                                     }
                                 }))
                                 .height(Length::Fill),
@@ -356,45 +399,212 @@ impl ModalComponent {
     }
 }
 
+/// Iced lays out every row in a scrollable on every frame, so the game list only
+/// builds the rows near the scroll position and stands in for the rest with blank space.
+/// These are the approximate heights used to place that space.
+const GAME_LIST_ENTRY_HEIGHT: u32 = 44;
+const GAME_LIST_LINE_HEIGHT: u32 = 24;
+/// This covers the tallest window that the list could fill.
+const GAME_LIST_VIEWPORT_HEIGHT: u32 = 2160;
+/// How far beyond the viewport to build rows, so that estimates which are a bit off
+/// don't leave a gap while scrolling.
+const GAME_LIST_OVERSCAN_HEIGHT: u32 = 1000;
+/// How many lines of an expanded game are shown before the user asks for more.
+const GAME_LIST_ENTRY_PAGE_SIZE: usize = 200;
+/// How long to wait after the last keystroke before filtering the game list.
+const SEARCH_DEBOUNCE_MS: u64 = 250;
+/// How long to wait for the backup target before reporting it as unreachable.
+const BACKUP_TARGET_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Text for a game list entry that is too expensive to rebuild on every frame.
+/// It is built when first shown and cleared when its inputs change.
+#[derive(Default)]
+struct GameListEntryCache {
+    title: String,
+    size: String,
+    /// This is only set when screenshots were found.
+    screenshot_size: Option<String>,
+    timing: String,
+    /// This is only built once the entry is expanded.
+    lines: Option<Vec<String>>,
+}
+
 #[derive(Default)]
 struct GameListEntry {
     scan_info: ScanInfo,
     backup_info: Option<BackupInfo>,
     button: button::State,
+    open_button: button::State,
+    more_lines_button: button::State,
     expanded: bool,
+    /// How many extra pages of lines to show when expanded.
+    extra_line_pages: usize,
+    /// This is only set for games that have not been backed up yet.
+    estimated_backup_time: Option<std::time::Duration>,
+    cache: Option<GameListEntryCache>,
+}
+
+fn game_list_entry_title(scan_info: &ScanInfo, backup_info: &Option<BackupInfo>, translator: &Translator) -> String {
+    let successful = match backup_info {
+        Some(x) => x.successful(),
+        _ => true,
+    };
+    let total_failure = match backup_info {
+        Some(x) => x.is_total_failure(),
+        _ => false,
+    };
+    let registry_only = match &scan_info.classification {
+        Some(x) => x.registry_only(),
+        None => false,
+    };
+
+    if scan_info.backup_is_broken() {
+        translator.game_list_entry_title_broken(&scan_info.game_name)
+    } else if total_failure {
+        translator.game_list_entry_title_failed(&scan_info.game_name)
+    } else if !successful {
+        translator.game_list_entry_title_partially_failed(&scan_info.game_name)
+    } else if scan_info.shrunk_from_bytes.is_some() {
+        translator.game_list_entry_title_shrunk(&scan_info.game_name)
+    } else if registry_only {
+        translator.game_list_entry_title_registry_only(&scan_info.game_name)
+    } else if scan_info.uninstalled_prefix {
+        translator.game_list_entry_title_uninstalled(&scan_info.game_name)
+    } else {
+        scan_info.game_name.clone()
+    }
+}
+
+fn game_list_entry_lines(
+    scan_info: &ScanInfo,
+    backup_info: &Option<BackupInfo>,
+    translator: &Translator,
+    config: &Config,
+) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    let redirects = config.get_redirects();
+
+    if let Some(backup_info) = backup_info {
+        if !backup_info.successful() {
+            lines.push(backup_info.summary());
+        }
+    }
+    for group in scan_info.ambiguous_locations() {
+        let locations: Vec<_> = group
+            .iter()
+            .map(|x| (display_path(&x.path, &config.roots), x.modified))
+            .collect();
+        lines.push(translator.ambiguous_locations(&locations));
+    }
+    for item in itertools::sorted(&scan_info.found_files) {
+        let mut redirected_from = None;
+        let mut line = display_path(&item.path, &config.roots);
+        if let Some(original_path) = &item.original_path {
+            let (target, original_target) = game_file_restoration_target(&original_path, &redirects);
+            redirected_from = original_target;
+            line = display_path(&target, &config.roots);
+        }
+        let corrupted = match &scan_info.integrity {
+            Some(x) => x.corrupted_files.contains(&item),
+            None => false,
+        };
+        if corrupted {
+            line = translator.corrupted_file_entry_line(&line);
+        } else if let Some(backup_info) = backup_info {
+            if backup_info.failed_files.contains(&item) {
+                line = translator.failed_file_entry_line(&line);
+            } else if backup_info.skipped_files.contains(&item) {
+                line = translator.skipped_file_entry_line(&line);
+            }
+        }
+        lines.push(line);
+        if let Some(redirected_from) = redirected_from {
+            lines.push(translator.redirected_file_entry_line(&display_path(&redirected_from, &config.roots)));
+        }
+        if let Some(backup_info) = backup_info {
+            if backup_info.restored_with_stored_names.contains(&item) {
+                lines.push(translator.restored_with_stored_name_entry_line());
+            }
+            if backup_info.owner_not_restored(&item) {
+                lines.push(translator.unrestored_owner_entry_line());
+            }
+        }
+    }
+    if let Some(integrity) = &scan_info.integrity {
+        for item in itertools::sorted(&integrity.missing_files) {
+            lines.push(translator.missing_file_entry_line(&display_path(&item, &config.roots)));
+        }
+    }
+    for item in itertools::sorted(&scan_info.found_registry_keys) {
+        lines.push(item.clone());
+    }
+    if let Some(backup_info) = backup_info {
+        for item in itertools::sorted(&backup_info.registry_type_conflicts) {
+            lines.push(translator.registry_type_conflict_entry_line(item));
+        }
+        for item in itertools::sorted(&backup_info.unmapped_registry_keys) {
+            lines.push(translator.unmapped_registry_key_entry_line(item));
+        }
+    }
+    for item in itertools::sorted(&scan_info.unsafe_paths) {
+        lines.push(translator.unsafe_path_entry_line(&display_path(item, &config.roots)));
+    }
+
+    lines
 }
 
 impl GameListEntry {
-    fn view(&mut self, restoring: bool, translator: &Translator, config: &Config) -> Container<Message> {
-        let mut lines = Vec::<String>::new();
+    fn duration(&self) -> std::time::Duration {
+        self.scan_info.duration.0 + self.backup_info.as_ref().map(|x| x.duration.0).unwrap_or_default()
+    }
+
+    /// How tall the entry is roughly drawn, including the space after it.
+    fn estimated_height(&self) -> u32 {
+        if !self.expanded {
+            return GAME_LIST_ENTRY_HEIGHT;
+        }
+        let lines = match self.cache.as_ref().and_then(|x| x.lines.as_ref()) {
+            Some(lines) => lines.len(),
+            None => self.scan_info.found_files.len() + self.scan_info.found_registry_keys.len(),
+        };
+        let limit = (1 + self.extra_line_pages) * GAME_LIST_ENTRY_PAGE_SIZE;
+        let more_button = if lines > limit { GAME_LIST_LINE_HEIGHT } else { 0 };
+        GAME_LIST_ENTRY_HEIGHT + lines.min(limit) as u32 * GAME_LIST_LINE_HEIGHT + more_button
+    }
+
+    fn view(
+        &mut self,
+        restoring: bool,
+        translator: &Translator,
+        config: &Config,
+        show_timings: bool,
+    ) -> Container<Message> {
         let successful = match &self.backup_info {
             Some(x) => x.successful(),
             _ => true,
         };
-
-        if self.expanded {
-            for item in itertools::sorted(&self.scan_info.found_files) {
-                let mut redirected_from = None;
-                let mut line = item.path.render();
-                if let Some(original_path) = &item.original_path {
-                    let (target, original_target) =
-                        game_file_restoration_target(&original_path, &config.get_redirects());
-                    redirected_from = original_target;
-                    line = target.render();
-                }
-                if let Some(backup_info) = &self.backup_info {
-                    if backup_info.failed_files.contains(&item) {
-                        line = translator.failed_file_entry_line(&line);
-                    }
-                }
-                lines.push(line);
-                if let Some(redirected_from) = redirected_from {
-                    lines.push(translator.redirected_file_entry_line(&redirected_from));
-                }
-            }
-            for item in itertools::sorted(&self.scan_info.found_registry_keys) {
-                lines.push(item.clone());
-            }
+        let broken = self.scan_info.backup_is_broken();
+
+        let scan_info = &self.scan_info;
+        let backup_info = &self.backup_info;
+        let cache = self.cache.get_or_insert_with(|| GameListEntryCache {
+            title: game_list_entry_title(scan_info, backup_info, translator),
+            size: translator.mib(scan_info.sum_bytes(backup_info), false),
+            screenshot_size: match scan_info.screenshot_bytes() {
+                0 => None,
+                bytes => Some(translator.screenshots_size(bytes)),
+            },
+            timing: match self.estimated_backup_time {
+                Some(estimate) => translator.game_timing_with_estimate(&scan_info.duration, estimate),
+                None => translator.game_timing(
+                    &scan_info.duration,
+                    &backup_info.as_ref().map(|x| x.duration).unwrap_or_default(),
+                ),
+            },
+            lines: None,
+        });
+        if self.expanded && cache.lines.is_none() {
+            cache.lines = Some(game_list_entry_lines(scan_info, backup_info, translator, config));
         }
 
         let enabled = if restoring {
@@ -404,85 +614,249 @@ impl GameListEntry {
         };
         let name_for_checkbox = self.scan_info.game_name.clone();
 
+        let mut title_row = Row::new()
+            .push(Checkbox::new(enabled, "", move |enabled| {
+                Message::ToggleGameListEntryEnabled {
+                    name: name_for_checkbox.clone(),
+                    enabled,
+                    restoring,
+                }
+            }))
+            .push(
+                Button::new(
+                    &mut self.button,
+                    Text::new(&cache.title).horizontal_alignment(HorizontalAlignment::Center),
+                )
+                .on_press(Message::ToggleGameListEntryExpanded {
+                    name: self.scan_info.game_name.clone(),
+                })
+                .style(if !enabled {
+                    style::Button::GameListEntryTitleDisabled
+                } else if successful && !broken {
+                    style::Button::GameListEntryTitle
+                } else {
+                    style::Button::GameListEntryTitleFailed
+                })
+                .width(Length::Fill)
+                .padding(2),
+            );
+        if show_timings {
+            title_row = title_row.push(
+                Container::new(Text::new(&cache.timing))
+                    .width(Length::Units(230))
+                    .center_x(),
+            );
+        }
+        if let Some(screenshot_size) = &cache.screenshot_size {
+            title_row = title_row.push(
+                Container::new(Text::new(screenshot_size))
+                    .width(Length::Units(180))
+                    .center_x(),
+            );
+        }
+        title_row = title_row.push(
+            Container::new(Text::new(&cache.size))
+                .width(Length::Units(115))
+                .center_x(),
+        );
+        title_row = title_row.push(
+            Button::new(&mut self.open_button, Icon::FolderOpen.as_text())
+                .on_press(Message::OpenGameBackupDir {
+                    name: self.scan_info.game_name.clone(),
+                    restoring,
+                })
+                .style(style::Button::Primary)
+                .padding(2),
+        );
+
+        let mut body = Column::new().width(Length::Fill);
+        if let (true, Some(lines)) = (self.expanded, &cache.lines) {
+            let limit = (1 + self.extra_line_pages) * GAME_LIST_ENTRY_PAGE_SIZE;
+            body = body.push(
+                Container::new(Text::new(&lines[..lines.len().min(limit)].join("\n")))
+                    .width(Length::Fill)
+                    .style(style::Container::GameListEntryBody),
+            );
+            if lines.len() > limit {
+                body = body.push(
+                    Button::new(
+                        &mut self.more_lines_button,
+                        Text::new(&translator.show_more_button(lines.len() - limit))
+                            .horizontal_alignment(HorizontalAlignment::Center),
+                    )
+                    .on_press(Message::ShowMoreGameListEntryLines {
+                        name: self.scan_info.game_name.clone(),
+                        restoring,
+                    })
+                    .style(style::Button::Primary)
+                    .padding(2),
+                );
+            }
+        }
+
         Container::new(
             Column::new()
                 .padding(5)
                 .spacing(5)
                 .align_items(Align::Center)
-                .push(
-                    Row::new()
-                        .push(Checkbox::new(enabled, "", move |enabled| {
-                            Message::ToggleGameListEntryEnabled {
-                                name: name_for_checkbox.clone(),
-                                enabled,
-                                restoring,
-                            }
-                        }))
-                        .push(
-                            Button::new(
-                                &mut self.button,
-                                Text::new(if successful {
-                                    self.scan_info.game_name.clone()
-                                } else {
-                                    translator.game_list_entry_title_failed(&self.scan_info.game_name)
-                                })
-                                .horizontal_alignment(HorizontalAlignment::Center),
-                            )
-                            .on_press(Message::ToggleGameListEntryExpanded {
-                                name: self.scan_info.game_name.clone(),
-                            })
-                            .style(if !enabled {
-                                style::Button::GameListEntryTitleDisabled
-                            } else if successful {
-                                style::Button::GameListEntryTitle
-                            } else {
-                                style::Button::GameListEntryTitleFailed
-                            })
-                            .width(Length::Fill)
-                            .padding(2),
-                        )
-                        .push(
-                            Container::new(Text::new(
-                                translator.mib(self.scan_info.sum_bytes(&self.backup_info), false),
-                            ))
-                            .width(Length::Units(115))
-                            .center_x(),
-                        ),
-                )
-                .push(
-                    Row::new().push(
-                        Container::new(Text::new(lines.join("\n")))
-                            .width(Length::Fill)
-                            .style(style::Container::GameListEntryBody),
-                    ),
-                ),
+                .push(title_row)
+                .push(Row::new().push(body)),
         )
         .style(style::Container::GameListEntry)
     }
 }
 
+/// Blank space can only be so tall, so a long stretch is split up.
+fn push_game_list_space(mut list: Scrollable<'_, Message>, mut height: u32) -> Scrollable<'_, Message> {
+    while height > 0 {
+        let part = height.min(u16::MAX as u32);
+        list = list.push(Space::new(Length::Units(0), Length::Units(part as u16)));
+        height -= part;
+    }
+    list
+}
+
 #[derive(Default)]
 struct GameList {
     entries: Vec<GameListEntry>,
     scroll: scrollable::State,
+    /// This is the text in the search box.
+    search: String,
+    /// This is the lowercase filter that the list currently uses, which catches up
+    /// to `search` once typing pauses.
+    applied_search: String,
+    /// This increases with each keystroke, so that only the latest pause applies a search.
+    search_generation: u64,
+    search_input: text_input::State,
+    /// This game's entry will be expanded when it arrives.
+    focus: Option<String>,
+    /// When enabled, the slowest games are listed first.
+    show_timings: bool,
 }
 
 impl GameList {
     fn view(&mut self, restoring: bool, translator: &Translator, config: &Config) -> Container<Message> {
-        self.entries.sort_by_key(|x| x.scan_info.game_name.clone());
-        Container::new({
-            self.entries.iter_mut().enumerate().fold(
-                Scrollable::new(&mut self.scroll)
-                    .width(Length::Fill)
-                    .padding(10)
-                    .style(style::Scrollable),
-                |parent: Scrollable<'_, Message>, (_i, x)| {
-                    parent
-                        .push(x.view(restoring, translator, &config))
-                        .push(Space::new(Length::Units(0), Length::Units(10)))
-                },
-            )
-        })
+        let search = &self.applied_search;
+        let show_timings = self.show_timings;
+        let matches: Vec<_> = self
+            .entries
+            .iter_mut()
+            .filter(|x| search.is_empty() || x.scan_info.game_name.to_lowercase().contains(search))
+            .collect();
+
+        // The state only clamps its offset against the real content while drawing,
+        // so we read the raw offset and clamp it against the estimated height instead.
+        // Scrolling sends events through our subscription, so this runs again as the list moves.
+        let offset = self.scroll.offset(
+            Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+            },
+            Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: f32::MAX,
+            },
+        );
+        let total: u32 = matches.iter().map(|x| x.estimated_height()).sum();
+        let offset = offset.min(total.saturating_sub(GAME_LIST_VIEWPORT_HEIGHT));
+        let window_start = offset.saturating_sub(GAME_LIST_OVERSCAN_HEIGHT);
+        let window_end = offset + GAME_LIST_VIEWPORT_HEIGHT + GAME_LIST_OVERSCAN_HEIGHT;
+
+        let mut above = 0;
+        let mut below = 0;
+        let mut visible = vec![];
+        let mut top = 0;
+        for entry in matches {
+            let height = entry.estimated_height();
+            if top + height <= window_start {
+                above += height;
+            } else if top >= window_end {
+                below += height;
+            } else {
+                visible.push(entry);
+            }
+            top += height;
+        }
+
+        let mut list = push_game_list_space(
+            Scrollable::new(&mut self.scroll)
+                .width(Length::Fill)
+                .padding(10)
+                .style(style::Scrollable),
+            above,
+        );
+        for entry in visible {
+            list = list
+                .push(entry.view(restoring, translator, &config, show_timings))
+                .push(Space::new(Length::Units(0), Length::Units(10)));
+        }
+        list = push_game_list_space(list, below);
+
+        Container::new(
+            Column::new()
+                .push(
+                    Row::new()
+                        .padding(10)
+                        .spacing(20)
+                        .align_items(Align::Center)
+                        .push(
+                            TextInput::new(
+                                &mut self.search_input,
+                                &translator.search_game_name_placeholder(),
+                                &self.search,
+                                move |text| Message::EditedSearchGameName { restoring, text },
+                            )
+                            .padding(5),
+                        )
+                        .push(Checkbox::new(
+                            show_timings,
+                            translator.show_timings_label(),
+                            move |enabled| Message::ToggleGameListTimings { restoring, enabled },
+                        )),
+                )
+                .push(list),
+        )
+    }
+
+    /// Filter the list right away, such as when the search is set programmatically.
+    fn apply_search(&mut self) {
+        self.applied_search = self.search.to_lowercase();
+    }
+
+    fn compare(&self, a: &GameListEntry, b: &GameListEntry) -> std::cmp::Ordering {
+        if self.show_timings {
+            b.duration()
+                .cmp(&a.duration())
+                .then_with(|| a.scan_info.game_name.cmp(&b.scan_info.game_name))
+        } else {
+            a.scan_info.game_name.cmp(&b.scan_info.game_name)
+        }
+    }
+
+    /// Add an entry where it belongs, so that the list never has to be sorted while drawing.
+    fn insert(&mut self, entry: GameListEntry) {
+        let index = match self.entries.binary_search_by(|x| self.compare(x, &entry)) {
+            Ok(index) | Err(index) => index,
+        };
+        self.entries.insert(index, entry);
+    }
+
+    fn set_show_timings(&mut self, enabled: bool) {
+        self.show_timings = enabled;
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by(|a, b| self.compare(a, b));
+        self.entries = entries;
+    }
+
+    /// Display paths depend on the roots and redirects, so they must be rebuilt when those change.
+    fn clear_cache(&mut self) {
+        for entry in &mut self.entries {
+            entry.cache = None;
+        }
     }
 
     fn all_entries_selected(&self, config: &Config, restoring: bool) -> bool {
@@ -562,6 +936,14 @@ impl RootEditor {
                                             move |v| Message::SelectedRootStore(i, v),
                                         )
                                     })
+                                    .push({
+                                        Radio::new(
+                                            Store::Rockstar,
+                                            translator.store(&Store::Rockstar),
+                                            Some(roots[i].store),
+                                            move |v| Message::SelectedRootStore(i, v),
+                                        )
+                                    })
                                     .push({
                                         Radio::new(
                                             Store::Other,
@@ -626,6 +1008,11 @@ impl RedirectEditor {
         operation: &Option<OngoingOperation>,
     ) -> Container<Message> {
         let redirects = config.get_redirects();
+        let conflicting: Vec<_> = config
+            .find_conflicting_redirects()
+            .into_iter()
+            .flat_map(|(first, second)| vec![first, second])
+            .collect();
         if redirects.is_empty() {
             Container::new(Space::new(Length::Units(0), Length::Units(0)))
         } else {
@@ -636,6 +1023,11 @@ impl RedirectEditor {
                         .max_height(100)
                         .style(style::Scrollable),
                     |parent: Scrollable<'_, Message>, (i, x)| {
+                        let text_input_style = if conflicting.contains(&redirects[i]) {
+                            style::TextInput::Conflicting
+                        } else {
+                            style::TextInput::Normal
+                        };
                         parent
                             .push(
                                 Row::new()
@@ -659,7 +1051,8 @@ impl RedirectEditor {
                                             },
                                         )
                                         .width(Length::FillPortion(3))
-                                        .padding(5),
+                                        .padding(5)
+                                        .style(text_input_style),
                                     )
                                     .push(
                                         Button::new(&mut x.source_browse_button_state, Icon::FolderOpen.as_text())
@@ -685,7 +1078,8 @@ impl RedirectEditor {
                                             },
                                         )
                                         .width(Length::FillPortion(3))
-                                        .padding(5),
+                                        .padding(5)
+                                        .style(text_input_style),
                                     )
                                     .push(
                                         Button::new(&mut x.target_browse_button_state, Icon::FolderOpen.as_text())
@@ -938,9 +1332,75 @@ impl DisappearingProgress {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+enum BackupTargetState {
+    /// The target has been edited since the last check.
+    Unchecked,
+    Checking,
+    /// The check timed out.
+    Unreachable,
+    Checked(BackupTargetHealth),
+}
+
+impl Default for BackupTargetState {
+    fn default() -> Self {
+        Self::Checking
+    }
+}
+
+impl BackupTargetState {
+    fn usable(&self) -> bool {
+        match self {
+            Self::Unchecked | Self::Checking => true,
+            Self::Unreachable => false,
+            Self::Checked(health) => health.writable,
+        }
+    }
+
+    fn view(&self, translator: &Translator) -> Text {
+        let text = match self {
+            Self::Unchecked => translator.backup_target_unchecked(),
+            Self::Checking => translator.backup_target_checking(),
+            Self::Unreachable => translator.backup_target_unreachable(),
+            Self::Checked(health) => translator.backup_target_health(health),
+        };
+        if self.usable() {
+            Text::new(text)
+        } else {
+            Text::new(text).color(iced::Color::from_rgb8(201, 77, 77))
+        }
+    }
+}
+
+/// Check the backup target on its own thread, since it may be on a slow or offline drive,
+/// and report it as unreachable if it doesn't respond in time. Waiting for the result
+/// doesn't tie up the executor, even if the check never finishes.
+fn check_backup_target(path: StrictPath) -> Command<Message> {
+    use iced::futures::{channel::mpsc, StreamExt};
+
+    let (sender, mut receiver) = mpsc::unbounded();
+    let timeout_sender = sender.clone();
+    let target = path.clone();
+    std::thread::spawn(move || {
+        let _ = sender.unbounded_send(Some(check_backup_target_health(&target)));
+    });
+    std::thread::spawn(move || {
+        std::thread::sleep(BACKUP_TARGET_CHECK_TIMEOUT);
+        let _ = timeout_sender.unbounded_send(None);
+    });
+
+    Command::perform(
+        async move { (path, receiver.next().await.flatten()) },
+        move |(path, health)| Message::CheckedBackupTarget { path, health },
+    )
+}
+
 #[derive(Default)]
 struct BackupScreenComponent {
     status: OperationStatus,
+    target_state: BackupTargetState,
+    /// Whether to ask for confirmation to back up once the pending target check is done.
+    confirm_after_check: bool,
     log: GameList,
     start_button: button::State,
     preview_button: button::State,
@@ -992,7 +1452,10 @@ impl BackupScreenComponent {
                                 .horizontal_alignment(HorizontalAlignment::Center),
                             )
                             .on_press(match operation {
-                                None => Message::BackupStart { preview: true },
+                                None => Message::BackupStart {
+                                    preview: true,
+                                    games: None,
+                                },
                                 Some(OngoingOperation::PreviewBackup) => Message::CancelOperation,
                                 _ => Message::Ignore,
                             })
@@ -1014,13 +1477,13 @@ impl BackupScreenComponent {
                                 .horizontal_alignment(HorizontalAlignment::Center),
                             )
                             .on_press(match operation {
-                                None => Message::ConfirmBackupStart,
+                                None if self.target_state.usable() => Message::ConfirmBackupStart,
                                 Some(OngoingOperation::Backup) => Message::CancelOperation,
                                 _ => Message::Ignore,
                             })
                             .width(Length::Units(125))
                             .style(match operation {
-                                None => style::Button::Primary,
+                                None if self.target_state.usable() => style::Button::Primary,
                                 Some(OngoingOperation::Backup) => style::Button::Negative,
                                 _ => style::Button::Disabled,
                             }),
@@ -1061,6 +1524,14 @@ impl BackupScreenComponent {
                         .align_items(Align::Center)
                         .push(Text::new(translator.processed_games(&self.status)).size(40)),
                 )
+                .push(
+                    self.status
+                        .roots
+                        .iter()
+                        .fold(Column::new().align_items(Align::Center), |column, root| {
+                            column.push(Text::new(translator.root_status(root)))
+                        }),
+                )
                 .push(
                     Row::new()
                         .padding(20)
@@ -1074,6 +1545,7 @@ impl BackupScreenComponent {
                                 &config.backup.path.raw(),
                                 Message::EditedBackupTarget,
                             )
+                            .on_submit(Message::CheckBackupTarget)
                             .padding(5),
                         )
                         .push(Checkbox::new(
@@ -1093,6 +1565,7 @@ impl BackupScreenComponent {
                                 }),
                         ),
                 )
+                .push(self.target_state.view(translator))
                 .push(self.root_editor.view(&config, &translator, &operation))
                 .push(Space::new(Length::Units(0), Length::Units(30)))
                 .push(self.log.view(false, translator, &config)),
@@ -1159,7 +1632,10 @@ impl RestoreScreenComponent {
                                 .horizontal_alignment(HorizontalAlignment::Center),
                             )
                             .on_press(match operation {
-                                None => Message::RestoreStart { preview: true },
+                                None => Message::RestoreStart {
+                                    preview: true,
+                                    games: None,
+                                },
                                 Some(OngoingOperation::PreviewRestore) => Message::CancelOperation,
                                 _ => Message::Ignore,
                             })
@@ -1356,6 +1832,39 @@ impl OtherScreenComponent {
                                     translator.explanation_for_exclude_store_screenshots(),
                                     Message::EditedExcludeStoreScreenshots,
                                 )),
+                        )
+                        .push(
+                            Row::new()
+                                .padding(20)
+                                .spacing(20)
+                                .align_items(Align::Center)
+                                .push(Checkbox::new(
+                                    config.backup.group_by_store,
+                                    translator.explanation_for_group_by_store(),
+                                    Message::EditedGroupByStore,
+                                )),
+                        )
+                        .push(
+                            Row::new()
+                                .padding(20)
+                                .spacing(20)
+                                .align_items(Align::Center)
+                                .push(Checkbox::new(
+                                    config.backup.compress_large_files,
+                                    translator.explanation_for_compress_large_files(),
+                                    Message::EditedCompressLargeFiles,
+                                )),
+                        )
+                        .push(
+                            Row::new()
+                                .padding(20)
+                                .spacing(20)
+                                .align_items(Align::Center)
+                                .push(Checkbox::new(
+                                    config.restore.include_screenshots,
+                                    translator.explanation_for_include_screenshots(),
+                                    Message::EditedIncludeScreenshots,
+                                )),
                         ),
                 ),
         )
@@ -1368,9 +1877,9 @@ impl OtherScreenComponent {
 impl Application for App {
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(flags: Flags) -> (Self, Command<Message>) {
         let translator = Translator::default();
         let mut modal_theme: Option<ModalTheme> = None;
         let mut config = match Config::load() {
@@ -1391,18 +1900,60 @@ impl Application for App {
             }
         };
 
+        let screen = match flags.screen {
+            Some(GuiScreen::Restore) => Screen::Restore,
+            _ => Screen::Backup,
+        };
+        let mut backup_screen = BackupScreenComponent::new(&config);
+        let mut restore_screen = RestoreScreenComponent::new(&config);
+        let mut command = Command::none();
+        let check_target = check_backup_target(config.backup.target());
+
+        if let Some(game) = flags.game {
+            let restoring = screen == Screen::Restore;
+            let known = if restoring {
+                BackupLayout::new(config.restore.source())
+                    .mapping
+                    .games
+                    .contains_key(&game)
+            } else {
+                manifest.contains_game(&game) || config.custom_games.iter().any(|x| x.name == game)
+            };
+            let log = if restoring {
+                &mut restore_screen.log
+            } else {
+                &mut backup_screen.log
+            };
+
+            if known {
+                log.focus = Some(game.clone());
+                command = Command::perform(async move {}, move |_| {
+                    let games = Some(vec![game.clone()]);
+                    if restoring {
+                        Message::RestoreStart { preview: true, games }
+                    } else {
+                        Message::BackupStart { preview: true, games }
+                    }
+                });
+            } else {
+                log.search = game;
+                log.apply_search();
+            }
+        }
+
         (
             Self {
-                backup_screen: BackupScreenComponent::new(&config),
-                restore_screen: RestoreScreenComponent::new(&config),
+                backup_screen,
+                restore_screen,
                 custom_games_screen: CustomGamesScreenComponent::new(&config),
                 translator,
                 config,
                 manifest,
                 modal_theme,
+                screen,
                 ..Self::default()
             },
-            Command::none(),
+            Command::batch(vec![command, check_target]),
         )
     }
 
@@ -1417,25 +1968,44 @@ impl Application for App {
                 self.modal_theme = None;
                 self.progress.current = 0.0;
                 self.progress.max = 0.0;
-                self.operation_should_cancel
-                    .swap(false, std::sync::atomic::Ordering::Relaxed);
+                self.operation_should_cancel.reset();
                 Command::none()
             }
             Message::Ignore => Command::none(),
             Message::ConfirmBackupStart => {
-                self.modal_theme = Some(ModalTheme::ConfirmBackup);
+                // The target isn't checked while typing, so make sure it's usable first.
+                match self.backup_screen.target_state {
+                    BackupTargetState::Checked(_) => {}
+                    BackupTargetState::Checking => {
+                        self.backup_screen.confirm_after_check = true;
+                        return Command::none();
+                    }
+                    BackupTargetState::Unchecked | BackupTargetState::Unreachable => {
+                        self.backup_screen.confirm_after_check = true;
+                        self.backup_screen.target_state = BackupTargetState::Checking;
+                        return check_backup_target(self.config.backup.target());
+                    }
+                }
+                let existing_files = if self.config.backup.merge {
+                    0
+                } else {
+                    list_existing_files(&self.config.backup.target()).len()
+                };
+                self.modal_theme = Some(ModalTheme::ConfirmBackup { existing_files });
                 Command::none()
             }
             Message::ConfirmRestoreStart => {
                 self.modal_theme = Some(ModalTheme::ConfirmRestore);
                 Command::none()
             }
-            Message::BackupStart { preview } => {
+            Message::BackupStart { preview, games } => {
                 if self.operation.is_some() {
                     return Command::none();
                 }
 
-                let backup_path = &self.config.backup.path;
+                let backup_path = &self.config.backup.target();
+                // Read these before the target might be cleared out.
+                let recorded_sizes = BackupLayout::new(backup_path.clone()).recorded_sizes();
                 if !preview {
                     if let Err(e) = prepare_backup_target(&backup_path, self.config.backup.merge) {
                         self.modal_theme = Some(ModalTheme::Error { variant: e });
@@ -1443,16 +2013,27 @@ impl Application for App {
                     }
                 }
 
-                let mut all_games = self.manifest.0.clone();
-                for custom_game in &self.config.custom_games {
-                    all_games.insert(custom_game.name.clone(), Game::from(custom_game.to_owned()));
-                }
+                let selection = match &games {
+                    Some(games) => GameSelection::Only(games.clone()),
+                    None => GameSelection::All,
+                };
+                let run = std::sync::Arc::new(BackupRun::new(
+                    &self.config,
+                    &self.manifest,
+                    &selection,
+                    &BackupRunOptions {
+                        preview,
+                        recorded_sizes: Some(recorded_sizes),
+                        ..Default::default()
+                    },
+                ));
 
                 self.backup_screen.status.clear();
+                self.backup_screen.status.track_roots(run.roots());
                 self.backup_screen.log.entries.clear();
                 self.modal_theme = None;
                 self.progress.current = 0.0;
-                self.progress.max = all_games.len() as f32;
+                self.progress.max = run.subjects().len() as f32;
 
                 self.operation = Some(if preview {
                     OngoingOperation::PreviewBackup
@@ -1460,47 +2041,31 @@ impl Application for App {
                     OngoingOperation::Backup
                 });
 
-                let layout = std::sync::Arc::new(BackupLayout::new(backup_path.clone()));
-                let filter = std::sync::Arc::new(self.config.backup.filter.clone());
-
                 let mut commands: Vec<Command<Message>> = vec![];
-                for key in all_games.iter().map(|(k, _)| k.clone()) {
-                    let game = all_games[&key].clone();
-                    let roots = self.config.roots.clone();
-                    let layout2 = layout.clone();
-                    let filter2 = filter.clone();
-                    let steam_id = game.steam.clone().unwrap_or(SteamMetadata { id: None }).id;
+                for key in run.subjects().iter().cloned() {
+                    let run2 = run.clone();
                     let cancel_flag = self.operation_should_cancel.clone();
+                    // Refreshing the preview for specific games should still show them as ignored.
                     let ignored = !self.config.is_game_enabled_for_backup(&key);
                     commands.push(Command::perform(
                         async move {
-                            if key.trim().is_empty() {
-                                return (None, None, OperationStepDecision::Ignored);
-                            }
-                            if cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
-                                // TODO: https://github.com/hecrj/iced/issues/436
-                                std::thread::sleep(std::time::Duration::from_millis(1));
-                                return (None, None, OperationStepDecision::Cancelled);
-                            }
-
-                            let scan_info = scan_game_for_backup(
-                                &game,
-                                &key,
-                                &roots,
-                                &StrictPath::from_std_path_buf(&app_dir()),
-                                &steam_id,
-                                &filter2,
-                            );
-                            if ignored {
-                                return (Some(scan_info), None, OperationStepDecision::Ignored);
+                            let outcome = run2.run_game(&key, &cancel_flag);
+                            match outcome.decision {
+                                OperationStepDecision::Cancelled => {
+                                    // TODO: https://github.com/hecrj/iced/issues/436
+                                    std::thread::sleep(std::time::Duration::from_millis(1));
+                                    (None, None, OperationStepDecision::Cancelled)
+                                }
+                                OperationStepDecision::Processed if ignored => {
+                                    (Some(outcome.scan_info), None, OperationStepDecision::Ignored)
+                                }
+                                OperationStepDecision::Processed if !preview => (
+                                    Some(outcome.scan_info),
+                                    Some(outcome.backup_info),
+                                    OperationStepDecision::Processed,
+                                ),
+                                decision => (Some(outcome.scan_info), None, decision),
                             }
-
-                            let backup_info = if !preview {
-                                Some(back_up_game(&scan_info, &key, &layout2))
-                            } else {
-                                None
-                            };
-                            (Some(scan_info), backup_info, OperationStepDecision::Processed)
                         },
                         move |(scan_info, backup_info, decision)| Message::BackupStep {
                             scan_info,
@@ -1512,12 +2077,12 @@ impl Application for App {
 
                 Command::batch(commands)
             }
-            Message::RestoreStart { preview } => {
+            Message::RestoreStart { preview, games } => {
                 if self.operation.is_some() {
                     return Command::none();
                 }
 
-                let restore_path = &self.config.restore.path;
+                let restore_path = &self.config.restore.source();
                 if !restore_path.is_dir() {
                     self.modal_theme = Some(ModalTheme::Error {
                         variant: Error::RestorationSourceInvalid {
@@ -1527,8 +2092,18 @@ impl Application for App {
                     return Command::none();
                 }
 
-                let layout = std::sync::Arc::new(BackupLayout::new(restore_path.clone()));
-                let restorables: Vec<_> = layout.mapping.games.keys().cloned().collect();
+                let layout = std::sync::Arc::new(BackupLayout {
+                    roots: self.config.roots.clone(),
+                    include_screenshots: self.config.restore.include_screenshots,
+                    ..BackupLayout::new(restore_path.clone())
+                });
+                let restorables: Vec<_> = layout
+                    .mapping
+                    .games
+                    .keys()
+                    .filter(|x| games.as_ref().map(|games| games.contains(x)).unwrap_or(true))
+                    .cloned()
+                    .collect();
 
                 self.restore_screen.status.clear();
                 self.restore_screen.log.entries.clear();
@@ -1549,24 +2124,45 @@ impl Application for App {
                 let mut commands: Vec<Command<Message>> = vec![];
                 for name in restorables {
                     let redirects = self.config.get_redirects();
+                    let mode = self.config.restore.mode.clone();
+                    let preserve_ownership = self.config.restore.preserve_ownership;
+                    let allow_unsafe_paths = self.config.allow_unsafe_paths;
                     let layout2 = layout.clone();
                     let cancel_flag = self.operation_should_cancel.clone();
                     let ignored = !self.config.is_game_enabled_for_restore(&name);
+                    let excluded_locations = self.config.restore.excluded_locations_for(&name);
                     commands.push(Command::perform(
                         async move {
-                            if cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
+                            if cancel_flag.is_cancelled() {
                                 // TODO: https://github.com/hecrj/iced/issues/436
                                 std::thread::sleep(std::time::Duration::from_millis(1));
                                 return (None, None, OperationStepDecision::Cancelled);
                             }
 
-                            let scan_info = scan_game_for_restoration(&name, &layout2);
+                            let mut scan_info = scan_game_for_restoration(&name, &layout2);
+                            scan_info.exclude_locations(&excluded_locations);
                             if ignored {
                                 return (Some(scan_info), None, OperationStepDecision::Ignored);
                             }
 
                             let backup_info = if !preview {
-                                Some(restore_game(&scan_info, &redirects))
+                                restore_games(
+                                    std::slice::from_ref(&scan_info),
+                                    &redirects,
+                                    &mode,
+                                    &RestoreOptions {
+                                        allow_unsafe_paths,
+                                        ..Default::default()
+                                    },
+                                    &RetryPolicy::default(),
+                                    if preserve_ownership {
+                                        Some(&change_file_owner)
+                                    } else {
+                                        None
+                                    },
+                                    &|| {},
+                                )
+                                .remove(&scan_info.game_name)
                             } else {
                                 None
                             };
@@ -1588,16 +2184,30 @@ impl Application for App {
                 decision,
             } => {
                 self.progress.current += 1.0;
+                if let (None, Some(scan_info), Some(info)) = (self.backup_throughput, &scan_info, &backup_info) {
+                    let seconds = info.duration.0.as_secs_f64();
+                    let bytes = scan_info.sum_bytes(&backup_info);
+                    if decision == OperationStepDecision::Processed && bytes > 0 && seconds > 0.0 {
+                        self.backup_throughput = Some((bytes as f64 / seconds) as u64);
+                    }
+                }
                 if let Some(scan_info) = scan_info {
                     if scan_info.found_anything() {
+                        let estimated_backup_time = match (&backup_info, self.backup_throughput) {
+                            (None, Some(throughput)) => Some(scan_info.estimated_backup_time(throughput)),
+                            _ => None,
+                        };
                         self.backup_screen.status.add_game(
                             &scan_info,
                             &backup_info,
                             decision == OperationStepDecision::Processed,
                         );
-                        self.backup_screen.log.entries.push(GameListEntry {
+                        let expanded = self.backup_screen.log.focus.as_ref() == Some(&scan_info.game_name);
+                        self.backup_screen.log.insert(GameListEntry {
                             scan_info,
                             backup_info,
+                            expanded,
+                            estimated_backup_time,
                             ..Default::default()
                         });
                     }
@@ -1621,9 +2231,11 @@ impl Application for App {
                             &backup_info,
                             decision == OperationStepDecision::Processed,
                         );
-                        self.restore_screen.log.entries.push(GameListEntry {
+                        let expanded = self.restore_screen.log.focus.as_ref() == Some(&scan_info.game_name);
+                        self.restore_screen.log.insert(GameListEntry {
                             scan_info,
                             backup_info,
+                            expanded,
                             ..Default::default()
                         });
                     }
@@ -1635,8 +2247,7 @@ impl Application for App {
                 }
             }
             Message::CancelOperation => {
-                self.operation_should_cancel
-                    .swap(true, std::sync::atomic::Ordering::Relaxed);
+                self.operation_should_cancel.cancel();
                 match self.operation {
                     Some(OngoingOperation::Backup) => {
                         self.operation = Some(OngoingOperation::CancelBackup);
@@ -1655,19 +2266,25 @@ impl Application for App {
                 Command::none()
             }
             Message::BackupComplete => {
+                self.backup_screen.log.focus = None;
+                let check_target = check_backup_target(self.config.backup.target());
                 for entry in &self.backup_screen.log.entries {
                     if let Some(backup_info) = &entry.backup_info {
                         if !backup_info.successful() {
                             self.modal_theme = Some(ModalTheme::Error {
                                 variant: Error::SomeEntriesFailed,
                             });
-                            return Command::none();
+                            return check_target;
                         }
                     }
                 }
-                Command::perform(async move {}, move |_| Message::Idle)
+                Command::batch(vec![
+                    Command::perform(async move {}, move |_| Message::Idle),
+                    check_target,
+                ])
             }
             Message::RestoreComplete => {
+                self.restore_screen.log.focus = None;
                 for entry in &self.restore_screen.log.entries {
                     if let Some(backup_info) = &entry.backup_info {
                         if !backup_info.successful() {
@@ -1684,6 +2301,33 @@ impl Application for App {
                 self.backup_screen.backup_target_history.push(&text);
                 self.config.backup.path.reset(text);
                 self.config.save();
+                // Checking on every keystroke would hit the drive for each partial path,
+                // so wait until the user submits the path or starts a backup.
+                self.backup_screen.target_state = BackupTargetState::Unchecked;
+                self.backup_screen.confirm_after_check = false;
+                Command::none()
+            }
+            Message::CheckBackupTarget => {
+                if self.backup_screen.target_state == BackupTargetState::Checking {
+                    return Command::none();
+                }
+                self.backup_screen.target_state = BackupTargetState::Checking;
+                check_backup_target(self.config.backup.target())
+            }
+            Message::CheckedBackupTarget { path, health } => {
+                // Ignore checks for a path that has since been edited.
+                if path.raw() != self.config.backup.target().raw() {
+                    return Command::none();
+                }
+                self.backup_screen.target_state = match health {
+                    Some(health) => BackupTargetState::Checked(health),
+                    None => BackupTargetState::Unreachable,
+                };
+                if std::mem::take(&mut self.backup_screen.confirm_after_check)
+                    && self.backup_screen.target_state.usable()
+                {
+                    return Command::perform(async move {}, move |_| Message::ConfirmBackupStart);
+                }
                 Command::none()
             }
             Message::EditedBackupMerge(enabled) => {
@@ -1704,6 +2348,8 @@ impl Application for App {
                         self.config.roots.push(RootsConfig {
                             path: StrictPath::default(),
                             store: Store::Other,
+                            portable: false,
+                            scan_proton_prefixes: None,
                         });
                     }
                     EditAction::Change(index, value) => {
@@ -1715,6 +2361,8 @@ impl Application for App {
                         self.config.roots.remove(index);
                     }
                 }
+                self.backup_screen.log.clear_cache();
+                self.restore_screen.log.clear_cache();
                 self.config.save();
                 Command::none()
             }
@@ -1752,6 +2400,7 @@ impl Application for App {
                         self.config.restore.redirects.remove(index);
                     }
                 }
+                self.restore_screen.log.clear_cache();
                 self.config.save();
                 Command::none()
             }
@@ -1836,10 +2485,36 @@ impl Application for App {
                 self.config.save();
                 Command::none()
             }
+            Message::EditedGroupByStore(enabled) => {
+                self.config.backup.group_by_store = enabled;
+                self.config.save();
+                Command::none()
+            }
+            Message::EditedCompressLargeFiles(enabled) => {
+                self.config.backup.compress_large_files = enabled;
+                self.config.save();
+                Command::none()
+            }
+            Message::EditedIncludeScreenshots(enabled) => {
+                self.config.restore.include_screenshots = enabled;
+                self.config.save();
+                Command::none()
+            }
             Message::SwitchScreen(screen) => {
                 self.screen = screen;
                 Command::none()
             }
+            Message::OpenGameBackupDir { name, restoring } => {
+                let backup_dir = if restoring {
+                    self.config.restore.source()
+                } else {
+                    self.config.backup.target()
+                };
+                if let Err(e) = open_game_backup_dir(&backup_dir, &name) {
+                    self.modal_theme = Some(ModalTheme::Error { variant: e });
+                }
+                Command::none()
+            }
             Message::ToggleGameListEntryExpanded { name } => {
                 match self.screen {
                     Screen::Backup => {
@@ -1901,6 +2576,55 @@ impl Application for App {
                 });
                 Command::none()
             }
+            Message::EditedSearchGameName { restoring, text } => {
+                let log = if restoring {
+                    &mut self.restore_screen.log
+                } else {
+                    &mut self.backup_screen.log
+                };
+                log.search = text;
+                log.search_generation += 1;
+                let generation = log.search_generation;
+                Command::perform(
+                    async move {
+                        std::thread::sleep(std::time::Duration::from_millis(SEARCH_DEBOUNCE_MS));
+                    },
+                    move |_| Message::AppliedSearchGameName { restoring, generation },
+                )
+            }
+            Message::AppliedSearchGameName { restoring, generation } => {
+                let log = if restoring {
+                    &mut self.restore_screen.log
+                } else {
+                    &mut self.backup_screen.log
+                };
+                // A newer keystroke will apply its own search once typing pauses.
+                if log.search_generation == generation {
+                    log.apply_search();
+                }
+                Command::none()
+            }
+            Message::ShowMoreGameListEntryLines { name, restoring } => {
+                let log = if restoring {
+                    &mut self.restore_screen.log
+                } else {
+                    &mut self.backup_screen.log
+                };
+                for entry in &mut log.entries {
+                    if entry.scan_info.game_name == name {
+                        entry.extra_line_pages += 1;
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleGameListTimings { restoring, enabled } => {
+                if restoring {
+                    self.restore_screen.log.set_show_timings(enabled);
+                } else {
+                    self.backup_screen.log.set_show_timings(enabled);
+                }
+                Command::none()
+            }
             Message::SelectAllGames => {
                 match self.screen {
                     Screen::Backup => {
@@ -2148,7 +2872,7 @@ impl Application for App {
 }
 
 mod style {
-    use iced::{button, container, scrollable, Background, Color, Vector};
+    use iced::{button, container, scrollable, text_input, Background, Color, Vector};
 
     pub enum Button {
         Primary,
@@ -2257,6 +2981,52 @@ mod style {
         }
     }
 
+    #[derive(Clone, Copy)]
+    pub enum TextInput {
+        Normal,
+        /// For values that conflict with another entry.
+        Conflicting,
+    }
+
+    impl text_input::StyleSheet for TextInput {
+        fn active(&self) -> text_input::Style {
+            text_input::Style {
+                background: Background::Color(Color::WHITE),
+                border_radius: 5,
+                border_width: 1,
+                border_color: match self {
+                    Self::Normal => Color::from_rgb(0.7, 0.7, 0.7),
+                    Self::Conflicting => Color::from_rgb8(255, 0, 0),
+                },
+            }
+        }
+
+        fn focused(&self) -> text_input::Style {
+            text_input::Style {
+                border_color: match self {
+                    Self::Normal => Color::from_rgb(0.5, 0.5, 0.5),
+                    Self::Conflicting => Color::from_rgb8(201, 77, 77),
+                },
+                ..self.active()
+            }
+        }
+
+        fn placeholder_color(&self) -> Color {
+            Color::from_rgb(0.7, 0.7, 0.7)
+        }
+
+        fn value_color(&self) -> Color {
+            match self {
+                Self::Normal => Color::from_rgb(0.3, 0.3, 0.3),
+                Self::Conflicting => Color::from_rgb8(201, 77, 77),
+            }
+        }
+
+        fn selection_color(&self) -> Color {
+            Color::from_rgb(0.8, 0.8, 1.0)
+        }
+    }
+
     pub struct Scrollable;
     impl scrollable::StyleSheet for Scrollable {
         fn active(&self) -> scrollable::Scrollbar {
@@ -2289,8 +3059,15 @@ mod style {
     }
 }
 
-pub fn run_gui() {
+#[derive(Debug, Default)]
+pub struct Flags {
+    pub game: Option<String>,
+    pub screen: Option<GuiScreen>,
+}
+
+pub fn run_gui(flags: Flags) {
     let mut settings = iced::Settings::default();
+    settings.flags = flags;
     set_app_icon(&mut settings);
     set_app_min_size(&mut settings);
     App::run(settings)
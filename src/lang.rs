@@ -1,6 +1,11 @@
 use crate::{
+    config::RootsConfig,
     manifest::Store,
-    prelude::{Error, OperationStatus, OperationStepDecision, StrictPath},
+    prelude::{
+        BackupInfo, BackupTargetHealth, Error, OperationStatus, OperationStepDecision, PhaseTimings,
+        RestoreConflictSource, RootStatus, ScannedFile, StrictPath, Timing,
+    },
+    table::RowStatus,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -21,7 +26,7 @@ pub struct Translator {
 
 impl Translator {
     pub fn window_title(&self) -> String {
-        let version = option_env!("LUDUSAVI_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"));
+        let version = crate::prelude::app_version();
         match option_env!("LUDUSAVI_VARIANT") {
             Some(variant) => format!("Ludusavi v{} ({})", version, variant),
             None => format!("Ludusavi v{}", version),
@@ -36,17 +41,120 @@ impl Translator {
             Error::CliBackupTargetExists { path } => self.cli_backup_target_exists(path),
             Error::CliUnrecognizedGames { games } => self.cli_unrecognized_games(games),
             Error::CliUnableToRequestConfirmation => self.cli_unable_to_request_confirmation(),
+            Error::CliPruneWithoutPolicy => self.cli_prune_without_policy(),
             Error::SomeEntriesFailed => self.some_entries_failed(),
             Error::CannotPrepareBackupTarget { path } => self.cannot_prepare_backup_target(path),
             Error::RestorationSourceInvalid { path } => self.restoration_source_is_invalid(path),
             Error::RegistryIssue => self.registry_issue(),
+            Error::RegistryKeyNotFound { key } => self.registry_key_not_found(key),
             Error::UnableToBrowseFileSystem => self.unable_to_browse_file_system(),
+            Error::BackupFolderNotFound { game, path } => self.backup_folder_not_found(game, path),
+            Error::UnableToOpenDir { path } => self.unable_to_open_dir(path),
+            Error::UnableToExportArchive { path } => self.unable_to_export_archive(path),
+            Error::UnableToImportArchive { path } => self.unable_to_import_archive(path),
+            Error::UnableToWriteMetrics { path } => self.unable_to_write_metrics(path),
+            Error::UnableToRecordScan { path } => self.unable_to_record_scan(path),
+            Error::UnableToExportProfile { path } => self.unable_to_export_profile(path),
+            Error::UnableToImportProfile { path } => self.unable_to_import_profile(path),
+            #[cfg(feature = "android")]
+            Error::UnableToPullAndroidSaves { package } => self.unable_to_pull_android_saves(package),
         }
     }
 
     pub fn cli_backup_target_exists(&self, path: &StrictPath) -> String {
         match self.language {
-            Language::English => format!("The backup target already exists ( {} ). Either choose a different --target or delete it with --force.", path.render()),
+            Language::English => format!("The backup target already exists, but it doesn't look like a Ludusavi backup ( {} ). Either choose a different --path, use --merge, or replace it with --overwrite.", path.render()),
+        }
+    }
+
+    pub fn cli_nested_root(&self, outer: &RootsConfig, nested: &RootsConfig, skipped: bool) -> String {
+        match (self.language, skipped) {
+            (Language::English, false) => format!(
+                "WARNING: Root {} is inside root {}, so its folders may be scanned twice. Set `skipNestedRoots: true` in the config file to ignore nested roots of the same store.",
+                nested.path.render(),
+                outer.path.render()
+            ),
+            (Language::English, true) => format!(
+                "WARNING: Ignoring root {} because it is inside root {}.",
+                nested.path.render(),
+                outer.path.render()
+            ),
+        }
+    }
+
+    pub fn cli_unsafe_paths_skipped(&self, game: &str, paths: &std::collections::HashSet<StrictPath>) -> String {
+        let mut lines = vec![match self.language {
+            Language::English => format!(
+                "WARNING: Skipped paths for {} that climb out of the expected folders. Set `allowUnsafePaths: true` in the config file to scan them anyway.",
+                game
+            ),
+        }];
+        for path in itertools::sorted(paths) {
+            lines.push(format!("  - {}", path.render()));
+        }
+        lines.join("\n")
+    }
+
+    pub fn cli_restored_with_stored_names(&self, game: &str, files: &std::collections::HashSet<ScannedFile>) -> String {
+        let mut lines = vec![match self.language {
+            Language::English => format!(
+                "WARNING: Some files for {} could not be restored with their original names, so they were restored with the names from the backup instead:",
+                game
+            ),
+        }];
+        for file in itertools::sorted(files) {
+            if let Some(original_path) = &file.original_path {
+                lines.push(format!("  - {}", original_path.render()));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn cli_registry_type_conflicts(&self, game: &str, values: &std::collections::HashSet<String>) -> String {
+        let mut lines = vec![match self.language {
+            Language::English => format!(
+                "WARNING: Some registry values for {} were not restored because they already exist with a different type:",
+                game
+            ),
+        }];
+        for value in itertools::sorted(values) {
+            lines.push(format!("  - {}", value));
+        }
+        lines.join("\n")
+    }
+
+    pub fn cli_unmapped_registry_keys(&self, game: &str, keys: &std::collections::HashSet<String>) -> String {
+        let mut lines = vec![match self.language {
+            Language::English => format!(
+                "WARNING: Some registry keys for {} were not restored because they belong to a user who does not exist on this system:",
+                game
+            ),
+        }];
+        for key in itertools::sorted(keys) {
+            lines.push(format!("  - {}", key));
+        }
+        lines.join("\n")
+    }
+
+    pub fn cli_unrestored_owners(&self, game: &str, files: &std::collections::HashSet<StrictPath>) -> String {
+        let mut lines = vec![match self.language {
+            Language::English => format!(
+                "WARNING: Some files for {} were restored, but without their original owners, which usually requires running as root:",
+                game
+            ),
+        }];
+        for file in itertools::sorted(files.iter().map(|x| x.render())) {
+            lines.push(format!("  - {}", file));
+        }
+        lines.join("\n")
+    }
+
+    pub fn cli_deprecated_flag(&self, flag: &str, replacement: &str) -> String {
+        match self.language {
+            Language::English => format!(
+                "WARNING: {} is deprecated and will be removed in a future release. Use {} instead.",
+                flag, replacement
+            ),
         }
     }
 
@@ -64,6 +172,166 @@ impl Translator {
         }
     }
 
+    pub fn cli_confirm_prune(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Do you want to remove surplus game backups from {}?", path.render()),
+        }
+    }
+
+    pub fn cli_confirm_compact(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Do you want to remove these folders from {}?", path.render()),
+        }
+    }
+
+    pub fn cli_compact_removed_game(&self, game: &str, folder: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Backup of removed game {}: {}", game, folder.render()),
+        }
+    }
+
+    pub fn cli_compact_removed_folder(&self, folder: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Empty folder: {}", folder.render()),
+        }
+    }
+
+    pub fn cli_compact_summary(&self, folders: usize, bytes: u64, preview: bool) -> String {
+        let size = self.mib(bytes, true);
+        match self.language {
+            Language::English => {
+                if preview {
+                    format!("Would remove {} folder(s), reclaiming {}", folders, size)
+                } else {
+                    format!("Removed {} folder(s), reclaiming {}", folders, size)
+                }
+            }
+        }
+    }
+
+    pub fn cli_prune_removed_backup(&self, game: &str, folder: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Removed backup of {}: {}", game, folder.render()),
+        }
+    }
+
+    pub fn cli_prune_summary(&self, backups: usize, bytes: u64) -> String {
+        match self.language {
+            Language::English => format!("Removed {} backup(s), reclaiming {}", backups, self.mib(bytes, true)),
+        }
+    }
+
+    pub fn cli_verify_corrupt_game(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}:", name, self.label_broken_backup()),
+        }
+    }
+
+    pub fn cli_verify_missing_mapping(&self, folder: &str) -> String {
+        match self.language {
+            Language::English => format!("Folder without a mapping.yaml, so it can't be verified: {}", folder),
+        }
+    }
+
+    pub fn cli_verify_summary(&self, ok: usize, corrupt: usize, corrupt_files: usize, unmapped: usize) -> String {
+        match self.language {
+            Language::English => format!(
+                "Verified: {} OK, {} corrupted ({} failed file(s)), {} folder(s) without a mapping",
+                ok, corrupt, corrupt_files, unmapped
+            ),
+        }
+    }
+
+    pub fn cli_table_game(&self) -> String {
+        match self.language {
+            Language::English => "Game",
+        }
+        .into()
+    }
+
+    pub fn cli_table_files(&self) -> String {
+        match self.language {
+            Language::English => "Files",
+        }
+        .into()
+    }
+
+    pub fn cli_table_size(&self) -> String {
+        match self.language {
+            Language::English => "Size",
+        }
+        .into()
+    }
+
+    pub fn cli_table_new(&self) -> String {
+        match self.language {
+            Language::English => "New",
+        }
+        .into()
+    }
+
+    pub fn cli_table_changed(&self) -> String {
+        match self.language {
+            Language::English => "Changed",
+        }
+        .into()
+    }
+
+    pub fn cli_table_status(&self) -> String {
+        match self.language {
+            Language::English => "Status",
+        }
+        .into()
+    }
+
+    pub fn cli_table_row_status(&self, status: RowStatus) -> String {
+        match (self.language, status) {
+            (Language::English, RowStatus::Ok) => "OK",
+            (Language::English, RowStatus::Failed) => "FAILED",
+            (Language::English, RowStatus::Ignored) => "IGNORED",
+        }
+        .into()
+    }
+
+    pub fn cli_table_totals(&self, games: usize, files: usize, bytes: u64, location: &str) -> String {
+        match self.language {
+            Language::English => format!(
+                "Total: {} games, {} files, {} in {}",
+                games,
+                files,
+                self.adjusted_size(bytes),
+                location
+            ),
+        }
+    }
+
+    pub fn cli_unable_to_save_games_to_retry(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Warning: Unable to record which games to retry in {}, so --retry-failed won't find them.",
+                path.render()
+            ),
+        }
+    }
+
+    pub fn cli_prune_without_policy(&self) -> String {
+        match self.language {
+            Language::English => {
+                "Error: Specify --older-than or --min-free-space-mib. To use --min-free-space-mib without a value, set backup.minFreeSpaceMib in the config."
+            }
+        }
+        .into()
+    }
+
+    pub fn cli_confirm_backup_replacement(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Do you want to delete and recreate the existing backup at {}?",
+                path.render()
+            ),
+        }
+    }
+
     pub fn cli_unable_to_request_confirmation(&self) -> String {
         #[cfg(target_os = "windows")]
         let extra_note: String = match self.language {
@@ -75,7 +343,7 @@ impl Translator {
         let extra_note = "";
 
         match self.language {
-            Language::English => format!("Unable to request confirmation. {}", extra_note),
+            Language::English => format!("Unable to request confirmation. To skip it, use --yes. {}", extra_note),
         }
     }
 
@@ -92,6 +360,20 @@ impl Translator {
         .into()
     }
 
+    pub fn label_partially_failed(&self) -> String {
+        match self.language {
+            Language::English => "[PARTIALLY FAILED]",
+        }
+        .into()
+    }
+
+    pub fn label_skipped(&self) -> String {
+        match self.language {
+            Language::English => "[SKIPPED]",
+        }
+        .into()
+    }
+
     pub fn label_ignored(&self) -> String {
         match self.language {
             Language::English => "[IGNORED]",
@@ -99,15 +381,113 @@ impl Translator {
         .into()
     }
 
+    pub fn label_corrupted(&self) -> String {
+        match self.language {
+            Language::English => "[CORRUPTED]",
+        }
+        .into()
+    }
+
+    pub fn label_missing(&self) -> String {
+        match self.language {
+            Language::English => "[MISSING]",
+        }
+        .into()
+    }
+
+    pub fn label_unsafe(&self) -> String {
+        match self.language {
+            Language::English => "[UNSAFE]",
+        }
+        .into()
+    }
+
+    pub fn label_uninstalled(&self) -> String {
+        match self.language {
+            Language::English => "[UNINSTALLED]",
+        }
+        .into()
+    }
+
+    pub fn label_shrunk(&self) -> String {
+        match self.language {
+            Language::English => "[SHRUNK]",
+        }
+        .into()
+    }
+
+    pub fn label_overwrite(&self) -> String {
+        match self.language {
+            Language::English => "[OVERWRITE]",
+        }
+        .into()
+    }
+
+    pub fn label_broken_backup(&self) -> String {
+        match self.language {
+            Language::English => "[BROKEN BACKUP]",
+        }
+        .into()
+    }
+
+    pub fn label_registry_only(&self) -> String {
+        match self.language {
+            Language::English => "[REGISTRY ONLY]",
+        }
+        .into()
+    }
+
+    pub fn label_added(&self) -> String {
+        match self.language {
+            Language::English => "[ADDED]",
+        }
+        .into()
+    }
+
+    pub fn label_removed(&self) -> String {
+        match self.language {
+            Language::English => "[REMOVED]",
+        }
+        .into()
+    }
+
+    pub fn label_resized(&self) -> String {
+        match self.language {
+            Language::English => "[RESIZED]",
+        }
+        .into()
+    }
+
+    pub fn label_modified(&self) -> String {
+        match self.language {
+            Language::English => "[MODIFIED]",
+        }
+        .into()
+    }
+
     pub fn cli_game_header(&self, name: &str, bytes: u64, decision: &OperationStepDecision) -> String {
-        if *decision == OperationStepDecision::Processed {
-            match self.language {
-                Language::English => format!("{} [{}]:", name, self.mib(bytes, false)),
-            }
-        } else {
-            match self.language {
-                Language::English => format!("{} [{}] {}:", name, self.mib(bytes, false), self.label_ignored()),
-            }
+        let label = match decision {
+            OperationStepDecision::Processed => return format!("{} [{}]:", name, self.mib(bytes, false)),
+            OperationStepDecision::Skipped => self.label_skipped(),
+            OperationStepDecision::Cancelled | OperationStepDecision::Ignored => self.label_ignored(),
+        };
+        match self.language {
+            Language::English => format!("{} [{}] {}:", name, self.mib(bytes, false), label),
+        }
+    }
+
+    pub fn cli_game_shrunk(&self, previous_bytes: u64) -> String {
+        match self.language {
+            Language::English => format!(
+                "  (latest backup: {}; the save location may have moved)",
+                self.mib(previous_bytes, false)
+            ),
+        }
+    }
+
+    pub fn cli_game_overwrites(&self, overwrites: usize, files: usize) -> String {
+        match self.language {
+            Language::English => format!("  ({} of {} files replace existing files)", overwrites, files),
         }
     }
 
@@ -117,26 +497,74 @@ impl Translator {
         }
     }
 
+    pub fn cli_game_line_item_overwritten(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_overwrite(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_skipped(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_skipped(), item),
+        }
+    }
+
     pub fn cli_game_line_item_failed(&self, item: &str) -> String {
         match self.language {
             Language::English => format!("  - {} {}", self.label_failed(), item),
         }
     }
 
+    pub fn cli_game_line_item_corrupted(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_corrupted(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_missing(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_missing(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_added(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_added(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_removed(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_removed(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_resized(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_resized(), item),
+        }
+    }
+
+    pub fn cli_game_line_item_modified(&self, item: &str) -> String {
+        match self.language {
+            Language::English => format!("  - {} {}", self.label_modified(), item),
+        }
+    }
+
     pub fn cli_game_line_item_redirected(&self, item: &str) -> String {
         match self.language {
             Language::English => format!("    - Redirected from: {}", item),
         }
     }
 
-    pub fn cli_summary(&self, status: &OperationStatus, location: &StrictPath) -> String {
+    pub fn cli_summary(&self, status: &OperationStatus, location: &str) -> String {
         if status.completed() {
             match self.language {
                 Language::English => format!(
                     "\nOverall:\n  Games: {}\n  Size: {}\n  Location: {}",
                     status.total_games,
                     self.mib(status.total_bytes, true),
-                    location.render()
+                    location
                 ),
             }
         } else {
@@ -147,27 +575,296 @@ impl Translator {
                     status.total_games,
                     self.mib_unlabelled(status.processed_bytes),
                     self.mib(status.total_bytes, true),
-                    location.render()
+                    location
                 ),
             }
         }
     }
 
+    pub fn cli_integrity_summary(&self, intact: usize, broken: usize, unknown: usize) -> String {
+        match self.language {
+            Language::English => format!(
+                "\n  Integrity: {} intact, {} broken, {} unknown",
+                intact, broken, unknown
+            ),
+        }
+    }
+
+    pub fn cli_root_summary(&self, roots: &[RootStatus]) -> String {
+        let lines: Vec<_> = roots.iter().map(|x| format!("\n    {}", self.root_status(x))).collect();
+        match self.language {
+            Language::English => format!("\n  Roots:{}", lines.join("")),
+        }
+    }
+
+    pub fn cli_timing_summary(&self, timings: &PhaseTimings) -> String {
+        match self.language {
+            Language::English => format!(
+                "\n  Time:\n    Manifest update: {} ms\n    Scan: {} ms\n    Copy: {} ms",
+                timings.manifest_update_ms, timings.scan_ms, timings.copy_ms
+            ),
+        }
+    }
+
+    pub fn cli_game_ambiguous_locations(&self, locations: &[(String, Option<std::time::SystemTime>)]) -> String {
+        format!("  ({})", self.ambiguous_locations(locations))
+    }
+
+    /// Each location is a folder to display, along with when its files were last modified.
+    pub fn ambiguous_locations(&self, locations: &[(String, Option<std::time::SystemTime>)]) -> String {
+        let locations: Vec<_> = locations
+            .iter()
+            .map(|(path, modified)| match (self.language, modified) {
+                (Language::English, Some(modified)) => format!("{} (modified {})", path, self.timestamp(modified)),
+                (Language::English, None) => format!("{} (modification time unknown)", path),
+            })
+            .collect();
+        match self.language {
+            Language::English => format!("the same saves were found in several roots: {}", locations.join(", ")),
+        }
+    }
+
+    pub fn cli_game_timing(&self, scan: &Timing, copy: &Timing) -> String {
+        format!("  ({})", self.game_timing(scan, copy))
+    }
+
+    pub fn game_timing(&self, scan: &Timing, copy: &Timing) -> String {
+        match self.language {
+            Language::English => format!("scan: {} ms, copy: {} ms", scan.millis(), copy.millis()),
+        }
+    }
+
+    pub fn game_timing_with_estimate(&self, scan: &Timing, estimated_backup: std::time::Duration) -> String {
+        match self.language {
+            Language::English => format!(
+                "scan: {} ms, estimated backup: {} s",
+                scan.millis(),
+                estimated_backup.as_secs()
+            ),
+        }
+    }
+
+    pub fn show_timings_label(&self) -> String {
+        match self.language {
+            Language::English => "Show timings",
+        }
+        .into()
+    }
+
+    pub fn root_status(&self, root: &RootStatus) -> String {
+        let label = match (&root.path, &root.store) {
+            (Some(path), Some(store)) => format!("{} ({})", path, self.store(store)),
+            _ => match self.language {
+                Language::English => "No root".to_string(),
+            },
+        };
+        match self.language {
+            Language::English => format!("{}: {} games, {}", label, root.games, self.mib(root.bytes, true)),
+        }
+    }
+
+    /// Describe the outcome of processing a game, e.g.,
+    /// "Backed up 42 files (3 failed, 1 registry key failed)".
+    pub fn backup_info_summary(&self, backup_info: &BackupInfo) -> String {
+        let files = backup_info.found_files;
+        let failed_files = backup_info.failed_files.len();
+        let failed_registry = backup_info.failed_registry.len();
+        match self.language {
+            Language::English => {
+                let mut failures = vec![];
+                if failed_files > 0 {
+                    failures.push(format!("{} failed", failed_files));
+                }
+                if failed_registry > 0 {
+                    failures.push(format!(
+                        "{} registry {} failed",
+                        failed_registry,
+                        if failed_registry == 1 { "key" } else { "keys" }
+                    ));
+                }
+                let processed = format!("Backed up {} {}", files, if files == 1 { "file" } else { "files" });
+                if failures.is_empty() {
+                    processed
+                } else {
+                    format!("{} ({})", processed, failures.join(", "))
+                }
+            }
+        }
+    }
+
+    pub fn cli_game_summary(&self, summary: &str) -> String {
+        match self.language {
+            Language::English => format!("  {}", summary),
+        }
+    }
+
+    pub fn cli_restore_conflict(&self, target: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Multiple games would restore {}. Which one should be kept?",
+                target.render()
+            ),
+        }
+    }
+
+    pub fn restore_conflict_source(&self, source: &RestoreConflictSource) -> String {
+        match (self.language, &source.modified) {
+            (Language::English, Some(modified)) => {
+                format!("{} (backed up {})", source.game_name, self.timestamp(modified))
+            }
+            (Language::English, None) => format!("{} (backup time unknown)", source.game_name),
+        }
+    }
+
+    /// Render a time as UTC, since we don't have access to the local time zone.
+    pub fn timestamp(&self, time: &std::time::SystemTime) -> String {
+        let seconds = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(x) => x.as_secs() as i64,
+            Err(_) => 0,
+        };
+        let (days, day_seconds) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+        // Convert days since the epoch to a civil date:
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year,
+            month,
+            day,
+            day_seconds / 3600,
+            day_seconds % 3600 / 60,
+            day_seconds % 60
+        )
+    }
+
+    pub fn cli_report_line(&self, name: &str, bytes: u64, files: usize) -> String {
+        match self.language {
+            Language::English => format!("{} [{}]: {} files", name, self.mib(bytes, false), files),
+        }
+    }
+
+    pub fn cli_diff_header(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{}:", name),
+        }
+    }
+
+    pub fn cli_diff_no_changes(&self) -> String {
+        match self.language {
+            Language::English => "  No changes since the last backup.",
+        }
+        .into()
+    }
+
+    pub fn cli_diff_changed_bytes(&self, bytes: u64) -> String {
+        match self.language {
+            Language::English => format!("  {} to back up", self.adjusted_size(bytes)),
+        }
+    }
+
     pub fn game_list_entry_title_failed(&self, name: &str) -> String {
         match self.language {
             Language::English => format!("{} {}", name, self.label_failed()),
         }
     }
 
+    pub fn game_list_entry_title_partially_failed(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", name, self.label_partially_failed()),
+        }
+    }
+
+    pub fn game_list_entry_title_uninstalled(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", name, self.label_uninstalled()),
+        }
+    }
+
+    pub fn game_list_entry_title_shrunk(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", name, self.label_shrunk()),
+        }
+    }
+
+    pub fn game_list_entry_title_broken(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", name, self.label_broken_backup()),
+        }
+    }
+
+    pub fn game_list_entry_title_registry_only(&self, name: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", name, self.label_registry_only()),
+        }
+    }
+
+    pub fn corrupted_file_entry_line(&self, path: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", self.label_corrupted(), path),
+        }
+    }
+
+    pub fn missing_file_entry_line(&self, path: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", self.label_missing(), path),
+        }
+    }
+
+    pub fn unsafe_path_entry_line(&self, path: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", self.label_unsafe(), path),
+        }
+    }
+
     pub fn failed_file_entry_line(&self, path: &str) -> String {
         match self.language {
             Language::English => format!("{} {}", self.label_failed(), path),
         }
     }
 
-    pub fn redirected_file_entry_line(&self, path: &StrictPath) -> String {
+    pub fn skipped_file_entry_line(&self, path: &str) -> String {
+        match self.language {
+            Language::English => format!("{} {}", self.label_skipped(), path),
+        }
+    }
+
+    pub fn registry_type_conflict_entry_line(&self, value: &str) -> String {
+        match self.language {
+            Language::English => format!("[KEPT EXISTING TYPE] {}", value),
+        }
+    }
+
+    pub fn unmapped_registry_key_entry_line(&self, key: &str) -> String {
         match self.language {
-            Language::English => format!(". . . . . Redirected from: {}", path.render()),
+            Language::English => format!("[UNKNOWN USER] {}", key),
+        }
+    }
+
+    pub fn restored_with_stored_name_entry_line(&self) -> String {
+        match self.language {
+            Language::English => ". . . . . Restored with its name from the backup".to_string(),
+        }
+    }
+
+    pub fn unrestored_owner_entry_line(&self) -> String {
+        match self.language {
+            Language::English => ". . . . . Restored without its original owner".to_string(),
+        }
+    }
+
+    pub fn redirected_file_entry_line(&self, path: &str) -> String {
+        match self.language {
+            Language::English => format!(". . . . . Redirected from: {}", path),
         }
     }
 
@@ -269,6 +966,13 @@ impl Translator {
         .into()
     }
 
+    pub fn search_game_name_placeholder(&self) -> String {
+        match self.language {
+            Language::English => "Search by game name",
+        }
+        .into()
+    }
+
     pub fn select_all_button(&self) -> String {
         match self.language {
             Language::English => "Select all",
@@ -332,6 +1036,12 @@ impl Translator {
         .into()
     }
 
+    pub fn registry_key_not_found(&self, key: &str) -> String {
+        match self.language {
+            Language::English => format!("Error: Registry key not found: {}", key),
+        }
+    }
+
     pub fn unable_to_browse_file_system(&self) -> String {
         match self.language {
             Language::English => "Error: Unable to browse on your system.",
@@ -339,6 +1049,115 @@ impl Translator {
         .into()
     }
 
+    pub fn show_more_button(&self, remaining: usize) -> String {
+        match self.language {
+            Language::English => format!("Show more ({} remaining)", remaining),
+        }
+    }
+
+    pub fn backup_folder_not_found(&self, game: &str, folder: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Error: There is no backup of {} yet. Expected folder: {}",
+                game,
+                folder.render()
+            ),
+        }
+    }
+
+    pub fn unable_to_open_dir(&self, folder: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Error: Unable to open the folder in your file manager: {}",
+                folder.render()
+            ),
+        }
+    }
+
+    pub fn unable_to_write_metrics(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Error: Unable to write metrics: {}", path.render()),
+        }
+    }
+
+    pub fn unable_to_record_scan(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Error: Unable to record scan: {}", path.render()),
+        }
+    }
+
+    #[cfg(feature = "android")]
+    pub fn unable_to_pull_android_saves(&self, package: &str) -> String {
+        match self.language {
+            Language::English => format!(
+                "Error: Unable to pull the saves of {} over ADB. Make sure that the device is connected and that the app is debuggable.",
+                package
+            ),
+        }
+    }
+
+    #[cfg(feature = "android")]
+    pub fn cli_android_pull_summary(&self, files: usize, dest: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Pulled {} file(s) into {}", files, dest.render()),
+        }
+    }
+
+    pub fn unable_to_export_profile(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Error: Unable to export the profile: {}", path.render()),
+        }
+    }
+
+    pub fn unable_to_import_profile(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Error: Unable to read the profile: {}", path.render()),
+        }
+    }
+
+    pub fn cli_export_profile_summary(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Exported the profile to {}", path.render()),
+        }
+    }
+
+    pub fn cli_import_profile_summary(&self, path: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Imported the profile from {} into your config file", path.render()),
+        }
+    }
+
+    pub fn unable_to_export_archive(&self, archive: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Error: Unable to export the backup to an archive: {}", archive.render()),
+        }
+    }
+
+    pub fn unable_to_import_archive(&self, archive: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!(
+                "Error: Unable to import the backup from an archive (the target must be empty): {}",
+                archive.render()
+            ),
+        }
+    }
+
+    pub fn cli_export_summary(&self, archive: &StrictPath, bytes: u64) -> String {
+        match self.language {
+            Language::English => format!(
+                "Exported the backup to {} ({})",
+                archive.render(),
+                self.adjusted_size(bytes)
+            ),
+        }
+    }
+
+    pub fn cli_import_summary(&self, games: usize, target: &StrictPath) -> String {
+        match self.language {
+            Language::English => format!("Imported {} game(s) into {}", games, target.render()),
+        }
+    }
+
     pub fn mib(&self, bytes: u64, show_zero: bool) -> String {
         let mib = self.mib_unlabelled(bytes);
         if !show_zero && mib == "0.00" {
@@ -353,6 +1172,27 @@ impl Translator {
         }
     }
 
+    pub fn screenshots_size(&self, bytes: u64) -> String {
+        match self.language {
+            Language::English => format!("Screenshots: {}", self.mib(bytes, false)),
+        }
+    }
+
+    /// Like `mib`, but with whichever unit suits the size.
+    pub fn adjusted_size(&self, bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        if bytes < 1024 {
+            return format!("{} {}", bytes, UNITS[0]);
+        }
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+
     pub fn mib_unlabelled(&self, bytes: u64) -> String {
         format!("{:.2}", bytes as f64 / 1024.0 / 1024.0)
     }
@@ -382,6 +1222,54 @@ impl Translator {
         .into()
     }
 
+    pub fn backup_target_unchecked(&self) -> String {
+        match self.language {
+            Language::English => "The backup target will be checked when you press Enter or start a backup.",
+        }
+        .into()
+    }
+
+    pub fn backup_target_checking(&self) -> String {
+        match self.language {
+            Language::English => "Checking backup target...",
+        }
+        .into()
+    }
+
+    pub fn backup_target_unreachable(&self) -> String {
+        match self.language {
+            Language::English => "Warning: The backup target is not responding. It may be on a disconnected drive or an offline network share.",
+        }
+        .into()
+    }
+
+    pub fn backup_target_health(&self, health: &BackupTargetHealth) -> String {
+        if !health.writable {
+            return match (self.language, health.exists) {
+                (Language::English, true) => "Warning: The backup target is not writable.",
+                (Language::English, false) => "Warning: The backup target does not exist and cannot be created.",
+            }
+            .into();
+        }
+
+        let free = match health.free_bytes {
+            Some(bytes) => format!("{:.2} GiB", bytes as f64 / 1024.0 / 1024.0 / 1024.0),
+            None => "?".to_string(),
+        };
+        match self.language {
+            Language::English => match (health.exists, &health.last_backup) {
+                (false, _) => format!("New folder | {} free", free),
+                (true, Some(last_backup)) => format!(
+                    "{} games | {} free | Last backup: {}",
+                    health.games,
+                    free,
+                    self.timestamp(last_backup)
+                ),
+                (true, None) => format!("{} games | {} free", health.games, free),
+            },
+        }
+    }
+
     pub fn backup_merge_label(&self) -> String {
         match self.language {
             Language::English => "Merge",
@@ -414,6 +1302,7 @@ impl Translator {
         match self.language {
             Language::English => match store {
                 Store::Steam => "Steam",
+                Store::Rockstar => "Rockstar",
                 Store::Other => "Other",
             },
         }
@@ -455,10 +1344,37 @@ impl Translator {
         .into()
     }
 
-    pub fn modal_confirm_backup(&self, target: &StrictPath, target_exists: bool, merge: bool) -> String {
+    pub fn explanation_for_group_by_store(&self) -> String {
+        match self.language {
+            Language::English => "Group new backups by store, so that each game's backup goes in a folder like `steam/<game>`. When merging into an existing backup, games that are already in it will stay where they are.",
+        }
+        .into()
+    }
+
+    pub fn explanation_for_compress_large_files(&self) -> String {
+        match self.language {
+            Language::English => "Compress large files that shrink well, like text-based world files. Compressed files are stored with a `.zst` extension and decompressed automatically when restoring.",
+        }
+        .into()
+    }
+
+    pub fn explanation_for_include_screenshots(&self) -> String {
+        match self.language {
+            Language::English => "Restore store screenshots along with the saves. Screenshots are kept in a separate folder of each game's backup, and old ones are never removed by later backups.",
+        }
+        .into()
+    }
+
+    pub fn modal_confirm_backup(
+        &self,
+        target: &StrictPath,
+        target_exists: bool,
+        merge: bool,
+        existing_files: usize,
+    ) -> String {
         match (self.language, target_exists, merge) {
             (Language::English, false, _) => format!("Are you sure you want to proceed with the backup? The target folder will be created: {}", target.render()),
-            (Language::English, true, false) => format!("Are you sure you want to proceed with the backup? The target folder will be deleted and recreated from scratch: {}", target.render()),
+            (Language::English, true, false) => format!("Are you sure you want to proceed with the backup? The target folder will be deleted and recreated from scratch, removing {} existing files: {}", existing_files, target.render()),
             (Language::English, true, true) => format!("Are you sure you want to proceed with the backup? New save data will be merged into the target folder: {}", target.render()),
         }
     }
@@ -1,11 +1,41 @@
-use crate::{path::StrictPath, prelude::ScannedFile};
+use crate::{
+    config::RootsConfig,
+    manifest::Store,
+    path::StrictPath,
+    prelude::{display_path, is_within, reslashed, Error, IntegrityInfo, IntegrityStatus, ScannedFile},
+};
 
 const SAFE: &str = "_";
+/// Files compressed during backup are stored with this appended to their name.
+pub const COMPRESSED_SUFFIX: &str = ".zst";
+/// Store screenshots are kept in this subfolder of a game's backup,
+/// with the same drive and anchor folders as the rest of the backup.
+pub const SCREENSHOTS_FOLDER: &str = "screenshots";
+const STORE_FOLDERS: &[&str] = &["steam", "rockstar", "other"];
+
+fn store_folder_name(store: Store) -> &'static str {
+    match store {
+        Store::Steam => "steam",
+        Store::Rockstar => "rockstar",
+        Store::Other => "other",
+    }
+}
 
 fn encode_base64_for_folder(name: &str) -> String {
     base64::encode(&name).replace("/", SAFE)
 }
 
+/// Swap the target of a recorded reparse point back to the link that pointed to it.
+fn through_reparse_points(original: &str, reparse_points: &std::collections::HashMap<String, String>) -> String {
+    let reslashed = original.replace("\\", "/");
+    for (target, link) in reparse_points {
+        if reslashed.starts_with(&format!("{}/", target)) {
+            return reslashed.replacen(target.as_str(), link, 1);
+        }
+    }
+    original.to_string()
+}
+
 fn escape_folder_name(name: &str) -> String {
     let mut escaped = String::from(name);
 
@@ -37,11 +67,92 @@ fn escape_folder_name(name: &str) -> String {
         .replace("\0", SAFE)
 }
 
+/// The subfolder of a shared backup target for one machine or user.
+/// In the namespace, `<hostname>` and `<username>` are replaced with this machine's values.
+pub fn namespaced_path(base: &StrictPath, namespace: &Option<String>) -> StrictPath {
+    match namespace {
+        Some(namespace) if !namespace.trim().is_empty() => {
+            #[allow(deprecated)]
+            let hostname = whoami::hostname();
+            let expanded = namespace
+                .replace("<hostname>", &hostname)
+                .replace("<username>", &whoami::username());
+            base.joined(&escape_folder_name(&expanded))
+        }
+        _ => base.clone(),
+    }
+}
+
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct IndividualMapping {
     pub name: String,
     #[serde(serialize_with = "crate::serialization::ordered_map")]
     pub drives: std::collections::HashMap<String, String>,
+    /// Keys are paths relative to the game's backup folder, like `drive-C/foo/bar.txt`.
+    /// This is empty for backups made before file metadata was recorded.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::serialization::ordered_map"
+    )]
+    pub files: std::collections::HashMap<String, IndividualMappingFile>,
+    /// Keys are the targets of links (e.g., junctions) that the original save
+    /// paths went through, and values are the links themselves.
+    /// Restoration goes back through the links so that games can find their saves.
+    #[serde(
+        default,
+        rename = "reparsePoints",
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::serialization::ordered_map"
+    )]
+    pub reparse_points: std::collections::HashMap<String, String>,
+    /// Keys are paths relative to the game's backup folder for files that were stored
+    /// under an escaped name, and values are the original paths in the same form.
+    /// For example, `drive-C/foo/save_final.dat` might map to `drive-C/foo/save:final.dat`.
+    #[serde(
+        default,
+        rename = "escapedNames",
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::serialization::ordered_map"
+    )]
+    pub escaped_names: std::collections::HashMap<String, String>,
+    /// Keys are folder names like `anchor-0`, and values are the portable roots
+    /// that the files within were found in. The files are stored relative to the root,
+    /// so that they can be restored wherever the root is now.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::serialization::ordered_map"
+    )]
+    pub anchors: std::collections::HashMap<String, RootsConfig>,
+    /// Like `files`, but for store screenshots, with keys starting with `SCREENSHOTS_FOLDER`.
+    /// These are kept across backups, even once they're gone from the original location.
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::serialization::ordered_map"
+    )]
+    pub screenshots: std::collections::HashMap<String, IndividualMappingFile>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IndividualMappingFile {
+    /// This is the original size, even if the file was compressed.
+    pub size: u64,
+    /// This is set when the file was compressed, in which case it is stored
+    /// under its key plus `COMPRESSED_SUFFIX`.
+    #[serde(default, rename = "compressedSize", skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+    /// This is only recorded on Unix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<FileOwner>,
+}
+
+/// The user and group that owned a file when it was backed up.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct FileOwner {
+    pub uid: u32,
+    pub gid: u32,
 }
 
 impl IndividualMapping {
@@ -52,27 +163,110 @@ impl IndividualMapping {
         }
     }
 
-    fn reversed_drives(&self) -> std::collections::HashMap<String, String> {
-        self.drives.iter().map(|(k, v)| (v.to_owned(), k.to_owned())).collect()
+    /// Find or assign the folder for a drive. If the preferred name is already
+    /// taken by a different drive (e.g., `C:` and `C` both escape to `drive-C`),
+    /// then the drive gets the first unused `drive-N` instead, so that an existing
+    /// entry is never replaced.
+    pub fn drive_folder_name(&mut self, drive: &str) -> String {
+        if let Some((key, _)) = self.drives.iter().find(|(_, mapped)| *mapped == drive) {
+            return key.to_string();
+        }
+
+        let preferred = if drive.is_empty() {
+            "drive-0".to_string()
+        } else {
+            // Simplify "C:" to "drive-C" instead of "drive-C_" for the common case.
+            format!("drive-{}", escape_folder_name(&drive.replace(":", "")))
+        };
+        let key = if self.drives.contains_key(&preferred) {
+            (1..)
+                .map(|n| format!("drive-{}", n))
+                .find(|x| !self.drives.contains_key(x))
+                .unwrap()
+        } else {
+            preferred
+        };
+        self.drives.insert(key.clone(), drive.to_string());
+        key
     }
 
-    pub fn drive_folder_name(&mut self, drive: &str) -> String {
-        let reversed = self.reversed_drives();
-        match reversed.get::<str>(&drive) {
-            Some(mapped) => mapped.to_string(),
+    pub fn anchor_folder_name(&mut self, root: &RootsConfig) -> String {
+        let existing = self
+            .anchors
+            .iter()
+            .find(|(_, anchor)| anchor.path.render() == root.path.render() && anchor.store == root.store);
+        match existing {
+            Some((key, _)) => key.to_string(),
             None => {
-                let key = if drive.is_empty() {
-                    "drive-0".to_string()
-                } else {
-                    // Simplify "C:" to "drive-C" instead of "drive-C_" for the common case.
-                    format!("drive-{}", escape_folder_name(&drive.replace(":", "")))
-                };
-                self.drives.insert(key.to_string(), drive.to_string());
+                let key = format!("anchor-{}", self.anchors.len());
+                self.anchors.insert(key.clone(), root.clone());
                 key
             }
         }
     }
 
+    pub fn record_file(&mut self, key: &str, size: u64) {
+        self.files.insert(
+            key.to_string(),
+            IndividualMappingFile {
+                size,
+                compressed_size: None,
+                owner: None,
+            },
+        );
+    }
+
+    pub fn record_compressed_file(&mut self, key: &str, size: u64, compressed_size: u64) {
+        self.files.insert(
+            key.to_string(),
+            IndividualMappingFile {
+                size,
+                compressed_size: Some(compressed_size),
+                owner: None,
+            },
+        );
+    }
+
+    /// This applies to a file that has already been recorded.
+    pub fn record_file_owner(&mut self, key: &str, owner: FileOwner) {
+        if let Some(file) = self.files.get_mut(key) {
+            file.owner = Some(owner);
+        }
+    }
+
+    pub fn record_screenshot(&mut self, key: &str, size: u64) {
+        self.screenshots.insert(
+            key.to_string(),
+            IndividualMappingFile {
+                size,
+                compressed_size: None,
+                owner: None,
+            },
+        );
+    }
+
+    /// Carry over a screenshot from the game's previous backup,
+    /// along with the drive or anchor folder that it's stored under.
+    pub fn keep_screenshot(&mut self, key: &str, previous: &OverallMappingGame) {
+        let file = match previous.screenshots.get(key) {
+            Some(x) => x,
+            None => return,
+        };
+        if let Some(folder) = key.split('/').nth(1) {
+            if let Some(drive) = previous.drives.get(folder) {
+                self.drives.entry(folder.to_string()).or_insert_with(|| drive.clone());
+            }
+            if let Some(anchor) = previous.anchors.get(folder) {
+                self.anchors.entry(folder.to_string()).or_insert_with(|| anchor.clone());
+            }
+        }
+        self.screenshots.insert(key.to_string(), file.clone());
+    }
+
+    pub fn record_reparse_point(&mut self, target: &str, link: &str) {
+        self.reparse_points.insert(target.to_string(), link.to_string());
+    }
+
     pub fn save(&self, file: &StrictPath) {
         std::fs::write(file.interpret(), self.serialize().as_bytes()).unwrap();
     }
@@ -100,57 +294,247 @@ impl IndividualMapping {
 #[derive(Clone, Debug, Default)]
 pub struct OverallMapping {
     pub games: std::collections::HashMap<String, OverallMappingGame>,
+    /// These are subfolders that hold other machines' or users' backups
+    /// (see `namespaced_path`). Their games are not part of this mapping. Sorted.
+    pub namespaces: Vec<String>,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct OverallMappingGame {
     pub drives: std::collections::HashMap<String, String>,
     pub base: StrictPath,
+    pub files: std::collections::HashMap<String, IndividualMappingFile>,
+    pub reparse_points: std::collections::HashMap<String, String>,
+    pub escaped_names: std::collections::HashMap<String, String>,
+    pub anchors: std::collections::HashMap<String, RootsConfig>,
+    pub screenshots: std::collections::HashMap<String, IndividualMappingFile>,
+}
+
+impl OverallMappingGame {
+    /// This is the original location of a top-level folder in the game's backup,
+    /// which is either a drive or a portable root.
+    fn folder_target(&self, folder: &str, roots: &[RootsConfig]) -> Option<String> {
+        match self.drives.get(folder) {
+            Some(drive) => Some(drive.to_string()),
+            None => self.anchors.get(folder).map(|anchor| resolve_anchor(anchor, roots)),
+        }
+    }
+
+    /// This is the total original size of the backed up files,
+    /// or `None` for backups made before file metadata was recorded.
+    pub fn recorded_size(&self) -> Option<u64> {
+        if self.files.is_empty() {
+            None
+        } else {
+            Some(self.files.values().map(|x| x.size).sum())
+        }
+    }
+
+    /// If the stored file is a compressed one, then this is the key
+    /// of the original file, without `COMPRESSED_SUFFIX`.
+    pub fn uncompressed_key<'a>(&self, stored_key: &'a str) -> Option<&'a str> {
+        if !stored_key.ends_with(COMPRESSED_SUFFIX) {
+            return None;
+        }
+        let key = &stored_key[..stored_key.len() - COMPRESSED_SUFFIX.len()];
+        match self.files.get(key) {
+            Some(file) if file.compressed_size.is_some() => Some(key),
+            _ => None,
+        }
+    }
+}
+
+/// Find where a portable root is now. In order, this prefers a root with the same
+/// path, then a root on another drive with the same path otherwise, then the only
+/// portable root of the same store. If none match, then the original path is used.
+fn resolve_anchor(anchor: &RootsConfig, roots: &[RootsConfig]) -> String {
+    let anchor_path = anchor.path.render();
+    let (_, anchor_plain) = anchor.path.split_drive();
+    let same_store: Vec<_> = roots.iter().filter(|x| x.store == anchor.store).collect();
+    let portable: Vec<_> = same_store.iter().filter(|x| x.portable).collect();
+
+    same_store
+        .iter()
+        .find(|x| x.path.render() == anchor_path)
+        .or_else(|| same_store.iter().find(|x| x.path.split_drive().1 == anchor_plain))
+        .or_else(|| if portable.len() == 1 { Some(portable[0]) } else { None })
+        .map(|x| x.path.render())
+        .unwrap_or(anchor_path)
 }
 
 impl OverallMapping {
     pub fn load(base: &StrictPath) -> Self {
         let mut overall = Self::default();
 
-        for game_dir in walkdir::WalkDir::new(base.interpret())
-            .max_depth(1)
-            .follow_links(false)
-            .into_iter()
-            .skip(1) // the base path itself
-            .filter_map(|e| e.ok())
-            .filter(|x| x.file_type().is_dir())
-        {
-            let individual_file = &mut game_dir.path().to_path_buf();
-            individual_file.push("mapping.yaml");
-            if individual_file.is_file() {
-                let game = match IndividualMapping::load(&StrictPath::from_std_path_buf(&individual_file)) {
-                    Ok(x) => x,
-                    Err(_) => continue,
-                };
-                overall.games.insert(
-                    game.name,
-                    OverallMappingGame {
-                        base: StrictPath::from_std_path_buf(&game_dir.path().to_path_buf()),
-                        drives: game.drives,
-                    },
-                );
+        for game_dir in subdirectories(base.interpret()) {
+            if overall.load_game(&game_dir) {
+                continue;
+            }
+            // When backups are grouped by store, the games are one level deeper.
+            let folder_name = game_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if STORE_FOLDERS.contains(&folder_name.as_str()) {
+                for grouped_game_dir in subdirectories(&game_dir) {
+                    overall.load_game(&grouped_game_dir);
+                }
+            } else if Self::holds_games(&game_dir) {
+                overall.namespaces.push(folder_name);
             }
         }
 
+        overall.namespaces.sort();
         overall
     }
+
+    /// Whether the folder has any game backups, either directly or grouped by store.
+    fn holds_games(dir: &std::path::Path) -> bool {
+        subdirectories(dir).iter().any(|child| {
+            child.join("mapping.yaml").is_file()
+                || (STORE_FOLDERS.contains(&child.file_name().unwrap_or_default().to_string_lossy().as_ref())
+                    && subdirectories(child).iter().any(|x| x.join("mapping.yaml").is_file()))
+        })
+    }
+
+    /// Returns whether the folder contained a game's backup.
+    fn load_game(&mut self, game_dir: &std::path::Path) -> bool {
+        let individual_file = game_dir.join("mapping.yaml");
+        if !individual_file.is_file() {
+            return false;
+        }
+        let game = match IndividualMapping::load(&StrictPath::from_std_path_buf(&individual_file)) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+        self.games.insert(
+            game.name,
+            OverallMappingGame {
+                base: StrictPath::from_std_path_buf(&game_dir.to_path_buf()),
+                drives: game.drives,
+                files: game.files,
+                reparse_points: game.reparse_points,
+                escaped_names: game.escaped_names,
+                anchors: game.anchors,
+                screenshots: game.screenshots,
+            },
+        );
+        true
+    }
+}
+
+/// A hidden path in the same folder, so that renaming it into place is atomic.
+fn temporary_sibling(path: &StrictPath) -> Option<StrictPath> {
+    let name = path.as_std_path_buf().file_name()?.to_string_lossy().to_string();
+    Some(path.with_file_name(&format!(".{}.{}.tmp", name, std::process::id())))
+}
+
+fn subdirectories<P: AsRef<std::path::Path>>(dir: P) -> Vec<std::path::PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .max_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .skip(1) // the base path itself
+        .filter_map(|e| e.ok())
+        .filter(|x| x.file_type().is_dir())
+        .map(|x| x.path().to_path_buf())
+        .collect()
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BackupReport {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: std::time::SystemTime,
+    pub games: Vec<GameReport>,
+}
+
+impl BackupReport {
+    /// Replace the home folder with `<home>` in the listed paths.
+    pub fn redact_home(&mut self) {
+        for game in &mut self.games {
+            game.backup_folder = StrictPath::new(display_path(&game.backup_folder, &[]));
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct GameReport {
+    pub name: String,
+    #[serde(rename = "backupFolder")]
+    pub backup_folder: StrictPath,
+    /// This only counts the backed up save files, not Ludusavi's own metadata.
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: u64,
+    #[serde(rename = "fileCount")]
+    pub file_count: usize,
+    /// This is the most recent modification time of anything in the backup folder.
+    #[serde(rename = "lastModified", skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<std::time::SystemTime>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactionReport {
+    /// These are the backups of games that no longer exist, along with their names.
+    pub removed_games: Vec<(String, StrictPath)>,
+    /// These are empty drive folders in the backups that were kept.
+    pub removed_folders: Vec<StrictPath>,
+    /// This is the total size of the files in the removed folders.
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntegrityReport {
+    pub ok_games: Vec<String>,
+    /// Each broken game is listed with its corrupted backup files
+    /// and the original paths of any files missing from the backup.
+    pub corrupt_games: Vec<(String, Vec<StrictPath>)>,
+    /// These are folders in the backup without a readable `mapping.yaml`,
+    /// relative to the backup folder.
+    pub missing_mapping: Vec<String>,
+}
+
+/// Which surplus backups to remove when pruning. A game can have several backup folders
+/// (e.g., one grouped by store and one not), and only the older ones are surplus.
+/// Without any settings, nothing is removed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrunePolicy {
+    /// Only remove backups whose folder is older than this many days.
+    pub older_than_days: Option<u64>,
+    /// Remove the oldest backups only until the backup drive has this much free space.
+    pub min_free_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneReport {
+    /// Each removed backup is listed with its game's name, oldest first.
+    pub removed_backups: Vec<(String, StrictPath)>,
+    /// This is the total size of the files in the removed folders.
+    pub reclaimed_bytes: u64,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct BackupLayout {
     pub base: StrictPath,
     pub mapping: OverallMapping,
+    /// Place new game folders under a folder for their store.
+    /// Existing games are found either way.
+    pub group_by_store: bool,
+    /// These are the current roots, used to find where portable roots are now.
+    pub roots: Vec<RootsConfig>,
+    /// Compress large files when backing them up, if they compress well.
+    pub compress_large_files: bool,
+    /// Include store screenshots in the restorable files.
+    pub include_screenshots: bool,
 }
 
 impl BackupLayout {
     pub fn new(base: StrictPath) -> Self {
         let mapping = OverallMapping::load(&base);
-        Self { base, mapping }
+        Self {
+            base,
+            mapping,
+            group_by_store: false,
+            roots: vec![],
+            compress_large_files: false,
+            include_screenshots: false,
+        }
     }
 
     fn generate_total_rename(original_name: &str) -> String {
@@ -158,6 +542,13 @@ impl BackupLayout {
     }
 
     pub fn game_folder(&self, game_name: &str) -> StrictPath {
+        self.game_folder_for_store(game_name, None)
+    }
+
+    /// Like `game_folder`, but if the game doesn't have a folder yet and backups
+    /// are grouped by store, then the new folder goes under the store's folder.
+    /// Games without a store are grouped as `other`.
+    pub fn game_folder_for_store(&self, game_name: &str, store: Option<Store>) -> StrictPath {
         match self.mapping.games.get::<str>(&game_name) {
             Some(game) => game.base.clone(),
             None => {
@@ -168,29 +559,166 @@ impl BackupLayout {
                     safe_name = Self::generate_total_rename(&game_name);
                 }
 
-                self.base.joined(&safe_name)
+                if self.group_by_store {
+                    self.unblocked_folder(&format!(
+                        "{}/{}",
+                        store_folder_name(store.unwrap_or_default()),
+                        safe_name
+                    ))
+                } else {
+                    self.unblocked_folder(&safe_name)
+                }
+            }
+        }
+    }
+
+    /// If something other than a folder (e.g., a leftover file) is in the way,
+    /// then pick a numbered name instead, so that the backup doesn't replace it.
+    fn unblocked_folder(&self, relative: &str) -> StrictPath {
+        let folder = self.base.joined(relative);
+        if !folder.exists() || folder.is_dir() {
+            return folder;
+        }
+        let mut i = 1;
+        loop {
+            let candidate = self.base.joined(&format!("{}.{}", relative, i));
+            if !candidate.exists() {
+                return candidate;
             }
+            i += 1;
         }
     }
 
-    pub fn game_file(
+    pub fn game_file(&self, game_folder: &StrictPath, key: &str) -> StrictPath {
+        StrictPath::relative(key.to_string(), Some(game_folder.interpret()))
+    }
+
+    /// This is the location of a backed up file relative to the game folder.
+    pub fn game_file_key(&self, original_file: &StrictPath, mapping: &mut IndividualMapping) -> String {
+        let (drive, plain_path) = original_file.split_drive();
+        let drive_folder = mapping.drive_folder_name(&drive);
+        format!("{}/{}", drive_folder, plain_path)
+    }
+
+    /// Like `game_file_key`, but files in a portable root are stored relative to that root.
+    pub fn game_file_key_for_root(
         &self,
-        game_folder: &StrictPath,
         original_file: &StrictPath,
+        root: &Option<RootsConfig>,
         mapping: &mut IndividualMapping,
-    ) -> StrictPath {
+    ) -> String {
+        match portable_relative_path(original_file, root) {
+            Some((root, relative)) => format!("{}/{}", mapping.anchor_folder_name(root), relative),
+            None => self.game_file_key(original_file, mapping),
+        }
+    }
+
+    /// This is where a live file would be found in the game's existing backup,
+    /// using the same format as `game_file_key`. Unlike that function, this never
+    /// assigns a new drive folder, so it returns `None` if the drive was not backed up.
+    pub fn existing_game_file_key(
+        &self,
+        game_name: &str,
+        original_file: &StrictPath,
+        root: &Option<RootsConfig>,
+    ) -> Option<String> {
+        let game = self.mapping.games.get(game_name)?;
+        if let Some((root, relative)) = portable_relative_path(original_file, root) {
+            let root_path = root.path.render();
+            let (anchor_folder, _) = game
+                .anchors
+                .iter()
+                .find(|(folder, _)| game.folder_target(folder, &self.roots).as_ref() == Some(&root_path))?;
+            return Some(format!("{}/{}", anchor_folder, relative));
+        }
         let (drive, plain_path) = original_file.split_drive();
-        let drive_folder = mapping.drive_folder_name(&drive);
-        StrictPath::relative(
-            format!("{}/{}", drive_folder, plain_path),
-            Some(game_folder.interpret()),
+        let (drive_folder, _) = game.drives.iter().find(|(_, mapped)| **mapped == drive)?;
+        Some(format!("{}/{}", drive_folder, plain_path))
+    }
+
+    /// This is the inverse of `game_file`: it takes a file inside the game folder
+    /// and returns its key relative to that folder.
+    pub fn backed_up_file_key(&self, game_folder: &StrictPath, backed_up_file: &StrictPath) -> Option<String> {
+        let prefix = game_folder.interpret();
+        let raw = backed_up_file.raw();
+        if !raw.starts_with(&prefix) {
+            return None;
+        }
+        Some(
+            raw[prefix.len()..]
+                .trim_start_matches(&['/', '\\'][..])
+                .replace("\\", "/"),
         )
     }
 
+    /// Like `backed_up_file_key`, but for a compressed file,
+    /// this is the key of the original file.
+    pub fn backed_up_original_file_key(
+        &self,
+        game_name: &str,
+        game_folder: &StrictPath,
+        backed_up_file: &StrictPath,
+    ) -> Option<String> {
+        let key = self.backed_up_file_key(game_folder, backed_up_file)?;
+        match self
+            .mapping
+            .games
+            .get(game_name)
+            .and_then(|game| game.uncompressed_key(&key))
+        {
+            Some(original) => Some(original.to_string()),
+            None => Some(key),
+        }
+    }
+
     pub fn game_mapping_file(&self, game_folder: &StrictPath) -> StrictPath {
         game_folder.joined("mapping.yaml")
     }
 
+    /// The owners recorded for the restorable files, when known.
+    pub fn file_owners(
+        &self,
+        game_name: &str,
+        game_folder: &StrictPath,
+        files: &std::collections::HashSet<ScannedFile>,
+    ) -> std::collections::HashMap<StrictPath, FileOwner> {
+        let game = match self.mapping.games.get(game_name) {
+            Some(x) => x,
+            None => return Default::default(),
+        };
+        files
+            .iter()
+            .filter_map(|file| {
+                let key = self.backed_up_file_key(game_folder, &file.path)?;
+                let key = game.uncompressed_key(&key).unwrap_or(&key);
+                let owner = game.files.get(key)?.owner?;
+                Some((file.path.clone(), owner))
+            })
+            .collect()
+    }
+
+    /// These are the restorable files that were compressed during backup.
+    pub fn compressed_files(
+        &self,
+        game_name: &str,
+        game_folder: &StrictPath,
+        files: &std::collections::HashSet<ScannedFile>,
+    ) -> std::collections::HashSet<StrictPath> {
+        let game = match self.mapping.games.get(game_name) {
+            Some(x) => x,
+            None => return Default::default(),
+        };
+        files
+            .iter()
+            .filter(|file| {
+                self.backed_up_file_key(game_folder, &file.path)
+                    .map(|key| game.uncompressed_key(&key).is_some())
+                    .unwrap_or(false)
+            })
+            .map(|file| file.path.clone())
+            .collect()
+    }
+
     #[allow(dead_code)]
     pub fn game_registry_file(&self, game_folder: &StrictPath) -> StrictPath {
         game_folder.joined("registry.yaml")
@@ -201,43 +729,518 @@ impl BackupLayout {
         game_name: &str,
         game_folder: &StrictPath,
     ) -> std::collections::HashSet<ScannedFile> {
+        let mut files = self.restorable_files_in(game_name, game_folder);
+        if self.include_screenshots {
+            files.extend(self.restorable_files_in(game_name, &game_folder.joined(SCREENSHOTS_FOLDER)));
+        }
+        files
+    }
+
+    /// Find the restorable files in the drive and anchor folders directly within `dir`.
+    fn restorable_files_in(&self, game_name: &str, dir: &StrictPath) -> std::collections::HashSet<ScannedFile> {
         let mut files = std::collections::HashSet::new();
-        for drive_dir in walkdir::WalkDir::new(game_folder.interpret())
+        for drive_dir in walkdir::WalkDir::new(dir.interpret())
             .max_depth(1)
             .follow_links(false)
             .into_iter()
             .filter_map(|e| e.ok())
         {
-            let raw_drive_dir = drive_dir.path().display().to_string();
-            let drive_mapping = match self.mapping.games.get::<str>(&game_name) {
-                Some(x) => match x.drives.get::<str>(&drive_dir.file_name().to_string_lossy()) {
-                    Some(y) => y,
-                    None => continue,
-                },
+            let drive = StrictPath::new(drive_dir.path().display().to_string());
+            let raw_drive_dir = drive.interpret();
+            let game_mapping = match self.mapping.games.get::<str>(&game_name) {
+                Some(x) => x,
+                None => continue,
+            };
+            let drive_folder = drive_dir.file_name().to_string_lossy().to_string();
+            let drive_mapping = match game_mapping.folder_target(&drive_folder, &self.roots) {
+                Some(y) => y,
                 None => continue,
             };
 
-            for file in walkdir::WalkDir::new(drive_dir.path())
-                .max_depth(100)
-                .follow_links(false)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|x| x.file_type().is_file())
-            {
-                let raw_file = file.path().display().to_string();
-                let original_path = Some(StrictPath::new(raw_file.replace(&raw_drive_dir, drive_mapping)));
+            for file in drive.iter_files() {
+                let raw_file = file.raw();
+                let stored_key = format!("{}{}", drive_folder, reslashed(&raw_file[raw_drive_dir.len()..]));
+                let uncompressed_key = game_mapping.uncompressed_key(&stored_key);
+                let key = uncompressed_key.unwrap_or(&stored_key);
+                let unescaped = match (game_mapping.escaped_names.get(key), uncompressed_key) {
+                    (Some(original_key), _) => {
+                        format!("{}{}", drive_mapping, &original_key[drive_folder.len()..])
+                    }
+                    (None, Some(key)) => format!("{}{}", drive_mapping, &key[drive_folder.len()..]),
+                    (None, None) => raw_file.replace(&raw_drive_dir, &drive_mapping),
+                };
+                let original_path = Some(StrictPath::new(through_reparse_points(
+                    &unescaped,
+                    &game_mapping.reparse_points,
+                )));
+                let size = match (uncompressed_key, std::fs::metadata(&raw_file)) {
+                    (Some(key), _) => game_mapping.files[key].size,
+                    (None, Ok(m)) => m.len(),
+                    _ => 0,
+                };
                 files.insert(ScannedFile {
-                    path: StrictPath::new(raw_file),
-                    size: match file.metadata() {
-                        Ok(m) => m.len(),
-                        _ => 0,
-                    },
+                    path: file,
+                    size,
                     original_path,
+                    root: None,
                 });
             }
         }
         files
     }
+
+    /// The recorded size of each game's backup, keyed by game name.
+    pub fn recorded_sizes(&self) -> std::collections::HashMap<String, u64> {
+        self.mapping
+            .games
+            .iter()
+            .filter_map(|(name, game)| game.recorded_size().map(|size| (name.clone(), size)))
+            .collect()
+    }
+
+    pub fn generate_report(&self) -> BackupReport {
+        let mut games: Vec<_> = self
+            .mapping
+            .games
+            .iter()
+            .map(|(name, game)| {
+                let files = self.restorable_files(name, &game.base);
+                let last_modified = walkdir::WalkDir::new(game.base.interpret())
+                    .follow_links(false)
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| e.metadata().ok())
+                    .filter_map(|m| m.modified().ok())
+                    .max();
+                GameReport {
+                    name: name.to_string(),
+                    backup_folder: game.base.clone(),
+                    size_bytes: files.iter().map(|x| x.size).sum(),
+                    file_count: files.len(),
+                    last_modified,
+                }
+            })
+            .collect();
+        games.sort_by(|x, y| x.name.cmp(&y.name));
+
+        BackupReport {
+            generated_at: std::time::SystemTime::now(),
+            games,
+        }
+    }
+
+    /// Verify every game in the backup. Games without recorded file metadata
+    /// can't be checked, so they count as OK. All of the lists are sorted.
+    pub fn integrity_check(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+
+        let mut names: Vec<_> = self.mapping.games.keys().collect();
+        names.sort();
+        for name in names {
+            let game_folder = &self.mapping.games[name].base;
+            let files = self.restorable_files(name, game_folder);
+            let integrity = self.verify_integrity(name, game_folder, &files);
+            if integrity.status() == IntegrityStatus::Broken {
+                let mut paths: Vec<_> = integrity
+                    .corrupted_files
+                    .iter()
+                    .map(|x| x.path.clone())
+                    .chain(integrity.missing_files.iter().cloned())
+                    .collect();
+                paths.sort_by_key(|x| x.render());
+                report.corrupt_games.push((name.to_string(), paths));
+            } else {
+                report.ok_games.push(name.to_string());
+            }
+        }
+
+        report.missing_mapping = self.unmapped_folders();
+        report
+    }
+
+    fn unmapped_folders(&self) -> Vec<String> {
+        let mapped: std::collections::HashSet<_> = self.mapping.games.values().map(|x| x.base.render()).collect();
+        let is_mapped = |dir: &std::path::PathBuf| mapped.contains(&StrictPath::from_std_path_buf(dir).render());
+        let folder_name = |dir: &std::path::PathBuf| dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let mut unmapped = vec![];
+        for dir in subdirectories(self.base.interpret()) {
+            if is_mapped(&dir) {
+                continue;
+            }
+            let name = folder_name(&dir);
+            // Each namespace is checked on its own, with its own mapping.
+            if self.mapping.namespaces.contains(&name) {
+                continue;
+            }
+            if STORE_FOLDERS.contains(&name.as_str()) {
+                for grouped_dir in subdirectories(&dir) {
+                    if !is_mapped(&grouped_dir) {
+                        unmapped.push(format!("{}/{}", name, folder_name(&grouped_dir)));
+                    }
+                }
+                continue;
+            }
+            unmapped.push(name);
+        }
+        unmapped.sort();
+        unmapped
+    }
+
+    /// Find games whose backup folder has not been modified in more than this many days,
+    /// such as games that were uninstalled long ago. The names are sorted.
+    pub fn games_older_than(&self, days: u64) -> Vec<String> {
+        let threshold = std::time::Duration::from_secs(days * 86400);
+        let mut games: Vec<_> = self
+            .mapping
+            .games
+            .iter()
+            .filter(|(_, game)| game_folder_age(game).map(|age| age > threshold).unwrap_or(false))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        games.sort();
+        games
+    }
+
+    /// Remove surplus backups according to the policy, oldest first.
+    /// The newest backup of each game is always kept, so no game is ever lost entirely.
+    /// When there is a minimum free space, `free_space` is checked before each removal
+    /// so that pruning stops as soon as there is enough room. If the free space
+    /// can't be determined, then pruning stops as well, rather than removing too much.
+    pub fn prune(&self, policy: &PrunePolicy, mut free_space: impl FnMut() -> Option<u64>) -> PruneReport {
+        let mut report = PruneReport::default();
+        if policy == &PrunePolicy::default() {
+            return report;
+        }
+
+        let threshold = policy
+            .older_than_days
+            .map(|days| std::time::Duration::from_secs(days * 86400));
+        let mut candidates = vec![];
+        for (name, mut folders) in self.backup_folders_by_game() {
+            if folders.len() < 2 {
+                continue;
+            }
+            // Newest first, so that the newest can be kept.
+            folders.sort_by_key(|(age, _)| *age);
+            for (age, folder) in folders.into_iter().skip(1) {
+                if threshold.map(|threshold| age > threshold).unwrap_or(true) {
+                    candidates.push((age, name.clone(), folder));
+                }
+            }
+        }
+        // Oldest first, with ties broken by name so that the order is stable.
+        candidates.sort_by(|(age1, name1, folder1), (age2, name2, folder2)| {
+            age2.cmp(age1)
+                .then_with(|| name1.cmp(name2))
+                .then_with(|| folder1.render().cmp(&folder2.render()))
+        });
+
+        for (_, name, folder) in candidates {
+            if let Some(min_free_bytes) = policy.min_free_bytes {
+                match free_space() {
+                    Some(free) if free < min_free_bytes => {}
+                    _ => break,
+                }
+            }
+            let size = folder_size(&folder).unwrap_or_default();
+            if folder.remove().is_ok() {
+                report.removed_backups.push((name, folder));
+                report.reclaimed_bytes += size;
+            }
+        }
+
+        report
+    }
+
+    /// Every folder with a valid `mapping.yaml`, along with how old it is, keyed by game name.
+    /// Unlike the overall mapping, this keeps all of a game's folders when there are several.
+    fn backup_folders_by_game(&self) -> std::collections::HashMap<String, Vec<(std::time::Duration, StrictPath)>> {
+        let mut folders = std::collections::HashMap::<_, Vec<_>>::new();
+        let mut add = |dir: &std::path::PathBuf| {
+            let folder = StrictPath::from_std_path_buf(dir);
+            let mapping = match IndividualMapping::load(&folder.joined("mapping.yaml")) {
+                Ok(x) => x,
+                Err(_) => return false,
+            };
+            if let Some(age) = folder_age(&folder) {
+                folders.entry(mapping.name).or_default().push((age, folder));
+            }
+            true
+        };
+
+        for dir in subdirectories(self.base.interpret()) {
+            if add(&dir) {
+                continue;
+            }
+            let folder_name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if STORE_FOLDERS.contains(&folder_name.as_str()) {
+                for grouped_dir in subdirectories(&dir) {
+                    add(&grouped_dir);
+                }
+            }
+        }
+
+        folders
+    }
+
+    /// Package the whole backup into one ZIP archive at `dest`, such as for sharing it.
+    /// The archive is written next to `dest` first and only renamed once complete,
+    /// so a failure never leaves a partial archive behind.
+    /// Returns the size of the archive in bytes.
+    pub fn zip_export(&self, dest: &StrictPath) -> Result<u64, Error> {
+        let failed = || Error::UnableToExportArchive { path: dest.clone() };
+        if !self.base.is_dir() {
+            return Err(failed());
+        }
+        dest.create_parent_dir().map_err(|_| failed())?;
+        let temporary = temporary_sibling(dest).ok_or_else(failed)?;
+
+        let written = std::fs::File::create(temporary.interpret())
+            .and_then(|file| self.write_zip(std::io::BufWriter::new(file)))
+            .and_then(|mut writer| std::io::Write::flush(&mut writer))
+            .and_then(|_| std::fs::rename(temporary.interpret(), dest.interpret()))
+            .and_then(|_| std::fs::metadata(dest.interpret()));
+        match written {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(_) => {
+                let _ = temporary.remove();
+                Err(failed())
+            }
+        }
+    }
+
+    /// Write the whole backup as a ZIP archive into `writer` and hand it back once finished.
+    /// This is the same archive that `zip_export` creates. Entries use forward slashes
+    /// and are relative to the backup folder, so the archive is portable between systems.
+    pub fn write_zip<W: std::io::Write + std::io::Seek>(&self, writer: W) -> std::io::Result<W> {
+        let mut archive = zip::ZipWriter::new(writer);
+        let base = self.base.interpret();
+        for entry in walkdir::WalkDir::new(&base)
+            .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        {
+            let entry = entry?;
+            let name = match entry.path().strip_prefix(&base) {
+                Ok(relative) => reslashed(&relative.to_string_lossy()),
+                Err(_) => continue,
+            };
+            if entry.file_type().is_dir() {
+                archive.add_directory(name, zip::write::FileOptions::default())?;
+            } else if entry.file_type().is_file() {
+                let size = entry.metadata()?.len();
+                let options = zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(size > u32::MAX as u64);
+                archive.start_file(name, options)?;
+                std::io::copy(&mut std::fs::File::open(entry.path())?, &mut archive)?;
+            }
+        }
+        Ok(archive.finish()?)
+    }
+
+    /// Extract an archive from `zip_export` into `target_base` and load it as a backup.
+    /// The target must not exist yet or be empty. Like exporting, the archive is extracted
+    /// next to the target first, so a failure leaves the target as it was.
+    pub fn zip_import(archive: &StrictPath, target_base: &StrictPath) -> Result<BackupLayout, Error> {
+        let failed = || Error::UnableToImportArchive { path: archive.clone() };
+        let has_entries = std::fs::read_dir(target_base.interpret())
+            .map(|mut x| x.next().is_some())
+            .unwrap_or(false);
+        if target_base.is_file() || has_entries {
+            return Err(failed());
+        }
+        target_base.create_parent_dir().map_err(|_| failed())?;
+        let temporary = temporary_sibling(target_base).ok_or_else(failed)?;
+
+        let extracted = std::fs::File::open(archive.interpret())
+            .and_then(|file| {
+                let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file))?;
+                Ok(archive.extract(temporary.interpret())?)
+            })
+            .and_then(|_| {
+                if target_base.is_dir() {
+                    std::fs::remove_dir(target_base.interpret())?;
+                }
+                std::fs::rename(temporary.interpret(), target_base.interpret())
+            });
+        match extracted {
+            Ok(_) => Ok(Self::new(target_base.clone())),
+            Err(_) => {
+                let _ = temporary.remove();
+                Err(failed())
+            }
+        }
+    }
+
+    /// Remove the backups of games that no longer exist, according to `is_known_game`,
+    /// plus any empty drive folders in the other backups. Only folders with a readable
+    /// `mapping.yaml` are touched, so namespace folders, unrelated folders, and backups
+    /// from a newer version of Ludusavi are left alone. In preview mode, nothing is removed,
+    /// but the report lists what would be. The lists are sorted.
+    pub fn compact(&self, is_known_game: impl Fn(&str) -> bool, preview: bool) -> CompactionReport {
+        let mut report = CompactionReport::default();
+
+        for dir in subdirectories(self.base.interpret()) {
+            if Self::compact_game_folder(&dir, &is_known_game, preview, &mut report) {
+                continue;
+            }
+            let folder_name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if STORE_FOLDERS.contains(&folder_name.as_str()) {
+                for grouped_dir in subdirectories(&dir) {
+                    Self::compact_game_folder(&grouped_dir, &is_known_game, preview, &mut report);
+                }
+            }
+        }
+
+        report.removed_games.sort_by_key(|(_, folder)| folder.render());
+        report.removed_folders.sort_by_key(|x| x.render());
+        report
+    }
+
+    /// Returns whether the folder contained a game's backup.
+    fn compact_game_folder(
+        dir: &std::path::Path,
+        is_known_game: impl Fn(&str) -> bool,
+        preview: bool,
+        report: &mut CompactionReport,
+    ) -> bool {
+        let game_folder = StrictPath::from_std_path_buf(&dir.to_path_buf());
+        let mapping = match IndividualMapping::load(&game_folder.joined("mapping.yaml")) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+
+        let remove = |folder: StrictPath, report: &mut CompactionReport| {
+            let size = folder_size(&folder).unwrap_or_default();
+            if preview || folder.remove().is_ok() {
+                report.reclaimed_bytes += size;
+                Some(folder)
+            } else {
+                None
+            }
+        };
+
+        if !is_known_game(&mapping.name) {
+            if let Some(folder) = remove(game_folder, report) {
+                report.removed_games.push((mapping.name, folder));
+            }
+            return true;
+        }
+
+        for drive_dir in subdirectories(dir) {
+            let folder_name = drive_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let drive_dir = StrictPath::from_std_path_buf(&drive_dir);
+            if mapping.drives.contains_key(&folder_name) && folder_size(&drive_dir).is_none() {
+                if let Some(folder) = remove(drive_dir, report) {
+                    report.removed_folders.push(folder);
+                }
+            }
+        }
+        true
+    }
+
+    /// Compare the restorable files against the metadata recorded at backup time.
+    pub fn verify_integrity(
+        &self,
+        game_name: &str,
+        game_folder: &StrictPath,
+        found_files: &std::collections::HashSet<ScannedFile>,
+    ) -> IntegrityInfo {
+        let mut integrity = IntegrityInfo::default();
+        let game = match self.mapping.games.get::<str>(game_name) {
+            Some(x) if !x.files.is_empty() => x,
+            _ => return integrity,
+        };
+        integrity.known = true;
+
+        let mut seen = std::collections::HashSet::new();
+        for file in found_files {
+            let stored_key = match self.backed_up_file_key(game_folder, &file.path) {
+                Some(x) => x,
+                None => continue,
+            };
+            let (key, expected_size, actual_size) = match game.uncompressed_key(&stored_key) {
+                Some(key) => (
+                    key.to_string(),
+                    game.files[key].compressed_size,
+                    std::fs::metadata(file.path.interpret())
+                        .map(|m| m.len())
+                        .unwrap_or_default(),
+                ),
+                None => (
+                    stored_key.clone(),
+                    game.files
+                        .get(&stored_key)
+                        .or_else(|| game.screenshots.get(&stored_key))
+                        .map(|x| x.size),
+                    file.size,
+                ),
+            };
+            if let Some(expected_size) = expected_size {
+                if expected_size != actual_size {
+                    integrity.corrupted_files.insert(file.clone());
+                }
+                seen.insert(key);
+            }
+        }
+
+        for key in game.files.keys() {
+            if seen.contains(key) {
+                continue;
+            }
+            let parts: Vec<_> = key.splitn(2, '/').collect();
+            if parts.len() != 2 {
+                continue;
+            }
+            if let Some(drive) = game.folder_target(parts[0], &self.roots) {
+                integrity
+                    .missing_files
+                    .insert(StrictPath::new(format!("{}/{}", drive, parts[1])));
+            }
+        }
+
+        integrity
+    }
+}
+
+/// How long ago the game's backup folder was last modified.
+fn game_folder_age(game: &OverallMappingGame) -> Option<std::time::Duration> {
+    folder_age(&game.base)
+}
+
+fn folder_age(folder: &StrictPath) -> Option<std::time::Duration> {
+    std::fs::metadata(folder.interpret())
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+}
+
+/// This is the total size of the files in the folder, or `None` if it has no files.
+fn folder_size(dir: &StrictPath) -> Option<u64> {
+    let sizes: Vec<_> = dir
+        .iter_files()
+        .map(|x| std::fs::metadata(x.raw()).map(|m| m.len()).unwrap_or_default())
+        .collect();
+    if sizes.is_empty() {
+        None
+    } else {
+        Some(sizes.iter().sum())
+    }
+}
+
+/// If the file is in a portable root, then this is the root and the file's path relative to it.
+fn portable_relative_path<'a>(
+    original_file: &StrictPath,
+    root: &'a Option<RootsConfig>,
+) -> Option<(&'a RootsConfig, String)> {
+    let root = root.as_ref().filter(|x| x.portable)?;
+    let root_path = root.path.render();
+    let file_path = original_file.render();
+    if file_path == root_path || !is_within(&file_path, &root_path) {
+        return None;
+    }
+    Some((root, file_path[root_path.trim_end_matches('/').len() + 1..].to_string()))
 }
 
 #[cfg(test)]
@@ -248,8 +1251,13 @@ mod tests {
         env!("CARGO_MANIFEST_DIR").to_string()
     }
 
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
     mod individual_mapping {
         use super::*;
+        use maplit::hashmap;
         use pretty_assertions::assert_eq;
 
         #[test]
@@ -261,16 +1269,69 @@ mod tests {
             assert_eq!("drive-____C", mapping.drive_folder_name(r#"\\?\C:"#));
             assert_eq!("drive-__remote", mapping.drive_folder_name(r#"\\remote"#));
         }
+
+        #[test]
+        fn can_generate_drive_folder_name_repeatedly() {
+            let mut mapping = IndividualMapping::new("foo".to_owned());
+            assert_eq!("drive-C", mapping.drive_folder_name("C:"));
+            assert_eq!("drive-C", mapping.drive_folder_name("C:"));
+            assert_eq!("drive-0", mapping.drive_folder_name(""));
+            assert_eq!("drive-0", mapping.drive_folder_name(""));
+            assert_eq!(
+                hashmap! {
+                    "drive-C".to_string() => "C:".to_string(),
+                    "drive-0".to_string() => "".to_string(),
+                },
+                mapping.drives
+            );
+        }
+
+        #[test]
+        fn can_generate_drive_folder_name_with_existing_mapping() {
+            let mut mapping =
+                IndividualMapping::load_from_string("name: foo\ndrives:\n  drive-C: \"D:\"\n  drive-1: \"E:\"\n")
+                    .unwrap();
+            assert_eq!("drive-C", mapping.drive_folder_name("D:"));
+            assert_eq!("drive-1", mapping.drive_folder_name("E:"));
+            assert_eq!("drive-2", mapping.drive_folder_name("C:"));
+            assert_eq!("drive-3", mapping.drive_folder_name("C"));
+            assert_eq!("drive-2", mapping.drive_folder_name("C:"));
+            assert_eq!(
+                hashmap! {
+                    "drive-C".to_string() => "D:".to_string(),
+                    "drive-1".to_string() => "E:".to_string(),
+                    "drive-2".to_string() => "C:".to_string(),
+                    "drive-3".to_string() => "C".to_string(),
+                },
+                mapping.drives
+            );
+        }
     }
 
     mod backup_layout {
         use super::*;
+        use crate::testing::TempDir;
         use pretty_assertions::assert_eq;
 
         fn layout() -> BackupLayout {
             BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
         }
 
+        #[test]
+        fn can_determine_namespaced_path() {
+            let base = StrictPath::new(format!("{}/tests/backup", repo()));
+            assert_eq!(base.render(), namespaced_path(&base, &None).render());
+            assert_eq!(base.render(), namespaced_path(&base, &Some(" ".to_string())).render());
+            assert_eq!(
+                format!("{}/desktop", base.render()),
+                namespaced_path(&base, &Some("desktop".to_string())).render(),
+            );
+            assert_eq!(
+                format!("{}/{}_laptop", base.render(), whoami::username()),
+                namespaced_path(&base, &Some("<username>/laptop".to_string())).render(),
+            );
+        }
+
         #[test]
         fn can_find_existing_game_folder_with_matching_name() {
             assert_eq!(
@@ -342,5 +1403,436 @@ mod tests {
                 layout().game_folder("...")
             );
         }
+
+        #[test]
+        fn can_generate_report() {
+            let layout = layout();
+            let report = layout.generate_report();
+
+            assert_eq!(
+                vec![
+                    ("game1", layout.game_folder("game1"), 3, 2),
+                    ("game3", layout.game_folder("game3"), 0, 0),
+                    ("game4", layout.game_folder("game4"), 3, 2),
+                ],
+                report
+                    .games
+                    .iter()
+                    .map(|x| (x.name.as_str(), x.backup_folder.clone(), x.size_bytes, x.file_count))
+                    .collect::<Vec<_>>(),
+            );
+            assert!(report.games.iter().all(|x| x.last_modified.is_some()));
+        }
+
+        #[test]
+        fn can_check_integrity_of_whole_backup() {
+            let layout = layout();
+            assert_eq!(
+                IntegrityReport {
+                    ok_games: vec![s("game1"), s("game3")],
+                    corrupt_games: vec![(
+                        s("game4"),
+                        vec![
+                            StrictPath::new(s("X:/missing.txt")),
+                            StrictPath::new(format!("{}/tests/backup/game4/drive-X/resized.txt", repo())),
+                        ],
+                    )],
+                    missing_mapping: vec![s("ignored-invalid-mapping"), s("ignored-no-mapping")],
+                },
+                layout.integrity_check(),
+            );
+        }
+
+        #[test]
+        fn can_check_integrity_without_flagging_namespaces() {
+            let temp = TempDir::new("layout-integrity-namespaces");
+            temp.write("game1/mapping.yaml", "name: game1\ndrives: {}\n");
+            temp.write("desktop/game2/mapping.yaml", "name: game2\ndrives: {}\n");
+            temp.write("desktop/steam/game3/mapping.yaml", "name: game3\ndrives: {}\n");
+            temp.write("unmapped/drive-0/save.dat", "1");
+
+            let report = BackupLayout::new(temp.path().clone()).integrity_check();
+
+            assert_eq!(vec![s("game1")], report.ok_games);
+            assert_eq!(vec![s("unmapped")], report.missing_mapping);
+        }
+
+        #[test]
+        fn can_get_recorded_sizes() {
+            assert_eq!(
+                vec![("game4".to_string(), 3)],
+                layout().recorded_sizes().into_iter().collect::<Vec<_>>(),
+            );
+        }
+
+        #[test]
+        fn can_resolve_anchor_to_current_root() {
+            let root = |path: &str, store, portable| RootsConfig {
+                path: StrictPath::new(path.to_string()),
+                store,
+                portable,
+                scan_proton_prefixes: None,
+            };
+            let anchor = root("/old/Games", Store::Other, true);
+
+            assert_eq!(
+                "/old/Games".to_string(),
+                resolve_anchor(&anchor, &[root("/old/Games", Store::Other, false)])
+            );
+            assert_eq!(
+                "/new/Games".to_string(),
+                resolve_anchor(
+                    &anchor,
+                    &[
+                        root("/new/Games", Store::Steam, true),
+                        root("/new/Games", Store::Other, true),
+                        root("/new/Other", Store::Other, false),
+                    ]
+                )
+            );
+            assert_eq!(
+                "/old/Games".to_string(),
+                resolve_anchor(
+                    &anchor,
+                    &[
+                        root("/new/Games", Store::Other, true),
+                        root("/newer/Games", Store::Other, true),
+                    ]
+                )
+            );
+            assert_eq!("/old/Games".to_string(), resolve_anchor(&anchor, &[]));
+        }
+
+        #[test]
+        fn can_find_existing_game_file_key() {
+            assert_eq!(
+                if cfg!(target_os = "windows") {
+                    Some("drive-X/file1.txt".to_string())
+                } else {
+                    None
+                },
+                layout().existing_game_file_key("game1", &StrictPath::new("X:/file1.txt".to_string()), &None)
+            );
+            assert_eq!(
+                None,
+                layout().existing_game_file_key("nonexistent", &StrictPath::new("X:/file1.txt".to_string()), &None)
+            );
+        }
+
+        #[test]
+        fn can_find_backed_up_file_key() {
+            let layout = layout();
+            let game_folder = layout.game_folder("game1");
+            assert_eq!(
+                Some("drive-X/file1.txt".to_string()),
+                layout.backed_up_file_key(&game_folder, &game_folder.joined("drive-X/file1.txt"))
+            );
+            assert_eq!(
+                None,
+                layout.backed_up_file_key(&game_folder, &StrictPath::new("/elsewhere/file1.txt".to_string()))
+            );
+        }
+
+        #[test]
+        fn can_determine_game_folder_when_a_file_is_in_the_way() {
+            let temp = TempDir::new("blocked-game-folder");
+            let base = temp.path().clone();
+            std::fs::create_dir_all(base.joined("foo.1").interpret()).unwrap();
+            std::fs::write(base.joined("foo").interpret(), "leftover").unwrap();
+
+            let folder = BackupLayout::new(base.clone()).game_folder("foo");
+
+            assert_eq!(base.joined("foo.2").render(), folder.render());
+        }
+
+        #[test]
+        #[cfg(not(target_os = "windows"))]
+        fn can_find_games_older_than_some_days() {
+            let temp = TempDir::new("older-than");
+            let base = temp.path().clone();
+            for game in &["old", "new"] {
+                temp.write(
+                    &format!("{}/mapping.yaml", game),
+                    &format!("name: {}\ndrives: {{}}\n", game),
+                );
+            }
+            std::fs::File::open(base.joined("old").interpret())
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 86400))
+                .unwrap();
+
+            let layout = BackupLayout::new(base.clone());
+            let older_than_week = layout.games_older_than(7);
+            let older_than_month = layout.games_older_than(30);
+
+            assert_eq!(vec!["old".to_string()], older_than_week);
+            assert_eq!(Vec::<String>::new(), older_than_month);
+        }
+
+        #[test]
+        #[cfg(not(target_os = "windows"))]
+        fn can_prune_oldest_surplus_backups_until_enough_space_is_free() {
+            let temp = TempDir::new("prune");
+            let base = temp.path().clone();
+            let folders = [
+                ("a", "a", 0),
+                ("a", "a.1", 30),
+                ("a", "a.2", 90),
+                ("b", "b", 365),
+                ("c", "c", 10),
+                ("c", "steam/c", 60),
+            ];
+            for (game, folder, days) in &folders {
+                temp.write(
+                    &format!("{}/mapping.yaml", folder),
+                    &format!("name: {}\ndrives: {{}}\n", game),
+                );
+                std::fs::File::open(base.joined(folder).interpret())
+                    .unwrap()
+                    .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(days * 86400))
+                    .unwrap();
+            }
+
+            // Each removal frees up 100 bytes, so two of them reach the minimum.
+            // The only backup of "b" is the oldest, but it is never a candidate.
+            let layout = BackupLayout::new(base.clone());
+            let mut free = 50;
+            let report = layout.prune(
+                &PrunePolicy {
+                    older_than_days: None,
+                    min_free_bytes: Some(200),
+                },
+                || {
+                    free += 100;
+                    Some(free - 100)
+                },
+            );
+            let remaining: Vec<_> = folders
+                .iter()
+                .map(|(_, folder, _)| folder)
+                .filter(|x| base.joined(x).is_dir())
+                .collect();
+
+            assert_eq!(
+                vec![
+                    ("a".to_string(), base.joined("a.2")),
+                    ("c".to_string(), base.joined("steam/c")),
+                ],
+                report.removed_backups
+            );
+            assert_eq!(vec![&"a", &"a.1", &"b", &"c"], remaining);
+        }
+
+        #[test]
+        #[cfg(not(target_os = "windows"))]
+        fn can_prune_surplus_backups_older_than_threshold() {
+            let temp = TempDir::new("prune-older-than");
+            let base = temp.path().clone();
+            for (folder, days) in &[("a", 100), ("a.1", 200), ("a.2", 20)] {
+                temp.write(&format!("{}/mapping.yaml", folder), "name: a\ndrives: {}\n");
+                std::fs::File::open(base.joined(folder).interpret())
+                    .unwrap()
+                    .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(days * 86400))
+                    .unwrap();
+            }
+
+            let report = BackupLayout::new(base.clone()).prune(
+                &PrunePolicy {
+                    older_than_days: Some(50),
+                    min_free_bytes: None,
+                },
+                || None,
+            );
+
+            assert_eq!(
+                vec![
+                    ("a".to_string(), base.joined("a.1")),
+                    ("a".to_string(), base.joined("a"))
+                ],
+                report.removed_backups
+            );
+            assert!(base.joined("a.2").is_dir());
+        }
+
+        #[test]
+        fn can_compact_backup_directory() {
+            let temp = TempDir::new("compact");
+            let base = temp.path().clone();
+            temp.write(
+                "game1/mapping.yaml",
+                "name: game1\ndrives:\n  drive-0: \"\"\n  drive-X: \"X:\"\n",
+            );
+            temp.write("game1/drive-0/save.dat", "abc");
+            std::fs::create_dir_all(base.joined("game1/drive-X/empty").interpret()).unwrap();
+            temp.write("steam/gone/mapping.yaml", "name: gone\ndrives:\n  drive-0: \"\"\n");
+            temp.write("steam/gone/drive-0/save.dat", "12345");
+            temp.write("unreadable/mapping.yaml", "not a mapping");
+            temp.write("unreadable/drive-0/save.dat", "1");
+            temp.write("namespace/game2/mapping.yaml", "name: gone\ndrives: {}\n");
+            temp.write("unmapped/drive-0/save.dat", "1");
+            let is_known_game = |name: &str| name == "game1";
+            let relative = |path: &StrictPath| path.render()[base.render().len()..].to_string();
+
+            let preview = BackupLayout::new(base.clone()).compact(is_known_game, true);
+            let previewed_intact = base.joined("steam/gone").is_dir() && base.joined("game1/drive-X").is_dir();
+            let report = BackupLayout::new(base.clone()).compact(is_known_game, false);
+            let remaining: Vec<_> = [
+                "game1/mapping.yaml",
+                "game1/drive-0/save.dat",
+                "unreadable/drive-0/save.dat",
+                "namespace/game2/mapping.yaml",
+                "unmapped/drive-0/save.dat",
+            ]
+            .iter()
+            .map(|x| base.joined(x).is_file())
+            .collect();
+
+            assert_eq!(preview, report);
+            assert!(previewed_intact);
+            assert_eq!(vec![true, true, true, true, true], remaining);
+            assert_eq!(
+                vec![("gone".to_string(), "/steam/gone".to_string())],
+                report
+                    .removed_games
+                    .iter()
+                    .map(|(name, folder)| (name.clone(), relative(folder)))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                vec!["/game1/drive-X".to_string()],
+                report.removed_folders.iter().map(relative).collect::<Vec<_>>()
+            );
+            assert_eq!(38, report.reclaimed_bytes);
+        }
+
+        #[test]
+        fn can_export_and_import_archive() {
+            let temp = TempDir::new("archive");
+            let base = temp.path().clone();
+            temp.write("backup/game1/mapping.yaml", "name: game1\ndrives:\n  drive-0: \"\"\n");
+            temp.write("backup/game1/drive-0/save.dat", "abc");
+            temp.write("backup/game1/drive-0/nested/other.dat", "");
+            std::fs::create_dir_all(base.joined("backup/game1/drive-0/empty").interpret()).unwrap();
+
+            let archive = base.joined("export/backup.zip");
+            let written = BackupLayout::new(base.joined("backup")).zip_export(&archive);
+            let archive_size = std::fs::metadata(archive.interpret()).map(|x| x.len()).ok();
+            let imported = BackupLayout::zip_import(&archive, &base.joined("imported"));
+            let read = |path: &str| std::fs::read_to_string(base.joined(path).interpret()).ok();
+            let contents = vec![
+                read("imported/game1/mapping.yaml"),
+                read("imported/game1/drive-0/save.dat"),
+                read("imported/game1/drive-0/nested/other.dat"),
+            ];
+            let empty_dir = base.joined("imported/game1/drive-0/empty").is_dir();
+
+            assert_eq!(archive_size, written.ok());
+            assert_eq!(
+                vec!["game1".to_string()],
+                imported.unwrap().mapping.games.keys().cloned().collect::<Vec<_>>(),
+            );
+            assert_eq!(
+                vec![
+                    Some("name: game1\ndrives:\n  drive-0: \"\"\n".to_string()),
+                    Some("abc".to_string()),
+                    Some("".to_string()),
+                ],
+                contents,
+            );
+            assert!(empty_dir);
+        }
+
+        #[test]
+        fn can_write_zip_to_stream() {
+            let temp = TempDir::new("archive-stream");
+            let base = temp.path().clone();
+            temp.write("backup/game1/mapping.yaml", "name: game1\ndrives: {}\n");
+            temp.write("backup/game1/drive-0/save.dat", "abc");
+
+            let stream = BackupLayout::new(base.joined("backup"))
+                .write_zip(std::io::Cursor::new(vec![]))
+                .unwrap();
+            let archive = zip::ZipArchive::new(stream).unwrap();
+            let mut entries: Vec<_> = archive.file_names().collect();
+            entries.sort();
+
+            assert_eq!(
+                vec![
+                    "game1/",
+                    "game1/drive-0/",
+                    "game1/drive-0/save.dat",
+                    "game1/mapping.yaml"
+                ],
+                entries,
+            );
+        }
+
+        #[test]
+        fn can_fail_to_export_and_import_archive_cleanly() {
+            let temp = TempDir::new("archive-failure");
+            let base = temp.path().clone();
+            temp.write("backup/game1/mapping.yaml", "name: game1\ndrives: {}\n");
+            temp.write("not-an-archive.zip", "not an archive");
+            temp.write("occupied/file.txt", "keep");
+            let listing = || {
+                let mut names: Vec<_> = std::fs::read_dir(base.interpret())
+                    .unwrap()
+                    .map(|x| x.unwrap().file_name().to_string_lossy().to_string())
+                    .collect();
+                names.sort();
+                names
+            };
+
+            let missing_export = BackupLayout::new(base.joined("missing")).zip_export(&base.joined("missing.zip"));
+            let bad_import = BackupLayout::zip_import(&base.joined("not-an-archive.zip"), &base.joined("bad"));
+            let occupied_import =
+                BackupLayout::zip_import(&base.joined("not-an-archive.zip"), &base.joined("occupied"));
+
+            assert!(missing_export.is_err());
+            assert!(bad_import.is_err());
+            assert!(occupied_import.is_err());
+            assert_eq!(vec!["backup", "not-an-archive.zip", "occupied"], listing());
+            assert_eq!(
+                Some("keep".to_string()),
+                std::fs::read_to_string(base.joined("occupied/file.txt").interpret()).ok()
+            );
+        }
+    }
+
+    mod backup_report {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn can_redact_home_in_backup_report() {
+            let home = StrictPath::from_std_path_buf(&dirs::home_dir().unwrap()).render();
+            let mut report = BackupReport {
+                generated_at: std::time::UNIX_EPOCH,
+                games: vec![
+                    GameReport {
+                        name: "foo".to_string(),
+                        backup_folder: StrictPath::new(format!("{}/ludusavi-backup/foo", home)),
+                        size_bytes: 0,
+                        file_count: 0,
+                        last_modified: None,
+                    },
+                    GameReport {
+                        name: "bar".to_string(),
+                        backup_folder: StrictPath::new("/backup/bar".to_string()),
+                        size_bytes: 0,
+                        file_count: 0,
+                        last_modified: None,
+                    },
+                ],
+            };
+            report.redact_home();
+            assert_eq!(
+                vec![
+                    "<home>/ludusavi-backup/foo".to_string(),
+                    StrictPath::new("/backup/bar".to_string()).render()
+                ],
+                report.games.iter().map(|x| x.backup_folder.raw()).collect::<Vec<_>>(),
+            );
+        }
     }
 }
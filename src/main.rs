@@ -1,16 +1,25 @@
 mod cli;
 mod config;
+mod diagnostics;
 mod gui;
 mod lang;
 mod layout;
 mod manifest;
+mod metrics;
 mod path;
 mod prelude;
 mod serialization;
 mod shortcuts;
+mod table;
+#[cfg(test)]
+mod testing;
 
+#[cfg(feature = "android")]
+mod android;
 #[cfg(target_os = "windows")]
 mod registry;
+#[cfg(target_os = "windows")]
+mod vss;
 
 fn main() {
     let args = cli::parse_cli();
@@ -29,7 +38,10 @@ fn main() {
                     std::process::exit(1);
                 }
             }
-            gui::run_gui();
+            gui::run_gui(gui::Flags {
+                game: args.game,
+                screen: args.screen,
+            });
         }
         Some(sub) => {
             if let Err(e) = cli::run_cli(sub) {
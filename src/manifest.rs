@@ -21,10 +21,12 @@ impl Default for Os {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, serde::Serialize, serde::Deserialize)]
 pub enum Store {
     #[serde(rename = "steam")]
     Steam,
+    #[serde(rename = "rockstar")]
+    Rockstar,
     #[serde(other, rename = "other")]
     Other,
 }
@@ -61,6 +63,27 @@ pub struct Game {
     pub install_dir: Option<std::collections::HashMap<String, GameInstallDirEntry>>,
     pub registry: Option<std::collections::HashMap<String, GameRegistryEntry>>,
     pub steam: Option<SteamMetadata>,
+    /// This is the game's name in the Rockstar Games Launcher, if it differs from the manifest name.
+    #[serde(rename = "rockstarTitle")]
+    pub rockstar_title: Option<String>,
+    pub gog: Option<GogMetadata>,
+    /// Keys are executable paths, which may use the same placeholders as file paths.
+    /// This is only passed through for consumers; scans don't use it.
+    pub launch: Option<std::collections::HashMap<String, Vec<LaunchEntry>>>,
+    /// If set, only roots of these stores are scanned for the game.
+    #[serde(rename = "compatibleStores")]
+    pub compatible_stores: Option<Vec<Store>>,
+}
+
+impl Game {
+    /// Roots of the `other` store aren't tied to a particular store,
+    /// so they're always compatible.
+    pub fn is_compatible_with_store(&self, store: Store) -> bool {
+        match &self.compatible_stores {
+            Some(stores) => store == Store::Other || stores.contains(&store),
+            None => true,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -94,6 +117,26 @@ pub struct SteamMetadata {
     pub id: Option<u32>,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GogMetadata {
+    pub id: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LaunchEntry {
+    pub arguments: Option<String>,
+    #[serde(rename = "workingDir")]
+    pub working_dir: Option<String>,
+    pub when: Option<Vec<LaunchConstraint>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LaunchConstraint {
+    pub os: Option<Os>,
+    pub bit: Option<u32>,
+    pub store: Option<Store>,
+}
+
 impl From<CustomGame> for Game {
     fn from(item: CustomGame) -> Self {
         let file_tuples = item.files.iter().map(|x| (x.to_string(), GameFileEntry::default()));
@@ -110,12 +153,16 @@ impl From<CustomGame> for Game {
             install_dir: None,
             registry: Some(registry),
             steam: None,
+            rockstar_title: None,
+            gog: None,
+            launch: None,
+            compatible_stores: None,
         }
     }
 }
 
 impl Manifest {
-    fn file() -> std::path::PathBuf {
+    pub fn file() -> std::path::PathBuf {
         let mut path = app_dir();
         path.push("manifest.yaml");
         path
@@ -125,7 +172,12 @@ impl Manifest {
         if update || !StrictPath::from_std_path_buf(&Self::file()).exists() {
             Self::update(config)?;
         }
-        let content = std::fs::read_to_string(Self::file()).unwrap();
+        Self::load_from_file(&StrictPath::from_std_path_buf(&Self::file()))
+    }
+
+    pub fn load_from_file(path: &StrictPath) -> Result<Self, Error> {
+        let content =
+            std::fs::read_to_string(path.interpret()).map_err(|e| Error::ManifestInvalid { why: format!("{}", e) })?;
         Self::load_from_string(&content)
     }
 
@@ -162,6 +214,29 @@ impl Manifest {
         }
     }
 
+    /// Every game from the manifest, plus the config's custom games,
+    /// which take precedence over manifest entries with the same name.
+    pub fn with_custom_games(&self, config: &Config) -> std::collections::HashMap<String, Game> {
+        let mut games = self.0.clone();
+        for custom_game in &config.custom_games {
+            games.insert(custom_game.name.clone(), Game::from(custom_game.to_owned()));
+        }
+        games
+    }
+
+    pub fn contains_game(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn map_steam_ids_to_names(&self) -> std::collections::HashMap<u32, String> {
         self.0
             .iter()
@@ -186,6 +261,64 @@ mod tests {
         text.to_string()
     }
 
+    fn repo() -> String {
+        env!("CARGO_MANIFEST_DIR").to_string()
+    }
+
+    #[test]
+    fn can_check_games_in_manifest() {
+        let manifest = Manifest::load_from_string("game: {}").unwrap();
+
+        assert!(manifest.contains_game("game"));
+        assert!(!manifest.contains_game("other"));
+        assert_eq!(1, manifest.len());
+        assert!(!manifest.is_empty());
+        assert!(Manifest::default().is_empty());
+    }
+
+    #[test]
+    fn can_add_custom_games_to_manifest_games() {
+        let manifest = Manifest::load_from_string("game: {}\nother: {steam: {id: 1}}").unwrap();
+        let mut config = Config::default();
+        config.custom_games.push(CustomGame {
+            name: "other".to_string(),
+            files: vec![],
+            registry: vec![],
+        });
+
+        let games = manifest.with_custom_games(&config);
+
+        assert_eq!(2, games.len());
+        assert_eq!(None, games["other"].steam);
+        assert!(manifest.0["other"].steam.is_some());
+    }
+
+    #[test]
+    fn can_load_from_file() {
+        let manifest = Manifest::load_from_file(&StrictPath::new(format!("{}/tests/manifest.yaml", repo()))).unwrap();
+
+        assert_eq!(
+            Game {
+                files: None,
+                install_dir: None,
+                registry: None,
+                steam: Some(SteamMetadata { id: Some(123) }),
+                rockstar_title: None,
+                gog: None,
+                launch: None,
+                compatible_stores: None,
+            },
+            manifest.0["game"],
+        );
+    }
+
+    #[test]
+    fn cannot_load_from_nonexistent_file() {
+        let result = Manifest::load_from_file(&StrictPath::new(format!("{}/tests/fake.yaml", repo())));
+
+        assert!(matches!(result, Err(Error::ManifestInvalid { .. })));
+    }
+
     #[test]
     fn can_parse_game_with_no_fields() {
         let manifest = Manifest::load_from_string(
@@ -201,6 +334,10 @@ mod tests {
                 install_dir: None,
                 registry: None,
                 steam: None,
+                rockstar_title: None,
+                gog: None,
+                launch: None,
+                compatible_stores: None,
             },
             manifest.0["game"],
         );
@@ -228,6 +365,19 @@ mod tests {
                     - config
               steam:
                 id: 123
+              rockstarTitle: Example Game
+              gog:
+                id: 456
+              launch:
+                <base>/game.exe:
+                  - arguments: --windowed
+                    workingDir: <base>
+                    when:
+                      - os: windows
+                        bit: 64
+                        store: steam
+              compatibleStores:
+                - steam
             "#,
         )
         .unwrap();
@@ -259,11 +409,43 @@ mod tests {
                     },
                 }),
                 steam: Some(SteamMetadata { id: Some(123) }),
+                rockstar_title: Some(s("Example Game")),
+                gog: Some(GogMetadata { id: Some(456) }),
+                launch: Some(hashmap! {
+                    s("<base>/game.exe") => vec![
+                        LaunchEntry {
+                            arguments: Some(s("--windowed")),
+                            working_dir: Some(s("<base>")),
+                            when: Some(vec![
+                                LaunchConstraint {
+                                    os: Some(Os::Windows),
+                                    bit: Some(64),
+                                    store: Some(Store::Steam),
+                                }
+                            ]),
+                        }
+                    ],
+                }),
+                compatible_stores: Some(vec![Store::Steam]),
             },
             manifest.0["game"],
         );
     }
 
+    #[test]
+    fn can_check_compatible_stores() {
+        let unrestricted = Game::default();
+        let restricted = Game {
+            compatible_stores: Some(vec![Store::Steam]),
+            ..Default::default()
+        };
+
+        assert!(unrestricted.is_compatible_with_store(Store::Rockstar));
+        assert!(restricted.is_compatible_with_store(Store::Steam));
+        assert!(restricted.is_compatible_with_store(Store::Other));
+        assert!(!restricted.is_compatible_with_store(Store::Rockstar));
+    }
+
     #[test]
     fn can_parse_game_with_minimal_files() {
         let manifest = Manifest::load_from_string(
@@ -432,4 +614,57 @@ mod tests {
 
         assert_eq!(&SteamMetadata { id: None }, manifest.0["game"].steam.as_ref().unwrap());
     }
+
+    #[test]
+    fn can_parse_game_with_minimal_gog() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game:
+              gog: {}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(&GogMetadata { id: None }, manifest.0["game"].gog.as_ref().unwrap());
+    }
+
+    #[test]
+    fn can_parse_game_with_minimal_launch() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game:
+              launch:
+                foo:
+                  - {}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            &hashmap! { s("foo") => vec![LaunchEntry::default()] },
+            manifest.0["game"].launch.as_ref().unwrap()
+        );
+    }
+
+    #[test]
+    fn can_round_trip_game_with_launch_and_store_ids() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game:
+              steam:
+                id: 123
+              gog:
+                id: 456
+              launch:
+                <base>/game.exe:
+                  - arguments: --windowed
+                    when:
+                      - os: linux
+            "#,
+        )
+        .unwrap();
+
+        let serialized = serde_yaml::to_string(&manifest).unwrap();
+        assert_eq!(manifest, Manifest::load_from_string(&serialized).unwrap());
+    }
 }
@@ -0,0 +1,207 @@
+//! Metrics about a finished run, in the text format of Prometheus's
+//! node exporter textfile collector, so that runs can be monitored over time.
+
+use crate::prelude::{Error, OperationStatus, StrictPath};
+
+pub struct RunMetrics<'a> {
+    /// The CLI command, like `backup`.
+    pub command: &'a str,
+    pub finished: std::time::SystemTime,
+    pub duration: std::time::Duration,
+    /// Phase timings are included if they were tracked.
+    pub status: &'a OperationStatus,
+    pub failed_games: usize,
+}
+
+impl<'a> RunMetrics<'a> {
+    pub fn render(&self) -> String {
+        let command = format!("command=\"{}\"", escape_label_value(self.command));
+        let labelled = |extra: &str| format!("{},{}", command, extra);
+        let mut output = String::new();
+
+        let timestamp = self
+            .finished
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+        render_metric(
+            &mut output,
+            "ludusavi_last_run_timestamp_seconds",
+            "When the last run finished, as a Unix timestamp.",
+            &[(command.clone(), timestamp.to_string())],
+        );
+        render_metric(
+            &mut output,
+            "ludusavi_last_run_duration_seconds",
+            "How long the last run took.",
+            &[(command.clone(), format_seconds(self.duration))],
+        );
+        render_metric(
+            &mut output,
+            "ludusavi_last_run_games",
+            "How many games the last run found, processed, and failed to process.",
+            &[
+                (labelled("state=\"found\""), self.status.total_games.to_string()),
+                (labelled("state=\"processed\""), self.status.processed_games.to_string()),
+                (labelled("state=\"failed\""), self.failed_games.to_string()),
+            ],
+        );
+        render_metric(
+            &mut output,
+            "ludusavi_last_run_bytes",
+            "How many bytes the last run found and processed.",
+            &[
+                (labelled("state=\"found\""), self.status.total_bytes.to_string()),
+                (labelled("state=\"processed\""), self.status.processed_bytes.to_string()),
+            ],
+        );
+        if let Some(timings) = &self.status.timings {
+            let millis = |ms: u64| format_seconds(std::time::Duration::from_millis(ms));
+            render_metric(
+                &mut output,
+                "ludusavi_last_run_phase_duration_seconds",
+                "How long each phase of the last run took. Scanning and copying are summed across games.",
+                &[
+                    (
+                        labelled("phase=\"manifest_update\""),
+                        millis(timings.manifest_update_ms),
+                    ),
+                    (labelled("phase=\"scan\""), millis(timings.scan_ms)),
+                    (labelled("phase=\"copy\""), millis(timings.copy_ms)),
+                ],
+            );
+        }
+
+        output
+    }
+
+    /// Replace the file all at once, so that a scraper never reads a partial write.
+    pub fn write(&self, path: &StrictPath) -> Result<(), Error> {
+        let failed = || Error::UnableToWriteMetrics { path: path.clone() };
+        let file_name = path
+            .as_std_path_buf()
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .ok_or_else(failed)?;
+        // The temporary file is in the same folder so that renaming it is atomic.
+        let temporary = path.with_file_name(&format!(".{}.{}.tmp", file_name, std::process::id()));
+        temporary
+            .write_bytes(self.render().as_bytes())
+            .and_then(|_| std::fs::rename(temporary.interpret(), path.interpret()))
+            .map_err(|_| {
+                let _ = temporary.remove();
+                failed()
+            })
+    }
+}
+
+fn render_metric(output: &mut String, name: &str, help: &str, samples: &[(String, String)]) {
+    output.push_str(&format!("# HELP {} {}\n", name, escape_help(help)));
+    output.push_str(&format!("# TYPE {} gauge\n", name));
+    for (labels, value) in samples {
+        output.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+    }
+}
+
+fn format_seconds(duration: std::time::Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::PhaseTimings;
+    use crate::testing::TempDir;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn can_escape_label_values() {
+        assert_eq!("plain", escape_label_value("plain"));
+        assert_eq!(r#"a\\b \"c\" \nd"#, escape_label_value("a\\b \"c\" \nd"));
+    }
+
+    #[test]
+    fn can_escape_help() {
+        assert_eq!(r#"a\\b "c" \nd"#, escape_help("a\\b \"c\" \nd"));
+    }
+
+    #[test]
+    fn can_render_metrics() {
+        let status = OperationStatus {
+            total_games: 3,
+            total_bytes: 150,
+            processed_games: 2,
+            processed_bytes: 100,
+            timings: Some(PhaseTimings {
+                manifest_update_ms: 5,
+                scan_ms: 1_500,
+                copy_ms: 250,
+            }),
+            ..Default::default()
+        };
+        let metrics = RunMetrics {
+            command: "backup",
+            finished: std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000),
+            duration: std::time::Duration::from_millis(2_345),
+            status: &status,
+            failed_games: 1,
+        };
+        assert_eq!(
+            r#"
+# HELP ludusavi_last_run_timestamp_seconds When the last run finished, as a Unix timestamp.
+# TYPE ludusavi_last_run_timestamp_seconds gauge
+ludusavi_last_run_timestamp_seconds{command="backup"} 1600000000
+# HELP ludusavi_last_run_duration_seconds How long the last run took.
+# TYPE ludusavi_last_run_duration_seconds gauge
+ludusavi_last_run_duration_seconds{command="backup"} 2.345
+# HELP ludusavi_last_run_games How many games the last run found, processed, and failed to process.
+# TYPE ludusavi_last_run_games gauge
+ludusavi_last_run_games{command="backup",state="found"} 3
+ludusavi_last_run_games{command="backup",state="processed"} 2
+ludusavi_last_run_games{command="backup",state="failed"} 1
+# HELP ludusavi_last_run_bytes How many bytes the last run found and processed.
+# TYPE ludusavi_last_run_bytes gauge
+ludusavi_last_run_bytes{command="backup",state="found"} 150
+ludusavi_last_run_bytes{command="backup",state="processed"} 100
+# HELP ludusavi_last_run_phase_duration_seconds How long each phase of the last run took. Scanning and copying are summed across games.
+# TYPE ludusavi_last_run_phase_duration_seconds gauge
+ludusavi_last_run_phase_duration_seconds{command="backup",phase="manifest_update"} 0.005
+ludusavi_last_run_phase_duration_seconds{command="backup",phase="scan"} 1.500
+ludusavi_last_run_phase_duration_seconds{command="backup",phase="copy"} 0.250
+            "#
+            .trim_start()
+            .trim_end_matches(' '),
+            metrics.render()
+        );
+    }
+
+    #[test]
+    fn can_write_metrics_atomically() {
+        let temp = TempDir::new("metrics");
+        let file = temp.joined("ludusavi.prom");
+        file.write_bytes(b"old").unwrap();
+
+        let status = OperationStatus::default();
+        let metrics = RunMetrics {
+            command: "restore",
+            finished: std::time::SystemTime::now(),
+            duration: Default::default(),
+            status: &status,
+            failed_games: 0,
+        };
+        metrics.write(&file).unwrap();
+        let written = String::from_utf8(file.read_bytes().unwrap()).unwrap();
+        let leftovers = std::fs::read_dir(temp.path().interpret()).unwrap().count();
+
+        assert_eq!(metrics.render(), written);
+        assert_eq!(1, leftovers);
+    }
+}
@@ -14,15 +14,82 @@ const UNC_PREFIX: &str = "\\\\";
 const UNC_LOCAL_PREFIX: &str = "\\\\?\\";
 
 fn parse_home(path: &str) -> String {
-    if path == "~" || path.starts_with("~/") || path.starts_with("~\\") {
-        path.replacen("~", &dirs::home_dir().unwrap().to_string_lossy(), 1)
+    replace_home(path, dirs::home_dir())
+}
+
+/// When the home folder is unknown, `~` is left as-is.
+fn replace_home(path: &str, home: Option<std::path::PathBuf>) -> String {
+    match home {
+        Some(home) if path == "~" || path.starts_with("~/") || path.starts_with("~\\") => {
+            path.replacen("~", &home.to_string_lossy(), 1)
+        }
+        _ => path.to_owned(),
+    }
+}
+
+/// Parse a GUID like `{F1B32785-6FBA-4FCF-9D55-7B8E7F157091}`.
+#[cfg(target_os = "windows")]
+fn parse_guid(text: &str) -> Option<winapi::shared::guiddef::GUID> {
+    if text.len() != 38 || !text.is_ascii() || !text.starts_with('{') || !text.ends_with('}') {
+        return None;
+    }
+    let parts: Vec<_> = text[1..37].split('-').collect();
+    if parts.iter().map(|x| x.len()).collect::<Vec<_>>() != [8, 4, 4, 4, 12] {
+        return None;
+    }
+
+    let tail = format!("{}{}", parts[3], parts[4]);
+    let mut data4 = [0u8; 8];
+    for (i, byte) in data4.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&tail[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(winapi::shared::guiddef::GUID {
+        Data1: u32::from_str_radix(parts[0], 16).ok()?,
+        Data2: u16::from_str_radix(parts[1], 16).ok()?,
+        Data3: u16::from_str_radix(parts[2], 16).ok()?,
+        Data4: data4,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn get_known_folder(guid: &str) -> Option<String> {
+    let id = parse_guid(guid)?;
+    let mut raw: winapi::um::winnt::PWSTR = std::ptr::null_mut();
+    let code = unsafe { winapi::um::shlobj::SHGetKnownFolderPath(&id, 0, std::ptr::null_mut(), &mut raw) };
+    let folder = if code == winapi::shared::winerror::S_OK && !raw.is_null() {
+        let len = (0..).take_while(|&i| unsafe { *raw.offset(i) } != 0).count();
+        let wide = unsafe { std::slice::from_raw_parts(raw, len) };
+        Some(String::from_utf16_lossy(wide))
     } else {
-        path.to_owned()
+        None
+    };
+    // The buffer must be freed even if the call failed.
+    unsafe { winapi::um::combaseapi::CoTaskMemFree(raw as _) };
+    folder
+}
+
+/// Windows can refer to special folders by GUID, like
+/// `{F1B32785-6FBA-4FCF-9D55-7B8E7F157091}` for the local app data folder,
+/// so a path starting with one of those is resolved to the real folder.
+#[cfg(target_os = "windows")]
+fn parse_known_folder(path: &str) -> String {
+    if path.starts_with('{') {
+        let end = path.find(|c: char| c == '/' || c == '\\').unwrap_or_else(|| path.len());
+        if let Some(folder) = get_known_folder(&path[..end]) {
+            return format!("{}{}", folder, &path[end..]);
+        }
     }
+    path.to_owned()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn parse_known_folder(path: &str) -> String {
+    path.to_owned()
 }
 
 fn normalize(path: &str) -> String {
-    parse_home(path).replace(ATYPICAL_SEPARATOR, TYPICAL_SEPARATOR)
+    parse_known_folder(&parse_home(path)).replace(ATYPICAL_SEPARATOR, TYPICAL_SEPARATOR)
 }
 
 // Based on:
@@ -55,11 +122,9 @@ fn parse_dots(path: &str, basis: &str) -> String {
     render_pathbuf(&ret)
 }
 
-/// Convert a raw, possibly user-provided path into a suitable form for internal use.
-/// On Windows, this produces UNC paths.
-fn interpret<P: Into<String>>(path: P, basis: &Option<String>) -> String {
+fn absolutize<P: Into<String>>(path: P, basis: &Option<String>) -> String {
     let normalized = normalize(&path.into());
-    let absolutized = if std::path::Path::new(&normalized).is_absolute() {
+    if std::path::Path::new(&normalized).is_absolute() {
         normalized
     } else {
         render_pathbuf(
@@ -69,10 +134,26 @@ fn interpret<P: Into<String>>(path: P, basis: &Option<String>) -> String {
             }
             .join(normalized),
         )
-    };
-    match std::fs::canonicalize(&absolutized) {
-        Ok(x) => render_pathbuf(&x),
-        Err(_) => {
+    }
+}
+
+/// Convert a raw, possibly user-provided path into a suitable form for internal use.
+/// On Windows, this produces UNC paths.
+fn interpret<P: Into<String>>(path: P, basis: &Option<String>) -> String {
+    interpret_via(path, basis, |absolutized| {
+        std::fs::canonicalize(absolutized).ok().map(|x| render_pathbuf(&x))
+    })
+}
+
+fn interpret_via<P, F>(path: P, basis: &Option<String>, canonicalize: F) -> String
+where
+    P: Into<String>,
+    F: FnOnce(&str) -> Option<String>,
+{
+    let absolutized = absolutize(path, basis);
+    match canonicalize(&absolutized) {
+        Some(x) => x,
+        None => {
             let dedotted = parse_dots(
                 &absolutized,
                 &render_pathbuf(&match basis {
@@ -103,6 +184,38 @@ fn render_pathbuf(value: &std::path::PathBuf) -> String {
     value.as_path().display().to_string()
 }
 
+/// Canonicalization hits the file system, so this remembers the results
+/// for paths that get interpreted repeatedly during a single operation.
+/// Only successful results are kept, since a missing path may appear later
+/// (e.g., when restoring creates it). Create a new cache for each operation
+/// so that stale results are not carried over.
+#[derive(Debug, Default)]
+pub struct InterpretCache {
+    canonicalized: std::sync::Mutex<std::collections::HashMap<(String, Option<String>), String>>,
+    canonicalize_calls: std::sync::atomic::AtomicUsize,
+}
+
+impl InterpretCache {
+    fn canonicalize(&self, raw: &str, basis: &Option<String>, absolutized: &str) -> Option<String> {
+        let key = (raw.to_string(), basis.clone());
+        if let Some(cached) = self.canonicalized.lock().unwrap().get(&key) {
+            return Some(cached.clone());
+        }
+
+        self.canonicalize_calls
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let canonicalized = render_pathbuf(&std::fs::canonicalize(absolutized).ok()?);
+        self.canonicalized.lock().unwrap().insert(key, canonicalized.clone());
+        Some(canonicalized)
+    }
+
+    /// How many times the file system has actually been asked to canonicalize a path.
+    #[cfg(test)]
+    pub fn canonicalize_calls(&self) -> usize {
+        self.canonicalize_calls.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
 /// This is a wrapper around paths to make it more obvious when we're
 /// converting between different representations. This also handles
 /// things like `~`.
@@ -145,6 +258,16 @@ impl StrictPath {
         render(self.interpret())
     }
 
+    pub fn interpret_cached(&self, cache: &InterpretCache) -> String {
+        interpret_via(&self.raw, &self.basis, |absolutized| {
+            cache.canonicalize(&self.raw, &self.basis, absolutized)
+        })
+    }
+
+    pub fn render_cached(&self, cache: &InterpretCache) -> String {
+        render(self.interpret_cached(cache))
+    }
+
     pub fn is_file(&self) -> bool {
         std::path::Path::new(&self.interpret()).is_file()
     }
@@ -157,6 +280,43 @@ impl StrictPath {
         self.is_file() || self.is_dir()
     }
 
+    /// Check if this path is the same as `base` or somewhere inside of it,
+    /// ignoring case on operating systems with case-insensitive file systems.
+    pub fn is_under(&self, base: &StrictPath) -> bool {
+        crate::prelude::is_within(&self.render(), &base.render())
+    }
+
+    /// Check if the rendered path matches a glob pattern, without looking at
+    /// the file system. The pattern should use forward slashes, and `*` does
+    /// not match across them (use `**` for that). Invalid patterns never match.
+    pub fn matches_glob(&self, pattern: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: !crate::prelude::CASE_INSENSITIVE_OS,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        match glob::Pattern::new(pattern) {
+            Ok(pattern) => pattern.matches_with(&self.render(), options),
+            Err(_) => false,
+        }
+    }
+
+    pub fn contains_wildcard(&self) -> bool {
+        self.raw.contains(&['*', '?', '[', '{'][..])
+    }
+
+    /// All files within this directory, following symlinks.
+    /// If this is a file, then it is the only result.
+    pub fn iter_files(&self) -> impl Iterator<Item = StrictPath> {
+        walkdir::WalkDir::new(self.interpret())
+            .max_depth(100)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|x| x.file_type().is_file())
+            .map(|x| StrictPath::new(x.path().display().to_string()))
+    }
+
     pub fn remove(&self) -> Result<(), Box<dyn std::error::Error>> {
         if self.is_file() {
             std::fs::remove_file(&self.interpret())?;
@@ -170,6 +330,52 @@ impl StrictPath {
         Self::new(format!("{}/{}", self.interpret(), other))
     }
 
+    /// Replace the final component of the rendered path. If there is no final
+    /// component (e.g., `/` or `C:/`), then the name is appended instead.
+    pub fn with_file_name(&self, name: &str) -> Self {
+        let rendered = self.render();
+        let trimmed = rendered.trim_end_matches('/');
+        match trimmed.rfind('/') {
+            Some(i) => Self::new(format!("{}/{}", &trimmed[..i], name)),
+            None => Self::new(format!("{}/{}", trimmed, name)),
+        }
+    }
+
+    /// Rename the file or folder on disk by replacing its final component,
+    /// returning the path under its new name.
+    pub fn rename(&self, new_name: &str) -> Result<Self, std::io::Error> {
+        let renamed = self.with_file_name(new_name);
+        std::fs::rename(self.interpret(), renamed.interpret())?;
+        Ok(renamed)
+    }
+
+    /// Find the innermost link (e.g., a symlink or an NTFS junction) among the
+    /// components of the path as written, before the links are resolved.
+    /// This returns the link's target and the link itself, both rendered.
+    pub fn find_link(&self) -> Option<(String, String)> {
+        let absolutized = absolutize(&self.raw, &self.basis);
+        let dedotted = parse_dots(
+            &absolutized,
+            &render_pathbuf(&match &self.basis {
+                None => std::env::current_dir().unwrap(),
+                Some(b) => std::path::Path::new(b).to_path_buf(),
+            }),
+        );
+
+        let mut found = None;
+        let mut candidate = std::path::PathBuf::new();
+        for component in std::path::Path::new(&dedotted).components() {
+            candidate.push(component);
+            if std::fs::read_link(&candidate).is_ok() {
+                found = Some((
+                    Self::from_std_path_buf(&candidate).render(),
+                    render(render_pathbuf(&candidate)),
+                ));
+            }
+        }
+        found
+    }
+
     pub fn create_parent_dir(&self) -> std::io::Result<()> {
         let mut pb = self.as_std_path_buf();
         pb.pop();
@@ -177,6 +383,16 @@ impl StrictPath {
         Ok(())
     }
 
+    pub fn read_bytes(&self) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.interpret())
+    }
+
+    /// Write the whole file, creating its parent folders if needed.
+    pub fn write_bytes(&self, data: &[u8]) -> std::io::Result<()> {
+        self.create_parent_dir()?;
+        std::fs::write(self.interpret(), data)
+    }
+
     /// This splits a path into a drive (e.g., `C:` or `\\?\D:`) and the remainder.
     /// This is only used during backups to record drives in mapping.yaml, so it
     /// only has to deal with paths that can occur on the host OS.
@@ -260,6 +476,7 @@ mod tests {
 
     mod strict_path {
         use super::*;
+        use crate::testing::TempDir;
         use pretty_assertions::assert_eq;
 
         #[test]
@@ -387,6 +604,21 @@ mod tests {
             }
         }
 
+        #[test]
+        fn leaves_tilde_alone_without_home() {
+            assert_eq!("~/foo", replace_home("~/foo", None));
+            assert_eq!("~\\foo", replace_home("~\\foo", None));
+            assert_eq!("~", replace_home("~", None));
+        }
+
+        #[test]
+        fn can_replace_tilde_with_home() {
+            let home = Some(std::path::PathBuf::from("/home/user"));
+            assert_eq!("/home/user/foo", replace_home("~/foo", home.clone()));
+            assert_eq!("/home/user", replace_home("~", home.clone()));
+            assert_eq!("~foo", replace_home("~foo", home));
+        }
+
         #[test]
         fn converts_tilde_before_forward_slash() {
             if cfg!(target_os = "windows") {
@@ -423,6 +655,32 @@ mod tests {
             }
         }
 
+        #[test]
+        #[cfg(target_os = "windows")]
+        fn converts_known_folder_guid() {
+            let sp = StrictPath::new(s("{F1B32785-6FBA-4FCF-9D55-7B8E7F157091}/foo"));
+            assert_eq!(
+                format!("{}/foo", render(render_pathbuf(&dirs::data_local_dir().unwrap()))),
+                sp.render()
+            );
+        }
+
+        #[test]
+        fn does_not_convert_unknown_folder_guid() {
+            let sp = StrictPath::new(s("{00000000-0000-0000-0000-000000000000}/foo"));
+            if cfg!(target_os = "windows") {
+                assert_eq!(
+                    format!("\\\\?\\{}\\{{00000000-0000-0000-0000-000000000000}}\\foo", repo()),
+                    sp.interpret()
+                );
+            } else {
+                assert_eq!(
+                    format!("{}/{{00000000-0000-0000-0000-000000000000}}/foo", repo()),
+                    sp.interpret()
+                );
+            }
+        }
+
         #[test]
         fn can_check_if_it_is_a_file() {
             assert!(StrictPath::new(format!("{}/README.md", repo())).is_file());
@@ -435,6 +693,15 @@ mod tests {
             assert!(!StrictPath::new(format!("{}/README.md", repo())).is_dir());
         }
 
+        #[test]
+        fn can_check_if_it_is_under_another_path() {
+            let base = StrictPath::new(format!("{}/tests", repo()));
+            assert!(StrictPath::new(format!("{}/tests/backup/game1", repo())).is_under(&base));
+            assert!(StrictPath::new(format!("{}/tests", repo())).is_under(&base));
+            assert!(!StrictPath::new(format!("{}/tests-other/file", repo())).is_under(&base));
+            assert!(!StrictPath::new(repo()).is_under(&base));
+        }
+
         #[test]
         fn can_check_if_it_exists() {
             assert!(StrictPath::new(repo()).exists());
@@ -442,6 +709,140 @@ mod tests {
             assert!(!StrictPath::new(format!("{}/fake", repo())).exists());
         }
 
+        #[test]
+        fn can_iterate_files() {
+            let mut files: Vec<_> = StrictPath::new(format!("{}/tests/backup/game1", repo()))
+                .iter_files()
+                .map(|x| x.render())
+                .collect();
+            files.sort();
+            let rendered = |x: &str| StrictPath::new(format!("{}/{}", repo(), x)).render();
+            assert_eq!(
+                vec![
+                    rendered("tests/backup/game1/drive-X/file1.txt"),
+                    rendered("tests/backup/game1/drive-X/file2.txt"),
+                    rendered("tests/backup/game1/mapping.yaml"),
+                ],
+                files,
+            );
+            assert_eq!(
+                vec![rendered("README.md")],
+                StrictPath::new(format!("{}/README.md", repo()))
+                    .iter_files()
+                    .map(|x| x.render())
+                    .collect::<Vec<_>>(),
+            );
+            assert_eq!(0, StrictPath::new(format!("{}/fake", repo())).iter_files().count());
+        }
+
+        #[test]
+        fn can_interpret_repeatedly_with_one_canonicalization_per_path() {
+            let cache = InterpretCache::default();
+            let readme = StrictPath::new(format!("{}/README.md", repo()));
+            let fake = StrictPath::new(format!("{}/fake", repo()));
+
+            for _ in 0..10 {
+                assert_eq!(readme.interpret(), readme.interpret_cached(&cache));
+            }
+            assert_eq!(1, cache.canonicalize_calls());
+
+            // Missing paths are not cached, since they may be created later:
+            assert_eq!(fake.interpret(), fake.interpret_cached(&cache));
+            assert_eq!(fake.interpret(), fake.interpret_cached(&cache));
+            assert_eq!(3, cache.canonicalize_calls());
+        }
+
+        #[test]
+        fn can_check_if_it_matches_a_glob() {
+            let games = StrictPath::new(s("/games")).render();
+            let sp = StrictPath::new(format!("{}/Foo/save.dat", games));
+            assert!(sp.matches_glob(&format!("{}/Foo/save.dat", games)));
+            assert!(sp.matches_glob(&format!("{}/*/*.dat", games)));
+            assert!(sp.matches_glob(&format!("{}/**/save.???", games)));
+            assert!(sp.matches_glob("**/*.dat"));
+            assert!(!sp.matches_glob(&format!("{}/*.dat", games)));
+            assert!(!sp.matches_glob(&format!("{}/Foo/*.sav", games)));
+            assert!(!sp.matches_glob(&format!("{}/Bar/**", games)));
+            assert!(!sp.matches_glob(&format!("{}/[Foo/save.dat", games)));
+        }
+
+        #[test]
+        fn can_check_if_it_matches_a_glob_with_different_casing() {
+            let games = StrictPath::new(s("/games")).render();
+            let sp = StrictPath::new(format!("{}/Foo/save.dat", games));
+            assert_eq!(
+                crate::prelude::CASE_INSENSITIVE_OS,
+                sp.matches_glob(&format!("{}/foo/SAVE.DAT", games.to_uppercase()))
+            );
+            assert_eq!(crate::prelude::CASE_INSENSITIVE_OS, sp.matches_glob("**/*.DAT"));
+        }
+
+        #[test]
+        fn can_check_if_it_contains_a_wildcard() {
+            assert!(StrictPath::new(s("/foo/*.txt")).contains_wildcard());
+            assert!(StrictPath::new(s("/foo/file?.txt")).contains_wildcard());
+            assert!(StrictPath::new(s("/foo/[ab].txt")).contains_wildcard());
+            assert!(StrictPath::new(s("/foo/{a,b}.txt")).contains_wildcard());
+            assert!(!StrictPath::new(s("/foo/file.txt")).contains_wildcard());
+        }
+
+        #[test]
+        fn can_replace_file_name() {
+            assert_eq!(
+                StrictPath::new(s("/foo/baz")).render(),
+                StrictPath::new(s("/foo/bar")).with_file_name("baz").render(),
+            );
+            assert_eq!(
+                StrictPath::new(s("/foo/baz")).render(),
+                StrictPath::new(s("/foo/bar/")).with_file_name("baz").render(),
+            );
+        }
+
+        #[test]
+        fn can_replace_file_name_of_top_level_path() {
+            assert_eq!(
+                StrictPath::new(s("/baz")).render(),
+                StrictPath::new(s("/foo")).with_file_name("baz").render(),
+            );
+        }
+
+        #[test]
+        fn can_replace_file_name_of_root_by_appending() {
+            assert_eq!(
+                StrictPath::new(s("/baz")).render(),
+                StrictPath::new(s("/")).with_file_name("baz").render(),
+            );
+        }
+
+        #[test]
+        #[cfg(target_os = "windows")]
+        fn can_replace_file_name_of_drive_root_by_appending() {
+            assert_eq!(s("C:/baz"), StrictPath::new(s("C:/")).with_file_name("baz").render());
+            assert_eq!(s("C:/baz"), StrictPath::new(s("C:/foo")).with_file_name("baz").render());
+        }
+
+        #[test]
+        fn can_rename() {
+            let temp = TempDir::new("rename");
+            let old = temp.write("old.txt", "content");
+
+            let new = old.rename("new.txt").unwrap();
+            assert_eq!(temp.joined("new.txt").render(), new.render());
+            assert!(!old.exists());
+            assert!(new.is_file());
+            assert!(old.rename("other.txt").is_err());
+        }
+
+        #[test]
+        fn can_read_and_write_bytes() {
+            let temp = TempDir::new("bytes");
+
+            let file = temp.joined("sub/save.bin");
+            file.write_bytes(&[0, 159, 255]).unwrap();
+            assert_eq!(vec![0, 159, 255], file.read_bytes().unwrap());
+            assert!(temp.joined("fake.bin").read_bytes().is_err());
+        }
+
         #[test]
         #[cfg(target_os = "windows")]
         fn can_split_drive_for_windows_path() {
@@ -1,16 +1,39 @@
 use crate::{
-    config::{BackupFilter, RedirectConfig, RootsConfig},
-    layout::{BackupLayout, IndividualMapping},
-    manifest::{Game, Os, Store},
+    config::{BackupFilter, Config, RedirectConfig, RestoreMode, RootsConfig},
+    layout::{BackupLayout, FileOwner, IndividualMapping, COMPRESSED_SUFFIX, SCREENSHOTS_FOLDER},
+    manifest::{Game, GameFileEntry, Manifest, Os, Store},
+    path::InterpretCache,
 };
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 pub use crate::path::StrictPath;
 
 const WINDOWS: bool = cfg!(target_os = "windows");
 const MAC: bool = cfg!(target_os = "macos");
 const LINUX: bool = cfg!(target_os = "linux");
-const CASE_INSENSITIVE_OS: bool = WINDOWS || MAC;
+pub const CASE_INSENSITIVE_OS: bool = WINDOWS || MAC;
 const SKIP: &str = "<skip>";
+/// Every placeholder that can appear in a manifest path.
+const PLACEHOLDERS: [&str; 18] = [
+    "<root>",
+    "<game>",
+    "<base>",
+    "<home>",
+    "<storeUserId>",
+    "<osUserName>",
+    "<winAppData>",
+    "<winLocalAppData>",
+    "<rockstarLocalData>",
+    "<winDocuments>",
+    "<documents>",
+    "<winPublic>",
+    "<winProgramData>",
+    "<winDir>",
+    "<xdgData>",
+    "<xdgConfig>",
+    "<regHkcu>",
+    "<regHklm>",
+];
 
 #[derive(Clone, Debug, PartialEq, thiserror::Error)]
 pub enum Error {
@@ -32,6 +55,9 @@ pub enum Error {
     #[error("Unable to request confirmation")]
     CliUnableToRequestConfirmation,
 
+    #[error("No pruning criteria")]
+    CliPruneWithoutPolicy,
+
     #[error("Some entries failed")]
     SomeEntriesFailed,
 
@@ -45,8 +71,40 @@ pub enum Error {
     #[error("Error while working with the registry")]
     RegistryIssue,
 
+    #[allow(dead_code)]
+    #[error("Registry key not found: {key:?}")]
+    RegistryKeyNotFound { key: String },
+
     #[error("Unable to browse file system")]
     UnableToBrowseFileSystem,
+
+    #[error("Backup folder not found")]
+    BackupFolderNotFound { game: String, path: StrictPath },
+
+    #[error("Unable to open folder")]
+    UnableToOpenDir { path: StrictPath },
+
+    #[error("Unable to export archive")]
+    UnableToExportArchive { path: StrictPath },
+
+    #[error("Unable to import archive")]
+    UnableToImportArchive { path: StrictPath },
+
+    #[error("Unable to write metrics")]
+    UnableToWriteMetrics { path: StrictPath },
+
+    #[error("Unable to record scan")]
+    UnableToRecordScan { path: StrictPath },
+
+    #[error("Unable to export profile")]
+    UnableToExportProfile { path: StrictPath },
+
+    #[error("Unable to import profile")]
+    UnableToImportProfile { path: StrictPath },
+
+    #[cfg(feature = "android")]
+    #[error("Unable to pull Android saves")]
+    UnableToPullAndroidSaves { package: String },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -55,29 +113,398 @@ pub struct ScannedFile {
     pub size: u64,
     /// This is the restoration target path, without redirects applied.
     pub original_path: Option<StrictPath>,
+    /// This is the configured root that produced the file when scanning for backup.
+    /// It is unset for paths that don't depend on any root.
+    pub root: Option<RootsConfig>,
+}
+
+impl ScannedFile {
+    /// Where the file lives outside of a backup.
+    pub fn original_location(&self) -> &StrictPath {
+        self.original_path.as_ref().unwrap_or(&self.path)
+    }
+}
+
+/// These make it easier to set up test data.
+#[cfg(test)]
+impl ScannedFile {
+    pub fn new(path: StrictPath) -> Self {
+        Self {
+            path,
+            size: 0,
+            original_path: None,
+            root: None,
+        }
+    }
+
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_original_path(mut self, path: StrictPath) -> Self {
+        self.original_path = Some(path);
+        self
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct ScanInfo {
     pub game_name: String,
     pub found_files: std::collections::HashSet<ScannedFile>,
+    /// Prefer `registry_key_count` and `has_registry_keys` when you only need a summary.
+    /// This field may become private in the future.
     pub found_registry_keys: std::collections::HashSet<String>,
     pub registry_file: Option<StrictPath>,
+    /// This is only set when scanning for restoration.
+    pub integrity: Option<IntegrityInfo>,
+    /// This is only set when scanning for backup.
+    pub classification: Option<GameClassification>,
+    /// Links (e.g., junctions) that the found files were reached through,
+    /// mapping each target to its link. This is only set when scanning for backup.
+    pub reparse_points: std::collections::HashMap<String, String>,
+    /// Candidate paths that were skipped because they climb out of the expected
+    /// folders with `..`. This is only set when scanning for backup.
+    pub unsafe_paths: std::collections::HashSet<StrictPath>,
+    /// Backed up files that were compressed, which need to be decompressed
+    /// when restoring. This is only set when scanning for restoration.
+    pub compressed_files: std::collections::HashSet<StrictPath>,
+    /// The owners of backed up files, as recorded during backup.
+    /// This is only set when scanning for restoration.
+    pub file_owners: std::collections::HashMap<StrictPath, FileOwner>,
+    /// Every found file came from the Proton prefix of a game that is no longer installed.
+    /// This is only set when scanning for backup.
+    pub uninstalled_prefix: bool,
+    /// The size of the latest backup, set when this scan found much less than that,
+    /// which can mean that the game has moved its saves. This is only set when
+    /// scanning for backup.
+    pub shrunk_from_bytes: Option<u64>,
+    /// How long the scan took.
+    pub duration: Timing,
+}
+
+/// Wall-clock time spent on part of an operation, for diagnosing slow runs.
+/// This varies from run to run, so tests clear it before comparing results.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Timing(pub std::time::Duration);
+
+impl Timing {
+    pub fn since(start: std::time::Instant) -> Self {
+        Self(start.elapsed())
+    }
+
+    pub fn millis(&self) -> u64 {
+        self.0.as_millis() as u64
+    }
 }
 
 impl ScanInfo {
     pub fn sum_bytes(&self, backup_info: &Option<BackupInfo>) -> u64 {
         let successful_bytes = self.found_files.iter().map(|x| x.size).sum::<u64>();
         let failed_bytes = if let Some(backup_info) = &backup_info {
-            backup_info.failed_files.iter().map(|x| x.size).sum::<u64>()
+            backup_info
+                .failed_files
+                .iter()
+                .chain(&backup_info.skipped_files)
+                .map(|x| x.size)
+                .sum::<u64>()
         } else {
             0
         };
         successful_bytes - failed_bytes
     }
 
+    /// How long backing up the found files would take at this throughput,
+    /// with a minimum of 1 second.
+    pub fn estimated_backup_time(&self, throughput_bytes_per_sec: u64) -> std::time::Duration {
+        let seconds = self.sum_bytes(&None) as f64 / throughput_bytes_per_sec.max(1) as f64;
+        std::time::Duration::from_secs_f64(seconds).max(std::time::Duration::from_secs(1))
+    }
+
+    /// The size of the found store screenshots, which are tracked separately from saves.
+    pub fn screenshot_bytes(&self) -> u64 {
+        self.found_files
+            .iter()
+            .filter(|x| is_steam_screenshot(&x.path))
+            .map(|x| x.size)
+            .sum()
+    }
+
+    /// Found files within a particular folder, such as part of a game's install folder.
+    pub fn paths_under<'a>(&'a self, base: &StrictPath) -> Vec<&'a ScannedFile> {
+        let mut files: Vec<_> = self.found_files.iter().filter(|x| x.path.is_under(base)).collect();
+        files.sort();
+        files
+    }
+
+    pub fn registry_key_count(&self) -> usize {
+        self.found_registry_keys.len()
+    }
+
+    pub fn has_registry_keys(&self) -> bool {
+        !self.found_registry_keys.is_empty()
+    }
+
     pub fn found_anything(&self) -> bool {
-        !self.found_files.is_empty() || !self.found_registry_keys.is_empty()
+        !self.found_files.is_empty() || self.has_registry_keys()
+    }
+
+    pub fn backup_is_broken(&self) -> bool {
+        match &self.integrity {
+            Some(integrity) => integrity.status() == IntegrityStatus::Broken,
+            None => false,
+        }
+    }
+
+    /// This is the store of the roots through which files were found.
+    /// If there are several, then the first in `Store` order wins so that
+    /// the result is stable.
+    pub fn store(&self) -> Option<Store> {
+        self.found_files
+            .iter()
+            .filter_map(|x| x.root.as_ref())
+            .map(|x| x.store)
+            .min()
+    }
+
+    /// Groups of folders that look like alternative save locations (see `find_ambiguous_locations`),
+    /// along with when the files in each one were last modified.
+    /// Only backup scans have roots, so the files are still at their original locations.
+    pub fn ambiguous_locations(&self) -> Vec<Vec<AmbiguousLocation>> {
+        find_ambiguous_locations(&self.found_files)
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|path| {
+                        let modified = self
+                            .paths_under(&path)
+                            .into_iter()
+                            .filter_map(|x| std::fs::metadata(x.path.interpret()).ok()?.modified().ok())
+                            .max();
+                        AmbiguousLocation { path, modified }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Forget any found files within these folders.
+    /// When scanning for restoration, this applies to where the files would be restored.
+    pub fn exclude_locations(&mut self, locations: &[StrictPath]) {
+        if locations.is_empty() {
+            return;
+        }
+        self.found_files.retain(|x| {
+            !locations
+                .iter()
+                .any(|location| x.original_location().is_under(location))
+        });
+    }
+}
+
+/// One of several folders that look like alternative save locations for a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmbiguousLocation {
+    pub path: StrictPath,
+    /// The latest modification time of the files in this folder.
+    pub modified: Option<std::time::SystemTime>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanOptions {
+    /// On Windows, traverse NTFS junction points inside of save directories.
+    pub follow_junctions: bool,
+    /// Scan paths that climb out of the expected folders with `..`.
+    pub allow_unsafe_paths: bool,
+    /// Skip any folder containing a `.nobackup` file, along with everything inside of it.
+    pub respect_nobackup_files: bool,
+    /// Don't look inside of Proton prefixes, unless a root says otherwise.
+    pub skip_proton_prefixes: bool,
+    /// Don't look inside of Proton prefixes whose game is no longer installed.
+    pub skip_uninstalled_prefixes: bool,
+    /// Only keep files inside of this folder, ignoring matches elsewhere.
+    pub scope: Option<StrictPath>,
+}
+
+/// The folders where each root installs games, listed once per operation,
+/// so that a manifest's install folders can be matched to the actual folders
+/// even if they differ by case or surrounding spaces (e.g., `DARK SOULS III `).
+/// Clones share the same listings, so reuse one cache for every game in an operation.
+#[derive(Clone, Debug, Default)]
+pub struct InstallDirCache(std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, Vec<String>>>>);
+
+impl InstallDirCache {
+    /// List the folders where the root installs games, unless that was already done.
+    pub fn load(&self, root: &RootsConfig, fs: &dyn ScanFileSystem) {
+        let games_dir = games_folder(root);
+        let mut listings = self.0.lock().unwrap();
+        if listings.contains_key(&games_dir) {
+            return;
+        }
+        let folders = fs
+            .expand(&StrictPath::new(format!("{}/*", games_dir)))
+            .into_iter()
+            .filter(|x| fs.stat(x) == Some(FileStat::Dir))
+            .filter_map(|x| {
+                std::path::Path::new(&x)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .collect();
+        listings.insert(games_dir, folders);
+    }
+
+    /// The actual folder that the install folder refers to. If there's no exact match,
+    /// then this ignores case and surrounding spaces. If there's still no match
+    /// (or the root was never loaded), then the install folder is returned as-is.
+    pub fn resolve(&self, root: &RootsConfig, install_dir: &str) -> String {
+        let listings = self.0.lock().unwrap();
+        let folders = match listings.get(&games_folder(root)) {
+            Some(x) => x,
+            None => return install_dir.to_string(),
+        };
+        if folders.iter().any(|x| x == install_dir) {
+            return install_dir.to_string();
+        }
+        let wanted = install_dir.trim().to_lowercase();
+        folders
+            .iter()
+            .find(|x| x.trim().to_lowercase() == wanted)
+            .cloned()
+            .unwrap_or_else(|| install_dir.to_string())
+    }
+}
+
+/// The folder under which the root installs games, which is where `<base>` points.
+fn games_folder(root: &RootsConfig) -> String {
+    match root.store {
+        Store::Steam => format!("{}/steamapps/common", root.path.interpret()),
+        Store::Rockstar | Store::Other => root.path.interpret(),
+    }
+}
+
+/// This describes what a game's manifest entry can possibly yield on the current OS,
+/// so that a scan can skip categories that don't apply.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameClassification {
+    pub files: bool,
+    pub registry: bool,
+    /// Steam cloud saves and screenshots.
+    pub steam_extras: bool,
+    /// The game only runs on other operating systems, so nothing else applies.
+    pub other_os: bool,
+}
+
+impl GameClassification {
+    pub fn registry_only(&self) -> bool {
+        self.registry && !self.files && !self.steam_extras
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub enum IntegrityStatus {
+    Intact,
+    Broken,
+    Unknown,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntegrityInfo {
+    /// Older backups did not record any file metadata, so they cannot be checked.
+    pub known: bool,
+    /// These are the original paths of files that were backed up, but are no longer in the backup.
+    pub missing_files: std::collections::HashSet<StrictPath>,
+    /// These are backed up files whose current size does not match the recorded size.
+    pub corrupted_files: std::collections::HashSet<ScannedFile>,
+}
+
+impl IntegrityInfo {
+    pub fn status(&self) -> IntegrityStatus {
+        if !self.known {
+            IntegrityStatus::Unknown
+        } else if self.missing_files.is_empty() && self.corrupted_files.is_empty() {
+            IntegrityStatus::Intact
+        } else {
+            IntegrityStatus::Broken
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameDiff {
+    /// These are live files that are not in the backup.
+    pub added_files: std::collections::HashSet<StrictPath>,
+    /// These are the original paths of backed up files that no longer exist.
+    pub removed_files: std::collections::HashSet<StrictPath>,
+    /// These are live files whose size differs from the backed up copy.
+    pub resized_files: std::collections::HashSet<StrictPath>,
+    /// These are live files with the same size as the backed up copy, but different content.
+    pub modified_files: std::collections::HashSet<StrictPath>,
+    pub added_registry_keys: std::collections::HashSet<String>,
+    pub removed_registry_keys: std::collections::HashSet<String>,
+}
+
+impl GameDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_files.is_empty()
+            && self.removed_files.is_empty()
+            && self.resized_files.is_empty()
+            && self.modified_files.is_empty()
+            && self.added_registry_keys.is_empty()
+            && self.removed_registry_keys.is_empty()
+    }
+}
+
+/// A restoration target that more than one game would write.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestoreConflict {
+    pub target: StrictPath,
+    /// These are sorted from newest to oldest backup.
+    pub sources: Vec<RestoreConflictSource>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestoreConflictSource {
+    pub game_name: String,
+    pub file: ScannedFile,
+    pub modified: Option<std::time::SystemTime>,
+}
+
+/// How to retry copying a file that might be busy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub attempts: u64,
+    /// When disabled (e.g., for tests), retries happen immediately.
+    pub sleep: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 99,
+            sleep: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Keep trying the operation until it succeeds or we run out of attempts.
+    /// Each retry waits a bit longer than the last, in units of `delay_ms`.
+    /// Returns whether it succeeded and how long it slept in total.
+    pub fn run(&self, delay_ms: u64, mut operation: impl FnMut() -> bool) -> (bool, std::time::Duration) {
+        let mut slept = std::time::Duration::from_millis(0);
+        for i in 0..self.attempts {
+            if i > 0 && self.sleep {
+                let delay = std::time::Duration::from_millis(i * delay_ms);
+                std::thread::sleep(delay);
+                slept += delay;
+            }
+            if operation() {
+                return (true, slept);
+            }
+        }
+        (false, slept)
     }
 }
 
@@ -85,12 +512,58 @@ impl ScanInfo {
 pub struct BackupInfo {
     pub failed_files: std::collections::HashSet<ScannedFile>,
     pub failed_registry: std::collections::HashSet<String>,
+    /// How many files and registry keys there were to process,
+    /// so that the outcome can be summarized on its own.
+    pub found_files: usize,
+    pub found_registry_keys: usize,
+    /// Files whose original names could not be created at the restoration target,
+    /// so they were restored under the escaped names from the backup instead.
+    pub restored_with_stored_names: std::collections::HashSet<ScannedFile>,
+    /// Registry values that were not restored because the existing values have a different type.
+    pub registry_type_conflicts: std::collections::HashSet<String>,
+    /// Registry keys from another system's user that could not be mapped onto a user here.
+    pub unmapped_registry_keys: std::collections::HashSet<String>,
+    /// Files whose restoration target already existed and was replaced.
+    /// In a preview, these are the files that would replace something.
+    pub overwritten_files: std::collections::HashSet<ScannedFile>,
+    /// Files that were not restored because the existing file at the target was
+    /// modified recently (see `RestoreMode::BackupExisting::min_age_days`).
+    /// In a preview, these are the files that would be skipped.
+    pub skipped_files: std::collections::HashSet<ScannedFile>,
+    /// Where each restored file was written: its restoration target,
+    /// or the stored name next to it (see `restored_with_stored_names`).
+    pub restored_files: std::collections::HashMap<ScannedFile, StrictPath>,
+    /// Restored files whose recorded owner could not be reapplied,
+    /// usually because that requires root. These are the paths from `restored_files`.
+    pub unrestored_owners: std::collections::HashSet<StrictPath>,
+    /// How long the backup or restore took.
+    pub duration: Timing,
 }
 
 impl BackupInfo {
     pub fn successful(&self) -> bool {
         self.failed_files.is_empty() && self.failed_registry.is_empty()
     }
+
+    /// Whether everything that was found failed to be processed.
+    pub fn is_total_failure(&self) -> bool {
+        self.found_files + self.found_registry_keys > 0
+            && self.failed_files.len() >= self.found_files
+            && self.failed_registry.len() >= self.found_registry_keys
+    }
+
+    /// Whether this restored file is missing its recorded owner.
+    pub fn owner_not_restored(&self, file: &ScannedFile) -> bool {
+        match self.restored_files.get(file) {
+            Some(destination) => self.unrestored_owners.contains(destination),
+            None => false,
+        }
+    }
+
+    /// Describe the outcome for display, e.g., "Backed up 42 files (3 failed, 1 registry key failed)".
+    pub fn summary(&self) -> String {
+        crate::lang::Translator::default().backup_info_summary(self)
+    }
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize)]
@@ -103,6 +576,42 @@ pub struct OperationStatus {
     pub processed_games: usize,
     #[serde(rename = "processedBytes")]
     pub processed_bytes: u64,
+    /// This is only populated when backing up.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootStatus>,
+    /// This is only populated when timings were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<PhaseTimings>,
+}
+
+/// Total time spent in each phase of an operation, in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct PhaseTimings {
+    #[serde(rename = "manifestUpdateMs")]
+    pub manifest_update_ms: u64,
+    #[serde(rename = "scanMs")]
+    pub scan_ms: u64,
+    #[serde(rename = "copyMs")]
+    pub copy_ms: u64,
+}
+
+/// How much of a backup was found via a specific root.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RootStatus {
+    /// This is unset for files found without any root.
+    pub path: Option<String>,
+    pub store: Option<Store>,
+    pub games: usize,
+    pub bytes: u64,
+}
+
+impl RootStatus {
+    fn matches(&self, root: &Option<RootsConfig>) -> bool {
+        match root {
+            Some(root) => self.path.as_ref() == Some(&root.path.render()) && self.store == Some(root.store),
+            None => self.path.is_none(),
+        }
+    }
 }
 
 impl OperationStatus {
@@ -111,6 +620,26 @@ impl OperationStatus {
         self.total_bytes = 0;
         self.processed_games = 0;
         self.processed_bytes = 0;
+        self.roots.clear();
+        self.timings = None;
+    }
+
+    /// List the configured roots up front so that the breakdown also shows
+    /// roots that don't end up contributing anything.
+    pub fn track_roots(&mut self, roots: &[RootsConfig]) {
+        for root in roots {
+            if root.path.raw().trim().is_empty() {
+                continue;
+            }
+            let root = Some(root.clone());
+            if !self.roots.iter().any(|x| x.matches(&root)) {
+                self.roots.push(RootStatus {
+                    path: root.as_ref().map(|x| x.path.render()),
+                    store: root.as_ref().map(|x| x.store),
+                    ..Default::default()
+                });
+            }
+        }
     }
 
     pub fn add_game(&mut self, scan_info: &ScanInfo, backup_info: &Option<BackupInfo>, processed: bool) {
@@ -120,6 +649,48 @@ impl OperationStatus {
             self.processed_games += 1;
             self.processed_bytes += scan_info.sum_bytes(&backup_info);
         }
+        if scan_info.classification.is_some() {
+            self.add_game_roots(scan_info);
+        }
+    }
+
+    /// Games are scanned and copied in parallel, so each phase is timed as a whole
+    /// rather than by adding up the time of each game.
+    pub fn add_phase_timings(&mut self, scan: Timing, copy: Timing) {
+        if let Some(timings) = &mut self.timings {
+            timings.scan_ms += scan.millis();
+            timings.copy_ms += copy.millis();
+        }
+    }
+
+    /// Start adding up the time spent in each phase, beginning with the manifest update.
+    pub fn track_timings(&mut self, manifest_update: Timing) {
+        self.timings = Some(PhaseTimings {
+            manifest_update_ms: manifest_update.millis(),
+            ..Default::default()
+        });
+    }
+
+    fn add_game_roots(&mut self, scan_info: &ScanInfo) {
+        let mut contributed = std::collections::HashSet::new();
+        for file in &scan_info.found_files {
+            let index = match self.roots.iter().position(|x| x.matches(&file.root)) {
+                Some(i) => i,
+                None => {
+                    self.roots.push(RootStatus {
+                        path: file.root.as_ref().map(|x| x.path.render()),
+                        store: file.root.as_ref().map(|x| x.store),
+                        ..Default::default()
+                    });
+                    self.roots.len() - 1
+                }
+            };
+            self.roots[index].bytes += file.size;
+            contributed.insert(index);
+        }
+        for index in contributed {
+            self.roots[index].games += 1;
+        }
     }
 
     pub fn completed(&self) -> bool {
@@ -132,6 +703,8 @@ pub enum OperationStepDecision {
     Processed,
     Cancelled,
     Ignored,
+    /// The game was left out to stay within a size limit for the whole operation.
+    Skipped,
 }
 
 impl Default for OperationStepDecision {
@@ -141,36 +714,157 @@ impl Default for OperationStepDecision {
 }
 
 // This helps for unit tests when comparing StrictPaths.
-fn reslashed(path: &str) -> String {
+pub fn reslashed(path: &str) -> String {
     path.replace("\\", "/")
 }
 
+/// Check a file against an extension allowlist, where entries may include
+/// the leading `.` and may span several parts (e.g., `tar.gz`).
+fn has_allowed_extension(path: &StrictPath, allowed: &std::collections::HashSet<String>) -> bool {
+    let rendered = path.render();
+    let file_name = rendered.rsplit('/').next().unwrap_or_default();
+    let file_name = if CASE_INSENSITIVE_OS {
+        file_name.to_lowercase()
+    } else {
+        file_name.to_string()
+    };
+    allowed.iter().any(|extension| {
+        let extension = extension.trim_start_matches('.');
+        let suffix = if CASE_INSENSITIVE_OS {
+            format!(".{}", extension.to_lowercase())
+        } else {
+            format!(".{}", extension)
+        };
+        !extension.is_empty() && file_name.len() > suffix.len() && file_name.ends_with(&suffix)
+    })
+}
+
+/// Within one game, the same file may be matched more than once (e.g., when the
+/// manifest lists both a folder and a file inside of it), possibly with different
+/// casing or separators. This key identifies such matches as the same file.
+fn found_file_key(path: &StrictPath) -> String {
+    let rendered = path.render();
+    if CASE_INSENSITIVE_OS {
+        rendered.to_lowercase()
+    } else {
+        rendered
+    }
+}
+
+pub fn app_version() -> &'static str {
+    option_env!("LUDUSAVI_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"))
+}
+
+/// Where Ludusavi keeps its config and manifest. The `LUDUSAVI_CONFIG_DIR`
+/// environment variable takes precedence over the usual `~/.config/ludusavi`,
+/// and `.config/ludusavi` in the working directory is used if there's no home folder.
 pub fn app_dir() -> std::path::PathBuf {
-    let mut path = dirs::home_dir().unwrap();
+    choose_app_dir(std::env::var_os("LUDUSAVI_CONFIG_DIR"), dirs::home_dir())
+}
+
+fn choose_app_dir(env_override: Option<std::ffi::OsString>, home: Option<std::path::PathBuf>) -> std::path::PathBuf {
+    if let Some(dir) = env_override.filter(|x| !x.is_empty()) {
+        return std::path::PathBuf::from(dir);
+    }
+    let mut path = home.unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
     path.push(".config");
     path.push("ludusavi");
     path
 }
 
+/// Escape control characters (e.g., `\n` becomes `\\n`) so that unusual
+/// file names can't break up line-based output.
+pub fn escape_control_chars(text: &str) -> String {
+    if !text.chars().any(char::is_control) {
+        return text.to_string();
+    }
+    text.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Shorten a path for display by swapping known prefixes back to placeholders,
+/// like `<home>`, `<root1:Steam>`, or `<proton:123>` for a Proton prefix.
+/// Roots are numbered by their position in the config, so that several roots
+/// of the same store can be told apart.
+/// The longest matching prefix wins, so a root inside of the home folder
+/// takes precedence over the home folder itself. Control characters are escaped.
+pub fn display_path(path: &StrictPath, roots: &[RootsConfig]) -> String {
+    let rendered = path.render();
+
+    let mut prefixes = vec![];
+    if let Some(home) = dirs::home_dir() {
+        prefixes.push((StrictPath::from_std_path_buf(&home).render(), "<home>".to_string()));
+    }
+    for (i, root) in roots.iter().enumerate() {
+        if root.path.raw().trim().is_empty() {
+            continue;
+        }
+        let root_path = root.path.render();
+        let store = match root.store {
+            Store::Steam => "Steam",
+            Store::Rockstar => "Rockstar",
+            Store::Other => "Other",
+        };
+        prefixes.push((root_path.clone(), format!("<root{}:{}>", i + 1, store)));
+
+        if root.store == Store::Steam {
+            let compatdata = format!("{}/steamapps/compatdata/", root_path);
+            if rendered.starts_with(&compatdata) {
+                if let Some(id) = rendered[compatdata.len()..].split('/').next() {
+                    prefixes.push((format!("{}{}/pfx", compatdata, id), format!("<proton:{}>", id)));
+                }
+            }
+        }
+    }
+
+    let best = prefixes
+        .iter()
+        .filter(|(prefix, _)| rendered == *prefix || rendered.starts_with(&format!("{}/", prefix)))
+        .max_by_key(|(prefix, _)| prefix.len());
+    let shortened = match best {
+        Some((prefix, placeholder)) => format!("{}{}", placeholder, &rendered[prefix.len()..]),
+        None => rendered,
+    };
+    escape_control_chars(&shortened)
+}
+
 /// Returns the effective target and the original target (if different)
 pub fn game_file_restoration_target(
     original_target: &StrictPath,
     redirects: &[RedirectConfig],
 ) -> (StrictPath, Option<StrictPath>) {
-    let mut redirected_target = original_target.render();
+    game_file_restoration_target_cached(original_target, redirects, &InterpretCache::default())
+}
+
+/// Like `game_file_restoration_target`, but sharing canonicalization results
+/// across many files of the same operation, since the redirects are the same for each.
+pub fn game_file_restoration_target_cached(
+    original_target: &StrictPath,
+    redirects: &[RedirectConfig],
+    cache: &InterpretCache,
+) -> (StrictPath, Option<StrictPath>) {
+    let original_rendered = original_target.render_cached(cache);
+    let mut redirected_target = original_rendered.clone();
     for redirect in redirects {
         if redirect.source.raw().trim().is_empty() || redirect.target.raw().trim().is_empty() {
             continue;
         }
-        let source = redirect.source.render();
-        let target = redirect.target.render();
+        let source = redirect.source.render_cached(cache);
+        let target = redirect.target.render_cached(cache);
         if !source.is_empty() && !target.is_empty() && redirected_target.starts_with(&source) {
             redirected_target = redirected_target.replacen(&source, &target, 1);
         }
     }
 
     let redirected_target = StrictPath::new(redirected_target);
-    if original_target.render() != redirected_target.render() {
+    if original_rendered != redirected_target.render_cached(cache) {
         (redirected_target, Some(original_target.clone()))
     } else {
         (original_target.clone(), None)
@@ -207,85 +901,200 @@ fn check_nonwindows_path(path: Option<std::path::PathBuf>) -> String {
     }
 }
 
+/// These are the folders on the current machine that placeholders refer to.
+/// Any of them may be unknown in unusual environments (e.g., a container without a home folder),
+/// in which case the placeholders that need them are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostDirs {
+    pub home: Option<std::path::PathBuf>,
+    pub data: Option<std::path::PathBuf>,
+    pub data_local: Option<std::path::PathBuf>,
+    pub document: Option<std::path::PathBuf>,
+    pub public: Option<std::path::PathBuf>,
+    pub config: Option<std::path::PathBuf>,
+}
+
+impl HostDirs {
+    pub fn current() -> Self {
+        Self {
+            home: dirs::home_dir(),
+            data: dirs::data_dir(),
+            data_local: dirs::data_local_dir(),
+            document: dirs::document_dir(),
+            public: dirs::public_dir(),
+            config: dirs::config_dir(),
+        }
+    }
+}
+
+/// These are the values that stand in for a manifest's placeholders,
+/// other than `<root>` and `<base>`, which come from the root itself.
+/// Keeping them separate lets tests substitute fake values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlaceholderVars {
+    pub game: String,
+    pub home: String,
+    pub store_user_id: String,
+    pub os_user_name: String,
+    pub win_app_data: String,
+    pub win_local_app_data: String,
+    pub rockstar_local_data: String,
+    pub win_documents: String,
+    pub documents: String,
+    pub win_public: String,
+    pub win_program_data: String,
+    pub win_dir: String,
+    pub xdg_data: String,
+    pub xdg_config: String,
+}
+
+impl PlaceholderVars {
+    /// Values for the current machine.
+    pub fn for_host(root: &RootsConfig, install_dir: &str, dirs: &HostDirs) -> Self {
+        Self {
+            game: install_dir.to_string(),
+            home: check_path(dirs.home.clone()),
+            store_user_id: match root.store {
+                Store::Steam => "[0-9]*",
+                Store::Rockstar | Store::Other => "*",
+            }
+            .to_string(),
+            os_user_name: whoami::username(),
+            win_app_data: check_windows_path(dirs.data.clone()),
+            win_local_app_data: check_windows_path(dirs.data_local.clone()),
+            rockstar_local_data: check_windows_path(dirs.data_local.as_ref().map(|x| x.join("Rockstar Games"))),
+            win_documents: check_windows_path(dirs.document.clone()),
+            documents: check_path(dirs.document.clone()),
+            win_public: check_windows_path(dirs.public.clone()),
+            win_program_data: check_windows_path(Some(std::path::PathBuf::from("C:/Windows/ProgramData"))),
+            win_dir: check_windows_path(Some(std::path::PathBuf::from("C:/Windows"))),
+            xdg_data: check_nonwindows_path(dirs.data.clone()),
+            xdg_config: check_nonwindows_path(dirs.config.clone()),
+        }
+    }
+
+    /// Values for a Proton prefix, where `prefix` is its `drive_c` folder.
+    pub fn for_proton_prefix(prefix: &str, install_dir: &str, dirs: &HostDirs) -> Self {
+        Self {
+            game: install_dir.to_string(),
+            home: format!("{}/users/steamuser", prefix),
+            store_user_id: "*".to_string(),
+            os_user_name: "steamuser".to_string(),
+            win_app_data: format!("{}/users/steamuser/Application Data", prefix),
+            win_local_app_data: format!("{}/users/steamuser/Application Data", prefix),
+            rockstar_local_data: format!("{}/users/steamuser/Application Data/Rockstar Games", prefix),
+            win_documents: format!("{}/users/steamuser/My Documents", prefix),
+            documents: format!("{}/users/steamuser/My Documents", prefix),
+            win_public: format!("{}/users/Public", prefix),
+            win_program_data: format!("{}/ProgramData", prefix),
+            win_dir: format!("{}/windows", prefix),
+            xdg_data: check_nonwindows_path(dirs.data.clone()),
+            xdg_config: check_nonwindows_path(dirs.config.clone()),
+        }
+    }
+}
+
+/// Substitute each placeholder in a manifest path.
+/// Registry placeholders don't apply to files, so they become `SKIP`.
+pub fn expand_placeholders(path: &str, root: &RootsConfig, vars: &PlaceholderVars) -> String {
+    let root_path = root.path.interpret();
+    let base = format!("{}/{}", games_folder(root), vars.game);
+    path.replace("<root>", &root_path)
+        .replace("<game>", &vars.game)
+        .replace("<base>", &base)
+        .replace("<home>", &vars.home)
+        .replace("<storeUserId>", &vars.store_user_id)
+        .replace("<osUserName>", &vars.os_user_name)
+        .replace("<winAppData>", &vars.win_app_data)
+        .replace("<winLocalAppData>", &vars.win_local_app_data)
+        .replace("<rockstarLocalData>", &vars.rockstar_local_data)
+        .replace("<winDocuments>", &vars.win_documents)
+        .replace("<documents>", &vars.documents)
+        .replace("<winPublic>", &vars.win_public)
+        .replace("<winProgramData>", &vars.win_program_data)
+        .replace("<winDir>", &vars.win_dir)
+        .replace("<xdgData>", &vars.xdg_data)
+        .replace("<xdgConfig>", &vars.xdg_config)
+        .replace("<regHkcu>", SKIP)
+        .replace("<regHklm>", SKIP)
+}
+
+/// The placeholders in a manifest path, each mapped to whether it is skipped on this system
+/// (e.g., `<winAppData>` on Linux), which explains why a path would not be scanned.
+pub fn placeholder_applicability(path: &str) -> std::collections::BTreeMap<&'static str, bool> {
+    let root = RootsConfig {
+        path: StrictPath::new(String::new()),
+        store: Store::Other,
+        portable: false,
+        scan_proton_prefixes: None,
+    };
+    let vars = PlaceholderVars::for_host(&root, "", &HostDirs::current());
+    PLACEHOLDERS
+        .iter()
+        .filter(|x| path.contains(*x))
+        .map(|x| (*x, expand_placeholders(x, &root, &vars).contains(SKIP)))
+        .collect()
+}
+
 pub fn parse_paths(
     path: &str,
     root: &RootsConfig,
     install_dirs: &[&String],
     steam_id: &Option<u32>,
     manifest_dir: &StrictPath,
+    scan_proton_prefixes: bool,
+) -> std::collections::HashSet<StrictPath> {
+    parse_paths_with_dirs(
+        path,
+        root,
+        install_dirs,
+        steam_id,
+        manifest_dir,
+        scan_proton_prefixes,
+        &HostDirs::current(),
+    )
+}
+
+fn parse_paths_with_dirs(
+    path: &str,
+    root: &RootsConfig,
+    install_dirs: &[&String],
+    steam_id: &Option<u32>,
+    manifest_dir: &StrictPath,
+    scan_proton_prefixes: bool,
+    dirs: &HostDirs,
 ) -> std::collections::HashSet<StrictPath> {
     let mut paths = std::collections::HashSet::new();
 
+    // Flatpak apps keep their XDG folders in their own sandbox, like `~/.var/app/<app-id>/data`.
+    let flatpak_path = if get_os() == Os::Linux && (path.contains("<xdgData>") || path.contains("<xdgConfig>")) {
+        Some(
+            path.replace("<xdgData>", "<home>/.var/app/*/data")
+                .replace("<xdgConfig>", "<home>/.var/app/*/config"),
+        )
+    } else {
+        None
+    };
+
     for install_dir in install_dirs {
-        paths.insert(
-            path.replace("<root>", &root.path.interpret())
-                .replace("<game>", &install_dir)
-                .replace(
-                    "<base>",
-                    &match root.store {
-                        Store::Steam => format!("{}/steamapps/common/{}", root.path.interpret(), install_dir),
-                        Store::Other => format!("{}/{}", root.path.interpret(), install_dir),
-                    },
-                )
-                .replace(
-                    "<home>",
-                    &dirs::home_dir().unwrap_or_else(|| SKIP.into()).to_string_lossy(),
-                )
-                .replace(
-                    "<storeUserId>",
-                    match root.store {
-                        Store::Steam => "[0-9]*",
-                        Store::Other => "*",
-                    },
-                )
-                .replace("<osUserName>", &whoami::username())
-                .replace("<winAppData>", &check_windows_path(dirs::data_dir()))
-                .replace("<winLocalAppData>", &check_windows_path(dirs::data_local_dir()))
-                .replace("<winDocuments>", &check_windows_path(dirs::document_dir()))
-                .replace("<winPublic>", &check_windows_path(dirs::public_dir()))
-                .replace(
-                    "<winProgramData>",
-                    &check_windows_path(Some(std::path::PathBuf::from("C:/Windows/ProgramData"))),
-                )
-                .replace(
-                    "<winDir>",
-                    &check_windows_path(Some(std::path::PathBuf::from("C:/Windows"))),
-                )
-                .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
-                .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
-                .replace("<regHkcu>", SKIP)
-                .replace("<regHklm>", SKIP),
-        );
-        if get_os() == Os::Linux && root.store == Store::Steam && steam_id.is_some() {
-            let prefix = format!(
-                "{}/steamapps/compatdata/{}/pfx/drive_c",
-                root.path.interpret(),
-                steam_id.unwrap()
-            );
-            paths.insert(
-                path.replace("<root>", &root.path.interpret())
-                    .replace("<game>", &install_dir)
-                    .replace(
-                        "<base>",
-                        &format!("{}/steamapps/common/{}", root.path.interpret(), install_dir),
-                    )
-                    .replace("<home>", &format!("{}/users/steamuser", prefix))
-                    .replace("<storeUserId>", "*")
-                    .replace("<osUserName>", "steamuser")
-                    .replace("<winAppData>", &format!("{}/users/steamuser/Application Data", prefix))
-                    .replace(
-                        "<winLocalAppData>",
-                        &format!("{}/users/steamuser/Application Data", prefix),
-                    )
-                    .replace("<winDocuments>", &format!("{}/users/steamuser/My Documents", prefix))
-                    .replace("<winPublic>", &format!("{}/users/Public", prefix))
-                    .replace("<winProgramData>", &format!("{}/ProgramData", prefix))
-                    .replace("<winDir>", &format!("{}/windows", prefix))
-                    .replace("<xdgData>", &check_nonwindows_path(dirs::data_dir()))
-                    .replace("<xdgConfig>", &check_nonwindows_path(dirs::config_dir()))
-                    .replace("<regHkcu>", SKIP)
-                    .replace("<regHklm>", SKIP),
-            );
+        let vars = PlaceholderVars::for_host(root, install_dir, dirs);
+        for candidate in std::iter::once(path).chain(flatpak_path.as_deref()) {
+            paths.insert(expand_placeholders(candidate, root, &vars));
+        }
+        match steam_id {
+            Some(steam_id) if scan_proton_prefixes && get_os() == Os::Linux && root.store == Store::Steam => {
+                let prefix = format!(
+                    "{}/steamapps/compatdata/{}/pfx/drive_c",
+                    root.path.interpret(),
+                    steam_id
+                );
+                paths.insert(expand_placeholders(
+                    path,
+                    root,
+                    &PlaceholderVars::for_proton_prefix(&prefix, install_dir, dirs),
+                ));
+            }
+            _ => {}
         }
     }
 
@@ -295,141 +1104,594 @@ pub fn parse_paths(
         .collect()
 }
 
-fn glob_any(path: &StrictPath) -> Result<glob::Paths, ()> {
-    let options = glob::MatchOptions {
-        case_sensitive: CASE_INSENSITIVE_OS,
-        require_literal_separator: true,
-        require_literal_leading_dot: false,
-    };
-    let entries = glob::glob_with(&path.render(), options).map_err(|_| ())?;
-    Ok(entries)
-}
-
-pub fn scan_game_for_backup(
+/// These are the concrete paths that one of a game's file entries could refer to via the root,
+/// which may still contain wildcards. Install folders are matched to the actual folders
+/// listed in `install_dir_cache` for the root, if any.
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_file_entry(
     game: &Game,
     name: &str,
-    roots: &[RootsConfig],
-    manifest_dir: &StrictPath,
+    raw_path: &str,
+    root: &RootsConfig,
     steam_id: &Option<u32>,
-    filter: &BackupFilter,
-) -> ScanInfo {
-    let mut found_files = std::collections::HashSet::new();
+    manifest_dir: &StrictPath,
+    scan_proton_prefixes: bool,
+    install_dir_cache: &InstallDirCache,
+) -> std::collections::HashSet<StrictPath> {
+    let default_install_dir = name.to_string();
+    let install_dirs: Vec<_> = match (&root.store, &game.rockstar_title, &game.install_dir) {
+        (Store::Rockstar, Some(title), _) => vec![title],
+        (_, _, Some(x)) => x.keys().collect(),
+        _ => vec![&default_install_dir],
+    }
+    .into_iter()
+    .map(|x| install_dir_cache.resolve(root, x))
+    .collect();
+    parse_paths(
+        raw_path,
+        root,
+        &install_dirs.iter().collect::<Vec<_>>(),
+        steam_id,
+        manifest_dir,
+        scan_proton_prefixes,
+    )
+    .into_iter()
+    .filter(|x| !x.raw().contains(SKIP))
+    .collect()
+}
+
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    let (path, ancestor) = if CASE_INSENSITIVE_OS {
+        (path.to_lowercase(), ancestor.to_lowercase())
+    } else {
+        (path.to_string(), ancestor.to_string())
+    };
+    path == ancestor || path.starts_with(&format!("{}/", ancestor.trim_end_matches('/')))
+}
+
+/// Find groups of roots that each hold a copy of the same save data,
+/// such as two Steam libraries that both contain an old install of the game.
+/// A file is claimed by several roots when they found it at the same path,
+/// or at the same path relative to each root (e.g., `<root>/Game/save.dat`).
+/// Files that don't depend on a root are never ambiguous this way,
+/// and one path claimed by several games is a restore conflict instead (see `find_restore_conflicts`).
+/// Each group and the list of groups are sorted.
+pub fn find_ambiguous_locations(files: &std::collections::HashSet<ScannedFile>) -> Vec<Vec<StrictPath>> {
+    let key = |x: &str| {
+        if CASE_INSENSITIVE_OS {
+            x.to_lowercase()
+        } else {
+            x.to_string()
+        }
+    };
+
+    let mut claims = std::collections::BTreeMap::<String, std::collections::BTreeSet<String>>::new();
+    for file in files {
+        let root = match &file.root {
+            Some(root) => root.path.render(),
+            None => continue,
+        };
+        let path = file.original_location().render();
+        if let Some(relative) = path.strip_prefix(&format!("{}/", root.trim_end_matches('/'))) {
+            claims
+                .entry(format!("relative:{}", key(relative)))
+                .or_default()
+                .insert(root.clone());
+        }
+        claims
+            .entry(format!("absolute:{}", key(&path)))
+            .or_default()
+            .insert(root);
+    }
+
+    let mut groups: Vec<std::collections::BTreeSet<String>> = vec![];
+    for (_, roots) in claims.into_iter().filter(|(_, x)| x.len() > 1) {
+        let mut merged = roots;
+        groups.retain(|group| {
+            if group.iter().any(|x| merged.contains(x)) {
+                merged.extend(group.iter().cloned());
+                false
+            } else {
+                true
+            }
+        });
+        groups.push(merged);
+    }
+
+    let mut groups: Vec<Vec<_>> = groups
+        .into_iter()
+        .map(|group| group.into_iter().map(StrictPath::new).collect())
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// A path that climbs with `..` (e.g., from a hostile entry like `<base>/../../..`)
+/// is unsafe if it no longer ends up within the root or the home folder.
+/// Proton prefixes live within the root, so they're covered as well.
+fn escapes_anchors(candidate: &StrictPath, root: &RootsConfig) -> bool {
+    if !reslashed(&candidate.raw()).split('/').any(|x| x == "..") {
+        return false;
+    }
+
+    let mut anchors = vec![];
+    if root.path.raw() != SKIP {
+        anchors.push(root.path.render());
+    }
+    if let Some(home) = dirs::home_dir() {
+        anchors.push(StrictPath::from_std_path_buf(&home).render());
+    }
+
+    let rendered = candidate.render();
+    !anchors.iter().any(|anchor| is_within(&rendered, anchor))
+}
+
+/// Restoring into these could damage the system, so they're off limits
+/// unless unsafe paths are allowed.
+fn is_in_system_dir(path: &StrictPath) -> bool {
+    let system_dirs = if WINDOWS {
+        vec![reslashed(
+            &std::env::var("SystemRoot").unwrap_or_else(|_| "C:/Windows".to_string()),
+        )]
+    } else {
+        [
+            "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/sbin", "/sys", "/usr", "/System",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect()
+    };
+    let rendered = path.render();
+    system_dirs.iter().any(|x| is_within(&rendered, x))
+}
+
+/// Ludusavi's own config and manifest cache shouldn't be backed up as part of a game,
+/// nor overwritten by a restore while Ludusavi is running.
+fn is_in_app_dir(path: &StrictPath) -> bool {
+    is_within(&path.render(), &StrictPath::from_std_path_buf(&app_dir()).render())
+}
+
+fn exclude_files_within(files: &mut std::collections::HashSet<ScannedFile>, dir: &StrictPath) {
+    let dir = dir.render();
+    files.retain(|file| !is_within(&file.path.render(), &dir));
+}
+
+fn glob_any(path: &StrictPath) -> Result<glob::Paths, ()> {
+    let options = glob::MatchOptions {
+        case_sensitive: CASE_INSENSITIVE_OS,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let entries = glob::glob_with(&path.render(), options).map_err(|_| ())?;
+    Ok(entries)
+}
+
+fn file_entry_suits_os(entry: &GameFileEntry, maybe_proton: bool) -> bool {
+    match &entry.when {
+        Some(constraints) => {
+            let unconstrained_by_os = constraints.iter().any(|x| x.os == None);
+            let matches_os = constraints.iter().any(|x| x.os == Some(get_os()));
+            let suitable_for_proton = maybe_proton && constraints.iter().any(|x| x.os == Some(Os::Windows));
+
+            unconstrained_by_os || matches_os || suitable_for_proton
+        }
+        None => true,
+    }
+}
+
+/// The manifest's OS constraints also hint at which systems a game runs on.
+/// If every file entry is constrained to other operating systems, then there's
+/// no point in scanning for the game, unless it could be running through Proton.
+fn game_is_for_other_os(game: &Game, maybe_proton: bool) -> bool {
+    let files = match &game.files {
+        Some(x) if !x.is_empty() => x,
+        _ => return false,
+    };
+    files.values().all(|entry| match &entry.when {
+        Some(constraints) if !constraints.is_empty() => !file_entry_suits_os(entry, maybe_proton),
+        _ => false,
+    })
+}
+
+pub fn classify_game(
+    game: &Game,
+    roots: &[RootsConfig],
+    steam_id: &Option<u32>,
+    filter: &BackupFilter,
+) -> GameClassification {
+    let has_steam_root = roots.iter().any(|x| x.store == Store::Steam);
+    let maybe_proton = get_os() == Os::Linux && has_steam_root && steam_id.is_some();
+
+    if game_is_for_other_os(game, maybe_proton) {
+        return GameClassification {
+            other_os: true,
+            ..Default::default()
+        };
+    }
+
+    let files = match &game.files {
+        Some(files) => files.iter().any(|(raw_path, entry)| {
+            !raw_path.trim().is_empty() && (!filter.exclude_other_os_data || file_entry_suits_os(entry, maybe_proton))
+        }),
+        None => false,
+    };
+    let registry = match &game.registry {
+        Some(registry) => (WINDOWS || maybe_proton) && registry.keys().any(|x| !x.trim().is_empty()),
+        None => false,
+    };
+
+    GameClassification {
+        files,
+        registry,
+        // Entries without any applicable files are registry-only or placeholders,
+        // so their Steam ID shouldn't pull in unrelated cloud data.
+        steam_extras: files && has_steam_root && steam_id.is_some(),
+        other_os: false,
+    }
+}
+
+/// Steam leaves a game's Proton prefix behind when the game is uninstalled,
+/// so a prefix without a matching `appmanifest_<id>.acf` in the same library is stale.
+fn is_uninstalled_proton_prefix(fs: &dyn ScanFileSystem, root: &StrictPath, steam_id: u32) -> bool {
+    let steamapps = format!("{}/steamapps", root.interpret());
+    fs.stat(&format!("{}/compatdata/{}", steamapps, steam_id)) == Some(FileStat::Dir)
+        && !matches!(
+            fs.stat(&format!("{}/appmanifest_{}.acf", steamapps, steam_id)),
+            Some(FileStat::File { .. })
+        )
+}
+
+/// What a scan found at a path.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum FileStat {
+    #[serde(rename = "file")]
+    File { size: u64 },
+    #[serde(rename = "dir")]
+    Dir,
+}
+
+/// Everything that a backup scan asks of the file system, so that a scan can be
+/// recorded with `ScanRecorder` and then replayed from a `ScanFixture`.
+/// Registry keys are still read from the real registry.
+pub trait ScanFileSystem {
+    /// Find the existing paths that match a path, which may contain wildcards.
+    fn expand(&self, path: &StrictPath) -> Vec<String>;
+    fn stat(&self, path: &str) -> Option<FileStat>;
+    /// Find every file inside of a folder, along with its size.
+    fn files_in_dir(&self, dir: &str, options: &ScanOptions) -> std::collections::BTreeMap<String, u64>;
+    /// See `StrictPath::find_link`.
+    fn find_link(&self, path: &StrictPath) -> Option<(String, String)>;
+}
+
+pub struct RealFileSystem;
+
+impl ScanFileSystem for RealFileSystem {
+    fn expand(&self, path: &StrictPath) -> Vec<String> {
+        if path.contains_wildcard() {
+            match glob_any(path) {
+                Ok(x) => x
+                    .filter_map(|r| r.ok())
+                    .map(|x| x.to_string_lossy().to_string())
+                    .collect(),
+                Err(_) => vec![],
+            }
+        } else if path.exists() {
+            vec![path.render()]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stat(&self, path: &str) -> Option<FileStat> {
+        let p = std::path::Path::new(path);
+        if p.is_file() {
+            Some(FileStat::File {
+                size: match p.metadata() {
+                    Ok(m) => m.len(),
+                    _ => 0,
+                },
+            })
+        } else if p.is_dir() {
+            Some(FileStat::Dir)
+        } else {
+            None
+        }
+    }
+
+    fn files_in_dir(&self, dir: &str, options: &ScanOptions) -> std::collections::BTreeMap<String, u64> {
+        let dir = std::path::Path::new(dir);
+        let mut visited = std::collections::HashSet::<u64>::new();
+        if let Some(id) = get_file_id(dir) {
+            visited.insert(id);
+        }
+        let mut files = std::collections::BTreeMap::new();
+        scan_dir_for_files(dir, options, &mut visited, &mut files);
+        files
+    }
+
+    fn find_link(&self, path: &StrictPath) -> Option<(String, String)> {
+        path.find_link()
+    }
+}
+
+/// The file system as seen by one or more scans, which can be saved
+/// (e.g., as YAML) to reproduce a scan on another machine.
+/// Anything that wasn't recorded is treated as missing.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScanFixture {
+    pub expanded: std::collections::BTreeMap<String, Vec<String>>,
+    pub stats: std::collections::BTreeMap<String, FileStat>,
+    #[serde(rename = "filesInDirs")]
+    pub files_in_dirs: std::collections::BTreeMap<String, std::collections::BTreeMap<String, u64>>,
+    pub links: std::collections::BTreeMap<String, (String, String)>,
+}
+
+impl ScanFixture {
+    pub fn save(&self, path: &StrictPath) -> Result<(), Error> {
+        let failed = || Error::UnableToRecordScan { path: path.clone() };
+        let content = serde_yaml::to_string(self).map_err(|_| failed())?;
+        path.write_bytes(content.as_bytes()).map_err(|_| failed())
+    }
+}
+
+#[cfg(test)]
+impl ScanFileSystem for ScanFixture {
+    fn expand(&self, path: &StrictPath) -> Vec<String> {
+        self.expanded.get(&path.render()).cloned().unwrap_or_default()
+    }
+
+    fn stat(&self, path: &str) -> Option<FileStat> {
+        self.stats.get(path).cloned()
+    }
+
+    fn files_in_dir(&self, dir: &str, _options: &ScanOptions) -> std::collections::BTreeMap<String, u64> {
+        self.files_in_dirs.get(dir).cloned().unwrap_or_default()
+    }
+
+    fn find_link(&self, path: &StrictPath) -> Option<(String, String)> {
+        self.links.get(&path.render()).cloned()
+    }
+}
+
+/// Scan the real file system while recording what was found into a `ScanFixture`.
+#[derive(Default)]
+pub struct ScanRecorder {
+    fixture: std::sync::Mutex<ScanFixture>,
+}
+
+impl ScanRecorder {
+    pub fn into_fixture(self) -> ScanFixture {
+        self.fixture.into_inner().unwrap()
+    }
+}
+
+impl ScanFileSystem for ScanRecorder {
+    fn expand(&self, path: &StrictPath) -> Vec<String> {
+        let expanded = RealFileSystem.expand(path);
+        if !expanded.is_empty() {
+            let mut fixture = self.fixture.lock().unwrap();
+            fixture.expanded.insert(path.render(), expanded.clone());
+        }
+        expanded
+    }
+
+    fn stat(&self, path: &str) -> Option<FileStat> {
+        let stat = RealFileSystem.stat(path);
+        if let Some(stat) = &stat {
+            let mut fixture = self.fixture.lock().unwrap();
+            fixture.stats.insert(path.to_string(), stat.clone());
+        }
+        stat
+    }
+
+    fn files_in_dir(&self, dir: &str, options: &ScanOptions) -> std::collections::BTreeMap<String, u64> {
+        let files = RealFileSystem.files_in_dir(dir, options);
+        let mut fixture = self.fixture.lock().unwrap();
+        fixture.files_in_dirs.insert(dir.to_string(), files.clone());
+        files
+    }
+
+    fn find_link(&self, path: &StrictPath) -> Option<(String, String)> {
+        let link = RealFileSystem.find_link(path);
+        if let Some(link) = &link {
+            let mut fixture = self.fixture.lock().unwrap();
+            fixture.links.insert(path.render(), link.clone());
+        }
+        link
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn scan_game_for_backup(
+    game: &Game,
+    name: &str,
+    roots: &[RootsConfig],
+    manifest_dir: &StrictPath,
+    steam_id: &Option<u32>,
+    filter: &BackupFilter,
+    options: &ScanOptions,
+    install_dirs: &InstallDirCache,
+) -> ScanInfo {
+    scan_game_for_backup_with_fs(
+        game,
+        name,
+        roots,
+        manifest_dir,
+        steam_id,
+        filter,
+        options,
+        &RealFileSystem,
+        install_dirs,
+    )
+}
+
+/// Like `scan_game_for_backup`, but look for files through `fs`.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_game_for_backup_with_fs(
+    game: &Game,
+    name: &str,
+    roots: &[RootsConfig],
+    manifest_dir: &StrictPath,
+    steam_id: &Option<u32>,
+    filter: &BackupFilter,
+    options: &ScanOptions,
+    fs: &dyn ScanFileSystem,
+    install_dirs: &InstallDirCache,
+) -> ScanInfo {
+    let started = std::time::Instant::now();
+    let mut found_files = std::collections::HashMap::new();
     #[allow(unused_mut)]
     let mut found_registry_keys = std::collections::HashSet::new();
+    let roots: Vec<_> = roots
+        .iter()
+        .filter(|x| !filter.exclude_stores.contains(&x.store))
+        .cloned()
+        .collect();
 
     // Add a dummy root for checking paths without `<root>`.
     let mut roots_to_check: Vec<RootsConfig> = vec![RootsConfig {
         path: StrictPath::new(SKIP.to_string()),
         store: Store::Other,
+        portable: false,
+        scan_proton_prefixes: None,
     }];
     roots_to_check.extend(roots.iter().cloned());
 
-    let mut paths_to_check = std::collections::HashSet::<StrictPath>::new();
+    let classification = classify_game(game, &roots, steam_id, filter);
+    let mut unsafe_paths = std::collections::HashSet::new();
+    // Each path remembers the first root that produced it.
+    let mut paths_to_check = std::collections::HashMap::<StrictPath, usize>::new();
+    let mut uninstalled_prefixes = vec![];
 
-    for root in &roots_to_check {
+    for (root_index, root) in roots_to_check.iter().enumerate() {
         if root.path.raw().trim().is_empty() {
             continue;
         }
-        if let Some(files) = &game.files {
+        // A root can opt out of scanning its prefixes, but not back in when they are skipped overall.
+        let mut scan_proton_prefixes = !options.skip_proton_prefixes && root.scan_proton_prefixes.unwrap_or(true);
+        if let (true, Store::Steam, Some(steam_id)) = (scan_proton_prefixes, root.store, steam_id) {
+            if get_os() == Os::Linux && is_uninstalled_proton_prefix(fs, &root.path, *steam_id) {
+                if options.skip_uninstalled_prefixes {
+                    scan_proton_prefixes = false;
+                } else {
+                    uninstalled_prefixes.push(format!(
+                        "{}/steamapps/compatdata/{}/",
+                        reslashed(&root.path.interpret()),
+                        steam_id
+                    ));
+                }
+            }
+        }
+        if let (true, Some(files)) = (classification.files, &game.files) {
+            install_dirs.load(root, fs);
             let maybe_proton = get_os() == Os::Linux && root.store == Store::Steam && steam_id.is_some();
-            let default_install_dir = name.to_string();
-            let install_dirs: Vec<_> = match &game.install_dir {
-                Some(x) => x.keys().collect(),
-                _ => vec![&default_install_dir],
-            };
             for (raw_path, path_info) in files {
                 if raw_path.trim().is_empty() {
                     continue;
                 }
-                if filter.exclude_other_os_data {
-                    if let Some(constraints) = &path_info.when {
-                        let unconstrained_by_os = constraints.iter().any(|x| x.os == None);
-                        let matches_os = constraints.iter().any(|x| x.os == Some(get_os()));
-                        let suitable_for_proton = maybe_proton && constraints.iter().any(|x| x.os == Some(Os::Windows));
-
-                        if !unconstrained_by_os && !matches_os && !suitable_for_proton {
-                            continue;
-                        }
-                    }
+                if filter.exclude_other_os_data && !file_entry_suits_os(path_info, maybe_proton) {
+                    continue;
                 }
-                let candidates = parse_paths(raw_path, &root, &install_dirs, &steam_id, &manifest_dir);
+                let candidates = resolve_file_entry(
+                    game,
+                    name,
+                    raw_path,
+                    root,
+                    steam_id,
+                    manifest_dir,
+                    scan_proton_prefixes,
+                    install_dirs,
+                );
                 for candidate in candidates {
-                    if candidate.raw().contains(SKIP) {
+                    if !options.allow_unsafe_paths && escapes_anchors(&candidate, root) {
+                        unsafe_paths.insert(candidate);
                         continue;
                     }
-                    paths_to_check.insert(candidate);
+                    paths_to_check.entry(candidate).or_insert(root_index);
                 }
             }
         }
         if root.store == Store::Steam && steam_id.is_some() {
-            // Cloud saves:
-            paths_to_check.insert(StrictPath::relative(
-                format!("{}/userdata/*/{}/remote/", root.path.interpret(), &steam_id.unwrap()),
-                Some(manifest_dir.interpret()),
-            ));
+            if classification.steam_extras {
+                // Cloud saves:
+                paths_to_check
+                    .entry(StrictPath::relative(
+                        format!("{}/userdata/*/{}/remote/", root.path.interpret(), &steam_id.unwrap()),
+                        Some(manifest_dir.interpret()),
+                    ))
+                    .or_insert(root_index);
+            }
 
             // Screenshots:
-            if !filter.exclude_store_screenshots {
-                paths_to_check.insert(StrictPath::relative(
-                    format!(
-                        "{}/userdata/*/760/remote/{}/screenshots/*.*",
-                        root.path.interpret(),
-                        &steam_id.unwrap()
-                    ),
-                    Some(manifest_dir.interpret()),
-                ));
+            if classification.steam_extras && !filter.exclude_store_screenshots {
+                paths_to_check
+                    .entry(StrictPath::relative(
+                        format!(
+                            "{}/userdata/*/760/remote/{}/screenshots/*.*",
+                            root.path.interpret(),
+                            &steam_id.unwrap()
+                        ),
+                        Some(manifest_dir.interpret()),
+                    ))
+                    .or_insert(root_index);
             }
 
             // Registry:
-            if game.registry.is_some() {
+            if classification.registry && scan_proton_prefixes {
                 let prefix = format!(
                     "{}/steamapps/compatdata/{}/pfx",
                     root.path.interpret(),
                     steam_id.unwrap()
                 );
-                paths_to_check.insert(StrictPath::relative(
-                    format!("{}/*.reg", prefix),
-                    Some(manifest_dir.interpret()),
-                ));
+                paths_to_check
+                    .entry(StrictPath::relative(
+                        format!("{}/*.reg", prefix),
+                        Some(manifest_dir.interpret()),
+                    ))
+                    .or_insert(root_index);
             }
         }
     }
 
-    for path in paths_to_check {
-        let entries = match glob_any(&path) {
-            Ok(x) => x,
-            Err(_) => continue,
+    let mut reparse_points = std::collections::HashMap::<String, String>::new();
+
+    // Files found via several roots are attributed to the first one.
+    let mut paths_to_check: Vec<_> = paths_to_check.into_iter().collect();
+    paths_to_check.sort_by(|(path1, index1), (path2, index2)| (index1, path1).cmp(&(index2, path2)));
+
+    for (path, root_index) in paths_to_check {
+        let entries = fs.expand(&path);
+        if entries.is_empty() {
+            continue;
+        }
+        // Index 0 is the dummy root for paths without `<root>`.
+        let root = if root_index == 0 {
+            None
+        } else {
+            Some(roots_to_check[root_index].clone())
         };
-        for entry in entries.filter_map(|r| r.ok()) {
-            let plain = entry.to_string_lossy().to_string();
-            let p = std::path::Path::new(&plain);
-            if p.is_file() {
-                found_files.insert(ScannedFile {
-                    path: StrictPath::new(reslashed(&plain)),
-                    size: match p.metadata() {
-                        Ok(m) => m.len(),
-                        _ => 0,
-                    },
-                    original_path: None,
-                });
-            } else if p.is_dir() {
-                for child in walkdir::WalkDir::new(p)
-                    .max_depth(100)
-                    .follow_links(true)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                {
-                    if child.file_type().is_file() {
-                        found_files.insert(ScannedFile {
-                            path: StrictPath::new(reslashed(&child.path().display().to_string())),
-                            size: match child.metadata() {
-                                Ok(m) => m.len(),
-                                _ => 0,
-                            },
-                            original_path: None,
-                        });
+        if let Some((target, link)) = fs.find_link(&path) {
+            reparse_points.entry(target).or_insert(link);
+        }
+        for plain in entries {
+            let files = match fs.stat(&plain) {
+                Some(FileStat::File { size }) => {
+                    if options.respect_nobackup_files && has_nobackup_marker_in(fs, &plain) {
+                        continue;
                     }
+                    vec![(reslashed(&plain), size)]
                 }
+                Some(FileStat::Dir) => fs.files_in_dir(&plain, options).into_iter().collect(),
+                None => continue,
+            };
+            for (file, size) in files {
+                let path = StrictPath::new(file);
+                found_files.entry(found_file_key(&path)).or_insert(ScannedFile {
+                    path,
+                    size,
+                    original_path: None,
+                    root: root.clone(),
+                });
             }
         }
     }
@@ -437,13 +1699,15 @@ pub fn scan_game_for_backup(
     #[cfg(target_os = "windows")]
     {
         let mut hives = crate::registry::Hives::default();
-        if let Some(registry) = &game.registry {
+        if let (true, Some(registry)) = (classification.registry, &game.registry) {
             for key in registry.keys() {
                 if key.trim().is_empty() {
                     continue;
                 }
-                if let Ok(info) = hives.store_key_from_full_path(&key) {
-                    if info.found {
+                match hives.store_key_from_full_path(key) {
+                    Err(Error::RegistryKeyNotFound { .. }) => {}
+                    // Other errors are recorded so that the backup can report them as failures.
+                    _ => {
                         found_registry_keys.insert(key.to_string());
                     }
                 }
@@ -451,15 +1715,204 @@ pub fn scan_game_for_backup(
         }
     }
 
+    let mut found_files: std::collections::HashSet<_> = found_files.into_values().collect();
+    exclude_files_within(&mut found_files, &StrictPath::from_std_path_buf(&app_dir()));
+    if let Some(scope) = &options.scope {
+        found_files.retain(|x| x.path.is_under(scope));
+    }
+    if let Some(allowed) = filter.allowed_extensions_for(name) {
+        found_files.retain(|x| has_allowed_extension(&x.path, allowed));
+    }
+    let excluded_locations = filter.excluded_locations_for(name);
+    found_files.retain(|x| !excluded_locations.iter().any(|location| x.path.is_under(location)));
+    reparse_points.retain(|target, _| {
+        let prefix = format!("{}/", target);
+        found_files.iter().any(|x| x.path.render().starts_with(&prefix))
+    });
+    let uninstalled_prefix = !found_files.is_empty()
+        && found_registry_keys.is_empty()
+        && found_files.iter().all(|x| {
+            let path = x.path.render();
+            uninstalled_prefixes.iter().any(|prefix| path.starts_with(prefix))
+        });
+
     ScanInfo {
         game_name: name.to_string(),
         found_files,
         found_registry_keys,
         registry_file: None,
+        integrity: None,
+        classification: Some(classification),
+        reparse_points,
+        unsafe_paths,
+        compressed_files: Default::default(),
+        file_owners: Default::default(),
+        uninstalled_prefix,
+        shrunk_from_bytes: None,
+        duration: Timing::since(started),
+    }
+}
+
+/// Like `scan_game_for_backup`, but skip any roots for which `roots_filter` returns false.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_game_for_backup_with_roots_filter<F: Fn(&RootsConfig) -> bool>(
+    game: &Game,
+    name: &str,
+    roots: &[RootsConfig],
+    roots_filter: F,
+    manifest_dir: &StrictPath,
+    steam_id: &Option<u32>,
+    filter: &BackupFilter,
+    options: &ScanOptions,
+    install_dirs: &InstallDirCache,
+) -> ScanInfo {
+    let roots: Vec<_> = roots.iter().filter(|x| roots_filter(x)).cloned().collect();
+    scan_game_for_backup(
+        game,
+        name,
+        &roots,
+        manifest_dir,
+        steam_id,
+        filter,
+        options,
+        install_dirs,
+    )
+}
+
+/// Scan a single game whose manifest entry is given inline as YAML,
+/// using the default backup filter and scan options.
+#[cfg(test)]
+pub fn scan_game_for_backup_from_yaml(
+    game_yaml: &str,
+    name: &str,
+    roots: &[RootsConfig],
+    manifest_dir: &StrictPath,
+    steam_id: &Option<u32>,
+) -> Result<ScanInfo, Error> {
+    let game: Game = serde_yaml::from_str(game_yaml).map_err(|e| Error::ManifestInvalid { why: format!("{}", e) })?;
+    Ok(scan_game_for_backup(
+        &game,
+        name,
+        roots,
+        manifest_dir,
+        steam_id,
+        &BackupFilter::default(),
+        &ScanOptions::default(),
+        &InstallDirCache::default(),
+    ))
+}
+
+const NOBACKUP_MARKER: &str = ".nobackup";
+
+fn has_nobackup_marker(dir: &std::path::Path) -> bool {
+    dir.join(NOBACKUP_MARKER).is_file()
+}
+
+/// Check whether the folder containing a file has a marker.
+fn has_nobackup_marker_in(fs: &dyn ScanFileSystem, file: &str) -> bool {
+    match std::path::Path::new(file).parent() {
+        Some(dir) => matches!(
+            fs.stat(&dir.join(NOBACKUP_MARKER).to_string_lossy()),
+            Some(FileStat::File { .. })
+        ),
+        None => false,
+    }
+}
+
+fn scan_dir_for_files(
+    dir: &std::path::Path,
+    options: &ScanOptions,
+    visited: &mut std::collections::HashSet<u64>,
+    found_files: &mut std::collections::BTreeMap<String, u64>,
+) {
+    // Junctions are set aside and traversed separately, since walkdir may not
+    // follow them, and so that we can avoid looping through cyclic ones.
+    let mut junctions = vec![];
+    let walker = walkdir::WalkDir::new(dir)
+        .max_depth(100)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| {
+            if options.respect_nobackup_files && e.file_type().is_dir() && has_nobackup_marker(e.path()) {
+                false
+            } else if options.follow_junctions && e.depth() > 0 && is_junction(e.path()) {
+                junctions.push(e.path().to_path_buf());
+                false
+            } else {
+                true
+            }
+        });
+
+    for child in walker.filter_map(|e| e.ok()) {
+        if child.file_type().is_file() {
+            found_files
+                .entry(reslashed(&child.path().display().to_string()))
+                .or_insert(match child.metadata() {
+                    Ok(m) => m.len(),
+                    _ => 0,
+                });
+        }
+    }
+
+    for junction in junctions {
+        if let Some(id) = get_file_id(&junction) {
+            if visited.insert(id) {
+                scan_dir_for_files(&junction, options, visited, found_files);
+            }
+        }
+    }
+}
+
+/// Only mount points count as junctions. Symbolic links to folders are also
+/// reparse points, but walkdir already follows those.
+#[cfg(target_os = "windows")]
+fn is_junction(path: &std::path::Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::winnt::{FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_REPARSE_POINT, IO_REPARSE_TAG_MOUNT_POINT};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data: winapi::um::minwinbase::WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
+    let handle = unsafe { winapi::um::fileapi::FindFirstFileW(wide.as_ptr(), &mut data) };
+    if handle == winapi::um::handleapi::INVALID_HANDLE_VALUE {
+        return false;
+    }
+    unsafe { winapi::um::fileapi::FindClose(handle) };
+
+    // For reparse points, `dwReserved0` holds the reparse tag.
+    let attributes = FILE_ATTRIBUTE_DIRECTORY | FILE_ATTRIBUTE_REPARSE_POINT;
+    data.dwFileAttributes & attributes == attributes && data.dwReserved0 == IO_REPARSE_TAG_MOUNT_POINT
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_junction(_path: &std::path::Path) -> bool {
+    false
+}
+
+#[cfg(target_os = "windows")]
+fn get_file_id(path: &std::path::Path) -> Option<u64> {
+    use std::os::windows::{fs::OpenOptionsExt, io::AsRawHandle};
+
+    // Directories can only be opened with backup semantics.
+    let file = std::fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(winapi::um::winbase::FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let mut info: winapi::um::fileapi::BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    let code = unsafe { winapi::um::fileapi::GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) };
+    if code == 0 {
+        return None;
     }
+    Some(((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn get_file_id(_path: &std::path::Path) -> Option<u64> {
+    None
 }
 
 pub fn scan_game_for_restoration(name: &str, layout: &BackupLayout) -> ScanInfo {
+    let started = std::time::Instant::now();
     let mut found_files = std::collections::HashSet::new();
     #[allow(unused_mut)]
     let mut found_registry_keys = std::collections::HashSet::new();
@@ -470,6 +1923,9 @@ pub fn scan_game_for_restoration(name: &str, layout: &BackupLayout) -> ScanInfo
     if target_game.is_dir() {
         found_files = layout.restorable_files(&name, &target_game);
     }
+    let integrity = layout.verify_integrity(name, &target_game, &found_files);
+    let compressed_files = layout.compressed_files(name, &target_game, &found_files);
+    let file_owners = layout.file_owners(name, &target_game, &found_files);
 
     #[cfg(target_os = "windows")]
     {
@@ -488,14 +1944,73 @@ pub fn scan_game_for_restoration(name: &str, layout: &BackupLayout) -> ScanInfo
         found_files,
         found_registry_keys,
         registry_file,
+        integrity: Some(integrity),
+        classification: None,
+        reparse_points: Default::default(),
+        unsafe_paths: Default::default(),
+        compressed_files,
+        file_owners,
+        uninstalled_prefix: false,
+        shrunk_from_bytes: None,
+        duration: Timing::since(started),
+    }
+}
+
+/// This is the program and argument for showing a folder in the platform's file manager.
+/// The rendered form of the path is used, since some file managers reject UNC paths.
+fn open_dir_command(path: &StrictPath) -> (&'static str, String) {
+    let rendered = path.render();
+    if WINDOWS {
+        ("explorer", rendered.replace("/", "\\"))
+    } else if MAC {
+        ("open", rendered)
+    } else {
+        ("xdg-open", rendered)
+    }
+}
+
+/// Show a folder in the platform's file manager.
+/// The path is passed as a single argument rather than through a shell,
+/// so spaces and other special characters are kept intact.
+pub fn open_dir(path: &StrictPath) -> Result<(), Error> {
+    let (program, arg) = open_dir_command(path);
+    // Explorer reports a failure code even when it succeeds, so the exit status is not checked.
+    match std::process::Command::new(program).arg(arg).spawn() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(Error::UnableToOpenDir { path: path.clone() }),
+    }
+}
+
+/// Show a game's folder within a backup in the platform's file manager.
+pub fn open_game_backup_dir(backup_dir: &StrictPath, game: &str) -> Result<(), Error> {
+    let folder = BackupLayout::new(backup_dir.clone()).game_folder(game);
+    if !folder.is_dir() {
+        return Err(Error::BackupFolderNotFound {
+            game: game.to_string(),
+            path: folder,
+        });
     }
+    open_dir(&folder)
 }
 
+/// Without merging, everything in the target is removed, except for other namespaces' backups.
 pub fn prepare_backup_target(target: &StrictPath, merge: bool) -> Result<(), Error> {
     if !merge {
-        target
-            .remove()
-            .map_err(|_| Error::CannotPrepareBackupTarget { path: target.clone() })?;
+        let failed = || Error::CannotPrepareBackupTarget { path: target.clone() };
+        if target.is_dir() {
+            let namespaces = BackupLayout::new(target.clone()).mapping.namespaces;
+            for entry in std::fs::read_dir(target.interpret()).map_err(|_| failed())? {
+                let entry = entry.map_err(|_| failed())?;
+                if namespaces.contains(&entry.file_name().to_string_lossy().to_string()) {
+                    continue;
+                }
+                StrictPath::from_std_path_buf(&entry.path())
+                    .remove()
+                    .map_err(|_| failed())?;
+            }
+        } else {
+            target.remove().map_err(|_| failed())?;
+        }
     } else if target.exists() && !target.is_dir() {
         return Err(Error::CannotPrepareBackupTarget { path: target.clone() });
     }
@@ -506,27 +2021,288 @@ pub fn prepare_backup_target(target: &StrictPath, merge: bool) -> Result<(), Err
     Ok(())
 }
 
-pub fn back_up_game(info: &ScanInfo, name: &str, layout: &BackupLayout) -> BackupInfo {
-    let mut failed_files = std::collections::HashSet::new();
-    #[allow(unused_mut)]
-    let mut failed_registry = std::collections::HashSet::new();
+/// A quick look at the backup target, so that problems can be shown before starting a backup.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BackupTargetHealth {
+    pub exists: bool,
+    /// For a target that doesn't exist yet, this is whether it could be created.
+    pub writable: bool,
+    pub free_bytes: Option<u64>,
+    /// How many games are currently backed up there.
+    pub games: usize,
+    /// This is the most recent modification time of any game's `mapping.yaml`.
+    pub last_backup: Option<std::time::SystemTime>,
+}
 
-    let target_game = layout.game_folder(&name);
-    // Since we delete the game folder first, we don't need to worry about
-    // loading its existing mapping:
-    let mut mapping = IndividualMapping::new(name.to_string());
+pub fn check_backup_target(target: &StrictPath) -> BackupTargetHealth {
+    let nearest_dir = target
+        .as_std_path_buf()
+        .ancestors()
+        .find(|x| x.is_dir())
+        .map(|x| x.to_path_buf());
+    let writable = !target.is_file() && nearest_dir.as_ref().map(|x| is_writable_dir(x)).unwrap_or(false);
 
-    let mut unable_to_prepare = false;
-    if info.found_anything() {
-        match target_game.remove() {
-            Ok(_) => {
-                if std::fs::create_dir(target_game.interpret()).is_err() {
-                    unable_to_prepare = true;
-                }
-            }
-            Err(_) => {
-                unable_to_prepare = true;
-            }
+    let mut health = BackupTargetHealth {
+        exists: target.is_dir(),
+        writable,
+        free_bytes: nearest_dir.and_then(|x| get_free_space(&x)),
+        ..Default::default()
+    };
+    if health.exists {
+        let layout = BackupLayout::new(target.clone());
+        health.games = layout.mapping.games.len();
+        health.last_backup = layout
+            .mapping
+            .games
+            .values()
+            .filter_map(|game| std::fs::metadata(layout.game_mapping_file(&game.base).interpret()).ok())
+            .filter_map(|metadata| metadata.modified().ok())
+            .max();
+    }
+    health
+}
+
+fn is_writable_dir(dir: &std::path::Path) -> bool {
+    let probe = dir.join(format!(".ludusavi-write-test-{}", std::process::id()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Free space on the drive containing this folder.
+pub fn free_space(path: &StrictPath) -> Option<u64> {
+    get_free_space(&path.as_std_path_buf())
+}
+
+#[cfg(target_os = "windows")]
+fn get_free_space(path: &std::path::Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut free: winapi::um::winnt::ULARGE_INTEGER = unsafe { std::mem::zeroed() };
+    let code = unsafe {
+        winapi::um::fileapi::GetDiskFreeSpaceExW(wide.as_ptr(), &mut free, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    if code == 0 {
+        return None;
+    }
+    Some(unsafe { *free.QuadPart() })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn get_free_space(path: &std::path::Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let raw = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(raw.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// The user and group that own the file. This is only known on Unix.
+#[cfg(not(target_os = "windows"))]
+fn file_owner(path: &StrictPath) -> Option<FileOwner> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path.interpret()).ok()?;
+    Some(FileOwner {
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn file_owner(_path: &StrictPath) -> Option<FileOwner> {
+    None
+}
+
+/// Change the user and group that own the file. This requires root,
+/// unless only changing the group to another group of the current user.
+/// If the file is a symlink, then the link itself changes, not whatever it points to,
+/// so that a restored link can't be used to take over another file.
+#[cfg(not(target_os = "windows"))]
+pub fn change_file_owner(path: &StrictPath, owner: FileOwner) -> std::io::Result<()> {
+    // Interpreting the whole path would resolve the link, so only resolve its folder.
+    let raw = std::path::PathBuf::from(path.raw());
+    let link = match (raw.parent(), raw.file_name()) {
+        (Some(parent), Some(name)) => StrictPath::from_std_path_buf(&parent.to_path_buf())
+            .as_std_path_buf()
+            .join(name),
+        _ => path.as_std_path_buf(),
+    };
+    std::os::unix::fs::lchown(link, Some(owner.uid), Some(owner.gid))
+}
+
+#[cfg(target_os = "windows")]
+pub fn change_file_owner(_path: &StrictPath, _owner: FileOwner) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "file owners are not supported on Windows",
+    ))
+}
+
+/// List the files that currently exist in a backup target or game folder,
+/// which would be deleted by `prepare_backup_target` (without merge) or `back_up_game`.
+pub fn list_existing_files(target: &StrictPath) -> Vec<StrictPath> {
+    if target.is_file() {
+        return vec![target.clone()];
+    } else if !target.is_dir() {
+        return vec![];
+    }
+
+    // Other namespaces' backups are kept, like in `prepare_backup_target`.
+    let namespaces = BackupLayout::new(target.clone()).mapping.namespaces;
+    let mut files: Vec<_> = walkdir::WalkDir::new(target.interpret())
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() != 1 || !namespaces.contains(&e.file_name().to_string_lossy().to_string()))
+        .filter_map(|e| e.ok())
+        .filter(|x| x.file_type().is_file())
+        .map(|x| StrictPath::from_std_path_buf(&x.path().to_path_buf()))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Files at least this large are compressed when `compress_large_files` is enabled.
+const COMPRESSION_THRESHOLD: u64 = 1024 * 1024;
+const COMPRESSION_SAMPLE_BYTES: u64 = 64 * 1024;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Compress a sample from the start of the file to see if the whole file is worth
+/// compressing. Formats that are already compressed (e.g., images) barely shrink.
+fn compresses_well(path: &StrictPath) -> bool {
+    use std::io::Read;
+
+    let mut sample = vec![];
+    let read = std::fs::File::open(path.interpret())
+        .and_then(|file| file.take(COMPRESSION_SAMPLE_BYTES).read_to_end(&mut sample));
+    if read.is_err() || sample.is_empty() {
+        return false;
+    }
+    match zstd::encode_all(&sample[..], COMPRESSION_LEVEL) {
+        Ok(compressed) => compressed.len() * 5 <= sample.len() * 4,
+        Err(_) => false,
+    }
+}
+
+/// This returns the original and compressed sizes.
+fn compress_file(source: &str, target: &StrictPath) -> std::io::Result<(u64, u64)> {
+    let input = std::fs::File::open(source)?;
+    let size = input.metadata()?.len();
+    let output = std::fs::File::create(target.interpret())?;
+    zstd::stream::copy_encode(input, output, COMPRESSION_LEVEL)?;
+    Ok((size, std::fs::metadata(target.interpret())?.len()))
+}
+
+fn decompress_file(source: &StrictPath, target: &StrictPath) -> std::io::Result<()> {
+    let input = std::fs::File::open(source.interpret())?;
+    let output = std::fs::File::create(target.interpret())?;
+    zstd::stream::copy_decode(input, output)
+}
+
+/// Read the original content of a backed up file, decompressing it if needed.
+fn read_backed_up_file(path: &StrictPath, compressed: bool) -> std::io::Result<Vec<u8>> {
+    if compressed {
+        zstd::decode_all(std::fs::File::open(path.interpret())?)
+    } else {
+        path.read_bytes()
+    }
+}
+
+/// Large files are compressed if the layout allows it, unless the compressed name
+/// would clash with another file next to the original.
+fn should_compress(file: &ScannedFile, layout: &BackupLayout) -> bool {
+    layout.compress_large_files
+        && file.size >= COMPRESSION_THRESHOLD
+        && !StrictPath::new(format!("{}{}", file.path.interpret(), COMPRESSED_SUFFIX)).exists()
+        && compresses_well(&file.path)
+}
+
+/// Whether the file is a screenshot taken through Steam, either at its original location
+/// or within a backup. These are large and rarely change, so they are handled separately.
+pub fn is_steam_screenshot(path: &StrictPath) -> bool {
+    path.matches_glob("**/userdata/*/760/remote/*/screenshots/*")
+}
+
+/// Remove the contents of a game folder before backing it up again,
+/// except for the screenshots, which are kept across backups.
+fn clear_game_folder(folder: &StrictPath) -> Result<(), Box<dyn std::error::Error>> {
+    if !folder.is_dir() {
+        return folder.remove();
+    }
+    for entry in std::fs::read_dir(folder.interpret())? {
+        let entry = entry?;
+        if entry.file_name() == SCREENSHOTS_FOLDER {
+            continue;
+        }
+        StrictPath::from_std_path_buf(&entry.path()).remove()?;
+    }
+    Ok(())
+}
+
+/// Back up one game straight from the live files, which is handy for setting up tests.
+/// Real backups go through `BackupRun`.
+#[cfg(test)]
+pub fn back_up_game(info: &ScanInfo, name: &str, layout: &BackupLayout) -> BackupInfo {
+    back_up_game_from(info, name, layout, &|path| path.interpret(), &CancelToken::default())
+}
+
+/// Each file's content is read from the path given by `source`,
+/// such as the same file within a snapshot. If `cancel` is triggered before the game starts,
+/// then its previous backup is left alone and every file counts as failed, so the game
+/// can be retried. Once the game folder has been cleared, the game is always finished,
+/// so that cancelling never leaves a half-replaced backup behind.
+///
+/// Since the game folder is replaced on each backup, a file that was compressed
+/// before but is stored as-is now (or vice versa) never leaves a stale copy behind.
+/// Screenshots are the exception: they are only ever added, and an unchanged one
+/// is not copied again.
+fn back_up_game_from(
+    info: &ScanInfo,
+    name: &str,
+    layout: &BackupLayout,
+    source: &dyn Fn(&StrictPath) -> String,
+    cancel: &CancelToken,
+) -> BackupInfo {
+    let started = std::time::Instant::now();
+    let mut failed_files = std::collections::HashSet::new();
+    #[allow(unused_mut)]
+    let mut failed_registry = std::collections::HashSet::new();
+
+    let target_game = layout.game_folder_for_store(&name, info.store());
+    // Since we delete the game folder first, we don't need to worry about
+    // loading its existing mapping, other than for the screenshots we keep:
+    let mut mapping = IndividualMapping::new(name.to_string());
+    if let Some(previous) = layout.mapping.games.get(name) {
+        if previous.base.render() == target_game.render() {
+            for key in previous.screenshots.keys() {
+                if layout.game_file(&target_game, key).is_file() {
+                    mapping.keep_screenshot(key, previous);
+                }
+            }
+        }
+    }
+
+    let mut unable_to_prepare = false;
+    if cancel.is_cancelled() {
+        unable_to_prepare = true;
+    } else if info.found_anything() {
+        match clear_game_folder(&target_game) {
+            Ok(_) => {
+                if std::fs::create_dir_all(target_game.interpret()).is_err() {
+                    unable_to_prepare = true;
+                }
+            }
+            Err(_) => {
+                unable_to_prepare = true;
+            }
         }
     }
 
@@ -536,15 +2312,56 @@ pub fn back_up_game(info: &ScanInfo, name: &str, layout: &BackupLayout) -> Backu
             continue;
         }
 
-        let target_file = layout.game_file(&target_game, &file.path, &mut mapping);
+        let target_key = layout.game_file_key_for_root(&file.path, &file.root, &mut mapping);
+        for (target, link) in &info.reparse_points {
+            if file.path.render().starts_with(&format!("{}/", target)) {
+                mapping.record_reparse_point(target, link);
+            }
+        }
+        let screenshot = is_steam_screenshot(&file.path);
+        let target_key = if screenshot {
+            format!("{}/{}", SCREENSHOTS_FOLDER, target_key)
+        } else {
+            target_key
+        };
+        if screenshot && mapping.screenshots.get(&target_key).map(|x| x.size) == Some(file.size) {
+            continue;
+        }
+        let target_file = layout.game_file(&target_game, &target_key);
         if target_file.create_parent_dir().is_err() {
             failed_files.insert(file.clone());
             continue;
         }
-        if std::fs::copy(&file.path.interpret(), &target_file.interpret()).is_err() {
-            failed_files.insert(file.clone());
+        if screenshot {
+            match std::fs::copy(source(&file.path), target_file.interpret()) {
+                Ok(bytes) => mapping.record_screenshot(&target_key, bytes),
+                Err(_) => {
+                    failed_files.insert(file.clone());
+                }
+            }
             continue;
         }
+        if should_compress(file, layout) {
+            let compressed_key = format!("{}{}", target_key, COMPRESSED_SUFFIX);
+            match compress_file(&source(&file.path), &layout.game_file(&target_game, &compressed_key)) {
+                Ok((size, compressed_size)) => mapping.record_compressed_file(&target_key, size, compressed_size),
+                Err(_) => {
+                    failed_files.insert(file.clone());
+                    continue;
+                }
+            }
+        } else {
+            match std::fs::copy(source(&file.path), &target_file.interpret()) {
+                Ok(bytes) => mapping.record_file(&target_key, bytes),
+                Err(_) => {
+                    failed_files.insert(file.clone());
+                    continue;
+                }
+            }
+        }
+        if let Some(owner) = file_owner(&file.path) {
+            mapping.record_file_owner(&target_key, owner);
+        }
     }
 
     #[cfg(target_os = "windows")]
@@ -556,14 +2373,11 @@ pub fn back_up_game(info: &ScanInfo, name: &str, layout: &BackupLayout) -> Backu
             }
 
             let mut hives = crate::registry::Hives::default();
-            match hives.store_key_from_full_path(&reg_path) {
+            match hives.store_key_from_full_path(reg_path) {
                 Err(_) => {
                     failed_registry.insert(reg_path.to_string());
                 }
-                Ok(x) if !x.found => {
-                    failed_registry.insert(reg_path.to_string());
-                }
-                _ => {
+                Ok(_) => {
                     hives.save(&layout.game_registry_file(&target_game));
                 }
             }
@@ -577,273 +2391,4317 @@ pub fn back_up_game(info: &ScanInfo, name: &str, layout: &BackupLayout) -> Backu
     BackupInfo {
         failed_files,
         failed_registry,
+        found_files: info.found_files.len(),
+        found_registry_keys: info.registry_key_count(),
+        duration: Timing::since(started),
+        ..Default::default()
     }
 }
 
-pub fn restore_game(info: &ScanInfo, redirects: &[RedirectConfig]) -> BackupInfo {
-    let mut failed_files = std::collections::HashSet::new();
-    let failed_registry = std::collections::HashSet::new();
+/// Which games a backup run should consider.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameSelection {
+    /// Every known game, except the ones ignored in the config.
+    All,
+    /// Only these games, even if they're ignored in the config.
+    /// Names that aren't in the manifest or custom games are left out,
+    /// so front-ends should report them before starting.
+    Only(Vec<String>),
+}
 
-    'outer: for file in &info.found_files {
-        let original_path = match &file.original_path {
-            Some(x) => x,
-            None => continue,
-        };
-        let (target, _) = game_file_restoration_target(&original_path, &redirects);
+/// Settings for a backup run that aren't part of the config.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BackupRunOptions {
+    pub preview: bool,
+    /// Defaults to the backup path from the config.
+    pub path: Option<StrictPath>,
+    /// Don't look inside of Proton prefixes, regardless of the config.
+    pub skip_proton_prefixes: bool,
+    /// Traverse junction points, even if the config doesn't.
+    pub follow_junctions: bool,
+    /// Skip folders with a `.nobackup` file, even if the config doesn't.
+    pub respect_nobackup_files: bool,
+    /// Skip games that would take the total size over this many bytes.
+    pub max_total_size: Option<u64>,
+    /// Record what the scans see of the file system (see `RunSummary::recorded_scan`),
+    /// so that a user's scan can be reproduced elsewhere.
+    pub record_scan: bool,
+    /// The size of each game's latest backup, to warn when a game's scan shrinks.
+    /// When unset, these are read from the backup path when the run starts,
+    /// so read them first if the backup path will be cleared out beforehand.
+    pub recorded_sizes: Option<std::collections::HashMap<String, u64>>,
+    /// On Windows, read files from a Volume Shadow Copy snapshot, so that files
+    /// locked by a running game can still be backed up. This needs administrator
+    /// rights, and files are read normally if a snapshot can't be created.
+    pub use_vss: bool,
+}
 
-        if target.create_parent_dir().is_err() {
-            failed_files.insert(file.clone());
-            continue;
+/// Ask a run to stop. Games that haven't started yet are cancelled, but a game
+/// that is already being backed up is finished first, since its previous backup
+/// has already been cleared out. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+/// This is the flag of the token from `CancelToken::cancel_on_interrupt`.
+/// Its `Arc` is never released, so that the pointer stays valid for the signal handler.
+static INTERRUPT_FLAG: std::sync::atomic::AtomicPtr<std::sync::atomic::AtomicBool> =
+    std::sync::atomic::AtomicPtr::new(std::ptr::null_mut());
+
+fn interrupt_flag() -> Option<&'static std::sync::atomic::AtomicBool> {
+    let flag = INTERRUPT_FLAG.load(std::sync::atomic::Ordering::SeqCst);
+    unsafe { flag.as_ref() }
+}
+
+impl CancelToken {
+    /// Cancel this token when the user presses Ctrl-C. Only the first Ctrl-C is caught,
+    /// so pressing it again exits right away as usual. This is meant for the CLI,
+    /// and only the latest token hooked up this way is cancelled.
+    pub fn cancel_on_interrupt(&self) {
+        let flag = std::sync::Arc::into_raw(self.0.clone()) as *mut std::sync::atomic::AtomicBool;
+        INTERRUPT_FLAG.store(flag, std::sync::atomic::Ordering::SeqCst);
+        install_interrupt_handler();
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn install_interrupt_handler() {
+    extern "C" fn handle(_signal: libc::c_int) {
+        if let Some(flag) = interrupt_flag() {
+            flag.store(true, std::sync::atomic::Ordering::Relaxed);
         }
-        for i in 0..99 {
-            if std::fs::copy(&file.path.interpret(), &target.interpret()).is_ok() {
-                continue 'outer;
+        // Both of these are safe to do in a signal handler.
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+    }
+    unsafe { libc::signal(libc::SIGINT, handle as extern "C" fn(libc::c_int) as libc::sighandler_t) };
+}
+
+#[cfg(target_os = "windows")]
+fn install_interrupt_handler() {
+    unsafe extern "system" fn handle(kind: winapi::shared::minwindef::DWORD) -> winapi::shared::minwindef::BOOL {
+        match interrupt_flag() {
+            Some(flag)
+                if kind == winapi::um::wincon::CTRL_C_EVENT && !flag.load(std::sync::atomic::Ordering::Relaxed) =>
+            {
+                flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                winapi::shared::minwindef::TRUE
             }
-            // File might be busy, especially if multiple games share a file,
-            // like in a collection, so retry after a delay:
-            std::thread::sleep(std::time::Duration::from_millis(i * info.game_name.len() as u64));
+            _ => winapi::shared::minwindef::FALSE,
         }
-        failed_files.insert(file.clone());
     }
+    unsafe { winapi::um::consoleapi::SetConsoleCtrlHandler(Some(handle), winapi::shared::minwindef::TRUE) };
+}
 
-    #[cfg(target_os = "windows")]
+#[derive(Clone, Debug, Default)]
+pub struct GameOutcome {
+    pub name: String,
+    pub scan_info: ScanInfo,
+    pub backup_info: BackupInfo,
+    pub decision: OperationStepDecision,
+}
+
+impl GameOutcome {
+    pub fn successful(&self) -> bool {
+        self.backup_info.successful()
+    }
+}
+
+pub enum BackupEvent {
+    /// Sent once before any games are processed.
+    Started { total_games: usize },
+    /// Sent as each game finishes, in whatever order that happens.
+    GameFinished,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    /// These are sorted by name.
+    pub games: Vec<GameOutcome>,
+    /// How long it took to scan all of the games.
+    pub scan_duration: Timing,
+    /// How long it took to back up all of the games after scanning.
+    pub copy_duration: Timing,
+    /// What the scans saw of the file system, if `BackupRunOptions::record_scan` was set.
+    pub recorded_scan: Option<ScanFixture>,
+}
+
+impl RunSummary {
+    /// Games that should be tried again, because they failed or were cancelled.
+    pub fn games_to_retry(&self) -> Vec<String> {
+        self.games
+            .iter()
+            .filter(|x| !x.successful() || x.decision == OperationStepDecision::Cancelled)
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
+    /// Remember which games to retry, for `load_games_to_retry`.
+    pub fn save_games_to_retry(&self, file: &StrictPath) -> std::io::Result<()> {
+        file.create_parent_dir()?;
+        std::fs::write(file.interpret(), serde_yaml::to_string(&self.games_to_retry()).unwrap())
+    }
+
+    pub fn load_games_to_retry(file: &StrictPath) -> Vec<String> {
+        std::fs::read_to_string(file.interpret())
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Skip the games that would take the total size over the limit, if there is one.
+/// The games are considered in the given order, so that a run with the same data
+/// always skips the same games.
+fn apply_backup_budget(outcomes: &mut [GameOutcome], max: Option<u64>) {
+    let max = match max {
+        Some(x) => x,
+        None => return,
+    };
+    let mut used = 0;
+    for outcome in outcomes
+        .iter_mut()
+        .filter(|x| x.decision == OperationStepDecision::Processed)
     {
-        if let Some(registry_file) = &info.registry_file {
-            if let Some(hives) = crate::registry::Hives::load(&registry_file) {
-                // TODO: Track failed keys.
-                let _ = hives.restore();
-            }
+        let bytes = outcome.scan_info.sum_bytes(&None);
+        if used + bytes > max {
+            outcome.decision = OperationStepDecision::Skipped;
+        } else {
+            used += bytes;
         }
     }
+}
 
-    BackupInfo {
-        failed_files,
-        failed_registry,
-    }
+/// Everything needed to back up each game in a run, so that front-ends which
+/// process one game at a time make the same decisions as `run_backup`.
+pub struct BackupRun {
+    games: std::collections::HashMap<String, Game>,
+    subjects: Vec<String>,
+    explicit: bool,
+    ignored: std::collections::HashSet<String>,
+    roots: Vec<RootsConfig>,
+    layout: BackupLayout,
+    filter: BackupFilter,
+    scan_options: ScanOptions,
+    install_dirs: InstallDirCache,
+    options: BackupRunOptions,
+    recorded_sizes: std::collections::HashMap<String, u64>,
+    shrink_warning_percent: u8,
+    recorder: Option<ScanRecorder>,
+    /// Each volume's snapshot is taken the first time that a game needs it,
+    /// and it is released when the run is dropped. `None` means that it failed.
+    #[cfg(target_os = "windows")]
+    snapshots: std::sync::Mutex<std::collections::HashMap<String, Option<std::sync::Arc<crate::vss::Snapshot>>>>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::Config;
-    use crate::manifest::Manifest;
-    use maplit::hashset;
-    use pretty_assertions::assert_eq;
+impl BackupRun {
+    pub fn new(config: &Config, manifest: &Manifest, selection: &GameSelection, options: &BackupRunOptions) -> Self {
+        let games = manifest.with_custom_games(config);
 
-    fn s(text: &str) -> String {
-        text.to_string()
+        let (mut subjects, explicit): (Vec<_>, _) = match selection {
+            GameSelection::All => (games.keys().cloned().collect(), false),
+            GameSelection::Only(names) => (names.iter().filter(|x| games.contains_key(*x)).cloned().collect(), true),
+        };
+        subjects.retain(|x| !x.trim().is_empty());
+        subjects.sort();
+        subjects.dedup();
+
+        let layout = BackupLayout {
+            group_by_store: config.backup.group_by_store,
+            compress_large_files: config.backup.compress_large_files,
+            ..BackupLayout::new(options.path.clone().unwrap_or_else(|| config.backup.target()))
+        };
+
+        Self {
+            ignored: config.backup.ignored_games.clone(),
+            roots: config.scan_roots(),
+            recorded_sizes: options
+                .recorded_sizes
+                .clone()
+                .unwrap_or_else(|| layout.recorded_sizes()),
+            shrink_warning_percent: config.backup.shrink_warning_percent,
+            layout,
+            filter: config.backup.filter.clone(),
+            scan_options: ScanOptions {
+                follow_junctions: options.follow_junctions || config.backup.follow_junctions,
+                respect_nobackup_files: options.respect_nobackup_files || config.backup.respect_nobackup_files,
+                allow_unsafe_paths: config.allow_unsafe_paths,
+                skip_proton_prefixes: options.skip_proton_prefixes || !config.backup.scan_proton_prefixes,
+                skip_uninstalled_prefixes: !config.backup.include_uninstalled_prefixes,
+                ..Default::default()
+            },
+            install_dirs: InstallDirCache::default(),
+            recorder: if options.record_scan {
+                Some(ScanRecorder::default())
+            } else {
+                None
+            },
+            games,
+            subjects,
+            explicit,
+            options: options.clone(),
+            #[cfg(target_os = "windows")]
+            snapshots: Default::default(),
+        }
     }
 
-    fn repo() -> String {
-        reslashed(env!("CARGO_MANIFEST_DIR"))
+    /// The games to process, sorted by name.
+    pub fn subjects(&self) -> &[String] {
+        &self.subjects
     }
 
-    fn config() -> Config {
-        Config::load_from_string(&format!(
-            r#"
-            manifest:
-              url: example.com
-              etag: null
-            roots:
-              - path: {0}/tests/root1
-                store: other
-              - path: {0}/tests/root2
-                store: other
-            backup:
-              path: ~/backup
-            restore:
-              path: ~/restore
-            "#,
-            repo()
-        ))
-        .unwrap()
+    pub fn roots(&self) -> &[RootsConfig] {
+        &self.roots
     }
 
-    fn manifest() -> Manifest {
-        Manifest::load_from_string(
-            r#"
-            game1:
-              files:
-                <base>/file1.txt: {}
-                <base>/subdir: {}
-            game 2:
-              files:
-                <root>/<game>: {}
-              installDir:
-                game2: {}
-            game3:
-              registry:
-                HKEY_CURRENT_USER/Software/Ludusavi/game3: {}
-                HKEY_CURRENT_USER/Software/Ludusavi/fake: {}
-            game3-outer:
-              registry:
-                HKEY_CURRENT_USER/Software/Ludusavi: {}
-            "#,
-        )
-        .unwrap()
+    /// Scan one game and, unless this is a preview, back it up.
+    /// The size limit needs every game's size up front, so it only applies in `run_backup`.
+    pub fn run_game(&self, name: &str, cancel: &CancelToken) -> GameOutcome {
+        self.back_up(self.scan(name, cancel), cancel)
     }
 
-    #[test]
-    fn can_scan_game_for_backup_with_file_matches() {
-        assert_eq!(
-            ScanInfo {
-                game_name: s("game1"),
-                found_files: hashset! {
-                    ScannedFile {
-                        path: StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
-                        size: 2,
-                        original_path: None,
-                    },
-                    ScannedFile {
-                        path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
-                        size: 1,
-                        original_path: None,
-                    },
-                },
-                found_registry_keys: hashset! {},
-                registry_file: None,
+    fn cancelled(name: &str) -> GameOutcome {
+        GameOutcome {
+            name: name.to_string(),
+            scan_info: ScanInfo {
+                game_name: name.to_string(),
+                ..Default::default()
             },
-            scan_game_for_backup(
-                &manifest().0["game1"],
-                "game1",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-            ),
-        );
+            decision: OperationStepDecision::Cancelled,
+            ..Default::default()
+        }
+    }
 
-        assert_eq!(
+    fn scan(&self, name: &str, cancel: &CancelToken) -> GameOutcome {
+        if cancel.is_cancelled() {
+            return Self::cancelled(name);
+        }
+
+        let game = &self.games[name];
+        let steam_id = game.steam.as_ref().and_then(|x| x.id);
+        let roots: Vec<_> = self
+            .roots
+            .iter()
+            .filter(|root| game.is_compatible_with_store(root.store))
+            .cloned()
+            .collect();
+        let fs: &dyn ScanFileSystem = match &self.recorder {
+            Some(recorder) => recorder,
+            None => &RealFileSystem,
+        };
+        let mut scan_info = scan_game_for_backup_with_fs(
+            game,
+            name,
+            &roots,
+            &StrictPath::from_std_path_buf(&app_dir()),
+            &steam_id,
+            &self.filter,
+            &self.scan_options,
+            fs,
+            &self.install_dirs,
+        );
+        if let Some(&previous) = self.recorded_sizes.get(name) {
+            // Screenshots aren't part of the recorded size, so they don't count here either.
+            let current = scan_info.sum_bytes(&None) - scan_info.screenshot_bytes();
+            if has_shrunk_significantly(previous, current, self.shrink_warning_percent) {
+                scan_info.shrunk_from_bytes = Some(previous);
+            }
+        }
+
+        let decision = if !self.explicit && self.ignored.contains(name) {
+            OperationStepDecision::Ignored
+        } else {
+            OperationStepDecision::Processed
+        };
+
+        GameOutcome {
+            name: name.to_string(),
+            scan_info,
+            decision,
+            ..Default::default()
+        }
+    }
+
+    fn back_up(&self, outcome: GameOutcome, cancel: &CancelToken) -> GameOutcome {
+        if self.options.preview || outcome.decision != OperationStepDecision::Processed {
+            return outcome;
+        }
+        if cancel.is_cancelled() {
+            return Self::cancelled(&outcome.name);
+        }
+        let backup_info = if self.options.use_vss {
+            self.back_up_game_with_vss(&outcome.scan_info, &outcome.name, cancel)
+        } else {
+            back_up_game_from(
+                &outcome.scan_info,
+                &outcome.name,
+                &self.layout,
+                &|path| path.interpret(),
+                cancel,
+            )
+        };
+        GameOutcome { backup_info, ..outcome }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn back_up_game_with_vss(&self, scan_info: &ScanInfo, name: &str, cancel: &CancelToken) -> BackupInfo {
+        let mut snapshots = vec![];
+        {
+            let mut cache = self.snapshots.lock().unwrap();
+            for file in &scan_info.found_files {
+                if let Some(volume) = crate::vss::volume_of(&file.path.interpret()) {
+                    let snapshot = cache
+                        .entry(volume.clone())
+                        .or_insert_with(|| crate::vss::Snapshot::create(&volume).ok().map(std::sync::Arc::new));
+                    if let Some(snapshot) = snapshot {
+                        if !snapshots.iter().any(|x| std::sync::Arc::ptr_eq(x, snapshot)) {
+                            snapshots.push(snapshot.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        back_up_game_from(
+            scan_info,
+            name,
+            &self.layout,
+            &|path| {
+                let live = path.interpret();
+                snapshots.iter().find_map(|x| x.path_for(&live)).unwrap_or(live)
+            },
+            cancel,
+        )
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn back_up_game_with_vss(&self, scan_info: &ScanInfo, name: &str, cancel: &CancelToken) -> BackupInfo {
+        back_up_game_from(scan_info, name, &self.layout, &|path| path.interpret(), cancel)
+    }
+}
+
+/// Whether a game's data has dropped below `percent` of its previous size.
+/// A `percent` of zero never counts.
+pub fn has_shrunk_significantly(previous_bytes: u64, current_bytes: u64, percent: u8) -> bool {
+    percent > 0 && (current_bytes as u128) * 100 < (previous_bytes as u128) * (percent as u128)
+}
+
+/// Back up the selected games in parallel, reporting each one to `events` as it finishes.
+/// Preparing the backup target (and asking for any confirmation) is up to the caller.
+pub fn run_backup(
+    config: &Config,
+    manifest: &Manifest,
+    selection: &GameSelection,
+    options: &BackupRunOptions,
+    events: &(dyn Fn(&BackupEvent) + Sync),
+    cancel: &CancelToken,
+) -> RunSummary {
+    let run = BackupRun::new(config, manifest, selection, options);
+    events(&BackupEvent::Started {
+        total_games: run.subjects().len(),
+    });
+
+    // Every game is scanned before any are backed up, so that the size limit
+    // can be applied in name order rather than in whatever order the scans finish.
+    let scan_started = std::time::Instant::now();
+    let mut scanned: Vec<_> = run.subjects().par_iter().map(|name| run.scan(name, cancel)).collect();
+    let scan_duration = Timing::since(scan_started);
+    apply_backup_budget(&mut scanned, options.max_total_size);
+    let copy_started = std::time::Instant::now();
+    let games: Vec<_> = scanned
+        .into_par_iter()
+        .map(|outcome| {
+            let outcome = run.back_up(outcome, cancel);
+            events(&BackupEvent::GameFinished);
+            outcome
+        })
+        .collect();
+    let copy_duration = Timing::since(copy_started);
+
+    RunSummary {
+        games,
+        scan_duration,
+        copy_duration,
+        recorded_scan: run.recorder.map(ScanRecorder::into_fixture),
+    }
+}
+
+/// Scan every game from the manifest and the custom games in parallel,
+/// without backing anything up. Ignored games are scanned as well.
+/// The results are keyed by game name.
+pub fn scan_all_games(manifest: &Manifest, config: &Config) -> std::collections::HashMap<String, ScanInfo> {
+    run_backup(
+        config,
+        manifest,
+        &GameSelection::All,
+        &BackupRunOptions {
+            preview: true,
+            ..Default::default()
+        },
+        &|_| {},
+        &CancelToken::default(),
+    )
+    .games
+    .into_iter()
+    .map(|game| (game.name, game.scan_info))
+    .collect()
+}
+
+/// If either file cannot be read, then we can't tell, so this returns false.
+/// The backed up file is decompressed first if it was compressed.
+fn file_content_differs(live: &StrictPath, backed_up: &StrictPath, compressed: bool) -> bool {
+    match (live.read_bytes(), read_backed_up_file(backed_up, compressed)) {
+        (Ok(x), Ok(y)) => x != y,
+        _ => false,
+    }
+}
+
+/// Compare a live scan (from `scan_game_for_backup`) against the latest backup
+/// (from `scan_game_for_restoration`) of the same game.
+pub fn diff_game(live: &ScanInfo, backup: &ScanInfo, layout: &BackupLayout) -> GameDiff {
+    let mut diff = GameDiff::default();
+    let game_folder = layout.game_folder(&live.game_name);
+
+    let mut backed_up: std::collections::HashMap<String, &ScannedFile> = backup
+        .found_files
+        .iter()
+        .filter_map(|file| {
+            layout
+                .backed_up_original_file_key(&live.game_name, &game_folder, &file.path)
+                .map(|key| (key, file))
+        })
+        .collect();
+
+    for file in &live.found_files {
+        if is_steam_screenshot(&file.path) {
+            // Screenshots are kept even once they're gone, and they rarely change,
+            // so only new ones make a difference.
+            let kept = layout
+                .existing_game_file_key(&live.game_name, &file.path, &file.root)
+                .map(|key| format!("{}/{}", SCREENSHOTS_FOLDER, key))
+                .and_then(|key| layout.mapping.games.get(&live.game_name)?.screenshots.get(&key))
+                .is_some();
+            if !kept {
+                diff.added_files.insert(file.path.clone());
+            }
+            continue;
+        }
+        let stored = layout
+            .existing_game_file_key(&live.game_name, &file.path, &file.root)
+            .and_then(|key| backed_up.remove(&key));
+        match stored {
+            None => {
+                diff.added_files.insert(file.path.clone());
+            }
+            Some(stored) if stored.size != file.size => {
+                diff.resized_files.insert(file.path.clone());
+            }
+            Some(stored)
+                if file_content_differs(&file.path, &stored.path, backup.compressed_files.contains(&stored.path)) =>
+            {
+                diff.modified_files.insert(file.path.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    for file in backed_up.values() {
+        if is_steam_screenshot(&file.path) {
+            continue;
+        }
+        diff.removed_files
+            .insert(file.original_path.clone().unwrap_or_else(|| file.path.clone()));
+    }
+
+    for key in &live.found_registry_keys {
+        if !backup.found_registry_keys.contains(key) {
+            diff.added_registry_keys.insert(key.to_string());
+        }
+    }
+    for key in &backup.found_registry_keys {
+        // Subkeys are backed up along with their parent, so they only count
+        // as removed if neither they nor any parent were found live.
+        let still_present = live
+            .found_registry_keys
+            .iter()
+            .any(|live_key| key == live_key || key.starts_with(&format!("{}/", live_key)));
+        if !still_present {
+            diff.removed_registry_keys.insert(key.to_string());
+        }
+    }
+
+    diff
+}
+
+/// How many bytes an incremental backup would copy: the live files that `diff_game`
+/// found to be new or changed since the latest backup.
+pub fn incremental_delta_bytes(live: &ScanInfo, diff: &GameDiff) -> u64 {
+    live.found_files
+        .iter()
+        .filter(|file| {
+            diff.added_files.contains(&file.path)
+                || diff.resized_files.contains(&file.path)
+                || diff.modified_files.contains(&file.path)
+        })
+        .map(|file| file.size)
+        .sum()
+}
+
+/// Find an unused name for an existing file by appending the suffix,
+/// plus an incrementing number if that name is also taken.
+fn find_backup_existing_name(target: &StrictPath, suffix: &str) -> Option<String> {
+    let base = format!("{}{}", target.as_std_path_buf().file_name()?.to_string_lossy(), suffix);
+    let mut candidate = base.clone();
+    let mut i = 1;
+    while target.with_file_name(&candidate).exists() {
+        candidate = format!("{}.{}", base, i);
+        i += 1;
+    }
+    Some(candidate)
+}
+
+/// Find targets (after redirects) that would be restored by more than one game.
+pub fn find_restore_conflicts(infos: &[ScanInfo], redirects: &[RedirectConfig]) -> Vec<RestoreConflict> {
+    let mut claims = std::collections::BTreeMap::<String, (StrictPath, Vec<RestoreConflictSource>)>::new();
+    let cache = InterpretCache::default();
+
+    for info in infos {
+        for file in &info.found_files {
+            let original_path = match &file.original_path {
+                Some(x) => x,
+                None => continue,
+            };
+            let (target, _) = game_file_restoration_target_cached(original_path, redirects, &cache);
+            let (_, sources) = claims
+                .entry(target.render())
+                .or_insert_with(|| (target.clone(), vec![]));
+            if sources.iter().any(|x| x.game_name == info.game_name) {
+                continue;
+            }
+            sources.push(RestoreConflictSource {
+                game_name: info.game_name.clone(),
+                file: file.clone(),
+                modified: std::fs::metadata(file.path.interpret()).and_then(|x| x.modified()).ok(),
+            });
+        }
+    }
+
+    claims
+        .into_iter()
+        .filter(|(_, (_, sources))| sources.len() > 1)
+        .map(|(_, (target, mut sources))| {
+            sources.sort_by(|x, y| y.modified.cmp(&x.modified).then_with(|| x.game_name.cmp(&y.game_name)));
+            RestoreConflict { target, sources }
+        })
+        .collect()
+}
+
+/// Keep only the chosen source of each conflict, so that the other games skip that target.
+/// Each choice is an index into the corresponding conflict's sources.
+pub fn resolve_restore_conflicts(infos: &mut [ScanInfo], conflicts: &[RestoreConflict], choices: &[usize]) {
+    for (conflict, choice) in conflicts.iter().zip(choices) {
+        for (i, source) in conflict.sources.iter().enumerate() {
+            if i == *choice {
+                continue;
+            }
+            for info in infos.iter_mut() {
+                if info.game_name == source.game_name {
+                    info.found_files.remove(&source.file);
+                }
+            }
+        }
+    }
+}
+
+/// Where a restore would put each file, grouped by folder so that a UI can show it as a tree.
+/// Files are keyed by their name and folders by their path component, so the top level
+/// holds the drive or `/`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RestorePreviewTree {
+    pub folders: std::collections::BTreeMap<String, RestorePreviewTree>,
+    pub files: std::collections::BTreeSet<String>,
+}
+
+impl RestorePreviewTree {
+    fn insert(&mut self, target: &str) {
+        let mut components: Vec<_> = target.split('/').filter(|x| !x.is_empty()).collect();
+        if target.starts_with('/') {
+            components.insert(0, "/");
+        }
+        let file = match components.pop() {
+            Some(x) => x,
+            None => return,
+        };
+        let mut node = self;
+        for component in components {
+            node = node.folders.entry(component.to_string()).or_default();
+        }
+        node.files.insert(file.to_string());
+    }
+}
+
+/// Build a tree of the targets (after redirects) that restoring this game would write.
+pub fn restore_preview_tree(info: &ScanInfo, redirects: &[RedirectConfig]) -> RestorePreviewTree {
+    let cache = InterpretCache::default();
+    let mut tree = RestorePreviewTree::default();
+    for file in &info.found_files {
+        if let Some(original_path) = &file.original_path {
+            let (target, _) = game_file_restoration_target_cached(original_path, redirects, &cache);
+            tree.insert(&target.render_cached(&cache));
+        }
+    }
+    tree
+}
+
+/// Safety checks that a restore can be told to skip.
+/// Files that fail a check are not restored, and they are reported as failed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestoreOptions {
+    /// Restore files that failed the backup integrity check.
+    pub ignore_integrity: bool,
+    /// Restore files into system folders.
+    pub allow_unsafe_paths: bool,
+    /// Restore files into Ludusavi's own folder, which could replace its config.
+    pub allow_config_overwrite: bool,
+}
+
+pub fn restore_game(
+    info: &ScanInfo,
+    redirects: &[RedirectConfig],
+    mode: &RestoreMode,
+    options: &RestoreOptions,
+    retry: &RetryPolicy,
+) -> BackupInfo {
+    let started = std::time::Instant::now();
+    let mut failed_files = std::collections::HashSet::new();
+    let failed_registry = std::collections::HashSet::new();
+    let mut restored_with_stored_names = std::collections::HashSet::new();
+    #[allow(unused_mut)]
+    let mut registry_type_conflicts = std::collections::HashSet::new();
+    #[allow(unused_mut)]
+    let mut unmapped_registry_keys = std::collections::HashSet::new();
+    let mut overwritten_files = std::collections::HashSet::new();
+    let mut skipped_files = std::collections::HashSet::new();
+    let mut restored_files = std::collections::HashMap::new();
+    let cache = InterpretCache::default();
+
+    for file in &info.found_files {
+        let target = match check_restoration_target(info, file, redirects, options, &cache) {
+            RestorationTarget::Unneeded => continue,
+            RestorationTarget::Invalid => {
+                failed_files.insert(file.clone());
+                continue;
+            }
+            RestorationTarget::Valid(x) => x,
+        };
+
+        if target.create_parent_dir().is_err() {
+            failed_files.insert(file.clone());
+            continue;
+        }
+        if let RestoreMode::BackupExisting { suffix, .. } = mode {
+            if target.is_file() {
+                if keeps_existing_file(&target, mode) {
+                    skipped_files.insert(file.clone());
+                    continue;
+                }
+                let renamed = match find_backup_existing_name(&target, suffix) {
+                    Some(aside) => target.rename(&aside).is_ok(),
+                    None => false,
+                };
+                if !renamed {
+                    failed_files.insert(file.clone());
+                    continue;
+                }
+            }
+        }
+        // If the file was stored under an escaped name, then the original name
+        // may not be allowed on this file system, so we only try it once
+        // before falling back to the escaped name from the backup.
+        let compressed = info.compressed_files.contains(&file.path);
+        let fallback = stored_name_fallback(&file.path, &target, compressed);
+        if fallback.is_some() {
+            let existed = target.is_file();
+            if restore_file(&file.path, &target, compressed) {
+                if existed {
+                    overwritten_files.insert(file.clone());
+                }
+                restored_files.insert(file.clone(), target);
+                continue;
+            }
+        }
+        let destination = fallback.as_ref().unwrap_or(&target);
+        let existed = destination.is_file();
+
+        // File might be busy, especially if multiple games share a file,
+        // like in a collection, so retry after a delay:
+        let (copied, _) = retry.run(info.game_name.len() as u64, || {
+            restore_file(&file.path, destination, compressed)
+        });
+        if !copied {
+            failed_files.insert(file.clone());
+            continue;
+        }
+        if fallback.is_some() {
+            restored_with_stored_names.insert(file.clone());
+        }
+        if existed {
+            overwritten_files.insert(file.clone());
+        }
+        restored_files.insert(file.clone(), destination.clone());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(registry_file) = &info.registry_file {
+            if let Some(hives) = crate::registry::Hives::load(&registry_file) {
+                // TODO: Track failed keys.
+                if let Ok(warnings) = hives.restore() {
+                    registry_type_conflicts = warnings.type_conflicts;
+                    unmapped_registry_keys = warnings.unmapped_keys;
+                }
+            }
+        }
+    }
+
+    BackupInfo {
+        failed_files,
+        failed_registry,
+        found_files: info.found_files.len(),
+        found_registry_keys: info.registry_key_count(),
+        restored_with_stored_names,
+        registry_type_conflicts,
+        unmapped_registry_keys,
+        overwritten_files,
+        skipped_files,
+        restored_files,
+        unrestored_owners: Default::default(),
+        duration: Timing::since(started),
+    }
+}
+
+enum RestorationTarget {
+    /// The file has no original path, so there is nothing to restore.
+    Unneeded,
+    /// The file must not be restored, so it counts as a failure.
+    Invalid,
+    Valid(StrictPath),
+}
+
+fn check_restoration_target(
+    info: &ScanInfo,
+    file: &ScannedFile,
+    redirects: &[RedirectConfig],
+    options: &RestoreOptions,
+    cache: &InterpretCache,
+) -> RestorationTarget {
+    let original_path = match &file.original_path {
+        Some(x) => x,
+        None => return RestorationTarget::Unneeded,
+    };
+    if !options.ignore_integrity {
+        if let Some(integrity) = &info.integrity {
+            if integrity.corrupted_files.contains(file) {
+                return RestorationTarget::Invalid;
+            }
+        }
+    }
+    let (target, _) = game_file_restoration_target_cached(&original_path, &redirects, cache);
+    if !options.allow_unsafe_paths && is_in_system_dir(&target) {
+        return RestorationTarget::Invalid;
+    }
+    if !options.allow_config_overwrite && is_in_app_dir(&target) {
+        return RestorationTarget::Invalid;
+    }
+    RestorationTarget::Valid(target)
+}
+
+/// Predict the outcome of `restore_game` without writing anything,
+/// so that a preview can show what would fail and what would be overwritten.
+pub fn preview_restore_game(
+    info: &ScanInfo,
+    redirects: &[RedirectConfig],
+    mode: &RestoreMode,
+    options: &RestoreOptions,
+) -> BackupInfo {
+    let started = std::time::Instant::now();
+    let mut failed_files = std::collections::HashSet::new();
+    let mut overwritten_files = std::collections::HashSet::new();
+    let mut skipped_files = std::collections::HashSet::new();
+    let cache = InterpretCache::default();
+
+    for file in &info.found_files {
+        let target = match check_restoration_target(info, file, redirects, options, &cache) {
+            RestorationTarget::Unneeded => continue,
+            RestorationTarget::Invalid => {
+                failed_files.insert(file.clone());
+                continue;
+            }
+            RestorationTarget::Valid(x) => x,
+        };
+        if keeps_existing_file(&target, mode) {
+            skipped_files.insert(file.clone());
+        } else if target.is_file() && *mode == RestoreMode::Overwrite {
+            // When backing up existing files, they are renamed rather than replaced.
+            overwritten_files.insert(file.clone());
+        }
+    }
+
+    BackupInfo {
+        failed_files,
+        found_files: info.found_files.len(),
+        found_registry_keys: info.registry_key_count(),
+        overwritten_files,
+        skipped_files,
+        duration: Timing::since(started),
+        ..Default::default()
+    }
+}
+
+/// Whether the existing file at the target is recent enough that the mode leaves it alone.
+fn keeps_existing_file(target: &StrictPath, mode: &RestoreMode) -> bool {
+    match mode {
+        RestoreMode::BackupExisting {
+            min_age_days: Some(days),
+            ..
+        } => target.is_file() && modified_within_days(target, *days),
+        _ => false,
+    }
+}
+
+/// A modification time in the future also counts as recent.
+fn modified_within_days(path: &StrictPath, days: u64) -> bool {
+    let modified = match std::fs::metadata(path.interpret()).and_then(|m| m.modified()) {
+        Ok(x) => x,
+        Err(_) => return false,
+    };
+    match modified.elapsed() {
+        Ok(age) => age < std::time::Duration::from_secs(days * 24 * 60 * 60),
+        Err(_) => true,
+    }
+}
+
+fn restore_file(source: &StrictPath, target: &StrictPath, compressed: bool) -> bool {
+    if compressed {
+        decompress_file(source, target).is_ok()
+    } else {
+        std::fs::copy(source.interpret(), target.interpret()).is_ok()
+    }
+}
+
+/// If the backed up file was stored under a different name than the target's,
+/// then this is the target with the stored name instead.
+/// For a compressed file, the stored name is considered without `COMPRESSED_SUFFIX`.
+fn stored_name_fallback(stored: &StrictPath, target: &StrictPath, compressed: bool) -> Option<StrictPath> {
+    let stored_name = stored.as_std_path_buf().file_name()?.to_string_lossy().to_string();
+    let stored_name = if compressed {
+        stored_name.trim_end_matches(COMPRESSED_SUFFIX).to_string()
+    } else {
+        stored_name
+    };
+    let target_name = target.as_std_path_buf().file_name()?.to_string_lossy().to_string();
+    if stored_name == target_name {
+        None
+    } else {
+        Some(target.with_file_name(&stored_name))
+    }
+}
+
+/// Reapply the owners recorded during backup to the files that `restore_game` wrote,
+/// so that files it left alone keep their current owners.
+/// A file is skipped (and returned) if its owner can't be changed, such as when
+/// not running as root, so that the restore itself still counts as successful.
+pub fn restore_file_owners(
+    info: &ScanInfo,
+    backup_info: &BackupInfo,
+    change_owner: &dyn Fn(&StrictPath, FileOwner) -> std::io::Result<()>,
+) -> std::collections::HashSet<StrictPath> {
+    let mut unrestored = std::collections::HashSet::new();
+
+    for (file, destination) in &backup_info.restored_files {
+        let owner = match info.file_owners.get(&file.path) {
+            Some(x) => *x,
+            None => continue,
+        };
+        if change_owner(destination, owner).is_err() {
+            unrestored.insert(destination.clone());
+        }
+    }
+
+    unrestored
+}
+
+/// Changes a restored file's owner, like `change_file_owner`.
+pub type ChangeOwner<'a> = dyn Fn(&StrictPath, FileOwner) -> std::io::Result<()> + Sync + 'a;
+
+/// Restore several games at once. Each game restores to its own locations,
+/// so they can safely run in parallel. When `change_owner` is set, the owners
+/// are reapplied afterward with `restore_file_owners`. `on_game_finished` is called
+/// as each game finishes, such as to show progress. The results are keyed by game name.
+pub fn restore_games(
+    infos: &[ScanInfo],
+    redirects: &[RedirectConfig],
+    mode: &RestoreMode,
+    options: &RestoreOptions,
+    retry: &RetryPolicy,
+    change_owner: Option<&ChangeOwner<'_>>,
+    on_game_finished: &(dyn Fn() + Sync),
+) -> std::collections::HashMap<String, BackupInfo> {
+    infos
+        .par_iter()
+        .map(|info| {
+            let mut backup_info = restore_game(info, redirects, mode, options, retry);
+            if let Some(change_owner) = change_owner {
+                backup_info.unrestored_owners = restore_file_owners(info, &backup_info, change_owner);
+            }
+            on_game_finished();
+            (info.game_name.clone(), backup_info)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, CustomGame};
+    use crate::layout::namespaced_path;
+    use crate::manifest::Manifest;
+    use crate::testing::TempDir;
+    use maplit::{btreemap, hashmap, hashset};
+    use pretty_assertions::assert_eq;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn repo() -> String {
+        reslashed(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// Timings vary from run to run, so they're cleared before comparing scans.
+    fn untimed(mut info: ScanInfo) -> ScanInfo {
+        info.duration = Default::default();
+        info
+    }
+
+    fn config() -> Config {
+        Config::load_from_string(&format!(
+            r#"
+            manifest:
+              url: example.com
+              etag: null
+            roots:
+              - path: {0}/tests/root1
+                store: other
+              - path: {0}/tests/root2
+                store: other
+            backup:
+              path: ~/backup
+            restore:
+              path: ~/restore
+            "#,
+            repo()
+        ))
+        .unwrap()
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <base>/file1.txt: {}
+                <base>/subdir: {}
+            game 2:
+              files:
+                <root>/<game>: {}
+              installDir:
+                game2: {}
+            game3:
+              registry:
+                HKEY_CURRENT_USER/Software/Ludusavi/game3: {}
+                HKEY_CURRENT_USER/Software/Ludusavi/fake: {}
+            game3-outer:
+              registry:
+                HKEY_CURRENT_USER/Software/Ludusavi: {}
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn record_backup_run(
+        config: &Config,
+        selection: &GameSelection,
+        cancel: &CancelToken,
+    ) -> (RunSummary, Vec<String>) {
+        let events = std::sync::Mutex::new(vec![]);
+        let summary = run_backup(
+            config,
+            &manifest(),
+            selection,
+            &BackupRunOptions {
+                preview: true,
+                ..Default::default()
+            },
+            &|event| {
+                events.lock().unwrap().push(match event {
+                    BackupEvent::Started { total_games } => format!("started {}", total_games),
+                    BackupEvent::GameFinished => s("finished"),
+                })
+            },
+            cancel,
+        );
+        let mut events = events.into_inner().unwrap();
+        events.sort();
+        (summary, events)
+    }
+
+    #[test]
+    fn can_follow_junctions_in_backup_run_from_config_or_options() {
+        let mut config = config();
+        let follows = |config: &Config, follow_junctions| {
+            BackupRun::new(
+                config,
+                &manifest(),
+                &GameSelection::All,
+                &BackupRunOptions {
+                    follow_junctions,
+                    ..Default::default()
+                },
+            )
+            .scan_options
+            .follow_junctions
+        };
+
+        assert!(!follows(&config, false));
+        assert!(follows(&config, true));
+        config.backup.follow_junctions = true;
+        assert!(follows(&config, false));
+    }
+
+    #[test]
+    fn can_respect_nobackup_files_in_backup_run_from_config_or_options() {
+        let mut config = config();
+        let respects = |config: &Config, respect_nobackup_files| {
+            BackupRun::new(
+                config,
+                &manifest(),
+                &GameSelection::All,
+                &BackupRunOptions {
+                    respect_nobackup_files,
+                    ..Default::default()
+                },
+            )
+            .scan_options
+            .respect_nobackup_files
+        };
+
+        assert!(!respects(&config, false));
+        assert!(respects(&config, true));
+        config.backup.respect_nobackup_files = true;
+        assert!(respects(&config, false));
+    }
+
+    fn decisions(summary: &RunSummary) -> Vec<(String, OperationStepDecision)> {
+        summary
+            .games
+            .iter()
+            .map(|x| (x.name.clone(), x.decision.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn can_run_backup_headlessly() {
+        let mut config = config();
+        config.disable_game_for_backup("game1");
+        let (summary, events) = record_backup_run(&config, &GameSelection::All, &CancelToken::default());
+
+        assert_eq!(
+            vec![
+                s("finished"),
+                s("finished"),
+                s("finished"),
+                s("finished"),
+                s("started 4"),
+            ],
+            events,
+        );
+        assert_eq!(
+            vec![
+                (s("game 2"), OperationStepDecision::Processed),
+                (s("game1"), OperationStepDecision::Ignored),
+                (s("game3"), OperationStepDecision::Processed),
+                (s("game3-outer"), OperationStepDecision::Processed),
+            ],
+            decisions(&summary),
+        );
+        assert_eq!(1, summary.games[0].scan_info.found_files.len());
+        assert!(summary.games.iter().all(|x| x.successful()));
+        assert!(summary.games_to_retry().is_empty());
+    }
+
+    #[test]
+    fn can_run_backup_for_selected_games_even_if_ignored() {
+        let mut config = config();
+        config.disable_game_for_backup("game1");
+        let (summary, _) = record_backup_run(
+            &config,
+            &GameSelection::Only(vec![s("game1"), s("unknown")]),
+            &CancelToken::default(),
+        );
+
+        assert_eq!(
+            vec![(s("game1"), OperationStepDecision::Processed)],
+            decisions(&summary)
+        );
+    }
+
+    #[test]
+    fn can_cancel_backup_run_and_remember_games_to_retry() {
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let (summary, events) =
+            record_backup_run(&config(), &GameSelection::Only(vec![s("game1"), s("game 2")]), &cancel);
+
+        let temp = TempDir::new("retry");
+        let file = temp.joined("retry.yaml");
+        summary.save_games_to_retry(&file).unwrap();
+        let loaded = RunSummary::load_games_to_retry(&file);
+        let _ = std::fs::remove_file(file.interpret());
+
+        assert_eq!(vec![s("finished"), s("finished"), s("started 2")], events);
+        assert_eq!(
+            vec![
+                (s("game 2"), OperationStepDecision::Cancelled),
+                (s("game1"), OperationStepDecision::Cancelled),
+            ],
+            decisions(&summary),
+        );
+        assert!(summary.games[0].scan_info.found_files.is_empty());
+        assert_eq!(vec![s("game 2"), s("game1")], loaded);
+        assert_eq!(Vec::<String>::new(), RunSummary::load_games_to_retry(&file));
+    }
+
+    #[test]
+    fn can_scan_all_games() {
+        let mut config = config();
+        config.disable_game_for_backup("game1");
+        let scans = scan_all_games(&manifest(), &config);
+
+        assert_eq!(
+            vec!["game 2", "game1", "game3", "game3-outer"],
+            itertools::sorted(scans.keys()).collect::<Vec<_>>(),
+        );
+        assert_eq!(s("game 2"), scans["game 2"].game_name);
+        assert_eq!(1, scans["game 2"].found_files.len());
+        assert!(scans["game1"].found_anything());
+    }
+
+    #[test]
+    fn keeps_previous_backup_of_game_when_cancelled() {
+        let temp = TempDir::new("cancel-backup");
+        let backup = temp.joined("backup");
+        let saves = vec![temp.joined("saves/a.txt"), temp.joined("saves/b.txt")];
+        let write_saves = |content: &str| {
+            for save in &saves {
+                save.create_parent_dir().unwrap();
+                std::fs::write(save.interpret(), content).unwrap();
+            }
+        };
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: saves.iter().map(|x| ScannedFile::new(x.clone()).with_size(3)).collect(),
+            ..Default::default()
+        };
+        let backed_up = || {
+            let mut contents: Vec<_> = scan_game_for_restoration("game1", &BackupLayout::new(backup.clone()))
+                .found_files
+                .iter()
+                .map(|x| std::fs::read_to_string(x.path.interpret()).unwrap())
+                .collect();
+            contents.sort();
+            contents
+        };
+
+        write_saves("old");
+        assert!(back_up_game(&info, "game1", &BackupLayout::new(backup.clone())).successful());
+        write_saves("new");
+
+        // Cancelled before the game starts, so the previous backup is untouched.
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let backup_info = back_up_game_from(
+            &info,
+            "game1",
+            &BackupLayout::new(backup.clone()),
+            &|path| path.interpret(),
+            &cancel,
+        );
+        assert_eq!(2, backup_info.failed_files.len());
+        assert_eq!(vec![s("old"), s("old")], backed_up());
+
+        // Cancelled while reading the first file, like a Ctrl-C in the middle of a copy.
+        // The previous backup is already gone by then, so the game is finished.
+        cancel.reset();
+        let backup_info = back_up_game_from(
+            &info,
+            "game1",
+            &BackupLayout::new(backup.clone()),
+            &|path| {
+                cancel.cancel();
+                path.interpret()
+            },
+            &cancel,
+        );
+        assert!(backup_info.successful());
+        assert_eq!(vec![s("new"), s("new")], backed_up());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_cancel_on_interrupt() {
+        let cancel = CancelToken::default();
+        cancel.cancel_on_interrupt();
+        unsafe { libc::raise(libc::SIGINT) };
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn can_check_if_size_has_shrunk_significantly() {
+        assert!(has_shrunk_significantly(100, 24, 25));
+        assert!(!has_shrunk_significantly(100, 25, 25));
+        assert!(!has_shrunk_significantly(100, 200, 25));
+        assert!(!has_shrunk_significantly(0, 0, 25));
+        assert!(!has_shrunk_significantly(100, 0, 0));
+        assert!(has_shrunk_significantly(u64::MAX, 0, 100));
+    }
+
+    #[test]
+    fn can_flag_games_whose_scan_shrunk_since_the_latest_backup() {
+        let summary = run_backup(
+            &config(),
+            &manifest(),
+            &GameSelection::Only(vec![s("game1"), s("game 2")]),
+            &BackupRunOptions {
+                preview: true,
+                recorded_sizes: Some(hashmap! {
+                    s("game1") => 1,
+                    s("game 2") => 1_000,
+                }),
+                ..Default::default()
+            },
+            &|_| {},
+            &CancelToken::default(),
+        );
+
+        assert_eq!(
+            vec![(s("game 2"), Some(1_000)), (s("game1"), None)],
+            summary
+                .games
+                .iter()
+                .map(|x| (x.name.clone(), x.scan_info.shrunk_from_bytes))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn can_record_scan_of_backup_run_and_replay_it() {
+        let config = config();
+        let summary = run_backup(
+            &config,
+            &manifest(),
+            &GameSelection::Only(vec![s("game 2")]),
+            &BackupRunOptions {
+                preview: true,
+                record_scan: true,
+                ..Default::default()
+            },
+            &|_| {},
+            &CancelToken::default(),
+        );
+        let fixture = summary.recorded_scan.unwrap();
+        let replayed = scan_game_for_backup_with_fs(
+            &manifest().with_custom_games(&config)["game 2"],
+            "game 2",
+            &config.scan_roots(),
+            &StrictPath::from_std_path_buf(&app_dir()),
+            &None,
+            &config.backup.filter,
+            &ScanOptions::default(),
+            &fixture,
+            &InstallDirCache::default(),
+        );
+
+        assert_eq!(1, summary.games[0].scan_info.found_files.len());
+        assert_eq!(summary.games[0].scan_info.found_files, replayed.found_files);
+    }
+
+    fn budget_outcome(name: &str, bytes: u64, decision: OperationStepDecision) -> GameOutcome {
+        GameOutcome {
+            name: s(name),
+            scan_info: ScanInfo {
+                game_name: s(name),
+                found_files: hashset! {
+                    ScannedFile::new(StrictPath::new(format!("/{}.sav", name))).with_size(bytes),
+                },
+                ..Default::default()
+            },
+            decision,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_apply_backup_budget_in_order_until_the_limit() {
+        let mut outcomes = vec![
+            budget_outcome("a", 60, OperationStepDecision::Processed),
+            budget_outcome("b", 50, OperationStepDecision::Processed),
+            budget_outcome("c", 70, OperationStepDecision::Ignored),
+            budget_outcome("d", 40, OperationStepDecision::Processed),
+            budget_outcome("e", 1, OperationStepDecision::Processed),
+        ];
+        apply_backup_budget(&mut outcomes, Some(100));
+        assert_eq!(
+            vec![
+                OperationStepDecision::Processed,
+                OperationStepDecision::Skipped,
+                OperationStepDecision::Ignored,
+                OperationStepDecision::Processed,
+                OperationStepDecision::Skipped,
+            ],
+            outcomes.iter().map(|x| x.decision.clone()).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn can_apply_backup_budget_without_a_limit() {
+        let mut outcomes = vec![budget_outcome("a", u64::MAX, OperationStepDecision::Processed)];
+        apply_backup_budget(&mut outcomes, None);
+        assert_eq!(OperationStepDecision::Processed, outcomes[0].decision);
+    }
+
+    #[test]
+    fn can_display_path_under_home() {
+        let home = StrictPath::from_std_path_buf(&dirs::home_dir().unwrap()).render();
+        assert_eq!(
+            s("<home>/.config/game/save.dat"),
+            display_path(&StrictPath::new(format!("{}/.config/game/save.dat", home)), &[]),
+        );
+    }
+
+    #[test]
+    fn can_display_path_under_root() {
+        assert_eq!(
+            s("<root1:Other>/game1/subdir/file2.txt"),
+            display_path(
+                &StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+                &config().roots
+            ),
+        );
+        assert_eq!(
+            s("<root2:Other>/game1/file1.txt"),
+            display_path(
+                &StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                &config().roots
+            ),
+        );
+    }
+
+    #[test]
+    fn can_display_path_preferring_root_inside_home() {
+        let home = StrictPath::from_std_path_buf(&dirs::home_dir().unwrap()).render();
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(format!("{}/ludusavi-fake-root", home)),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+        assert_eq!(
+            s("<root1:Steam>/steamapps/common/Game/save.dat"),
+            display_path(
+                &StrictPath::new(format!("{}/ludusavi-fake-root/steamapps/common/Game/save.dat", home)),
+                &roots
+            ),
+        );
+        assert_eq!(
+            s("<home>/other/save.dat"),
+            display_path(&StrictPath::new(format!("{}/other/save.dat", home)), &roots),
+        );
+    }
+
+    #[test]
+    fn can_display_path_in_proton_prefix() {
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(s("/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+        assert_eq!(
+            s("<proton:123>/drive_c/save.dat"),
+            display_path(
+                &StrictPath::new(s("/steam/steamapps/compatdata/123/pfx/drive_c/save.dat")),
+                &roots
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_display_path_with_control_characters() {
+        assert_eq!(
+            s("/saves/line\\nbreak\\t\\u{1b}.sav"),
+            display_path(&StrictPath::new(s("/saves/line\nbreak\t\u{1b}.sav")), &[]),
+        );
+    }
+
+    #[test]
+    fn can_parse_paths_with_rockstar_local_data() {
+        let root = RootsConfig {
+            path: StrictPath::new(format!("{}/tests/rockstar", repo())),
+            store: Store::Rockstar,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let title = s("Grand Theft Auto V");
+
+        assert_eq!(
+            hashset! {
+                StrictPath::relative(
+                    if WINDOWS {
+                        format!(
+                            "{}/Grand Theft Auto V/Profiles",
+                            dirs::data_local_dir().unwrap().join("Rockstar Games").to_string_lossy()
+                        )
+                    } else {
+                        format!("{}/Grand Theft Auto V/Profiles", SKIP)
+                    },
+                    Some(repo()),
+                ),
+            },
+            parse_paths(
+                "<rockstarLocalData>/<game>/Profiles",
+                &root,
+                &[&title],
+                &None,
+                &StrictPath::new(repo()),
+                true,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_parse_paths_without_proton_prefixes() {
+        let root = RootsConfig {
+            path: StrictPath::new(s("/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let game = s("game1");
+
+        assert_eq!(
+            hashset! {
+                StrictPath::relative(
+                    format!("{}/game1/save.dat", check_path(dirs::document_dir())),
+                    Some(repo()),
+                ),
+            },
+            parse_paths(
+                "<documents>/<game>/save.dat",
+                &root,
+                &[&game],
+                &Some(123),
+                &StrictPath::new(repo()),
+                false,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_detect_uninstalled_proton_prefix() {
+        let temp = TempDir::new("uninstalled-prefix");
+        let root = temp.path().clone();
+        for dir in &["steamapps/compatdata/123/pfx", "steamapps/compatdata/456/pfx"] {
+            std::fs::create_dir_all(root.joined(dir).interpret()).unwrap();
+        }
+        std::fs::write(root.joined("steamapps/appmanifest_123.acf").interpret(), "").unwrap();
+
+        let installed = is_uninstalled_proton_prefix(&RealFileSystem, &root, 123);
+        let uninstalled = is_uninstalled_proton_prefix(&RealFileSystem, &root, 456);
+        let missing = is_uninstalled_proton_prefix(&RealFileSystem, &root, 789);
+
+        assert!(!installed);
+        assert!(uninstalled);
+        assert!(!missing);
+    }
+
+    #[test]
+    fn can_choose_app_dir() {
+        assert_eq!(
+            std::path::PathBuf::from("/custom"),
+            choose_app_dir(Some("/custom".into()), Some("/home/user".into())),
+        );
+        assert_eq!(
+            std::path::PathBuf::from("/home/user/.config/ludusavi"),
+            choose_app_dir(Some("".into()), Some("/home/user".into())),
+        );
+    }
+
+    #[test]
+    fn can_choose_app_dir_without_home() {
+        assert_eq!(
+            std::env::current_dir().unwrap().join(".config").join("ludusavi"),
+            choose_app_dir(None, None),
+        );
+        assert_eq!(
+            std::path::PathBuf::from("/custom"),
+            choose_app_dir(Some("/custom".into()), None),
+        );
+    }
+
+    #[test]
+    fn can_parse_all_placeholders_when_host_dirs_are_unknown() {
+        let needs_host_dirs = [
+            "<home>",
+            "<winAppData>",
+            "<winLocalAppData>",
+            "<rockstarLocalData>",
+            "<winDocuments>",
+            "<documents>",
+            "<winPublic>",
+            "<xdgData>",
+            "<xdgConfig>",
+            "<regHkcu>",
+            "<regHklm>",
+        ];
+        let install_dir = s("game1");
+
+        for store in [Store::Steam, Store::Rockstar, Store::Other] {
+            let root = RootsConfig {
+                path: StrictPath::new(s("/root")),
+                store,
+                portable: false,
+                scan_proton_prefixes: None,
+            };
+            for placeholder in &PLACEHOLDERS {
+                let paths = parse_paths_with_dirs(
+                    &format!("{}/save.dat", placeholder),
+                    &root,
+                    &[&install_dir],
+                    &Some(101),
+                    &StrictPath::new(repo()),
+                    false,
+                    &HostDirs::default(),
+                );
+                assert!(!paths.is_empty());
+                for path in paths {
+                    assert!(!path.raw().contains(placeholder), "{}", path.raw());
+                    if needs_host_dirs.contains(placeholder) {
+                        assert!(path.raw().contains(SKIP), "{}", path.raw());
+                    }
+                }
+
+                // Proton prefixes have their own folders, so these just need to not panic.
+                parse_paths_with_dirs(
+                    &format!("{}/save.dat", placeholder),
+                    &root,
+                    &[&install_dir],
+                    &Some(101),
+                    &StrictPath::new(repo()),
+                    true,
+                    &HostDirs::default(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn can_classify_placeholders_that_never_apply_to_files() {
+        assert_eq!(
+            btreemap! {
+                "<game>" => false,
+                "<regHkcu>" => true,
+            },
+            placeholder_applicability("<regHkcu>/<game>/save.dat"),
+        );
+        assert!(placeholder_applicability("C:/Games/save.dat").is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn can_classify_windows_placeholders_as_skipped_on_linux() {
+        assert_eq!(
+            btreemap! {
+                "<base>" => false,
+                "<winAppData>" => true,
+                "<winDir>" => true,
+                "<winDocuments>" => true,
+                "<winLocalAppData>" => true,
+                "<winProgramData>" => true,
+                "<winPublic>" => true,
+            },
+            placeholder_applicability(
+                "<winAppData>|<winLocalAppData>|<winDocuments>|<winPublic>|<winProgramData>|<winDir>|<base>"
+            ),
+        );
+    }
+
+    #[test]
+    fn skips_home_placeholder_when_home_is_unknown() {
+        let vars = PlaceholderVars {
+            home: SKIP.to_string(),
+            ..Default::default()
+        };
+        let root = RootsConfig {
+            path: StrictPath::new(s("/other")),
+            store: Store::Other,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        assert!(expand_placeholders("<home>/save.dat", &root, &vars).contains(SKIP));
+    }
+
+    #[test]
+    fn can_expand_placeholders_with_fake_values() {
+        let vars = PlaceholderVars {
+            game: s("game1"),
+            home: s("/fake/home"),
+            store_user_id: s("*"),
+            os_user_name: s("user"),
+            win_app_data: s("/fake/appdata"),
+            win_documents: s("/fake/documents"),
+            ..Default::default()
+        };
+        let root = |path: &str, store| RootsConfig {
+            path: StrictPath::new(s(path)),
+            store,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let steam = root("/steam", Store::Steam);
+        let other = root("/other", Store::Other);
+
+        assert_eq!(
+            s("/fake/home/user/game1/*.sav"),
+            expand_placeholders("<home>/<osUserName>/<game>/<storeUserId>.sav", &other, &vars),
+        );
+        assert_eq!(
+            s("/fake/appdata/a /fake/documents/b"),
+            expand_placeholders("<winAppData>/a <winDocuments>/b", &other, &vars),
+        );
+        assert_eq!(
+            format!("{}/game1/save.dat", StrictPath::new(s("/other")).interpret()),
+            expand_placeholders("<base>/save.dat", &other, &vars),
+        );
+        assert_eq!(
+            format!(
+                "{}/steamapps/common/game1/save.dat",
+                StrictPath::new(s("/steam")).interpret()
+            ),
+            expand_placeholders("<base>/save.dat", &steam, &vars),
+        );
+        assert_eq!(
+            format!("{}/key", SKIP),
+            expand_placeholders("<regHkcu>/key", &steam, &vars)
+        );
+    }
+
+    #[test]
+    fn can_parse_paths_with_documents() {
+        let root = RootsConfig {
+            path: StrictPath::new(s("/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let game = s("game1");
+        let mut expected = hashset! {
+            StrictPath::relative(
+                format!("{}/game1/save.dat", check_path(dirs::document_dir())),
+                Some(repo()),
+            ),
+        };
+        if get_os() == Os::Linux {
+            expected.insert(StrictPath::relative(
+                s("/steam/steamapps/compatdata/123/pfx/drive_c/users/steamuser/My Documents/game1/save.dat"),
+                Some(repo()),
+            ));
+        }
+
+        assert_eq!(
+            expected,
+            parse_paths(
+                "<documents>/<game>/save.dat",
+                &root,
+                &[&game],
+                &Some(123),
+                &StrictPath::new(repo()),
+                true,
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn can_parse_paths_with_flatpak_fallback() {
+        let root = RootsConfig {
+            path: StrictPath::new(s("/games")),
+            store: Store::Other,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let game = s("game1");
+        let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+
+        assert_eq!(
+            hashset! {
+                StrictPath::relative(
+                    format!("{}/game1/save.dat", dirs::data_dir().unwrap().to_string_lossy()),
+                    Some(repo()),
+                ),
+                StrictPath::relative(format!("{}/.var/app/*/data/game1/save.dat", home), Some(repo())),
+            },
+            parse_paths(
+                "<xdgData>/<game>/save.dat",
+                &root,
+                &[&game],
+                &None,
+                &StrictPath::new(repo()),
+                true,
+            ),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_rockstar_title() {
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(format!("{}/tests/rockstar", repo())),
+            store: Store::Rockstar,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!(
+                        "{}/tests/rockstar/Grand Theft Auto V/Profiles/ABCDEF01/SGTA50000",
+                        repo()
+                    )),
+                    size: 1,
+                    original_path: None,
+                    root: Some(roots[0].clone()),
+                },
+            },
+            scan_game_for_backup_from_yaml(
+                r#"
+                files:
+                  <base>/Profiles: {}
+                rockstarTitle: Grand Theft Auto V
+                "#,
+                "GTA V",
+                &roots,
+                &StrictPath::new(repo()),
+                &None,
+            )
+            .unwrap()
+            .found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_overlapping_paths() {
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+                    size: 2,
+                    original_path: None,
+                    root: Some(config().roots[0].clone()),
+                },
+            },
+            scan_game_for_backup_from_yaml(
+                r#"
+                files:
+                  <base>/subdir: {}
+                  <base>/subdir/file2.txt: {}
+                  <base>/subdir/*.txt: {}
+                  '<base>\subdir\file2.txt': {}
+                "#,
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+            )
+            .unwrap()
+            .found_files,
+        );
+    }
+
+    fn hostile_game() -> Game {
+        Game::from(CustomGame {
+            name: s("game1"),
+            files: vec![
+                s("<base>/../../../../../../../../../../../../../../etc/passwd"),
+                s("<base>/../game1/subdir/file2.txt"),
+            ],
+            registry: vec![],
+        })
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_while_skipping_unsafe_paths() {
+        let info = scan_game_for_backup(
+            &hostile_game(),
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &ScanOptions::default(),
+            &InstallDirCache::default(),
+        );
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+                    size: 2,
+                    original_path: None,
+                    root: Some(config().roots[0].clone()),
+                },
+            },
+            info.found_files,
+        );
+        assert_eq!(
+            hashset! { StrictPath::new(s("/etc/passwd")).render() },
+            info.unsafe_paths.iter().map(|x| x.render()).collect(),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_unsafe_paths_allowed() {
+        let info = scan_game_for_backup(
+            &hostile_game(),
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &ScanOptions {
+                allow_unsafe_paths: true,
+                ..Default::default()
+            },
+            &InstallDirCache::default(),
+        );
+        assert!(info.unsafe_paths.is_empty());
+    }
+
+    #[test]
+    fn cannot_scan_game_for_backup_from_invalid_yaml() {
+        assert!(matches!(
+            scan_game_for_backup_from_yaml("files: [", "game1", &[], &StrictPath::new(repo()), &None),
+            Err(Error::ManifestInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_file_matches() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile {
+                        path: StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+                        size: 2,
+                        original_path: None,
+                        root: Some(config().roots[0].clone()),
+                    },
+                    ScannedFile {
+                        path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                        size: 1,
+                        original_path: None,
+                        root: Some(config().roots[1].clone()),
+                    },
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+                integrity: None,
+                classification: Some(GameClassification {
+                    files: true,
+                    ..Default::default()
+                }),
+                reparse_points: Default::default(),
+                unsafe_paths: Default::default(),
+                compressed_files: Default::default(),
+                file_owners: Default::default(),
+                uninstalled_prefix: false,
+                shrunk_from_bytes: None,
+                duration: Default::default(),
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            )),
+        );
+
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game 2"),
+                found_files: hashset! {
+                    ScannedFile {
+                        path: StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())),
+                        size: 1,
+                        original_path: None,
+                        root: Some(config().roots[1].clone()),
+                    },
+                },
+                found_registry_keys: hashset! {},
+                registry_file: None,
+                integrity: None,
+                classification: Some(GameClassification {
+                    files: true,
+                    ..Default::default()
+                }),
+                reparse_points: Default::default(),
+                unsafe_paths: Default::default(),
+                compressed_files: Default::default(),
+                file_owners: Default::default(),
+                uninstalled_prefix: false,
+                shrunk_from_bytes: None,
+                duration: Default::default(),
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game 2"],
+                "game 2",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            )),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_and_attribute_files_to_first_root() {
+        let roots = vec![
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root1", repo())),
+                store: Store::Rockstar,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root1", repo())),
+                store: Store::Other,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+        ];
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())),
+                    size: 2,
+                    original_path: None,
+                    root: Some(roots[0].clone()),
+                },
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            ))
+            .found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_excluded_stores() {
+        let roots = vec![
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root1", repo())),
+                store: Store::Rockstar,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root2", repo())),
+                store: Store::Other,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+        ];
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                    size: 1,
+                    original_path: None,
+                    root: Some(roots[1].clone()),
+                },
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game1"],
+                "game1",
+                &roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter {
+                    exclude_stores: vec![Store::Rockstar],
+                    ..Default::default()
+                },
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            ))
+            .found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_roots_filter() {
+        let roots = vec![
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root1", repo())),
+                store: Store::Rockstar,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+            RootsConfig {
+                path: StrictPath::new(format!("{}/tests/root2", repo())),
+                store: Store::Other,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+        ];
+        let game = Game {
+            compatible_stores: Some(vec![Store::Steam]),
+            ..manifest().0["game1"].clone()
+        };
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())),
+                    size: 1,
+                    original_path: None,
+                    root: Some(roots[1].clone()),
+                },
+            },
+            untimed(scan_game_for_backup_with_roots_filter(
+                &game,
+                "game1",
+                &roots,
+                |root| game.is_compatible_with_store(root.store),
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            ))
+            .found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_install_dir_in_different_case() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game5:
+              files:
+                <base>/data.sav: {}
+              installDir:
+                "game5 ": {}
+            "#,
+        )
+        .unwrap();
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(format!("{}/tests/root3", repo())),
+            store: Store::Other,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root3/Game5/data.sav", repo())),
+                    size: 1,
+                    original_path: None,
+                    root: Some(roots[0].clone()),
+                },
+            },
+            untimed(scan_game_for_backup(
+                &manifest.0["game5"],
+                "game5",
+                &roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            ))
+            .found_files,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_while_respecting_nobackup_files() {
+        let temp = TempDir::new("nobackup");
+        let base = temp.path().clone();
+        for file in &["keep/a.txt", "skip/.nobackup", "skip/b.txt", "skip/deeper/c.txt"] {
+            temp.write(file, "");
+        }
+        let game: Game = serde_yaml::from_str(&format!("files: {{'{}': {{}}}}", base.render())).unwrap();
+        let scan = |respect_nobackup_files| {
+            let mut found: Vec<_> = scan_game_for_backup(
+                &game,
+                "game",
+                &[],
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions {
+                    respect_nobackup_files,
+                    ..Default::default()
+                },
+                &InstallDirCache::default(),
+            )
+            .found_files
+            .iter()
+            .map(|x| x.path.render()[base.render().len()..].to_string())
+            .collect();
+            found.sort();
+            found
+        };
+        let respected = scan(true);
+        let ignored = scan(false);
+
+        assert_eq!(vec![s("/keep/a.txt")], respected);
+        assert_eq!(
+            vec![
+                s("/keep/a.txt"),
+                s("/skip/.nobackup"),
+                s("/skip/b.txt"),
+                s("/skip/deeper/c.txt")
+            ],
+            ignored
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn can_scan_game_for_backup_while_following_junctions() {
+        let temp = TempDir::new("junctions");
+        let base = temp.path().clone();
+        temp.write("saves/a.txt", "");
+        temp.write("elsewhere/b.txt", "");
+        let link = |name: &str, target: &str| {
+            let native = |x: &str| temp.joined(x).interpret().replace('/', "\\");
+            std::process::Command::new("cmd")
+                .args(&["/C", "mklink", "/J", &native(name), &native(target)])
+                .output()
+                .unwrap();
+        };
+        link("saves/linked", "elsewhere");
+        // This loops back to the save folder itself.
+        link("elsewhere/loop", "saves");
+        // Directory symlinks need extra privileges, so this may not exist.
+        let _ = std::os::windows::fs::symlink_dir(
+            temp.joined("elsewhere").interpret(),
+            temp.joined("saves/symlink").interpret(),
+        );
+
+        assert!(is_junction(&temp.joined("saves/linked").as_std_path_buf()));
+        assert!(!is_junction(&temp.joined("saves/symlink").as_std_path_buf()));
+        assert!(!is_junction(&temp.joined("elsewhere").as_std_path_buf()));
+
+        let game: Game = serde_yaml::from_str(&format!("files: {{'{}/saves': {{}}}}", base.render())).unwrap();
+        let found: std::collections::HashSet<_> = scan_game_for_backup(
+            &game,
+            "game",
+            &[],
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &ScanOptions {
+                follow_junctions: true,
+                ..Default::default()
+            },
+            &InstallDirCache::default(),
+        )
+        .found_files
+        .iter()
+        .map(|x| x.path.render()[base.render().len()..].to_string())
+        .collect();
+
+        assert!(found.contains("/saves/a.txt"));
+        assert!(found.contains("/saves/linked/b.txt"));
+    }
+
+    fn file_in_root(path: &str, root: &str) -> ScannedFile {
+        ScannedFile {
+            root: Some(RootsConfig {
+                path: StrictPath::new(root.to_string()),
+                store: Store::Steam,
+                ..Default::default()
+            }),
+            ..ScannedFile::new(StrictPath::new(path.to_string()))
+        }
+    }
+
+    fn find_rendered_ambiguous_locations(files: Vec<ScannedFile>) -> Vec<Vec<String>> {
+        find_ambiguous_locations(&files.into_iter().collect())
+            .iter()
+            .map(|group| group.iter().map(|x| x.render()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn can_find_ambiguous_locations() {
+        let rendered = |path: &str| StrictPath::new(path.to_string()).render();
+
+        // The same file relative to each root:
+        assert_eq!(
+            vec![vec![rendered("/library1"), rendered("/library2")]],
+            find_rendered_ambiguous_locations(vec![
+                file_in_root("/library1/Game/save.dat", "/library1"),
+                file_in_root("/library1/Game/other.dat", "/library1"),
+                file_in_root("/library2/Game/save.dat", "/library2"),
+            ]),
+        );
+
+        // The same file through overlapping roots:
+        assert_eq!(
+            vec![vec![rendered("/games"), rendered("/games/steam")]],
+            find_rendered_ambiguous_locations(vec![
+                file_in_root("/games/steam/Game/save.dat", "/games"),
+                file_in_root("/games/steam/Game/save.dat", "/games/steam"),
+            ]),
+        );
+    }
+
+    #[test]
+    fn cannot_find_ambiguous_locations_without_shared_files() {
+        // Different files in each root:
+        assert!(find_rendered_ambiguous_locations(vec![
+            file_in_root("/library1/Game/a.dat", "/library1"),
+            file_in_root("/library2/Game/b.dat", "/library2"),
+        ])
+        .is_empty());
+        // Same file name in different folders of one root, such as after an update moved the saves:
+        assert!(find_rendered_ambiguous_locations(vec![
+            file_in_root("/home/Documents/Game/save.dat", "/home"),
+            file_in_root("/home/AppData/Roaming/Game/save.dat", "/home"),
+        ])
+        .is_empty());
+        // Files that don't depend on a root:
+        assert!(find_rendered_ambiguous_locations(vec![
+            ScannedFile::new(StrictPath::new(s("/home/old/Game/save.dat"))),
+            ScannedFile::new(StrictPath::new(s("/home/new/Game/save.dat"))),
+        ])
+        .is_empty());
+    }
+
+    #[test]
+    fn can_exclude_locations_from_scan() {
+        let mut scan_info = ScanInfo {
+            found_files: hashset! {
+                ScannedFile::new(StrictPath::new(s("/home/old/save.dat"))),
+                ScannedFile::new(StrictPath::new(s("/home/new/save.dat"))),
+            },
+            ..Default::default()
+        };
+        scan_info.exclude_locations(&[StrictPath::new(s("/home/old"))]);
+
+        assert_eq!(
+            hashset! { ScannedFile::new(StrictPath::new(s("/home/new/save.dat"))) },
+            scan_info.found_files,
+        );
+    }
+
+    #[test]
+    fn can_replay_recorded_scan_for_backup() {
+        let temp = TempDir::new("replay");
+        let base = temp.path().clone();
+        for file in &["saves/a.sav", "saves/deeper/b.sav", "config.ini"] {
+            temp.write(file, "data");
+        }
+        let game: Game = serde_yaml::from_str(&format!(
+            "files: {{'{0}/saves': {{}}, '{0}/*.ini': {{}}, '{0}/missing': {{}}}}",
+            base.render()
+        ))
+        .unwrap();
+        let scan = |fs: &dyn ScanFileSystem| {
+            untimed(scan_game_for_backup_with_fs(
+                &game,
+                "game",
+                &[],
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                fs,
+                &InstallDirCache::default(),
+            ))
+        };
+
+        let recorder = ScanRecorder::default();
+        let recorded = scan(&recorder);
+        let fixture = serde_yaml::to_string(&recorder.into_fixture()).unwrap();
+        let fixture: ScanFixture = serde_yaml::from_str(&fixture).unwrap();
+        let replayed = scan(&fixture);
+
+        assert_eq!(3, recorded.found_files.len());
+        assert_eq!(recorded, replayed);
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_within_scope() {
+        let temp = TempDir::new("scope");
+        let base = temp.path().clone();
+        for file in &[
+            "inside/a.txt",
+            "inside/deeper/b.txt",
+            "inside-not/c.txt",
+            "outside/d.txt",
+        ] {
+            temp.write(file, "");
+        }
+        let game: Game = serde_yaml::from_str(&format!("files: {{'{}': {{}}}}", base.render())).unwrap();
+        let mut found: Vec<_> = scan_game_for_backup(
+            &game,
+            "game",
+            &[],
+            &StrictPath::new(repo()),
+            &None,
+            &BackupFilter::default(),
+            &ScanOptions {
+                scope: Some(base.joined("inside")),
+                ..Default::default()
+            },
+            &InstallDirCache::default(),
+        )
+        .found_files
+        .iter()
+        .map(|x| x.path.render()[base.render().len()..].to_string())
+        .collect();
+        found.sort();
+
+        assert_eq!(vec![s("/inside/a.txt"), s("/inside/deeper/b.txt")], found);
+    }
+
+    #[test]
+    fn can_scan_game_for_backup_with_allowed_extensions() {
+        let temp = TempDir::new("allowed-extensions");
+        let base = temp.path().clone();
+        for file in &["a.sav", "b.SAV", "c.txt", "sav", "deeper/d.sav", "deeper/e.dat"] {
+            temp.write(file, "");
+        }
+        let game: Game = serde_yaml::from_str(&format!("files: {{'{}': {{}}}}", base.render())).unwrap();
+        let scan = |filter: &BackupFilter| {
+            let mut found: Vec<_> = scan_game_for_backup(
+                &game,
+                "game",
+                &[],
+                &StrictPath::new(repo()),
+                &None,
+                filter,
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            )
+            .found_files
+            .iter()
+            .map(|x| x.path.render()[base.render().len()..].to_string())
+            .collect();
+            found.sort();
+            found
+        };
+        let global = scan(&BackupFilter {
+            allowed_extensions: Some(hashset! { s("sav") }),
+            ..Default::default()
+        });
+        let per_game = scan(&BackupFilter {
+            allowed_extensions: Some(hashset! { s("sav") }),
+            game_allowed_extensions: hashmap! { s("game") => hashset! { s(".dat"), s("txt") } },
+            ..Default::default()
+        });
+
+        if CASE_INSENSITIVE_OS {
+            assert_eq!(vec![s("/a.sav"), s("/b.SAV"), s("/deeper/d.sav")], global);
+        } else {
+            assert_eq!(vec![s("/a.sav"), s("/deeper/d.sav")], global);
+        }
+        assert_eq!(vec![s("/c.txt"), s("/deeper/e.dat")], per_game);
+    }
+
+    #[test]
+    fn can_exclude_files_within_config_dir_nested_under_glob() {
+        let root = RootsConfig {
+            path: StrictPath::new(format!("{}/tests/root2", repo())),
+            store: Store::Other,
+            portable: false,
+            scan_proton_prefixes: None,
+        };
+        let mut found_files = scan_game_for_backup_from_yaml(
+            "files: {'<root>/*': {}}",
+            "game",
+            std::slice::from_ref(&root),
+            &StrictPath::new(repo()),
+            &None,
+        )
+        .unwrap()
+        .found_files;
+
+        exclude_files_within(
+            &mut found_files,
+            &StrictPath::new(format!("{}/tests/root2/game1", repo())),
+        );
+        assert_eq!(
+            hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())),
+                    size: 1,
+                    original_path: None,
+                    root: Some(root),
+                },
+            },
+            found_files,
+        );
+    }
+
+    #[test]
+    fn can_add_game_to_operation_status_with_root_breakdown() {
+        let roots = vec![
+            RootsConfig {
+                path: StrictPath::new(s("/root1")),
+                store: Store::Steam,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+            RootsConfig {
+                path: StrictPath::new(s("/root2")),
+                store: Store::Other,
+                portable: false,
+                scan_proton_prefixes: None,
+            },
+        ];
+        let mut status = OperationStatus::default();
+        status.track_roots(&roots);
+        status.add_game(
+            &ScanInfo {
+                game_name: s("game1"),
+                found_files: hashset! {
+                    ScannedFile {
+                        path: StrictPath::new(s("/root1/file1")),
+                        size: 1,
+                        original_path: None,
+                        root: Some(roots[0].clone()),
+                    },
+                    ScannedFile {
+                        path: StrictPath::new(s("/root1/file2")),
+                        size: 2,
+                        original_path: None,
+                        root: Some(roots[0].clone()),
+                    },
+                    ScannedFile::new(StrictPath::new(s("/home/file3"))).with_size(4),
+                },
+                classification: Some(GameClassification::default()),
+                ..Default::default()
+            },
+            &None,
+            true,
+        );
+
+        assert_eq!(
+            vec![
+                RootStatus {
+                    path: Some(StrictPath::new(s("/root1")).render()),
+                    store: Some(Store::Steam),
+                    games: 1,
+                    bytes: 3,
+                },
+                RootStatus {
+                    path: Some(StrictPath::new(s("/root2")).render()),
+                    store: Some(Store::Other),
+                    games: 0,
+                    bytes: 0,
+                },
+                RootStatus {
+                    path: None,
+                    store: None,
+                    games: 1,
+                    bytes: 4,
+                },
+            ],
+            status.roots,
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_registry_matches_on_leaf_key_with_values() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game3"),
+                found_files: hashset! {},
+                found_registry_keys: hashset! {
+                    s("HKEY_CURRENT_USER/Software/Ludusavi/game3")
+                },
+                registry_file: None,
+                integrity: None,
+                classification: Some(GameClassification {
+                    registry: true,
+                    ..Default::default()
+                }),
+                reparse_points: Default::default(),
+                unsafe_paths: Default::default(),
+                compressed_files: Default::default(),
+                file_owners: Default::default(),
+                uninstalled_prefix: false,
+                shrunk_from_bytes: None,
+                duration: Default::default(),
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game3"],
+                "game3",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            )),
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_scan_game_for_backup_with_registry_matches_on_parent_key_without_values() {
+        assert_eq!(
+            ScanInfo {
+                game_name: s("game3-outer"),
+                found_files: hashset! {},
+                found_registry_keys: hashset! {
+                    s("HKEY_CURRENT_USER/Software/Ludusavi")
+                },
+                registry_file: None,
+                integrity: None,
+                classification: Some(GameClassification {
+                    registry: true,
+                    ..Default::default()
+                }),
+                reparse_points: Default::default(),
+                unsafe_paths: Default::default(),
+                compressed_files: Default::default(),
+                file_owners: Default::default(),
+                uninstalled_prefix: false,
+                shrunk_from_bytes: None,
+                duration: Default::default(),
+            },
+            untimed(scan_game_for_backup(
+                &manifest().0["game3-outer"],
+                "game3-outer",
+                &config().roots,
+                &StrictPath::new(repo()),
+                &None,
+                &BackupFilter::default(),
+                &ScanOptions::default(),
+                &InstallDirCache::default(),
+            )),
+        );
+    }
+
+    #[test]
+    fn can_classify_game() {
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(s("~/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+        let manifest = Manifest::load_from_string(
+            r#"
+            install-dir-only:
+              installDir:
+                foo: {}
+              steam:
+                id: 101
+            registry-only:
+              registry:
+                HKEY_CURRENT_USER/Software/Ludusavi/foo: {}
+              steam:
+                id: 102
+            other-os-only:
+              files:
+                <base>/foo.txt:
+                  when:
+                    - os: fake
+              steam:
+                id: 103
+            "#,
+        )
+        .unwrap();
+        let classify = |name: &str, filter: &BackupFilter| {
+            let game = &manifest.0[name];
+            classify_game(game, &roots, &game.steam.clone().unwrap().id, filter)
+        };
+        let exclusive = BackupFilter {
+            exclude_other_os_data: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            GameClassification::default(),
+            classify("install-dir-only", &BackupFilter::default())
+        );
+        assert_eq!(
+            GameClassification {
+                registry: WINDOWS || LINUX,
+                ..Default::default()
+            },
+            classify("registry-only", &BackupFilter::default())
+        );
+        assert_eq!(
+            GameClassification {
+                other_os: true,
+                ..Default::default()
+            },
+            classify("other-os-only", &exclusive)
+        );
+        assert_eq!(
+            GameClassification {
+                other_os: true,
+                ..Default::default()
+            },
+            classify("other-os-only", &BackupFilter::default())
+        );
+    }
+
+    #[test]
+    fn can_skip_windows_only_game_on_other_os_without_proton_root() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <base>/file1.txt:
+                  when:
+                    - os: windows
+            "#,
+        )
+        .unwrap();
+        let scan_info = scan_game_for_backup(
+            &manifest.0["game1"],
+            "game1",
+            &config().roots,
+            &StrictPath::new(repo()),
+            &Some(123),
+            &BackupFilter::default(),
+            &ScanOptions::default(),
+            &InstallDirCache::default(),
+        );
+
+        assert_eq!(
+            Some(GameClassification {
+                files: WINDOWS,
+                other_os: !WINDOWS,
+                ..Default::default()
+            }),
+            scan_info.classification,
+        );
+        assert_eq!(WINDOWS, scan_info.found_anything());
+    }
+
+    #[test]
+    fn can_classify_windows_only_game_with_proton_root() {
+        let manifest = Manifest::load_from_string(
+            r#"
+            game1:
+              files:
+                <base>/file1.txt:
+                  when:
+                    - os: windows
+            "#,
+        )
+        .unwrap();
+        let roots = vec![RootsConfig {
+            path: StrictPath::new(s("~/steam")),
+            store: Store::Steam,
+            portable: false,
+            scan_proton_prefixes: None,
+        }];
+
+        assert_eq!(
+            !WINDOWS && !LINUX,
+            classify_game(&manifest.0["game1"], &roots, &Some(123), &BackupFilter::default()).other_os,
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_restoration_with_files() {
+        let make_path = |x| {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(format!(
+                    "\\\\?\\{}\\tests\\backup\\game1\\drive-X\\{}",
+                    repo().replace("/", "\\"),
+                    x
+                ))
+            } else {
+                StrictPath::new(format!("{}/tests/backup/game1/drive-X/{}", repo(), x))
+            }
+        };
+
+        assert_eq!(
             ScanInfo {
-                game_name: s("game 2"),
+                game_name: s("game1"),
                 found_files: hashset! {
-                    ScannedFile {
-                        path: StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())),
-                        size: 1,
-                        original_path: None,
+                    ScannedFile { path: make_path("file1.txt"), size: 1, original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file1.txt" } else { "X:/file1.txt" }))), root: None },
+                    ScannedFile { path: make_path("file2.txt"), size: 2, original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file2.txt" } else { "X:/file2.txt" }))), root: None },
+                },
+                integrity: Some(IntegrityInfo::default()),
+                ..Default::default()
+            },
+            untimed(scan_game_for_restoration(
+                "game1",
+                &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
+            )),
+        );
+    }
+
+    #[test]
+    fn can_scan_game_for_restoration_with_registry() {
+        if cfg!(target_os = "windows") {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game3"),
+                    found_registry_keys: hashset! {
+                        s("HKEY_CURRENT_USER/Software/Ludusavi/game3")
                     },
+                    registry_file: Some(StrictPath::new(format!(
+                        "\\\\?\\{}\\tests\\backup\\game3-renamed/registry.yaml",
+                        repo().replace("/", "\\")
+                    ))),
+                    integrity: Some(IntegrityInfo::default()),
+                    ..Default::default()
                 },
-                found_registry_keys: hashset! {},
-                registry_file: None,
+                untimed(scan_game_for_restoration(
+                    "game3",
+                    &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
+                )),
+            );
+        } else {
+            assert_eq!(
+                ScanInfo {
+                    game_name: s("game3"),
+                    integrity: Some(IntegrityInfo::default()),
+                    ..Default::default()
+                },
+                untimed(scan_game_for_restoration(
+                    "game3",
+                    &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
+                )),
+            );
+        }
+    }
+
+    #[test]
+    fn can_scan_game_for_restoration_with_integrity_problems() {
+        let make_path = |x| {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(format!(
+                    "\\\\?\\{}\\tests\\backup\\game4\\drive-X\\{}",
+                    repo().replace("/", "\\"),
+                    x
+                ))
+            } else {
+                StrictPath::new(format!("{}/tests/backup/game4/drive-X/{}", repo(), x))
+            }
+        };
+        let make_original_path = |x| {
+            StrictPath::new(if cfg!(target_os = "windows") {
+                format!("X:\\{}", x)
+            } else {
+                format!("X:/{}", x)
+            })
+        };
+
+        let scan_info = scan_game_for_restoration(
+            "game4",
+            &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo()))),
+        );
+
+        assert_eq!(
+            Some(IntegrityInfo {
+                known: true,
+                missing_files: hashset! {
+                    StrictPath::new(s("X:/missing.txt")),
+                },
+                corrupted_files: hashset! {
+                    ScannedFile { path: make_path("resized.txt"), size: 2, original_path: Some(make_original_path("resized.txt")), root: None },
+                },
+            }),
+            scan_info.integrity,
+        );
+        assert!(scan_info.backup_is_broken());
+    }
+
+    #[test]
+    fn retry_policy_does_not_sleep_when_first_attempt_succeeds() {
+        let mut calls = 0;
+        let (succeeded, slept) = RetryPolicy::default().run(1000, || {
+            calls += 1;
+            true
+        });
+        assert!(succeeded);
+        assert_eq!(1, calls);
+        assert_eq!(std::time::Duration::from_millis(0), slept);
+    }
+
+    #[test]
+    fn retry_policy_sleeps_between_retries() {
+        let mut calls = 0;
+        let (succeeded, slept) = RetryPolicy::default().run(1, || {
+            calls += 1;
+            calls == 3
+        });
+        assert!(succeeded);
+        assert_eq!(3, calls);
+        assert_eq!(std::time::Duration::from_millis(1 + 2), slept);
+    }
+
+    #[test]
+    fn retry_policy_can_disable_sleep() {
+        let mut calls = 0;
+        let (succeeded, slept) = RetryPolicy {
+            attempts: 5,
+            sleep: false,
+        }
+        .run(1000, || {
+            calls += 1;
+            false
+        });
+        assert!(!succeeded);
+        assert_eq!(5, calls);
+        assert_eq!(std::time::Duration::from_millis(0), slept);
+    }
+
+    #[test]
+    fn can_check_if_backup_info_is_total_failure() {
+        let file1 = ScannedFile::new(StrictPath::new(s("/file1"))).with_size(1);
+        let file2 = ScannedFile::new(StrictPath::new(s("/file2"))).with_size(2);
+
+        assert!(!BackupInfo {
+            found_files: 2,
+            ..Default::default()
+        }
+        .is_total_failure());
+        assert!(!BackupInfo {
+            failed_files: hashset! { file1.clone() },
+            found_files: 2,
+            ..Default::default()
+        }
+        .is_total_failure());
+        assert!(BackupInfo {
+            failed_files: hashset! { file1, file2 },
+            found_files: 2,
+            ..Default::default()
+        }
+        .is_total_failure());
+        assert!(!BackupInfo::default().is_total_failure());
+    }
+
+    #[test]
+    fn can_summarize_backup_info() {
+        let file = ScannedFile::new(StrictPath::new(s("/file"))).with_size(1);
+
+        assert_eq!(
+            "Backed up 42 files",
+            BackupInfo {
+                found_files: 42,
+                ..Default::default()
+            }
+            .summary()
+        );
+        assert_eq!(
+            "Backed up 42 files (1 failed, 1 registry key failed)",
+            BackupInfo {
+                failed_files: hashset! { file },
+                failed_registry: hashset! { s("HKEY_CURRENT_USER/Key") },
+                found_files: 42,
+                found_registry_keys: 1,
+                ..Default::default()
+            }
+            .summary()
+        );
+    }
+
+    fn restorable_scan(game_name: &str, files: &[&str]) -> ScanInfo {
+        ScanInfo {
+            game_name: s(game_name),
+            found_files: files
+                .iter()
+                .map(|x| {
+                    ScannedFile::new(StrictPath::new(format!("/backup/{}/{}", game_name, x)))
+                        .with_size(1)
+                        .with_original_path(StrictPath::new(format!("/original/{}", x)))
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn can_build_open_dir_command_without_unc_prefix() {
+        assert_eq!(
+            ("explorer", s(r#"C:\Some Folder\Spiel für alle"#)),
+            open_dir_command(&StrictPath::new(s(r#"\\?\C:\Some Folder\Spiel für alle"#))),
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_build_open_dir_command_with_spaces_and_non_ascii() {
+        let program = if MAC { "open" } else { "xdg-open" };
+        assert_eq!(
+            (program, s("/some folder/Spiel für alle")),
+            open_dir_command(&StrictPath::new(s("/some folder/Spiel für alle"))),
+        );
+    }
+
+    #[test]
+    fn cannot_open_game_backup_dir_that_does_not_exist() {
+        let backup_dir = StrictPath::new(format!("{}/tests/backup", repo()));
+        match open_game_backup_dir(&backup_dir, "nonexistent game") {
+            Err(Error::BackupFolderNotFound { game, .. }) => assert_eq!("nonexistent game", game),
+            x => panic!("unexpected result: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn can_summarize_registry_keys() {
+        let empty = ScanInfo::default();
+        let info = ScanInfo {
+            found_registry_keys: hashset! { s("HKEY_CURRENT_USER/a"), s("HKEY_CURRENT_USER/b") },
+            ..Default::default()
+        };
+        assert_eq!((0, false), (empty.registry_key_count(), empty.has_registry_keys()));
+        assert_eq!((2, true), (info.registry_key_count(), info.has_registry_keys()));
+        assert!(info.found_anything());
+    }
+
+    #[test]
+    fn can_find_scanned_files_under_a_folder() {
+        let file = |path: &str| ScannedFile::new(StrictPath::new(s(path)));
+        let info = ScanInfo {
+            found_files: hashset! {
+                file("/game/saves/a.sav"),
+                file("/game/saves/sub/b.sav"),
+                file("/game/saves-old/c.sav"),
+                file("/game/config.ini"),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vec![&file("/game/saves/a.sav"), &file("/game/saves/sub/b.sav")],
+            info.paths_under(&StrictPath::new(s("/game/saves"))),
+        );
+        assert!(info.paths_under(&StrictPath::new(s("/other"))).is_empty());
+    }
+
+    #[test]
+    fn can_find_restore_conflicts() {
+        let infos = vec![
+            restorable_scan("game1", &["shared.cfg", "game1.sav"]),
+            restorable_scan("game2", &["shared.cfg", "game2.sav"]),
+            restorable_scan("game3", &["game3.sav"]),
+        ];
+
+        assert_eq!(
+            vec![RestoreConflict {
+                target: StrictPath::new(s("/original/shared.cfg")),
+                sources: vec![
+                    RestoreConflictSource {
+                        game_name: s("game1"),
+                        file: ScannedFile::new(StrictPath::new(s("/backup/game1/shared.cfg")))
+                            .with_size(1)
+                            .with_original_path(StrictPath::new(s("/original/shared.cfg"))),
+                        modified: None,
+                    },
+                    RestoreConflictSource {
+                        game_name: s("game2"),
+                        file: ScannedFile::new(StrictPath::new(s("/backup/game2/shared.cfg")))
+                            .with_size(1)
+                            .with_original_path(StrictPath::new(s("/original/shared.cfg"))),
+                        modified: None,
+                    },
+                ],
+            }],
+            find_restore_conflicts(&infos, &[]),
+        );
+    }
+
+    #[test]
+    fn can_find_restore_conflicts_after_redirects() {
+        let infos = vec![
+            restorable_scan("game1", &["a/shared.cfg"]),
+            restorable_scan("game2", &["b/shared.cfg"]),
+        ];
+        let redirects = vec![
+            RedirectConfig {
+                source: StrictPath::new(s("/original/a")),
+                target: StrictPath::new(s("/redirected")),
+            },
+            RedirectConfig {
+                source: StrictPath::new(s("/original/b")),
+                target: StrictPath::new(s("/redirected")),
+            },
+        ];
+
+        assert!(find_restore_conflicts(&infos, &[]).is_empty());
+
+        let conflicts = find_restore_conflicts(&infos, &redirects);
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            StrictPath::new(s("/redirected/shared.cfg")).render(),
+            conflicts[0].target.render()
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_build_restore_preview_tree() {
+        let info = restorable_scan("game1", &["a/one.sav", "a/two.sav", "b/c/three.sav", "four.cfg"]);
+        let redirects = vec![RedirectConfig {
+            source: StrictPath::new(s("/original/b")),
+            target: StrictPath::new(s("/redirected")),
+        }];
+
+        let tree = |folders: Vec<(&str, RestorePreviewTree)>, files: &[&str]| RestorePreviewTree {
+            folders: folders.into_iter().map(|(k, v)| (s(k), v)).collect(),
+            files: files.iter().map(|x| s(x)).collect(),
+        };
+        assert_eq!(
+            tree(
+                vec![(
+                    "/",
+                    tree(
+                        vec![
+                            (
+                                "original",
+                                tree(vec![("a", tree(vec![], &["one.sav", "two.sav"]))], &["four.cfg"])
+                            ),
+                            ("redirected", tree(vec![("c", tree(vec![], &["three.sav"]))], &[])),
+                        ],
+                        &[]
+                    )
+                )],
+                &[]
+            ),
+            restore_preview_tree(&info, &redirects),
+        );
+    }
+
+    #[test]
+    fn can_resolve_restore_conflicts() {
+        let mut infos = vec![
+            restorable_scan("game1", &["shared.cfg", "game1.sav"]),
+            restorable_scan("game2", &["shared.cfg", "game2.sav"]),
+        ];
+        let conflicts = find_restore_conflicts(&infos, &[]);
+        resolve_restore_conflicts(&mut infos, &conflicts, &[1]);
+
+        assert_eq!(restorable_scan("game1", &["game1.sav"]), infos[0]);
+        assert_eq!(restorable_scan("game2", &["shared.cfg", "game2.sav"]), infos[1]);
+        assert!(find_restore_conflicts(&infos, &[]).is_empty());
+    }
+
+    #[test]
+    fn can_list_existing_files_in_backup_target() {
+        let make_path = |x: &str| {
+            if cfg!(target_os = "windows") {
+                StrictPath::new(format!(
+                    "\\\\?\\{}\\tests\\backup\\game1\\{}",
+                    repo().replace("/", "\\"),
+                    x.replace("/", "\\")
+                ))
+            } else {
+                StrictPath::new(format!("{}/tests/backup/game1/{}", repo(), x))
+            }
+        };
+
+        assert_eq!(
+            vec![
+                make_path("drive-X/file1.txt"),
+                make_path("drive-X/file2.txt"),
+                make_path("mapping.yaml"),
+            ],
+            list_existing_files(&StrictPath::new(format!("{}/tests/backup/game1", repo()))),
+        );
+        assert_eq!(
+            Vec::<StrictPath>::new(),
+            list_existing_files(&StrictPath::new(format!("{}/tests/backup/nonexistent", repo()))),
+        );
+    }
+
+    #[test]
+    fn can_check_backup_target_health() {
+        let temp = TempDir::new("target-health");
+        let base = temp.path().clone();
+        let mapping = base.joined("backup/game1/mapping.yaml");
+        mapping.create_parent_dir().unwrap();
+        std::fs::write(mapping.interpret(), "name: game1\ndrives: {}\n").unwrap();
+        std::fs::write(base.joined("file.txt").interpret(), "").unwrap();
+
+        let existing = check_backup_target(&base.joined("backup"));
+        let missing = check_backup_target(&base.joined("new/backup"));
+        let blocked = check_backup_target(&base.joined("file.txt"));
+
+        assert!(existing.exists && existing.writable);
+        assert_eq!(1, existing.games);
+        assert!(existing.last_backup.is_some());
+        assert!(existing.free_bytes.is_some());
+        assert_eq!(
+            BackupTargetHealth {
+                exists: false,
+                writable: true,
+                free_bytes: missing.free_bytes,
+                games: 0,
+                last_backup: None,
+            },
+            missing,
+        );
+        assert!(!blocked.exists && !blocked.writable);
+    }
+
+    #[test]
+    fn can_back_up_and_restore_games_grouped_by_store() {
+        let temp = TempDir::new("grouped-by-store");
+        let backup = temp.path().clone();
+
+        let scan = |name: &str, file: &str, store: Store| ScanInfo {
+            game_name: s(name),
+            found_files: hashset! {
+                ScannedFile {
+                    path: StrictPath::new(format!("{}/{}", repo(), file)),
+                    size: 2,
+                    original_path: None,
+                    root: Some(RootsConfig {
+                        path: StrictPath::new(repo()),
+                        store,
+                        portable: false,
+                        scan_proton_prefixes: None,
+                    }),
+                },
+            },
+            ..Default::default()
+        };
+        let layout = BackupLayout {
+            group_by_store: true,
+            ..BackupLayout::new(backup.clone())
+        };
+        assert!(back_up_game(
+            &scan("game1", "tests/root1/game1/subdir/file2.txt", Store::Steam),
+            "game1",
+            &layout
+        )
+        .successful());
+        assert!(back_up_game(
+            &scan("game2", "tests/root2/game2/file1.txt", Store::Other),
+            "game2",
+            &layout
+        )
+        .successful());
+
+        let layout = BackupLayout::new(backup.clone());
+        let folders = (
+            layout.game_folder("game1").render(),
+            layout.game_folder("game2").render(),
+        );
+        let original_paths: Vec<_> = ["game1", "game2"]
+            .iter()
+            .flat_map(|name| scan_game_for_restoration(name, &layout).found_files)
+            .filter_map(|x| x.original_path.map(|y| y.render()))
+            .collect();
+
+        assert_eq!(
+            (
+                format!("{}/steam/game1", backup.render()),
+                format!("{}/other/game2", backup.render())
+            ),
+            folders,
+        );
+        assert_eq!(
+            vec![
+                StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())).render(),
+                StrictPath::new(format!("{}/tests/root2/game2/file1.txt", repo())).render(),
+            ],
+            original_paths,
+        );
+    }
+
+    #[test]
+    fn can_back_up_games_concurrently_with_shared_layout() {
+        let temp = TempDir::new("concurrent");
+        let layout = BackupLayout::new(temp.joined("backup"));
+        let games: Vec<_> = (0..16)
+            .map(|i| {
+                let name = format!("game{}", i);
+                let file = temp.write(&format!("saves/{}/save.dat", name), "x");
+                let info = ScanInfo {
+                    game_name: name.clone(),
+                    found_files: hashset! { ScannedFile::new(file).with_size(1) },
+                    ..Default::default()
+                };
+                (name, info)
+            })
+            .collect();
+
+        let successful = games
+            .par_iter()
+            .all(|(name, info)| back_up_game(info, name, &layout).successful());
+        let restorable: Vec<_> = games
+            .iter()
+            .map(|(name, _)| {
+                untimed(scan_game_for_restoration(
+                    name,
+                    &BackupLayout::new(temp.joined("backup")),
+                ))
+                .found_files
+                .into_iter()
+                .filter_map(|x| x.original_path.map(|y| y.render()))
+                .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert!(successful);
+        assert_eq!(
+            games
+                .iter()
+                .map(|(name, _)| vec![temp.joined(&format!("saves/{}/save.dat", name)).render()])
+                .collect::<Vec<_>>(),
+            restorable,
+        );
+    }
+
+    #[test]
+    fn can_back_up_and_restore_games_in_separate_namespaces() {
+        let temp = TempDir::new("namespaces");
+        let backup = temp.path().clone();
+
+        let scan = |file: &str| ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile::new(StrictPath::new(format!("{}/{}", repo(), file))).with_size(2),
+            },
+            ..Default::default()
+        };
+        let desktop = namespaced_path(&backup, &Some(s("desktop")));
+        let laptop = namespaced_path(&backup, &Some(s("laptop")));
+        assert!(back_up_game(
+            &scan("tests/root1/game1/subdir/file2.txt"),
+            "game1",
+            &BackupLayout::new(desktop.clone())
+        )
+        .successful());
+        assert!(back_up_game(
+            &scan("tests/root2/game1/file1.txt"),
+            "game1",
+            &BackupLayout::new(laptop.clone())
+        )
+        .successful());
+
+        let restorable = |target: &StrictPath| -> Vec<_> {
+            untimed(scan_game_for_restoration("game1", &BackupLayout::new(target.clone())))
+                .found_files
+                .into_iter()
+                .filter_map(|x| x.original_path.map(|y| y.render()))
+                .collect()
+        };
+        let from_desktop = restorable(&desktop);
+        let from_laptop = restorable(&laptop);
+        let unnamespaced = BackupLayout::new(backup.clone()).mapping;
+
+        // Replacing the unnamespaced backup leaves the namespaces alone.
+        let stray = temp.write("stray/file.txt", "");
+        let listed = list_existing_files(&backup);
+        prepare_backup_target(&backup, false).unwrap();
+        let after_cleanup = (stray.exists(), desktop.is_dir(), laptop.is_dir());
+
+        assert_eq!(
+            vec![StrictPath::new(format!("{}/tests/root1/game1/subdir/file2.txt", repo())).render()],
+            from_desktop,
+        );
+        assert_eq!(
+            vec![StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())).render()],
+            from_laptop,
+        );
+        assert_eq!(0, unnamespaced.games.len());
+        assert_eq!(vec![s("desktop"), s("laptop")], unnamespaced.namespaces);
+        assert_eq!(vec![stray], listed);
+        assert_eq!((false, true, true), after_cleanup);
+    }
+
+    #[test]
+    fn can_back_up_and_restore_game_from_moved_portable_root() {
+        let temp = TempDir::new("portable-root");
+        let base = temp.path().clone();
+        let old_root = base.joined("old-drive/Games");
+        let new_root = base.joined("new-drive/Games");
+        let outside = base.joined("outside/settings.ini");
+        let backup = base.joined("backup");
+        let root = |path: &StrictPath| RootsConfig {
+            path: path.clone(),
+            store: Store::Other,
+            portable: true,
+            scan_proton_prefixes: None,
+        };
+
+        let save = old_root.joined("game1/save.dat");
+        for (file, content) in &[(&save, "a"), (&outside, "b")] {
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), content).unwrap();
+        }
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile {
+                    path: save.clone(),
+                    size: 1,
+                    original_path: None,
+                    root: Some(root(&old_root)),
+                },
+                ScannedFile {
+                    path: outside.clone(),
+                    size: 1,
+                    original_path: None,
+                    root: Some(root(&old_root)),
+                },
+            },
+            ..Default::default()
+        };
+        assert!(back_up_game(&info, "game1", &BackupLayout::new(backup.clone())).successful());
+
+        std::fs::remove_dir_all(base.joined("old-drive").interpret()).unwrap();
+        std::fs::remove_file(outside.interpret()).unwrap();
+        let layout = BackupLayout {
+            roots: vec![root(&new_root)],
+            ..BackupLayout::new(backup.clone())
+        };
+        let restorable = scan_game_for_restoration("game1", &layout);
+        let backup_info = restore_game(
+            &restorable,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        let restored: Vec<_> = [new_root.joined("game1/save.dat"), outside.clone()]
+            .iter()
+            .map(|x| std::fs::read_to_string(x.interpret()).ok())
+            .collect();
+
+        assert!(backup_info.successful());
+        assert_eq!(vec![Some(s("a")), Some(s("b"))], restored);
+    }
+
+    #[test]
+    fn can_back_up_and_restore_game_with_compressed_files() {
+        let temp = TempDir::new("compression");
+        let base = temp.path().clone();
+        let backup = base.joined("backup");
+
+        let world = base.joined("saves/world.json");
+        let noise = base.joined("saves/noise.dat");
+        let small = base.joined("saves/small.json");
+        let mut seed: u32 = 1;
+        let contents: Vec<(&StrictPath, Vec<u8>)> = vec![
+            (&world, "{\"tile\": 1}\n".repeat(200_000).into_bytes()),
+            (
+                &noise,
+                (0..2_000_000)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                        (seed >> 16) as u8
+                    })
+                    .collect(),
+            ),
+            (&small, b"{}".to_vec()),
+        ];
+        let mut found_files = std::collections::HashSet::new();
+        for (file, content) in &contents {
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), content).unwrap();
+            found_files.insert(ScannedFile::new((*file).clone()).with_size(content.len() as u64));
+        }
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files,
+            ..Default::default()
+        };
+        let layout = BackupLayout {
+            compress_large_files: true,
+            ..BackupLayout::new(backup.clone())
+        };
+        assert!(back_up_game(&info, "game1", &layout).successful());
+
+        let layout = BackupLayout::new(backup.clone());
+        let mapping = &layout.mapping.games["game1"];
+        let compressed: Vec<_> = {
+            let mut x: Vec<_> = mapping
+                .files
+                .iter()
+                .filter(|(_, file)| file.compressed_size.is_some())
+                .map(|(key, _)| key.rsplit('/').next().unwrap().to_string())
+                .collect();
+            x.sort();
+            x
+        };
+        assert_eq!(vec![s("world.json")], compressed);
+
+        let restorable = scan_game_for_restoration("game1", &layout);
+        assert_eq!(1, restorable.compressed_files.len());
+        assert_eq!(GameDiff::default(), diff_game(&info, &restorable, &layout));
+        for (file, _) in &contents {
+            file.remove().unwrap();
+        }
+        assert_eq!(IntegrityStatus::Intact, restorable.integrity.as_ref().unwrap().status());
+        let backup_info = restore_game(
+            &restorable,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        let restored: Vec<_> = contents
+            .iter()
+            .map(|(file, content)| std::fs::read(file.interpret()).ok() == Some(content.clone()))
+            .collect();
+
+        assert!(backup_info.successful());
+        assert_eq!(vec![true, true, true], restored);
+    }
+
+    #[test]
+    fn can_keep_screenshots_across_backups() {
+        let temp = TempDir::new("screenshots");
+        let base = temp.path().clone();
+        let backup = base.joined("backup");
+        let save = base.joined("saves/save.dat");
+        let old_screenshot = base.joined("steam/userdata/1/760/remote/2/screenshots/old.jpg");
+        let new_screenshot = base.joined("steam/userdata/1/760/remote/2/screenshots/new.jpg");
+        for file in &[&save, &old_screenshot, &new_screenshot] {
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), "x").unwrap();
+        }
+        let scan = |files: Vec<&StrictPath>| ScanInfo {
+            game_name: s("game1"),
+            found_files: files
+                .into_iter()
+                .map(|x| ScannedFile::new(x.clone()).with_size(1))
+                .collect(),
+            ..Default::default()
+        };
+
+        let first = scan(vec![&save, &old_screenshot]);
+        assert!(back_up_game(&first, "game1", &BackupLayout::new(backup.clone())).successful());
+        assert_eq!(1, first.screenshot_bytes());
+
+        old_screenshot.remove().unwrap();
+        let second = scan(vec![&save, &new_screenshot]);
+        let layout = BackupLayout::new(backup.clone());
+        assert_eq!(
+            vec![new_screenshot.clone()],
+            diff_game(&second, &scan_game_for_restoration("game1", &layout), &layout)
+                .added_files
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert!(back_up_game(&second, "game1", &layout).successful());
+
+        let layout = BackupLayout::new(backup.clone());
+        let mapping = &layout.mapping.games["game1"];
+        let mut screenshots: Vec<_> = mapping
+            .screenshots
+            .keys()
+            .map(|key| key.rsplit('/').next().unwrap().to_string())
+            .collect();
+        screenshots.sort();
+        assert_eq!(vec![s("new.jpg"), s("old.jpg")], screenshots);
+        assert_eq!(1, mapping.files.len());
+        assert_eq!(
+            GameDiff::default(),
+            diff_game(&second, &scan_game_for_restoration("game1", &layout), &layout)
+        );
+
+        let restorable = scan_game_for_restoration("game1", &layout);
+        let with_screenshots = scan_game_for_restoration(
+            "game1",
+            &BackupLayout {
+                include_screenshots: true,
+                ..BackupLayout::new(backup.clone())
             },
-            scan_game_for_backup(
-                &manifest().0["game 2"],
-                "game 2",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-            ),
+        );
+
+        assert_eq!(1, restorable.found_files.len());
+        assert_eq!(3, with_screenshots.found_files.len());
+        assert!(with_screenshots
+            .found_files
+            .iter()
+            .any(|x| x.original_path.as_ref().map(|x| x.render()) == Some(old_screenshot.render())));
+        assert_eq!(
+            IntegrityStatus::Intact,
+            with_screenshots.integrity.as_ref().unwrap().status()
         );
     }
 
     #[test]
-    #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_registry_matches_on_leaf_key_with_values() {
+    fn cannot_restore_game_into_system_dir() {
+        let target = StrictPath::new(s(if WINDOWS {
+            "C:/Windows/ludusavi-test.txt"
+        } else {
+            "/etc/ludusavi-test.txt"
+        }));
+        let file = ScannedFile::new(StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())))
+            .with_size(1)
+            .with_original_path(target.clone());
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! { file.clone() },
+            ..Default::default()
+        };
+
+        let backup_info = restore_game(
+            &info,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        assert_eq!(hashset! { file }, backup_info.failed_files);
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn cannot_restore_game_into_config_dir() {
+        let target = StrictPath::from_std_path_buf(&app_dir().join("ludusavi-test.txt"));
+        let file = ScannedFile::new(StrictPath::new(format!("{}/tests/root2/game1/file1.txt", repo())))
+            .with_size(1)
+            .with_original_path(target.clone());
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! { file.clone() },
+            ..Default::default()
+        };
+
+        let backup_info = restore_game(
+            &info,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        assert_eq!(hashset! { file }, backup_info.failed_files);
+        assert!(!target.exists());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_restore_game_with_escaped_names() {
+        let temp = TempDir::new("restore-escaped");
+        let base = temp.path().clone();
+        let target = base.joined("target");
+        let game_folder = base.joined("backup/game1");
+
+        let mut mapping = crate::layout::IndividualMapping::new(s("game1"));
+        let (drive, plain_path) = target.split_drive();
+        let drive_folder = mapping.drive_folder_name(&drive);
+        let stored = |name: &str| format!("{}/{}/{}", drive_folder, plain_path, name);
+        // The first two escaped to the same name, so the second was disambiguated.
+        // The last one can't be created on any file system, so it should fall back.
+        mapping.escaped_names = hashmap! {
+            stored("save_final.dat") => stored("save:final.dat"),
+            stored("save_final (2).dat") => stored("save?final.dat"),
+            stored("bad_name.dat") => stored("bad\0name.dat"),
+        };
+        for (name, content) in &[
+            ("save_final.dat", "a"),
+            ("save_final (2).dat", "b"),
+            ("plain.dat", "c"),
+            ("bad_name.dat", "d"),
+        ] {
+            let file = game_folder.joined(&stored(name));
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), content).unwrap();
+        }
+        mapping.save(&game_folder.joined("mapping.yaml"));
+
+        let info = scan_game_for_restoration("game1", &BackupLayout::new(base.joined("backup")));
+        let originals: std::collections::HashSet<_> = info
+            .found_files
+            .iter()
+            .filter_map(|x| x.original_path.as_ref().map(|y| y.render()))
+            .collect();
+        let backup_info = restore_game(
+            &info,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        let restored: Vec<_> = ["save:final.dat", "save?final.dat", "plain.dat", "bad_name.dat"]
+            .iter()
+            .map(|name| std::fs::read_to_string(target.joined(name).interpret()).ok())
+            .collect();
+
         assert_eq!(
-            ScanInfo {
-                game_name: s("game3"),
-                found_files: hashset! {},
-                found_registry_keys: hashset! {
-                    s("HKEY_CURRENT_USER/Software/Ludusavi/game3")
-                },
-                registry_file: None,
+            hashset! {
+                format!("{}/save:final.dat", target.render()),
+                format!("{}/save?final.dat", target.render()),
+                format!("{}/plain.dat", target.render()),
+                format!("{}/bad\0name.dat", target.render()),
             },
-            scan_game_for_backup(
-                &manifest().0["game3"],
-                "game3",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-            ),
+            originals,
+        );
+        assert!(backup_info.failed_files.is_empty());
+        assert_eq!(
+            vec![format!("{}/bad\0name.dat", target.render())],
+            backup_info
+                .restored_with_stored_names
+                .iter()
+                .filter_map(|x| x.original_path.as_ref().map(|y| y.render()))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(vec![Some(s("a")), Some(s("b")), Some(s("c")), Some(s("d"))], restored);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_back_up_and_restore_game_with_control_characters_in_names() {
+        let temp = TempDir::new("control-chars");
+        let base = temp.path().clone();
+        let saves = base.joined("sa\nves");
+        let names = [
+            "line\nbreak.sav",
+            "tab\there.sav",
+            "escape\u{1b}[0m.sav",
+            "carriage\rreturn.sav",
+        ];
+        for name in &names {
+            let file = saves.joined(name);
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), name).unwrap();
+        }
+
+        let info = scan_game_for_backup_from_yaml(
+            &format!("files:\n  {:?}: {{}}", format!("{}/*", saves.render())),
+            "game1",
+            &[],
+            &StrictPath::new(repo()),
+            &None,
+        )
+        .unwrap();
+        let found: std::collections::HashSet<_> = info.found_files.iter().map(|x| x.path.render()).collect();
+        assert_eq!(
+            names
+                .iter()
+                .map(|x| saves.joined(x).render())
+                .collect::<std::collections::HashSet<_>>(),
+            found,
+        );
+
+        let backup = base.joined("backup");
+        assert!(back_up_game(&info, "game1", &BackupLayout::new(backup.clone())).successful());
+        std::fs::remove_dir_all(saves.interpret()).unwrap();
+
+        let layout = BackupLayout::new(backup);
+        let restorable = scan_game_for_restoration("game1", &layout);
+        let backup_info = restore_game(
+            &restorable,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        let restored: Vec<_> = names
+            .iter()
+            .map(|name| std::fs::read_to_string(saves.joined(name).interpret()).ok())
+            .collect();
+
+        assert!(backup_info.successful());
+        assert!(backup_info.restored_with_stored_names.is_empty());
+        assert_eq!(names.iter().map(|x| Some(s(x))).collect::<Vec<_>>(), restored);
+    }
+
+    fn check_restoring_through_reparse_point(name: &str, make_link: fn(&str, &str), remove_link: fn(&str)) {
+        let temp = TempDir::new(name);
+        let base = temp.path().clone();
+        std::fs::create_dir_all(base.joined("target").interpret()).unwrap();
+        std::fs::write(base.joined("target/save.txt").interpret(), "x").unwrap();
+        let link = format!("{}/link", base.render());
+        make_link(&link, &base.joined("target").render());
+
+        let info = scan_game_for_backup_from_yaml(
+            &format!("files:\n  '{}/save.txt': {{}}", link),
+            "game1",
+            &[],
+            &StrictPath::new(repo()),
+            &None,
+        )
+        .unwrap();
+        assert_eq!(
+            hashmap! { base.joined("target").render() => link.clone() },
+            info.reparse_points,
+        );
+
+        let backup = base.joined("backup");
+        std::fs::create_dir_all(backup.interpret()).unwrap();
+        assert!(back_up_game(&info, "game1", &BackupLayout::new(backup.clone())).successful());
+
+        // Without the link, like on a fresh system, the target wouldn't resolve back to it:
+        remove_link(&link);
+        let restorable = scan_game_for_restoration("game1", &BackupLayout::new(backup));
+        let original_paths: Vec<_> = restorable
+            .found_files
+            .iter()
+            .filter_map(|x| x.original_path.as_ref().map(|y| y.render()))
+            .collect();
+
+        assert_eq!(
+            vec![StrictPath::new(format!("{}/save.txt", link)).render()],
+            original_paths
         );
     }
 
     #[test]
     #[cfg(target_os = "windows")]
-    fn can_scan_game_for_backup_with_registry_matches_on_parent_key_without_values() {
+    fn can_restore_through_junction() {
+        check_restoring_through_reparse_point(
+            "junction",
+            |link, target| {
+                let status = std::process::Command::new("cmd")
+                    .args(&[
+                        "/C",
+                        "mklink",
+                        "/J",
+                        &link.replace("/", "\\"),
+                        &target.replace("/", "\\"),
+                    ])
+                    .output()
+                    .unwrap()
+                    .status;
+                assert!(status.success());
+            },
+            |link| std::fs::remove_dir(link).unwrap(),
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_restore_through_symlink() {
+        check_restoring_through_reparse_point(
+            "symlink",
+            |link, target| std::os::unix::fs::symlink(target, link).unwrap(),
+            |link| std::fs::remove_file(link).unwrap(),
+        );
+    }
+
+    #[test]
+    fn can_restore_game_while_backing_up_existing_files() {
+        let temp = TempDir::new("restore-backup-existing");
+        let target_dir = temp.path().clone();
+        let target = target_dir.joined("file1.txt");
+        std::fs::write(target.interpret(), "old").unwrap();
+        std::fs::write(target_dir.joined("file1.txt.bak").interpret(), "older").unwrap();
+
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile::new(StrictPath::new(format!("{}/tests/backup/game1/drive-X/file1.txt", repo()))).with_size(1).with_original_path(target.clone()),
+            },
+            ..Default::default()
+        };
+        let backup_info = restore_game(
+            &info,
+            &[],
+            &RestoreMode::BackupExisting {
+                suffix: s(".bak"),
+                min_age_days: None,
+            },
+            &RestoreOptions::default(),
+            &RetryPolicy {
+                sleep: false,
+                ..Default::default()
+            },
+        );
+
+        assert!(backup_info.successful());
+        assert!(backup_info.overwritten_files.is_empty());
+        assert_eq!(".", std::fs::read_to_string(target.interpret()).unwrap());
         assert_eq!(
-            ScanInfo {
-                game_name: s("game3-outer"),
-                found_files: hashset! {},
-                found_registry_keys: hashset! {
-                    s("HKEY_CURRENT_USER/Software/Ludusavi")
+            "older",
+            std::fs::read_to_string(target_dir.joined("file1.txt.bak").interpret()).unwrap()
+        );
+        assert_eq!(
+            "old",
+            std::fs::read_to_string(target_dir.joined("file1.txt.bak.1").interpret()).unwrap()
+        );
+    }
+
+    #[test]
+    fn can_preview_restore_with_overwrites() {
+        let temp = TempDir::new("restore-preview");
+        let target_dir = temp.path().clone();
+        let existing = target_dir.joined("existing.txt");
+        let new = target_dir.joined("new.txt");
+        std::fs::write(existing.interpret(), "old").unwrap();
+
+        let backed_up = StrictPath::new(format!("{}/tests/backup/game1/drive-X/file1.txt", repo()));
+        let replacing = ScannedFile::new(backed_up.clone())
+            .with_size(1)
+            .with_original_path(existing.clone());
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                replacing.clone(),
+                ScannedFile::new(backed_up).with_size(1).with_original_path(new.clone()),
+            },
+            ..Default::default()
+        };
+
+        let preview = preview_restore_game(&info, &[], &RestoreMode::Overwrite, &RestoreOptions::default());
+        assert!(preview.successful());
+        assert_eq!(hashset! { replacing.clone() }, preview.overwritten_files);
+        assert_eq!("old", std::fs::read_to_string(existing.interpret()).unwrap());
+        assert!(!new.is_file());
+
+        let backup_info = restore_game(
+            &info,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        assert_eq!(preview.overwritten_files, backup_info.overwritten_files);
+
+        let preview = preview_restore_game(
+            &info,
+            &[],
+            &RestoreMode::BackupExisting {
+                suffix: s(".bak"),
+                min_age_days: None,
+            },
+            &RestoreOptions::default(),
+        );
+        assert!(preview.overwritten_files.is_empty());
+    }
+
+    #[test]
+    fn can_restore_game_with_backup_existing_mode_and_min_age() {
+        let temp = TempDir::new("restore-min-age");
+        let target_dir = temp.path().clone();
+        let recent = target_dir.joined("recent.txt");
+        let old = target_dir.joined("old.txt");
+        std::fs::write(recent.interpret(), "recent").unwrap();
+        std::fs::write(old.interpret(), "old").unwrap();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(old.interpret())
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 24 * 60 * 60))
+            .unwrap();
+
+        let backed_up = StrictPath::new(format!("{}/tests/backup/game1/drive-X/file1.txt", repo()));
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile::new(backed_up.clone()).with_size(1).with_original_path(recent.clone()),
+                ScannedFile::new(backed_up).with_size(1).with_original_path(old.clone()),
+            },
+            ..Default::default()
+        };
+        let mode = RestoreMode::BackupExisting {
+            suffix: s(".bak"),
+            min_age_days: Some(7),
+        };
+        let recent_file = info
+            .found_files
+            .iter()
+            .find(|x| x.original_path == Some(recent.clone()))
+            .cloned()
+            .unwrap();
+
+        let preview = preview_restore_game(&info, &[], &mode, &RestoreOptions::default());
+        assert_eq!(hashset! { recent_file.clone() }, preview.skipped_files);
+
+        let backup_info = restore_game(&info, &[], &mode, &RestoreOptions::default(), &RetryPolicy::default());
+        let read = |path: &StrictPath| std::fs::read_to_string(path.interpret()).ok();
+        let results = vec![
+            read(&recent),
+            read(&target_dir.joined("recent.txt.bak")),
+            read(&old),
+            read(&target_dir.joined("old.txt.bak")),
+        ];
+
+        assert!(backup_info.successful());
+        assert_eq!(hashset! { recent_file }, backup_info.skipped_files);
+        assert_eq!(vec![Some(s("recent")), None, Some(s(".")), Some(s("old"))], results);
+    }
+
+    fn diff_layout() -> BackupLayout {
+        use crate::layout::{OverallMapping, OverallMappingGame};
+
+        let game_folder = StrictPath::new(format!("{}/tests/backup/game5", repo()));
+        BackupLayout {
+            base: StrictPath::new(format!("{}/tests/backup", repo())),
+            mapping: OverallMapping {
+                games: hashmap! {
+                    s("game5") => OverallMappingGame {
+                        drives: hashmap! {
+                            s("drive-X") => s(if cfg!(target_os = "windows") { "X:" } else { "" }),
+                        },
+                        base: game_folder,
+                        files: hashmap! {},
+                        reparse_points: hashmap! {},
+                        escaped_names: hashmap! {},
+                        anchors: hashmap! {},
+                        screenshots: hashmap! {},
+                    },
                 },
-                registry_file: None,
+                namespaces: vec![],
             },
-            scan_game_for_backup(
-                &manifest().0["game3-outer"],
-                "game3-outer",
-                &config().roots,
-                &StrictPath::new(repo()),
-                &None,
-                &BackupFilter::default(),
-            ),
+            group_by_store: false,
+            roots: vec![],
+            compress_large_files: false,
+            include_screenshots: false,
+        }
+    }
+
+    fn diff_live_file(name: &str, size: u64) -> ScannedFile {
+        ScannedFile::new(StrictPath::new(format!("X:/{}", name))).with_size(size)
+    }
+
+    fn diff_backup_file(layout: &BackupLayout, name: &str, size: u64) -> ScannedFile {
+        let original = StrictPath::new(format!("X:/{}", name));
+        let key = layout.existing_game_file_key("game5", &original, &None).unwrap();
+        ScannedFile::new(layout.game_folder("game5").joined(&key))
+            .with_size(size)
+            .with_original_path(original)
+    }
+
+    #[test]
+    fn can_diff_game() {
+        let layout = diff_layout();
+        let live_file = diff_live_file;
+        let backup_file = |name: &str, size| diff_backup_file(&layout, name, size);
+
+        let live = ScanInfo {
+            game_name: s("game5"),
+            found_files: hashset! {
+                live_file("same.txt", 1),
+                live_file("resized.txt", 2),
+                live_file("added.txt", 1),
+            },
+            found_registry_keys: hashset! {
+                s("HKEY_CURRENT_USER/Software/Ludusavi/game5"),
+                s("HKEY_CURRENT_USER/Software/Ludusavi/added"),
+            },
+            ..Default::default()
+        };
+        let backup = ScanInfo {
+            game_name: s("game5"),
+            found_files: hashset! {
+                backup_file("same.txt", 1),
+                backup_file("resized.txt", 1),
+                backup_file("removed.txt", 1),
+            },
+            found_registry_keys: hashset! {
+                s("HKEY_CURRENT_USER/Software/Ludusavi/game5"),
+                s("HKEY_CURRENT_USER/Software/Ludusavi/game5/subkey"),
+                s("HKEY_CURRENT_USER/Software/Ludusavi/removed"),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            GameDiff {
+                added_files: hashset! { StrictPath::new(s("X:/added.txt")) },
+                removed_files: hashset! { StrictPath::new(s("X:/removed.txt")) },
+                resized_files: hashset! { StrictPath::new(s("X:/resized.txt")) },
+                modified_files: hashset! {},
+                added_registry_keys: hashset! { s("HKEY_CURRENT_USER/Software/Ludusavi/added") },
+                removed_registry_keys: hashset! { s("HKEY_CURRENT_USER/Software/Ludusavi/removed") },
+            },
+            diff_game(&live, &backup, &layout),
         );
     }
 
     #[test]
-    fn can_scan_game_for_restoration_with_files() {
-        let make_path = |x| {
-            if cfg!(target_os = "windows") {
-                StrictPath::new(format!(
-                    "\\\\?\\{}\\tests\\backup\\game1\\drive-X\\{}",
-                    repo().replace("/", "\\"),
-                    x
-                ))
-            } else {
-                StrictPath::new(format!("{}/tests/backup/game1/drive-X/{}", repo(), x))
-            }
+    fn can_compute_incremental_delta_bytes() {
+        let layout = diff_layout();
+        let live = ScanInfo {
+            game_name: s("game5"),
+            found_files: hashset! {
+                diff_live_file("same.txt", 1),
+                diff_live_file("resized.txt", 20),
+                diff_live_file("added.txt", 300),
+            },
+            ..Default::default()
+        };
+        let backup = ScanInfo {
+            game_name: s("game5"),
+            found_files: hashset! {
+                diff_backup_file(&layout, "same.txt", 1),
+                diff_backup_file(&layout, "resized.txt", 2),
+                diff_backup_file(&layout, "removed.txt", 4000),
+            },
+            ..Default::default()
+        };
+
+        let diff = diff_game(&live, &backup, &layout);
+
+        assert_eq!(320, incremental_delta_bytes(&live, &diff));
+    }
+
+    #[test]
+    fn can_estimate_backup_time() {
+        let info = ScanInfo {
+            game_name: s("foo"),
+            found_files: hashset! {
+                ScannedFile::new(StrictPath::new(s("/saves/a.dat"))).with_size(3_000),
+                ScannedFile::new(StrictPath::new(s("/saves/b.dat"))).with_size(7_000),
+            },
+            ..Default::default()
         };
 
+        assert_eq!(std::time::Duration::from_secs(5), info.estimated_backup_time(2_000));
         assert_eq!(
-            ScanInfo {
-                game_name: s("game1"),
-                found_files: hashset! {
-                    ScannedFile { path: make_path("file1.txt"), size: 1, original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file1.txt" } else { "X:/file1.txt" }))) },
-                    ScannedFile { path: make_path("file2.txt"), size: 2, original_path: Some(StrictPath::new(s(if cfg!(target_os = "windows") { "X:\\file2.txt" } else { "X:/file2.txt" }))) },
-                },
-                ..Default::default()
+            std::time::Duration::from_millis(2_500),
+            info.estimated_backup_time(4_000)
+        );
+        assert_eq!(std::time::Duration::from_secs(1), info.estimated_backup_time(1_000_000));
+        assert_eq!(
+            std::time::Duration::from_secs(1),
+            ScanInfo::default().estimated_backup_time(0)
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn can_back_up_and_restore_file_owners() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = TempDir::new("owners");
+        let base = temp.path().clone();
+        let backup = base.joined("backup");
+        let kept = base.joined("saves/kept.dat");
+        let denied = base.joined("saves/denied.dat");
+        let mut found_files = std::collections::HashSet::new();
+        for file in &[&kept, &denied] {
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), "abc").unwrap();
+            found_files.insert(ScannedFile::new((*file).clone()).with_size(3));
+        }
+        let metadata = std::fs::metadata(kept.interpret()).unwrap();
+        let owner = FileOwner {
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+        };
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files,
+            ..Default::default()
+        };
+        assert!(back_up_game(&info, "game1", &BackupLayout::new(backup.clone())).successful());
+
+        let layout = BackupLayout::new(backup);
+        let restorable = scan_game_for_restoration("game1", &layout);
+        let backup_info = restore_game(
+            &restorable,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+        );
+        let changed = std::sync::Mutex::new(vec![]);
+        let unrestored = restore_file_owners(&restorable, &backup_info, &|path, owner| {
+            if path.render().ends_with("denied.dat") {
+                return Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+            }
+            changed.lock().unwrap().push((path.render(), owner));
+            Ok(())
+        });
+
+        assert_eq!(
+            vec![owner, owner],
+            restorable.file_owners.values().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(kept.render(), owner)], changed.into_inner().unwrap());
+        assert_eq!(hashset! { denied }, unrestored);
+    }
+
+    /// Restore the files, then record which paths get their owners changed.
+    #[cfg(not(target_os = "windows"))]
+    fn restore_and_record_owners(info: &ScanInfo, mode: &RestoreMode) -> Vec<String> {
+        let backup_info = restore_game(info, &[], mode, &RestoreOptions::default(), &RetryPolicy::default());
+        let changed = std::sync::Mutex::new(vec![]);
+        restore_file_owners(info, &backup_info, &|path, _| {
+            changed.lock().unwrap().push(path.render());
+            Ok(())
+        });
+        let mut changed = changed.into_inner().unwrap();
+        changed.sort();
+        changed
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn keeps_owner_of_existing_file_left_alone_by_min_age() {
+        let temp = TempDir::new("owner-min-age");
+        let backed_up = temp.joined("backup/save.dat");
+        let live = temp.joined("live/save.dat");
+        for file in &[&backed_up, &live] {
+            file.create_parent_dir().unwrap();
+            std::fs::write(file.interpret(), "abc").unwrap();
+        }
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile::new(backed_up.clone()).with_size(3).with_original_path(live.clone()),
+            },
+            file_owners: hashmap! { backed_up => FileOwner { uid: 1, gid: 1 } },
+            ..Default::default()
+        };
+
+        let changed = restore_and_record_owners(
+            &info,
+            &RestoreMode::BackupExisting {
+                suffix: s(".bak"),
+                min_age_days: Some(7),
             },
-            scan_game_for_restoration(
-                "game1",
-                &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
-            ),
         );
+
+        assert_eq!(Vec::<String>::new(), changed);
     }
 
     #[test]
-    fn can_scan_game_for_restoration_with_registry() {
-        if cfg!(target_os = "windows") {
-            assert_eq!(
+    #[cfg(not(target_os = "windows"))]
+    fn changes_owner_of_file_restored_with_stored_name() {
+        let temp = TempDir::new("owner-stored-name");
+        let backed_up = temp.joined("backup/bad_name.dat");
+        backed_up.create_parent_dir().unwrap();
+        std::fs::write(backed_up.interpret(), "abc").unwrap();
+        let target = temp.joined("live");
+        let info = ScanInfo {
+            game_name: s("game1"),
+            found_files: hashset! {
+                ScannedFile::new(backed_up.clone()).with_size(3).with_original_path(target.joined("bad\0name.dat")),
+            },
+            file_owners: hashmap! { backed_up => FileOwner { uid: 1, gid: 1 } },
+            ..Default::default()
+        };
+
+        let changed = restore_and_record_owners(&info, &RestoreMode::Overwrite);
+
+        assert_eq!(vec![target.joined("bad_name.dat").render()], changed);
+    }
+
+    #[test]
+    fn can_restore_games_in_parallel() {
+        let temp = TempDir::new("restore-games");
+        let infos: Vec<_> = ["game1", "game2"]
+            .iter()
+            .map(|name| {
+                let backed_up = temp.joined(&format!("backup/{}/save.txt", name));
+                backed_up.create_parent_dir().unwrap();
+                std::fs::write(backed_up.interpret(), name).unwrap();
                 ScanInfo {
-                    game_name: s("game3"),
-                    found_registry_keys: hashset! {
-                        s("HKEY_CURRENT_USER/Software/Ludusavi/game3")
+                    game_name: s(name),
+                    found_files: hashset! {
+                        ScannedFile::new(backed_up.clone())
+                            .with_size(5)
+                            .with_original_path(temp.joined(&format!("live/{}/save.txt", name))),
                     },
-                    registry_file: Some(StrictPath::new(format!(
-                        "\\\\?\\{}\\tests\\backup\\game3-renamed/registry.yaml",
-                        repo().replace("/", "\\")
-                    ))),
-                    ..Default::default()
-                },
-                scan_game_for_restoration(
-                    "game3",
-                    &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
-                ),
-            );
-        } else {
-            assert_eq!(
-                ScanInfo {
-                    game_name: s("game3"),
+                    file_owners: hashmap! { backed_up => FileOwner { uid: 1, gid: 1 } },
                     ..Default::default()
-                },
-                scan_game_for_restoration(
-                    "game3",
-                    &BackupLayout::new(StrictPath::new(format!("{}/tests/backup", repo())))
-                ),
-            );
-        }
+                }
+            })
+            .collect();
+        let finished = std::sync::atomic::AtomicUsize::new(0);
+        let changed = std::sync::Mutex::new(vec![]);
+
+        let results = restore_games(
+            &infos,
+            &[],
+            &RestoreMode::Overwrite,
+            &RestoreOptions::default(),
+            &RetryPolicy::default(),
+            Some(&|path: &StrictPath, _| {
+                changed.lock().unwrap().push(path.render());
+                Ok(())
+            }),
+            &|| {
+                finished.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            },
+        );
+        let restored: Vec<_> = ["game1", "game2"]
+            .iter()
+            .map(|name| std::fs::read_to_string(temp.joined(&format!("live/{}/save.txt", name)).interpret()).ok())
+            .collect();
+        let mut changed = changed.into_inner().unwrap();
+        changed.sort();
+
+        assert_eq!(
+            vec!["game1", "game2"],
+            itertools::sorted(results.keys()).collect::<Vec<_>>()
+        );
+        assert!(results.values().all(|x| x.successful()));
+        assert_eq!(vec![Some(s("game1")), Some(s("game2"))], restored);
+        assert_eq!(
+            vec![
+                temp.joined("live/game1/save.txt").render(),
+                temp.joined("live/game2/save.txt").render()
+            ],
+            changed
+        );
+        assert_eq!(2, finished.into_inner());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn changes_owner_of_symlink_rather_than_its_target() {
+        let temp = TempDir::new("owner-symlink");
+        let link = temp.joined("link.dat");
+        // The target doesn't exist, so following the link would fail.
+        std::os::unix::fs::symlink(temp.joined("missing.dat").interpret(), link.interpret()).unwrap();
+        let owner = file_owner(temp.path()).unwrap();
+
+        assert!(change_file_owner(&link, owner).is_ok());
     }
 }
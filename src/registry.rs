@@ -16,6 +16,15 @@ pub struct Entries(
     #[serde(serialize_with = "crate::serialization::ordered_map")] pub std::collections::HashMap<String, Entry>,
 );
 
+/// Problems that did not stop the rest of the registry from being restored.
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreWarnings {
+    /// Full paths of values that were left alone because they already exist with a different type.
+    pub type_conflicts: std::collections::HashSet<String>,
+    /// Full paths of `HKEY_USERS` keys whose SID could not be mapped onto a hive on this system.
+    pub unmapped_keys: std::collections::HashSet<String>,
+}
+
 #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Entry {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -30,10 +39,6 @@ pub struct Entry {
     qword: Option<u64>,
 }
 
-pub struct RegistryInfo {
-    pub found: bool,
-}
-
 impl Hives {
     pub fn load(file: &StrictPath) -> Option<Self> {
         if file.is_file() {
@@ -54,7 +59,7 @@ impl Hives {
         serde_yaml::to_string(self).unwrap()
     }
 
-    pub fn store_key_from_full_path(&mut self, path: &str) -> Result<RegistryInfo, Error> {
+    pub fn store_key_from_full_path(&mut self, path: &str) -> Result<(), Error> {
         let path = path.replace('/', "\\");
 
         let parts: Vec<&str> = path.splitn(2, '\\').collect();
@@ -62,19 +67,27 @@ impl Hives {
             return Err(Error::RegistryIssue);
         }
 
-        let hive_name = parts[0];
+        let hive_name = normalize_hive_name(parts[0]).ok_or(Error::RegistryIssue)?;
         let hive = get_hkey_from_name(hive_name).ok_or(Error::RegistryIssue)?;
-        let key = parts[1];
+        let key = get_actual_key_casing(hive, parts[1]).unwrap_or_else(|| parts[1].to_string());
 
-        let info = self.store_key(hive, hive_name, key)?;
+        // The current user's SID differs between systems, so store those keys
+        // under HKEY_CURRENT_USER, which is the same hive by another name.
+        let (hive_name, key) = to_portable_key(hive_name, &key, current_user_sid().as_deref());
+        let hive = get_hkey_from_name(hive_name).ok_or(Error::RegistryIssue)?;
 
-        Ok(info)
+        self.store_key(hive, hive_name, &key)
     }
 
-    pub fn store_key(&mut self, hive: winreg::HKEY, hive_name: &str, key: &str) -> Result<RegistryInfo, Error> {
+    pub fn store_key(&mut self, hive: winreg::HKEY, hive_name: &str, key: &str) -> Result<(), Error> {
         let subkey = winreg::RegKey::predef(hive)
             .open_subkey(key)
-            .map_err(|_| Error::RegistryIssue)?;
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Error::RegistryKeyNotFound {
+                    key: format!("{}\\{}", hive_name, key),
+                },
+                _ => Error::RegistryIssue,
+            })?;
 
         self.0
             .entry(hive_name.to_string())
@@ -108,23 +121,43 @@ impl Hives {
             return Err(Error::RegistryIssue);
         }
 
-        Ok(RegistryInfo { found: true })
+        Ok(())
     }
 
-    pub fn restore(&self) -> Result<(), Error> {
+    /// Values are restored with the exact types from the backup. If a value
+    /// already exists with a different type, then it is left alone, since the
+    /// game may not be able to read the other type.
+    ///
+    /// Keys under another system's user SID in `HKEY_USERS` are restored
+    /// into the current user's hive instead.
+    pub fn restore(&self) -> Result<RestoreWarnings, Error> {
         let mut failed = false;
+        let mut warnings = RestoreWarnings::default();
+        let current_sid = current_user_sid();
 
         for (hive_name, keys) in self.0.iter() {
-            let hive = match get_hkey_from_name(hive_name) {
-                Some(x) => winreg::RegKey::predef(x),
-                None => {
-                    failed = true;
-                    continue;
-                }
-            };
+            if get_hkey_from_name(hive_name).is_none() {
+                failed = true;
+                continue;
+            }
 
             for (key_name, entries) in keys.0.iter() {
-                let (key, _) = match hive.create_subkey(key_name) {
+                let (target_hive_name, target_key_name) =
+                    match to_local_key(hive_name, key_name, current_sid.as_deref(), hku_subkey_exists) {
+                        Some(x) => x,
+                        None => {
+                            warnings.unmapped_keys.insert(format!("{}\\{}", hive_name, key_name));
+                            continue;
+                        }
+                    };
+                let hive = match get_hkey_from_name(target_hive_name) {
+                    Some(x) => winreg::RegKey::predef(x),
+                    None => {
+                        failed = true;
+                        continue;
+                    }
+                };
+                let (key, _) = match hive.create_subkey(&target_key_name) {
                     Ok(x) => x,
                     Err(_) => {
                         failed = true;
@@ -134,6 +167,14 @@ impl Hives {
 
                 for (entry_name, entry) in entries.0.iter() {
                     if let Some(value) = Option::<winreg::RegValue>::from(entry) {
+                        if let Ok(existing) = key.get_raw_value(entry_name) {
+                            if existing.vtype != value.vtype {
+                                warnings
+                                    .type_conflicts
+                                    .insert(format!("{}\\{}\\{}", target_hive_name, target_key_name, entry_name));
+                                continue;
+                            }
+                        }
                         if key.set_raw_value(entry_name, &value).is_err() {
                             failed = true;
                         }
@@ -148,7 +189,7 @@ impl Hives {
             return Err(Error::RegistryIssue);
         }
 
-        Ok(())
+        Ok(warnings)
     }
 }
 
@@ -214,10 +255,117 @@ impl From<&Entry> for Option<winreg::RegValue> {
     }
 }
 
+fn normalize_hive_name(name: &str) -> Option<&'static str> {
+    match name.to_uppercase().as_str() {
+        "HKEY_CURRENT_USER" => Some("HKEY_CURRENT_USER"),
+        "HKEY_LOCAL_MACHINE" => Some("HKEY_LOCAL_MACHINE"),
+        "HKEY_USERS" => Some("HKEY_USERS"),
+        _ => None,
+    }
+}
+
+/// Each user's classes are loaded as a separate hive, but they also appear
+/// under this key in the user's main hive.
+const USER_CLASSES_SUFFIX: &str = "_Classes";
+const USER_CLASSES_KEY: &str = "Software\\Classes";
+
+/// Regular user accounts have SIDs with this prefix, unlike built-in accounts
+/// such as `S-1-5-18`, which are the same on every system.
+const USER_SID_PREFIX: &str = "S-1-5-21-";
+
+/// Find the current user's SID by matching the home folder against the profile list.
+fn current_user_sid() -> Option<String> {
+    let home = dirs::home_dir()?.display().to_string().to_lowercase();
+    let profiles = winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE)
+        .open_subkey("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\ProfileList")
+        .ok()?;
+    profiles.enum_keys().filter_map(|x| x.ok()).find(|sid| {
+        profiles
+            .open_subkey(sid)
+            .and_then(|x| x.get_value::<String, _>("ProfileImagePath"))
+            .map(|x| x.to_lowercase() == home)
+            .unwrap_or(false)
+    })
+}
+
+fn hku_subkey_exists(sid: &str) -> bool {
+    winreg::RegKey::predef(winreg::enums::HKEY_USERS)
+        .open_subkey(sid)
+        .is_ok()
+}
+
+/// Split an `HKEY_USERS` key into its SID and the rest of the key,
+/// which is rebased onto the user's main hive for `_Classes` hives.
+fn split_user_key(key: &str) -> (&str, String) {
+    let (sid, rest) = match key.find('\\') {
+        Some(i) => (&key[..i], &key[i + 1..]),
+        None => (key, ""),
+    };
+    match sid.strip_suffix(USER_CLASSES_SUFFIX) {
+        Some(sid) if rest.is_empty() => (sid, USER_CLASSES_KEY.to_string()),
+        Some(sid) => (sid, format!("{}\\{}", USER_CLASSES_KEY, rest)),
+        None => (sid, rest.to_string()),
+    }
+}
+
+/// Convert a key under the current user's SID to the equivalent key under `HKEY_CURRENT_USER`.
+/// Other keys are returned as-is.
+fn to_portable_key<'a>(hive_name: &'a str, key: &str, current_sid: Option<&str>) -> (&'a str, String) {
+    if hive_name == "HKEY_USERS" {
+        let (sid, rest) = split_user_key(key);
+        if Some(sid) == current_sid && !rest.is_empty() {
+            return ("HKEY_CURRENT_USER", rest);
+        }
+    }
+    (hive_name, key.to_string())
+}
+
+/// Decide where a backed up key should be restored on this system.
+/// Keys under `HKEY_USERS` are mapped onto the current user's hive if they belong
+/// to the current user or to a user who doesn't exist here (i.e., the backup came
+/// from another system or account). Other SIDs are only restored if they exist.
+fn to_local_key<'a>(
+    hive_name: &'a str,
+    key: &str,
+    current_sid: Option<&str>,
+    sid_exists: impl Fn(&str) -> bool,
+) -> Option<(&'a str, String)> {
+    if hive_name != "HKEY_USERS" {
+        return Some((hive_name, key.to_string()));
+    }
+
+    let (sid, rest) = split_user_key(key);
+    if !rest.is_empty() && (Some(sid) == current_sid || (sid.starts_with(USER_SID_PREFIX) && !sid_exists(sid))) {
+        Some(("HKEY_CURRENT_USER", rest))
+    } else if sid_exists(key.split('\\').next().unwrap_or_default()) {
+        Some((hive_name, key.to_string()))
+    } else {
+        None
+    }
+}
+
+/// The registry is case-insensitive, so the manifest's casing may not match the
+/// actual key. This finds the actual casing so that restoration recreates it faithfully.
+fn get_actual_key_casing(hive: winreg::HKEY, key: &str) -> Option<String> {
+    let mut current = winreg::RegKey::predef(hive);
+    let mut actual = vec![];
+    for part in key.split('\\').filter(|x| !x.is_empty()) {
+        let lowered = part.to_lowercase();
+        let name = current
+            .enum_keys()
+            .filter_map(|x| x.ok())
+            .find(|x| x.to_lowercase() == lowered)?;
+        current = current.open_subkey(&name).ok()?;
+        actual.push(name);
+    }
+    Some(actual.join("\\"))
+}
+
 fn get_hkey_from_name(name: &str) -> Option<winreg::HKEY> {
     match name {
         "HKEY_CURRENT_USER" => Some(winreg::enums::HKEY_CURRENT_USER),
         "HKEY_LOCAL_MACHINE" => Some(winreg::enums::HKEY_LOCAL_MACHINE),
+        "HKEY_USERS" => Some(winreg::enums::HKEY_USERS),
         _ => None,
     }
 }
@@ -225,7 +373,7 @@ fn get_hkey_from_name(name: &str) -> Option<winreg::HKEY> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use maplit::hashmap;
+    use maplit::{hashmap, hashset};
     use pretty_assertions::assert_eq;
 
     fn s(text: &str) -> String {
@@ -324,6 +472,185 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_store_key_from_full_path_with_different_casing() {
+        let mut hives = Hives::default();
+        hives
+            .store_key_from_full_path("hkey_current_user/software/LUDUSAVI/Other")
+            .unwrap();
+        assert_eq!(
+            Hives(hashmap! {
+                s("HKEY_CURRENT_USER") => Keys(hashmap! {
+                    s("Software\\Ludusavi\\other") => Entries::default()
+                })
+            }),
+            hives,
+        );
+    }
+
+    #[test]
+    fn cannot_store_key_from_full_path_of_missing_key() {
+        let mut hives = Hives::default();
+        assert_eq!(
+            Err(Error::RegistryKeyNotFound {
+                key: s("HKEY_CURRENT_USER\\Software\\Ludusavi\\fake")
+            }),
+            hives.store_key_from_full_path("HKEY_CURRENT_USER/Software/Ludusavi/fake"),
+        );
+        assert_eq!(Hives::default(), hives);
+    }
+
+    #[test]
+    fn can_restore_values_without_changing_existing_types() {
+        let key_name = "Software\\Ludusavi\\restore-type-conflict";
+        let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
+        let _ = hkcu.delete_subkey_all(key_name);
+        let (key, _) = hkcu.create_subkey(key_name).unwrap();
+        key.set_value("mismatched", &1u32).unwrap();
+        key.set_value("matched", &s("old")).unwrap();
+
+        let hives = Hives(hashmap! {
+            s("HKEY_CURRENT_USER") => Keys(hashmap! {
+                s(key_name) => Entries(hashmap! {
+                    s("mismatched") => Entry {
+                        sz: Some(s("foo")),
+                        ..Default::default()
+                    },
+                    s("matched") => Entry {
+                        sz: Some(s("new")),
+                        ..Default::default()
+                    },
+                    s("added") => Entry {
+                        qword: Some(2),
+                        ..Default::default()
+                    },
+                })
+            })
+        });
+        let result = hives.restore();
+
+        let mismatched: Result<u32, _> = key.get_value("mismatched");
+        let matched: Result<String, _> = key.get_value("matched");
+        let added: Result<u64, _> = key.get_value("added");
+        let _ = hkcu.delete_subkey_all(key_name);
+
+        assert_eq!(
+            Ok(RestoreWarnings {
+                type_conflicts: hashset! { s("HKEY_CURRENT_USER\\Software\\Ludusavi\\restore-type-conflict\\mismatched") },
+                ..Default::default()
+            }),
+            result,
+        );
+        assert_eq!(1, mismatched.unwrap());
+        assert_eq!(s("new"), matched.unwrap());
+        assert_eq!(2, added.unwrap());
+    }
+
+    #[test]
+    fn can_store_key_from_full_path_under_current_user_sid() {
+        let sid = current_user_sid().unwrap();
+        let mut hives = Hives::default();
+        hives
+            .store_key_from_full_path(&format!("HKEY_USERS/{}/Software/Ludusavi/other", sid))
+            .unwrap();
+        assert_eq!(
+            Hives(hashmap! {
+                s("HKEY_CURRENT_USER") => Keys(hashmap! {
+                    s("Software\\Ludusavi\\other") => Entries::default()
+                })
+            }),
+            hives,
+        );
+    }
+
+    #[test]
+    fn can_convert_user_sid_keys_to_portable_keys() {
+        let sid = Some("S-1-5-21-1-2-3-1001");
+        assert_eq!(
+            ("HKEY_CURRENT_USER", s("Software\\Foo")),
+            to_portable_key("HKEY_USERS", "S-1-5-21-1-2-3-1001\\Software\\Foo", sid),
+        );
+        assert_eq!(
+            ("HKEY_CURRENT_USER", s("Software\\Classes\\Foo")),
+            to_portable_key("HKEY_USERS", "S-1-5-21-1-2-3-1001_Classes\\Foo", sid),
+        );
+        assert_eq!(
+            ("HKEY_USERS", s("S-1-5-21-1-2-3-1002\\Software\\Foo")),
+            to_portable_key("HKEY_USERS", "S-1-5-21-1-2-3-1002\\Software\\Foo", sid),
+        );
+        assert_eq!(
+            ("HKEY_USERS", s("S-1-5-21-1-2-3-1001")),
+            to_portable_key("HKEY_USERS", "S-1-5-21-1-2-3-1001", sid),
+        );
+        assert_eq!(
+            ("HKEY_LOCAL_MACHINE", s("S-1-5-21-1-2-3-1001\\Software\\Foo")),
+            to_portable_key("HKEY_LOCAL_MACHINE", "S-1-5-21-1-2-3-1001\\Software\\Foo", sid),
+        );
+    }
+
+    #[test]
+    fn can_map_user_sid_keys_to_local_keys() {
+        let sid = Some("S-1-5-21-1-2-3-1001");
+        let exists = |x: &str| ["S-1-5-21-1-2-3-1001", "S-1-5-21-1-2-3-1002", "S-1-5-18"].contains(&x);
+        assert_eq!(
+            Some(("HKEY_CURRENT_USER", s("Software\\Foo"))),
+            to_local_key("HKEY_CURRENT_USER", "Software\\Foo", sid, exists),
+        );
+        assert_eq!(
+            Some(("HKEY_CURRENT_USER", s("Software\\Foo"))),
+            to_local_key("HKEY_USERS", "S-1-5-21-1-2-3-1001\\Software\\Foo", sid, exists),
+        );
+        assert_eq!(
+            Some(("HKEY_CURRENT_USER", s("Software\\Foo"))),
+            to_local_key("HKEY_USERS", "S-1-5-21-9-9-9-1001\\Software\\Foo", sid, exists),
+        );
+        assert_eq!(
+            Some(("HKEY_CURRENT_USER", s("Software\\Classes\\Foo"))),
+            to_local_key("HKEY_USERS", "S-1-5-21-9-9-9-1001_Classes\\Foo", sid, exists),
+        );
+        assert_eq!(
+            Some(("HKEY_USERS", s("S-1-5-21-1-2-3-1002\\Software\\Foo"))),
+            to_local_key("HKEY_USERS", "S-1-5-21-1-2-3-1002\\Software\\Foo", sid, exists),
+        );
+        assert_eq!(
+            Some(("HKEY_USERS", s("S-1-5-18\\Software\\Foo"))),
+            to_local_key("HKEY_USERS", "S-1-5-18\\Software\\Foo", sid, exists),
+        );
+        assert_eq!(None, to_local_key("HKEY_USERS", "S-1-5-99\\Software\\Foo", sid, exists),);
+    }
+
+    #[test]
+    fn can_restore_keys_from_another_user_sid_under_current_user() {
+        let key_name = "Software\\Ludusavi\\restore-other-sid";
+        let hkcu = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER);
+        let _ = hkcu.delete_subkey_all(key_name);
+
+        let hives = Hives(hashmap! {
+            s("HKEY_USERS") => Keys(hashmap! {
+                format!("S-1-5-21-0-0-0-999999\\{}", key_name) => Entries(hashmap! {
+                    s("sz") => Entry {
+                        sz: Some(s("foo")),
+                        ..Default::default()
+                    },
+                }),
+                s("S-1-5-99-ludusavi\\Software\\Ludusavi") => Entries::default(),
+            })
+        });
+        let result = hives.restore();
+
+        let restored: Result<String, _> = hkcu.open_subkey(key_name).and_then(|x| x.get_value("sz"));
+        let _ = hkcu.delete_subkey_all(key_name);
+
+        assert_eq!(
+            Ok(RestoreWarnings {
+                unmapped_keys: hashset! { s("HKEY_USERS\\S-1-5-99-ludusavi\\Software\\Ludusavi") },
+                ..Default::default()
+            }),
+            result,
+        );
+        assert_eq!(s("foo"), restored.unwrap());
+    }
+
     #[test]
     fn can_be_serialized() {
         assert_eq!(
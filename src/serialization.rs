@@ -20,6 +20,27 @@ where
     ordered.serialize(serializer)
 }
 
+pub fn ordered_optional_set<S>(value: &Option<HashSet<String>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let ordered: Option<std::collections::BTreeSet<_>> = value.as_ref().map(|x| x.iter().collect());
+    ordered.serialize(serializer)
+}
+
+pub fn ordered_map_of_sets<S>(value: &HashMap<String, HashSet<String>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let ordered: BTreeMap<_, std::collections::BTreeSet<_>> =
+        value.iter().map(|(k, v)| (k, v.iter().collect())).collect();
+    ordered.serialize(serializer)
+}
+
 pub fn is_false(v: &bool) -> bool {
     !v
 }
+
+pub fn default_true() -> bool {
+    true
+}
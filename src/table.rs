@@ -0,0 +1,369 @@
+//! A compact table of the games in a backup or restore, for `--output table`.
+//! Widths are measured in terminal cells rather than characters,
+//! so that titles with wide characters (e.g., Japanese) stay aligned.
+
+use crate::lang::Translator;
+use console::measure_text_width;
+
+const SEPARATOR: &str = "  ";
+const ELLIPSIS: char = '…';
+/// Game names are not truncated below this, even if the terminal is narrower.
+const MIN_NAME_WIDTH: usize = 12;
+/// This is used when the output is not a terminal (e.g., piped to a file).
+const DEFAULT_WIDTH: usize = 80;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowStatus {
+    Ok,
+    Failed,
+    Ignored,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub name: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Only restores know which files are new and which replace existing files.
+    pub new_files: Option<usize>,
+    pub changed_files: Option<usize>,
+    pub status: RowStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Name,
+    /// Largest first.
+    Size,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "size" => Ok(Self::Size),
+            _ => Err(format!("invalid sort: {}", s)),
+        }
+    }
+}
+
+pub fn sort_rows(rows: &mut [Row], key: SortKey) {
+    match key {
+        SortKey::Name => rows.sort_by(|x, y| x.name.cmp(&y.name)),
+        SortKey::Size => rows.sort_by(|x, y| y.bytes.cmp(&x.bytes).then_with(|| x.name.cmp(&y.name))),
+    }
+}
+
+struct Column {
+    header: String,
+    cells: Vec<String>,
+    align_right: bool,
+}
+
+impl Column {
+    fn width(&self) -> usize {
+        std::iter::once(&self.header)
+            .chain(&self.cells)
+            .map(|x| measure_text_width(x))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// One row per game, then the totals. The name column shrinks to fit `width`,
+/// and the new/changed columns are left out unless some row has them.
+pub fn render(translator: &Translator, rows: &[Row], location: &str, width: usize, color: bool) -> String {
+    let counts = |get: fn(&Row) -> Option<usize>| {
+        rows.iter()
+            .map(|x| get(x).map(|n| n.to_string()).unwrap_or_else(|| "-".to_string()))
+            .collect()
+    };
+
+    let mut columns = vec![
+        Column {
+            header: translator.cli_table_game(),
+            cells: rows.iter().map(|x| x.name.clone()).collect(),
+            align_right: false,
+        },
+        Column {
+            header: translator.cli_table_files(),
+            cells: rows.iter().map(|x| x.files.to_string()).collect(),
+            align_right: true,
+        },
+        Column {
+            header: translator.cli_table_size(),
+            cells: rows.iter().map(|x| translator.adjusted_size(x.bytes)).collect(),
+            align_right: true,
+        },
+    ];
+    if rows.iter().any(|x| x.new_files.is_some() || x.changed_files.is_some()) {
+        columns.push(Column {
+            header: translator.cli_table_new(),
+            cells: counts(|x| x.new_files),
+            align_right: true,
+        });
+        columns.push(Column {
+            header: translator.cli_table_changed(),
+            cells: counts(|x| x.changed_files),
+            align_right: true,
+        });
+    }
+    columns.push(Column {
+        header: translator.cli_table_status(),
+        cells: rows.iter().map(|x| translator.cli_table_row_status(x.status)).collect(),
+        align_right: false,
+    });
+
+    let mut widths: Vec<_> = columns.iter().map(Column::width).collect();
+    let others = widths[1..].iter().sum::<usize>() + SEPARATOR.len() * (widths.len() - 1);
+    widths[0] = widths[0].min(width.saturating_sub(others).max(MIN_NAME_WIDTH));
+    let total_width = widths.iter().sum::<usize>() + SEPARATOR.len() * (widths.len() - 1);
+
+    // The status is last, so it's never padded, which keeps color codes out of the width math.
+    let line = |cells: Vec<&str>, status: Option<RowStatus>| {
+        let last = cells.len() - 1;
+        cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let cell = truncate_middle(cell, widths[i]);
+                match status {
+                    _ if i != last => pad(&cell, widths[i], columns[i].align_right),
+                    Some(status) if color => paint(&cell, status),
+                    _ => cell,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(SEPARATOR)
+    };
+
+    let rule = "-".repeat(total_width);
+    let mut lines = vec![
+        line(columns.iter().map(|x| x.header.as_str()).collect(), None),
+        rule.clone(),
+    ];
+    for (i, row) in rows.iter().enumerate() {
+        lines.push(line(
+            columns.iter().map(|x| x.cells[i].as_str()).collect(),
+            Some(row.status),
+        ));
+    }
+    lines.push(rule);
+    lines.push(translator.cli_table_totals(
+        rows.len(),
+        rows.iter().map(|x| x.files).sum(),
+        rows.iter().map(|x| x.bytes).sum(),
+        location,
+    ));
+
+    lines.join("\n")
+}
+
+pub fn terminal_width() -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, columns)| columns as usize)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Color is only used in a terminal, and never when `NO_COLOR` is set (see https://no-color.org).
+pub fn use_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && console::Term::stdout().is_term()
+}
+
+fn paint(text: &str, status: RowStatus) -> String {
+    let styled = console::style(text).force_styling(true);
+    match status {
+        RowStatus::Ok => styled.green(),
+        RowStatus::Failed => styled.red(),
+        RowStatus::Ignored => styled.yellow(),
+    }
+    .to_string()
+}
+
+fn pad(text: &str, width: usize, align_right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(measure_text_width(text)));
+    if align_right {
+        format!("{}{}", fill, text)
+    } else {
+        format!("{}{}", text, fill)
+    }
+}
+
+/// Shorten the text by cutting out its middle, since both ends of a title
+/// tend to matter (e.g., `Some Game: Definitive Edition` vs `Some Game 2`).
+fn truncate_middle(text: &str, width: usize) -> String {
+    if measure_text_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let budget = width - 1;
+    let head = take_width(text.chars(), budget - budget / 2);
+    let tail: String = take_width(text.chars().rev(), budget - measure_text_width(&head))
+        .chars()
+        .rev()
+        .collect();
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+fn take_width(chars: impl Iterator<Item = char>, width: usize) -> String {
+    let mut taken = String::new();
+    let mut used = 0;
+    for c in chars {
+        let needed = measure_text_width(&c.to_string());
+        if used + needed > width {
+            break;
+        }
+        used += needed;
+        taken.push(c);
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn row(name: &str, files: usize, bytes: u64, status: RowStatus) -> Row {
+        Row {
+            name: s(name),
+            files,
+            bytes,
+            new_files: None,
+            changed_files: None,
+            status,
+        }
+    }
+
+    #[test]
+    fn can_truncate_middle_of_text() {
+        assert_eq!("short", truncate_middle("short", 10));
+        assert_eq!("abcd…6789", truncate_middle("abcdefghij0123456789", 9));
+        assert_eq!("ab…89", truncate_middle("abcdefghij0123456789", 5));
+        assert_eq!("…", truncate_middle("abc", 1));
+        assert_eq!("", truncate_middle("abc", 0));
+    }
+
+    #[test]
+    fn can_truncate_middle_of_text_with_wide_characters() {
+        // Each of these characters takes up two cells.
+        // A wide character that doesn't fit leaves a gap, which padding fills later.
+        assert_eq!("ゲー…ブ", truncate_middle("ゲームのセーブ", 8));
+        assert_eq!(7, measure_text_width(&truncate_middle("ゲームのセーブ", 8)));
+        assert_eq!("ゲ…ブ", truncate_middle("ゲームのセーブ", 6));
+    }
+
+    #[test]
+    fn can_pad_text_with_wide_characters() {
+        assert_eq!("ゲーム  ", pad("ゲーム", 8, false));
+        assert_eq!("  ゲーム", pad("ゲーム", 8, true));
+        assert_eq!("ゲーム", pad("ゲーム", 4, false));
+    }
+
+    #[test]
+    fn can_sort_rows() {
+        let mut rows = vec![
+            row("b", 1, 10, RowStatus::Ok),
+            row("a", 1, 10, RowStatus::Ok),
+            row("c", 1, 30, RowStatus::Ok),
+        ];
+
+        sort_rows(&mut rows, SortKey::Size);
+        assert_eq!(
+            vec!["c", "a", "b"],
+            rows.iter().map(|x| x.name.as_str()).collect::<Vec<_>>()
+        );
+
+        sort_rows(&mut rows, SortKey::Name);
+        assert_eq!(
+            vec!["a", "b", "c"],
+            rows.iter().map(|x| x.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn can_render_table_with_unicode_names() {
+        let rows = vec![
+            row("Celeste", 2, 2048, RowStatus::Ok),
+            row("ゼルダの伝説", 1, 512, RowStatus::Failed),
+            row("Skipped Game", 3, 3 * 1024 * 1024, RowStatus::Ignored),
+        ];
+
+        assert_eq!(
+            r#"
+Game          Files      Size  Status
+--------------------------------------
+Celeste           2  2.00 KiB  OK
+ゼルダの伝説      1     512 B  FAILED
+Skipped Game      3  3.00 MiB  IGNORED
+--------------------------------------
+Total: 3 games, 6 files, 3.00 MiB in /backup
+            "#
+            .trim(),
+            render(&Translator::default(), &rows, "/backup", 80, false),
+        );
+    }
+
+    #[test]
+    fn can_render_table_with_new_and_changed_files() {
+        let rows = vec![
+            Row {
+                new_files: Some(1),
+                changed_files: Some(2),
+                ..row("Celeste", 3, 100, RowStatus::Ok)
+            },
+            row("Hades", 1, 10, RowStatus::Ignored),
+        ];
+
+        assert_eq!(
+            r#"
+Game     Files   Size  New  Changed  Status
+--------------------------------------------
+Celeste      3  100 B    1        2  OK
+Hades        1   10 B    -        -  IGNORED
+--------------------------------------------
+Total: 2 games, 4 files, 110 B in /restore
+            "#
+            .trim(),
+            render(&Translator::default(), &rows, "/restore", 80, false),
+        );
+    }
+
+    #[test]
+    fn can_render_table_narrowed_to_terminal_width() {
+        let rows = vec![row("The Elder Scrolls V: Skyrim Special Edition", 1, 10, RowStatus::Ok)];
+
+        assert_eq!(
+            r#"
+Game          Files  Size  Status
+---------------------------------
+The El…ition      1  10 B  OK
+---------------------------------
+Total: 1 games, 1 files, 10 B in /backup
+            "#
+            .trim(),
+            render(&Translator::default(), &rows, "/backup", 20, false),
+        );
+    }
+
+    #[test]
+    fn can_render_table_with_colored_status() {
+        let rendered = render(
+            &Translator::default(),
+            &[row("Celeste", 1, 10, RowStatus::Failed)],
+            "/backup",
+            80,
+            true,
+        );
+        assert!(rendered.contains("\u{1b}[31mFAILED\u{1b}[0m"));
+    }
+}
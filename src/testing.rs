@@ -0,0 +1,45 @@
+//! Helpers shared by the unit tests.
+
+use crate::prelude::{reslashed, StrictPath};
+
+/// A scratch folder for one test, which is removed when dropped, even if the test fails.
+/// Each test needs its own name, since tests run in parallel.
+pub struct TempDir {
+    path: StrictPath,
+}
+
+impl TempDir {
+    /// Start from an empty folder, in case a previous run left something behind.
+    pub fn new(name: &str) -> Self {
+        let path = StrictPath::new(format!(
+            "{}/ludusavi-test-{}",
+            reslashed(&std::env::temp_dir().to_string_lossy()),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(path.interpret());
+        std::fs::create_dir_all(path.interpret()).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &StrictPath {
+        &self.path
+    }
+
+    pub fn joined(&self, relative: &str) -> StrictPath {
+        self.path.joined(relative)
+    }
+
+    /// Create a file relative to the folder, along with any missing parent folders.
+    pub fn write(&self, relative: &str, content: &str) -> StrictPath {
+        let file = self.joined(relative);
+        file.create_parent_dir().unwrap();
+        std::fs::write(file.interpret(), content).unwrap();
+        file
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(self.path.interpret());
+    }
+}
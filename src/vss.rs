@@ -0,0 +1,189 @@
+//! Volume Shadow Copy Service (VSS) snapshots, so that files can be read
+//! even while a running game has them locked.
+//! Creating a snapshot requires administrator rights.
+
+use std::os::windows::ffi::OsStrExt;
+use winapi::{
+    shared::{guiddef::GUID, ntdef::HRESULT, winerror::FAILED},
+    um::{
+        combaseapi::CoInitializeEx,
+        fileapi::GetVolumePathNameW,
+        objbase::COINIT_MULTITHREADED,
+        vsbackup::{CreateVssBackupComponents, IVssBackupComponents, VssFreeSnapshotProperties},
+        vss::{IVssAsync, VSS_BT_COPY, VSS_SNAPSHOT_PROP},
+    },
+};
+
+const VERBATIM_PREFIX: &str = "\\\\?\\";
+const VSS_S_ASYNC_CANCELLED: HRESULT = 0x0004_230B;
+
+/// A non-persistent snapshot of one volume. It is deleted when this is dropped.
+pub struct Snapshot {
+    components: *mut IVssBackupComponents,
+    volume: String,
+    device: String,
+}
+
+// The backup components are only released on drop, and the COM apartment is
+// multithreaded, so the snapshot can be shared between threads.
+unsafe impl Send for Snapshot {}
+unsafe impl Sync for Snapshot {}
+
+impl Snapshot {
+    /// Take a snapshot of the volume, like `C:\`.
+    pub fn create(volume: &str) -> Result<Self, ()> {
+        let mut snapshot = Self {
+            components: std::ptr::null_mut(),
+            volume: volume.to_string(),
+            device: String::new(),
+        };
+
+        unsafe {
+            // This fails harmlessly if the thread has already initialized COM.
+            CoInitializeEx(std::ptr::null_mut(), COINIT_MULTITHREADED);
+
+            check(CreateVssBackupComponents(&mut snapshot.components))?;
+            let components = &*snapshot.components;
+            check(components.InitializeForBackup(std::ptr::null_mut()))?;
+            // A copy backup doesn't affect the backup history of other tools.
+            check(components.SetBackupState(false, false, VSS_BT_COPY, false))?;
+            wait(|x| components.GatherWriterMetadata(x))?;
+
+            let mut set_id: GUID = std::mem::zeroed();
+            let mut snapshot_id: GUID = std::mem::zeroed();
+            check(components.StartSnapshotSet(&mut set_id))?;
+            let mut wide_volume = to_wide(volume);
+            check(components.AddToSnapshotSet(wide_volume.as_mut_ptr(), std::mem::zeroed(), &mut snapshot_id))?;
+            wait(|x| components.PrepareForBackup(x))?;
+            wait(|x| components.DoSnapshotSet(x))?;
+
+            let mut properties: VSS_SNAPSHOT_PROP = std::mem::zeroed();
+            check(components.GetSnapshotProperties(snapshot_id, &mut properties))?;
+            snapshot.device = from_wide(properties.m_pwszSnapshotDeviceObject);
+            VssFreeSnapshotProperties(&mut properties);
+        }
+
+        if snapshot.device.is_empty() {
+            return Err(());
+        }
+        Ok(snapshot)
+    }
+
+    /// Where to read a file from within the snapshot, if it is on the snapshot's volume.
+    pub fn path_for(&self, path: &str) -> Option<String> {
+        let path = path.strip_prefix(VERBATIM_PREFIX).unwrap_or(path);
+        match path.get(..self.volume.len()) {
+            Some(volume) if volume.eq_ignore_ascii_case(&self.volume) => {
+                Some(format!("{}\\{}", self.device, &path[self.volume.len()..]))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if !self.components.is_null() {
+            unsafe {
+                (*self.components).Release();
+            }
+        }
+    }
+}
+
+/// The root of the volume containing the path, like `C:\`.
+pub fn volume_of(path: &str) -> Option<String> {
+    let path = path.strip_prefix(VERBATIM_PREFIX).unwrap_or(path);
+    let wide_path = to_wide(path);
+    let mut buffer = vec![0u16; 1024];
+    let found = unsafe { GetVolumePathNameW(wide_path.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32) };
+    if found == 0 {
+        return None;
+    }
+    let volume = unsafe { from_wide(buffer.as_ptr()) };
+    if volume.is_empty() {
+        None
+    } else {
+        Some(volume)
+    }
+}
+
+fn check(result: HRESULT) -> Result<(), ()> {
+    if FAILED(result) {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// Start an asynchronous operation and wait for it to finish.
+unsafe fn wait(start: impl FnOnce(*mut *mut IVssAsync) -> HRESULT) -> Result<(), ()> {
+    let mut operation: *mut IVssAsync = std::ptr::null_mut();
+    check(start(&mut operation))?;
+    if operation.is_null() {
+        return Err(());
+    }
+
+    let mut status: HRESULT = 0;
+    let waited = check((*operation).Wait(winapi::um::winbase::INFINITE))
+        .and_then(|_| check((*operation).QueryStatus(&mut status, std::ptr::null_mut())));
+    (*operation).Release();
+
+    waited?;
+    if FAILED(status) || status == VSS_S_ASYNC_CANCELLED {
+        return Err(());
+    }
+    Ok(())
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(text).encode_wide().chain(Some(0)).collect()
+}
+
+unsafe fn from_wide(text: *const u16) -> String {
+    if text.is_null() {
+        return String::new();
+    }
+    let mut len = 0;
+    while *text.add(len) != 0 {
+        len += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(text, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            components: std::ptr::null_mut(),
+            volume: "C:\\".to_string(),
+            device: "\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1".to_string(),
+        }
+    }
+
+    #[test]
+    fn can_find_path_within_snapshot() {
+        assert_eq!(
+            Some("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1\\Users\\foo\\save.dat".to_string()),
+            snapshot().path_for("\\\\?\\C:\\Users\\foo\\save.dat"),
+        );
+        assert_eq!(
+            Some("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1\\save.dat".to_string()),
+            snapshot().path_for("c:\\save.dat"),
+        );
+    }
+
+    #[test]
+    fn cannot_find_path_on_another_volume_within_snapshot() {
+        assert_eq!(None, snapshot().path_for("D:\\save.dat"));
+    }
+
+    #[test]
+    fn can_find_volume_of_path() {
+        let repo = env!("CARGO_MANIFEST_DIR");
+        assert_eq!(Some(format!("{}\\", &repo[..2])), volume_of(repo));
+    }
+}